@@ -12,7 +12,13 @@ const invoke = async <T,>(cmd: string, args?: Record<string, unknown>): Promise<
 };
 import {
   AppearanceConfig,
+  Diagnostic,
+  FileStamp,
+  ImportMode,
+  SaveResult,
   SystemInfo,
+  UnmanagedItem,
+  ValidationResult,
   DEFAULT_APPEARANCE_CONFIG,
 } from "./types/config";
 
@@ -22,14 +28,17 @@ interface ConfigLoadResult {
   config_path: string;
   config_exists: boolean;
   raw_content: string | null;
-  parse_errors: string[];
+  diagnostics: Diagnostic[];
+  unmanaged: UnmanagedItem[];
+  settings_module: string | null;
+  stamps: FileStamp[];
 }
 
-// Type for the save result from backend
-interface SaveConfigResult {
-  success: boolean;
-  config_path: string;
-  backup_path: string | null;
+// Settings merged with the config as it is on disk now
+interface ConfigMergeResult {
+  config: AppearanceConfig;
+  conflicts: string[];
+  loaded: ConfigLoadResult;
 }
 
 // Settings panel type
@@ -47,12 +56,28 @@ interface BackdropImage {
   path: string;
 }
 
+// `wezterm.lua:12:3`, leaving out what isn't known
+function formatLocation(d: { file: string; line: number | null; column?: number | null }): string {
+  const file = d.file.split(/[\\/]/).pop() ?? d.file;
+  return [file, d.line, d.column]
+    .filter((part) => part !== null && part !== undefined && part !== "")
+    .join(":");
+}
+
 function App() {
   const [config, setConfig] = useState<AppearanceConfig>(DEFAULT_APPEARANCE_CONFIG);
+  // The settings as loaded, and the files they came from, to notice edits made elsewhere
+  const [loadedConfig, setLoadedConfig] = useState<AppearanceConfig>(DEFAULT_APPEARANCE_CONFIG);
+  const [stamps, setStamps] = useState<FileStamp[]>([]);
+  // Files changed on disk since loading, while asking what to do about them
+  const [externalChanges, setExternalChanges] = useState<string[]>([]);
   const [systemInfo, setSystemInfo] = useState<SystemInfo | null>(null);
   const [configPath, setConfigPath] = useState<string>("");
   const [configExists, setConfigExists] = useState<boolean>(false);
-  const [parseErrors, setParseErrors] = useState<string[]>([]);
+  const [diagnostics, setDiagnostics] = useState<Diagnostic[]>([]);
+  const [unmanaged, setUnmanaged] = useState<UnmanagedItem[]>([]);
+  const [importMode, setImportMode] = useState<ImportMode>("static");
+  const [validation, setValidation] = useState<ValidationResult>({ valid: true, errors: [] });
   const [loading, setLoading] = useState<boolean>(true);
   const [saving, setSaving] = useState<boolean>(false);
   const [hasChanges, setHasChanges] = useState<boolean>(false);
@@ -68,6 +93,13 @@ function App() {
     loadBuiltinSchemes();
   }, []);
 
+  // Re-check the settings after every edit; saving is blocked while any are invalid
+  useEffect(() => {
+    invoke<ValidationResult>("validate_config", { config })
+      .then(setValidation)
+      .catch((error) => console.error("Failed to validate config:", error));
+  }, [config]);
+
   async function loadBuiltinSchemes() {
     try {
       const schemes = await invoke<ColorSchemeInfo[]>("get_builtin_color_schemes");
@@ -77,17 +109,21 @@ function App() {
     }
   }
 
-  async function loadConfig() {
+  async function loadConfig(mode: ImportMode = importMode) {
     setLoading(true);
     try {
-      const result = await invoke<ConfigLoadResult>("load_wezterm_config");
+      const result = await invoke<ConfigLoadResult>("load_wezterm_config", { mode });
       setConfig(result.config);
+      setLoadedConfig(result.config);
+      setStamps(result.stamps);
+      setExternalChanges([]);
       setConfigPath(result.config_path);
       setConfigExists(result.config_exists);
-      setParseErrors(result.parse_errors);
+      setDiagnostics(result.diagnostics);
+      setUnmanaged(result.unmanaged);
       setHasChanges(false);
-      if (result.parse_errors.length > 0) {
-        setStatusMessage(`Loaded with ${result.parse_errors.length} warnings`);
+      if (result.diagnostics.length > 0) {
+        setStatusMessage(`Loaded with ${result.diagnostics.length} warnings`);
       } else if (result.config_exists) {
         setStatusMessage("Config loaded successfully");
       } else {
@@ -115,20 +151,43 @@ function App() {
     }
   }
 
+  // Save unless the files changed on disk since loading; then ask whether
+  // to reload, overwrite or merge instead
   async function saveConfig() {
+    try {
+      const changed = await invoke<string[]>("check_config_changes", { stamps });
+      if (changed.length > 0) {
+        setExternalChanges(changed);
+        return;
+      }
+    } catch (error) {
+      console.error("Failed to check for external changes:", error);
+    }
+    await writeConfig(config, stamps);
+  }
+
+  // `checkStamps` of null overwrites whatever is on disk
+  async function writeConfig(settings: AppearanceConfig, checkStamps: FileStamp[] | null) {
     setSaving(true);
     try {
-      const result = await invoke<SaveConfigResult>("save_wezterm_config", {
-        config,
+      const result = await invoke<SaveResult>("save_wezterm_config", {
+        config: settings,
+        mode: importMode,
+        stamps: checkStamps,
       });
       if (result.success) {
         setHasChanges(false);
         setConfigExists(true);
+        setExternalChanges([]);
         setStatusMessage(
-          result.backup_path
-            ? `Saved! Backup created at ${result.backup_path}`
+          result.backups_created.length > 0
+            ? `Saved! Backup created at ${result.backups_created.join(", ")}`
             : "Config saved successfully!"
         );
+        // Later saves compare against what was just written
+        const reloaded = await invoke<ConfigLoadResult>("load_wezterm_config", { mode: importMode });
+        setLoadedConfig(settings);
+        setStamps(reloaded.stamps);
       }
     } catch (error) {
       console.error("Failed to save config:", error);
@@ -138,6 +197,29 @@ function App() {
     }
   }
 
+  // Keep the edits made here and the ones made on disk, then save the result
+  async function mergeAndSave() {
+    try {
+      const merged = await invoke<ConfigMergeResult>("merge_wezterm_config", {
+        base: loadedConfig,
+        config,
+        mode: importMode,
+      });
+      setConfig(merged.config);
+      setLoadedConfig(merged.loaded.config);
+      setStamps(merged.loaded.stamps);
+      setDiagnostics(merged.loaded.diagnostics);
+      setUnmanaged(merged.loaded.unmanaged);
+      await writeConfig(merged.config, merged.loaded.stamps);
+      if (merged.conflicts.length > 0) {
+        setStatusMessage(`Merged and saved; kept your values for ${merged.conflicts.join(", ")}`);
+      }
+    } catch (error) {
+      console.error("Failed to merge config:", error);
+      setStatusMessage(`Error merging config: ${error}`);
+    }
+  }
+
   async function resetToDefaults() {
     try {
       const defaultConfig = await invoke<AppearanceConfig>("get_default_config");
@@ -205,19 +287,74 @@ function App() {
 
       {/* Status bar */}
       {statusMessage && (
-        <div className={`status-bar ${parseErrors.length > 0 ? "warning" : ""}`}>
+        <div className={`status-bar ${diagnostics.length > 0 ? "warning" : ""}`}>
           {statusMessage}
         </div>
       )}
 
-      {/* Parse errors */}
-      {parseErrors.length > 0 && (
+      {/* Files edited elsewhere since loading */}
+      {externalChanges.length > 0 && (
+        <div className="parse-errors external-changes">
+          <p>
+            Changed on disk since it was loaded: <code>{externalChanges.join(", ")}</code>
+          </p>
+          <button onClick={() => loadConfig()} disabled={saving}>
+            Reload (discard my changes)
+          </button>
+          <button onClick={() => writeConfig(config, null)} disabled={saving}>
+            Overwrite
+          </button>
+          <button onClick={mergeAndSave} disabled={saving} className="primary">
+            Merge and save
+          </button>
+          <button onClick={() => setExternalChanges([])} disabled={saving}>
+            Cancel
+          </button>
+        </div>
+      )}
+
+      {/* Parse diagnostics */}
+      {diagnostics.length > 0 && (
         <div className="parse-errors">
           <details>
-            <summary>Parse warnings ({parseErrors.length})</summary>
+            <summary>Parse warnings ({diagnostics.length})</summary>
             <ul>
-              {parseErrors.map((err, i) => (
-                <li key={i}>{err}</li>
+              {diagnostics.map((d, i) => (
+                <li key={i} className={d.severity}>
+                  <code>{formatLocation(d)}</code> {d.message}
+                </li>
+              ))}
+            </ul>
+          </details>
+        </div>
+      )}
+
+      {/* Settings that would be rejected on save */}
+      {!validation.valid && (
+        <div className="parse-errors validation-errors">
+          <details open>
+            <summary>Invalid settings ({validation.errors.length}) - fix these to save</summary>
+            <ul>
+              {validation.errors.map((e, i) => (
+                <li key={i}>
+                  <code>{e.field}</code> {e.message} (got <code>{e.value}</code>)
+                </li>
+              ))}
+            </ul>
+          </details>
+        </div>
+      )}
+
+      {/* Lua the settings leave alone */}
+      {unmanaged.length > 0 && (
+        <div className="unmanaged">
+          <details>
+            <summary>Unmanaged Lua ({unmanaged.length}) - kept as written on save</summary>
+            <ul>
+              {unmanaged.map((u, i) => (
+                <li key={i}>
+                  <code>{formatLocation(u)}</code> {u.kind.replace("_", " ")} <strong>{u.name}</strong>
+                </li>
               ))}
             </ul>
           </details>
@@ -290,12 +427,26 @@ function App() {
         <button onClick={resetToDefaults} disabled={saving}>
           Reset to Defaults
         </button>
-        <button onClick={loadConfig} disabled={saving}>
+        <button onClick={() => loadConfig()} disabled={saving}>
           Reload
         </button>
+        <label title="Run wezterm.lua in a sandboxed Lua VM to read values built by loops and helpers">
+          <input
+            type="checkbox"
+            checked={importMode === "evaluate"}
+            disabled={saving || hasChanges}
+            onChange={(e) => {
+              const mode: ImportMode = e.target.checked ? "evaluate" : "static";
+              setImportMode(mode);
+              loadConfig(mode);
+            }}
+          />
+          Run config
+        </label>
         <button
           onClick={saveConfig}
-          disabled={saving || !hasChanges}
+          disabled={saving || !hasChanges || !validation.valid}
+          title={validation.valid ? undefined : "Fix the invalid settings first"}
           className="primary"
         >
           {saving ? "Saving..." : hasChanges ? "Save Changes" : "Saved"}
@@ -1138,20 +1289,11 @@ function BackdropsPanel({ config, updateConfig, backdropImages, setBackdropImage
         </div>
 
         <div className="form-group">
-          <label>Focus Color (solid background when focus mode is on)</label>
-          <div className="color-input-row">
-            <input
-              type="color"
-              value={config.backdrop.focus_color.startsWith("#") ? config.backdrop.focus_color : "#000000"}
-              onChange={(e) => updateConfig("backdrop", { focus_color: e.target.value })}
-            />
-            <input
-              type="text"
-              value={config.backdrop.focus_color}
-              onChange={(e) => updateConfig("backdrop", { focus_color: e.target.value })}
-              placeholder="#000000"
-            />
-          </div>
+          <ColorInput
+            label="Focus Color (solid background when focus mode is on)"
+            value={config.backdrop.focus_color}
+            onChange={(focus_color) => updateConfig("backdrop", { focus_color })}
+          />
         </div>
       </div>
 
@@ -1382,22 +1524,54 @@ interface ColorInputProps {
 }
 
 function ColorInput({ label, value, onChange }: ColorInputProps) {
+  // What is typed; only text the backend accepts as a color reaches the config
+  const [draft, setDraft] = useState(value);
+  const [hex, setHex] = useState("#000000");
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    setDraft(value);
+    checkColor(value);
+  }, [value]);
+
+  async function checkColor(text: string): Promise<boolean> {
+    try {
+      const parsed = await invoke<string>("parse_color", { text });
+      setHex(parsed.slice(0, 7));
+      setError(null);
+      return true;
+    } catch (error) {
+      setError(String(error));
+      return false;
+    }
+  }
+
+  async function update(text: string) {
+    setDraft(text);
+    if (await checkColor(text)) {
+      onChange(text);
+    }
+  }
+
   return (
     <div className="color-input">
       <label>{label}</label>
       <div className="color-input-row">
         <input
           type="color"
-          value={value.startsWith("#") ? value : "#000000"}
-          onChange={(e) => onChange(e.target.value)}
+          value={hex}
+          onChange={(e) => update(e.target.value)}
         />
         <input
           type="text"
-          value={value}
-          onChange={(e) => onChange(e.target.value)}
+          value={draft}
+          className={error ? "invalid" : undefined}
+          title={error ?? undefined}
+          onChange={(e) => update(e.target.value)}
           placeholder="#000000"
         />
       </div>
+      {error && <span className="color-error">{error}</span>}
     </div>
   );
 }
@@ -5,14 +5,18 @@
 // Color Types
 // ============================================================================
 
+// A color as written in the config: #rrggbb, #rgb, #rrggbbaa, rgb(), rgba(),
+// hsl() or a color name. The backend rejects anything else.
+export type Color = string;
+
 export interface TabColors {
-  bg_color: string;
-  fg_color: string;
+  bg_color: Color;
+  fg_color: Color;
   italic?: boolean;
 }
 
 export interface TabBarColors {
-  background: string;
+  background: Color;
   active_tab: TabColors;
   inactive_tab: TabColors;
   inactive_tab_hover: TabColors;
@@ -22,25 +26,25 @@ export interface TabBarColors {
 
 export interface ColorScheme {
   // Core colors
-  foreground: string;
-  background: string;
-  cursor_bg: string;
-  cursor_border: string;
-  cursor_fg: string;
-  selection_bg: string;
-  selection_fg: string;
+  foreground: Color;
+  background: Color;
+  cursor_bg: Color;
+  cursor_border: Color;
+  cursor_fg: Color;
+  selection_bg: Color;
+  selection_fg: Color;
 
   // ANSI palette (16 colors)
-  ansi: [string, string, string, string, string, string, string, string];
-  brights: [string, string, string, string, string, string, string, string];
+  ansi: [Color, Color, Color, Color, Color, Color, Color, Color];
+  brights: [Color, Color, Color, Color, Color, Color, Color, Color];
 
   // Tab bar colors
   tab_bar: TabBarColors;
 
   // Optional
-  visual_bell?: string;
-  scrollbar_thumb?: string;
-  split?: string;
+  visual_bell?: Color;
+  scrollbar_thumb?: Color;
+  split?: Color;
 }
 
 // ============================================================================
@@ -143,7 +147,7 @@ export interface BackdropConfig {
   images_dir: string;
   images: string[];
   current_index: number;
-  focus_color: string;
+  focus_color: Color;
   overlay_opacity: number;
   random_on_start: boolean;
 }
@@ -153,8 +157,8 @@ export interface BackdropConfig {
 // ============================================================================
 
 export interface CommandPaletteConfig {
-  fg_color: string;
-  bg_color: string;
+  fg_color: Color;
+  bg_color: Color;
   font_size: number;
 }
 
@@ -232,6 +236,20 @@ export interface SaveResult {
   config_dir: string;
 }
 
+// A config file as it was when loaded; saves check it hasn't changed since
+export interface FileStamp {
+  path: string;
+  hash: string | null;
+  modified: number | null;
+}
+
+export interface SettingsModuleStatus {
+  config_path: string;
+  module_path: string;
+  hook_installed: boolean;
+  module_exists: boolean;
+}
+
 export interface ValidationError {
   field: string;
   message: string;
@@ -243,6 +261,28 @@ export interface ValidationResult {
   errors: ValidationError[];
 }
 
+export interface Diagnostic {
+  file: string;
+  line: number | null;
+  column: number | null;
+  severity: 'error' | 'warning';
+  key: string | null;
+  message: string;
+}
+
+// How values are read: 'evaluate' runs the config in a sandboxed Lua VM
+export type ImportMode = 'static' | 'evaluate';
+
+export type UnmanagedKind = 'setting' | 'event_handler' | 'plugin' | 'function';
+
+// Lua the settings don't manage; saves keep it as written
+export interface UnmanagedItem {
+  kind: UnmanagedKind;
+  name: string;
+  file: string;
+  line: number;
+}
+
 export interface SystemInfo {
   platform: 'windows' | 'macos' | 'linux';
   config_dir: string;
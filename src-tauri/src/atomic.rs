@@ -0,0 +1,307 @@
+// Atomic writes - replacing config files without ever leaving half of one
+//
+// A save writes the new content to a temporary file in the same directory,
+// flushes it to disk, and renames it over the old file, so WezTerm (which
+// reloads on change) and a crash mid-save only ever see the old file or the
+// new one. Symlinks are followed first: a wezterm.lua linked into a dotfiles
+// repo or a stow tree is replaced at its real location and the link stays a
+// link. The new file keeps the old one's permissions and, where it can, its
+// owner. When it can't (the file belongs to someone else, or its directory
+// isn't writable) the file is overwritten in place instead.
+
+use anyhow::{anyhow, Context, Result};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Symlinks followed before giving up, as the OS does
+const MAX_LINKS: usize = 40;
+
+/// Replace the file at `path` with `content`, following symlinks to the
+/// file they point at. Returns the path actually written. Read-only files
+/// are refused with advice on what to do instead.
+pub fn write_atomic(path: &Path, content: &str) -> Result<PathBuf> {
+    let target = resolve_symlinks(path)?;
+    let existing = match fs::metadata(&target) {
+        Ok(metadata) => Some(metadata),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => return Err(write_error(&target, path, e)),
+    };
+    if existing.as_ref().is_some_and(|m| m.permissions().readonly()) {
+        return Err(anyhow!(
+            "{} is read-only, so it can't be saved. {}",
+            describe(&target, path),
+            READ_ONLY_ADVICE
+        ));
+    }
+    let dir = match target.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+
+    match replace(&target, dir, content, existing.as_ref()) {
+        Ok(()) => Ok(target),
+        // No temporary file allowed in the directory, or the owner can't
+        // be kept: write the file where it is
+        Err(Fallback::InPlace) => write_in_place(&target, content)
+            .map(|()| target.clone())
+            .map_err(|e| write_error(&target, path, e)),
+        Err(Fallback::Failed(e)) => Err(write_error(&target, path, e)),
+    }
+}
+
+enum Fallback {
+    InPlace,
+    Failed(io::Error),
+}
+
+/// Write `content` to a temporary file next to `target` and rename it over
+/// `target`, keeping `existing`'s permissions and owner
+fn replace(target: &Path, dir: &Path, content: &str, existing: Option<&fs::Metadata>) -> Result<(), Fallback> {
+    let (temp, mut file) = match create_temp(dir, target) {
+        Ok(created) => created,
+        Err(e) if e.kind() == io::ErrorKind::PermissionDenied && existing.is_some() => {
+            return Err(Fallback::InPlace)
+        }
+        Err(e) => return Err(Fallback::Failed(e)),
+    };
+
+    let result = (|| -> io::Result<bool> {
+        file.write_all(content.as_bytes())?;
+        if let Some(metadata) = existing {
+            fs::set_permissions(&temp, metadata.permissions())?;
+            if !keep_owner(&temp, metadata) {
+                return Ok(false);
+            }
+        }
+        file.sync_all()?;
+        drop(file);
+        fs::rename(&temp, target)?;
+        sync_dir(dir);
+        Ok(true)
+    })();
+
+    match result {
+        Ok(true) => Ok(()),
+        Ok(false) => {
+            let _ = fs::remove_file(&temp);
+            Err(Fallback::InPlace)
+        }
+        Err(e) => {
+            let _ = fs::remove_file(&temp);
+            Err(Fallback::Failed(e))
+        }
+    }
+}
+
+/// Create `.<name>.tmp<pid>-<n>` in `dir`, picking a name nothing else uses
+fn create_temp(dir: &Path, target: &Path) -> io::Result<(PathBuf, File)> {
+    let name = target
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "wezterm.lua".to_string());
+    let mut n = 0;
+    loop {
+        let temp = dir.join(format!(".{}.tmp{}-{}", name, std::process::id(), n));
+        match OpenOptions::new().write(true).create_new(true).open(&temp) {
+            Ok(file) => return Ok((temp, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists && n < 100 => n += 1,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Give `temp` the owner and group of the file it replaces. Returns false
+/// when that isn't allowed, so renaming would change who owns the config.
+#[cfg(unix)]
+fn keep_owner(temp: &Path, metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match fs::metadata(temp) {
+        Ok(created) if created.uid() == metadata.uid() && created.gid() == metadata.gid() => true,
+        _ => std::os::unix::fs::chown(temp, Some(metadata.uid()), Some(metadata.gid())).is_ok(),
+    }
+}
+
+#[cfg(not(unix))]
+fn keep_owner(_temp: &Path, _metadata: &fs::Metadata) -> bool {
+    true
+}
+
+/// Flush the rename itself to disk. Not every platform can open a
+/// directory, and the file is already written, so failures are ignored.
+fn sync_dir(dir: &Path) {
+    if let Ok(dir) = File::open(dir) {
+        let _ = dir.sync_all();
+    }
+}
+
+fn write_in_place(target: &Path, content: &str) -> io::Result<()> {
+    let mut file = OpenOptions::new().write(true).truncate(true).open(target)?;
+    file.write_all(content.as_bytes())?;
+    file.sync_all()
+}
+
+/// The file `path` ends up at once every symlink is followed. The target
+/// doesn't have to exist yet, so a link to a file about to be created is
+/// followed too.
+pub fn resolve_symlinks(path: &Path) -> Result<PathBuf> {
+    let mut current = path.to_path_buf();
+    for _ in 0..MAX_LINKS {
+        match fs::symlink_metadata(&current) {
+            Ok(metadata) if metadata.file_type().is_symlink() => {
+                let link = fs::read_link(&current)
+                    .with_context(|| format!("Failed to read the symlink {}", current.display()))?;
+                current = match current.parent() {
+                    Some(parent) if link.is_relative() => parent.join(link),
+                    _ => link,
+                };
+            }
+            _ => return Ok(current),
+        }
+    }
+    Err(anyhow!("{} is a symlink loop", path.display()))
+}
+
+const READ_ONLY_ADVICE: &str = "If you manage it with another tool (home-manager, a read-only dotfiles \
+     checkout), change the settings there; otherwise make it writable with `chmod u+w`.";
+
+/// `wezterm.lua` or `dotfiles/wezterm.lua (linked from wezterm.lua)`
+fn describe(target: &Path, path: &Path) -> String {
+    if target == path {
+        target.display().to_string()
+    } else {
+        format!("{} (linked from {})", target.display(), path.display())
+    }
+}
+
+fn write_error(target: &Path, path: &Path, error: io::Error) -> anyhow::Error {
+    let file = describe(target, path);
+    if error.kind() == io::ErrorKind::PermissionDenied {
+        let dir = target.parent().unwrap_or(Path::new("."));
+        anyhow!(
+            "Permission denied writing {}. Check that you own it and its folder \
+             (`ls -ld {} {}`), or save to a config directory you can write with --config-dir.",
+            file,
+            target.display(),
+            dir.display()
+        )
+    } else {
+        anyhow!(error).context(format!("Failed to write {}", file))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_config_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "wezterm-settings-atomic-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// File names in `dir`, to check no temporary file is left behind
+    fn names(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_write_atomic_creates_and_replaces() {
+        let dir = temp_config_dir("replace");
+        let file = dir.join("wezterm.lua");
+
+        assert_eq!(write_atomic(&file, "return {}\n").unwrap(), file);
+        assert_eq!(fs::read_to_string(&file).unwrap(), "return {}\n");
+        write_atomic(&file, "return { font_size = 14 }\n").unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "return { font_size = 14 }\n");
+        assert_eq!(names(&dir), ["wezterm.lua"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_atomic_writes_through_symlinks() {
+        use std::os::unix::fs::symlink;
+
+        let dir = temp_config_dir("symlink");
+        fs::create_dir_all(dir.join("dotfiles/wezterm")).unwrap();
+        fs::create_dir_all(dir.join("config")).unwrap();
+        let real = dir.join("dotfiles/wezterm/wezterm.lua");
+        fs::write(&real, "-- old\n").unwrap();
+        // Stow-style relative link, through a linked directory
+        symlink("../dotfiles/wezterm", dir.join("config/wezterm")).unwrap();
+        let link = dir.join("wezterm.lua");
+        symlink("config/wezterm/wezterm.lua", &link).unwrap();
+
+        let written = write_atomic(&link, "-- new\n").unwrap();
+        assert_eq!(fs::canonicalize(&written).unwrap(), fs::canonicalize(&real).unwrap());
+        assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read_to_string(&real).unwrap(), "-- new\n");
+        assert_eq!(names(&dir.join("dotfiles/wezterm")), ["wezterm.lua"]);
+
+        // A link to a file that doesn't exist yet creates it
+        let dangling = dir.join("new.lua");
+        symlink("dotfiles/wezterm/new.lua", &dangling).unwrap();
+        write_atomic(&dangling, "return {}\n").unwrap();
+        assert_eq!(fs::read_to_string(dir.join("dotfiles/wezterm/new.lua")).unwrap(), "return {}\n");
+
+        // Loops are reported rather than followed forever
+        symlink("loop-b.lua", dir.join("loop-a.lua")).unwrap();
+        symlink("loop-a.lua", dir.join("loop-b.lua")).unwrap();
+        let err = write_atomic(&dir.join("loop-a.lua"), "").unwrap_err();
+        assert!(err.to_string().contains("symlink loop"), "{}", err);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_atomic_keeps_permissions() {
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+        let dir = temp_config_dir("permissions");
+        let file = dir.join("wezterm.lua");
+        fs::write(&file, "-- old\n").unwrap();
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o640)).unwrap();
+        let before = fs::metadata(&file).unwrap();
+
+        write_atomic(&file, "-- new\n").unwrap();
+        let after = fs::metadata(&file).unwrap();
+        assert_eq!(after.permissions().mode() & 0o777, 0o640);
+        assert_eq!((after.uid(), after.gid()), (before.uid(), before.gid()));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_atomic_refuses_read_only_files() {
+        let dir = temp_config_dir("read-only");
+        let file = dir.join("wezterm.lua");
+        fs::write(&file, "-- managed elsewhere\n").unwrap();
+        let mut permissions = fs::metadata(&file).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&file, permissions.clone()).unwrap();
+
+        let err = write_atomic(&file, "-- new\n").unwrap_err().to_string();
+        assert!(err.contains("is read-only"), "{}", err);
+        assert!(err.contains("chmod u+w"), "{}", err);
+        assert_eq!(fs::read_to_string(&file).unwrap(), "-- managed elsewhere\n");
+        assert_eq!(names(&dir), ["wezterm.lua"]);
+
+        #[allow(clippy::permissions_set_readonly_false)]
+        permissions.set_readonly(false);
+        fs::set_permissions(&file, permissions).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,405 @@
+// Backups - the copies a save keeps of the files it replaces
+//
+// Before a save changes a file it copies it to
+// `<name>.backup.YYYYMMDD_HHMMSS`, next to the file or, when the config
+// directory has a `.backups/` folder, under that folder at the file's path
+// relative to the config directory (`.backups/config/fonts.lua.backup.…`).
+// This module finds those copies again, compares them with the current
+// files, restores them and prunes old ones.
+
+use anyhow::{bail, Context, Result};
+use chrono::{Duration, Local, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::atomic::write_atomic;
+use crate::diff::FileDiff;
+use crate::models::SaveResult;
+
+/// Folder in the config directory that holds backups when it exists
+pub const BACKUP_FOLDER: &str = ".backups";
+
+const STAMP_FORMAT: &str = "%Y%m%d_%H%M%S";
+
+/// Directory levels searched below the config directory
+/// (`config/fonts.lua`, `.backups/config/fonts.lua`)
+const MAX_DEPTH: usize = 3;
+
+/// A backup of a config file
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BackupInfo {
+    pub path: String,
+    /// File name of the backup (`wezterm.lua.backup.20240102_030405`)
+    pub name: String,
+    /// The file it is a copy of
+    pub original: String,
+    /// When it was taken, in local time (`2024-01-02 03:04:05`)
+    pub created: String,
+    /// Size in bytes
+    pub size: u64,
+    #[serde(skip)]
+    timestamp: NaiveDateTime,
+}
+
+/// Which backups pruning keeps. A backup is kept when either rule keeps
+/// it; rules are applied to each file's backups separately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    /// Keep this many of the newest backups of each file
+    pub keep_last: Option<usize>,
+    /// Keep every backup taken within this many days
+    pub keep_days: Option<u32>,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            keep_last: Some(10),
+            keep_days: Some(30),
+        }
+    }
+}
+
+/// Copy `file` to a new timestamped backup and return its path. Backups go
+/// under `.backups/` when `config_dir` has that folder, otherwise next to
+/// the file.
+pub fn create_backup(file: &Path, config_dir: &Path) -> Result<PathBuf> {
+    let file_name = file
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "wezterm.lua".to_string());
+    let dir = backup_dir_for(file, config_dir);
+    fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+
+    let stamp = Local::now().format(STAMP_FORMAT).to_string();
+    let mut backup_path = dir.join(format!("{}.backup.{}", file_name, stamp));
+    // Two saves within a second mustn't overwrite each other's backup
+    let mut n = 2;
+    while backup_path.exists() {
+        backup_path = dir.join(format!("{}.backup.{}-{}", file_name, stamp, n));
+        n += 1;
+    }
+    fs::copy(file, &backup_path).context("Failed to create backup")?;
+    Ok(backup_path)
+}
+
+fn backup_dir_for(file: &Path, config_dir: &Path) -> PathBuf {
+    let folder = config_dir.join(BACKUP_FOLDER);
+    let parent = file.parent().unwrap_or(config_dir);
+    match parent.strip_prefix(config_dir) {
+        Ok(relative) if folder.is_dir() => folder.join(relative),
+        _ => parent.to_path_buf(),
+    }
+}
+
+/// Every backup in the config directory, its module folders and
+/// `.backups/`, newest first
+pub fn list_backups(config_dir: &Path) -> Vec<BackupInfo> {
+    let mut backups = Vec::new();
+    collect_backups(config_dir, config_dir, 0, &mut backups);
+    backups.sort_by(|a, b| b.timestamp.cmp(&a.timestamp).then_with(|| b.name.cmp(&a.name)));
+    backups
+}
+
+fn collect_backups(config_dir: &Path, dir: &Path, depth: usize, backups: &mut Vec<BackupInfo>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let path = entry.path();
+        if file_type.is_dir() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let searched = !name.starts_with('.') || (depth == 0 && name == BACKUP_FOLDER);
+            if searched && depth < MAX_DEPTH {
+                collect_backups(config_dir, &path, depth + 1, backups);
+            }
+        } else if file_type.is_file() {
+            if let Some(backup) = backup_info(config_dir, &path) {
+                backups.push(backup);
+            }
+        }
+    }
+}
+
+/// The backup at `path`, if its name is a backup name
+fn backup_info(config_dir: &Path, path: &Path) -> Option<BackupInfo> {
+    let name = path.file_name()?.to_str()?;
+    let (original_name, stamp) = name.rsplit_once(".backup.")?;
+    // A suffix tells apart backups taken within the same second
+    let stamp = match stamp.split_once('-') {
+        Some((stamp, n)) if !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()) => stamp,
+        Some(_) => return None,
+        None => stamp,
+    };
+    if original_name.is_empty() {
+        return None;
+    }
+    let timestamp = NaiveDateTime::parse_from_str(stamp, STAMP_FORMAT).ok()?;
+
+    let parent = path.parent()?;
+    let original = match parent.strip_prefix(config_dir.join(BACKUP_FOLDER)) {
+        Ok(relative) => config_dir.join(relative).join(original_name),
+        Err(_) => parent.join(original_name),
+    };
+    Some(BackupInfo {
+        path: path.to_string_lossy().to_string(),
+        name: name.to_string(),
+        original: original.to_string_lossy().to_string(),
+        created: timestamp.format("%Y-%m-%d %H:%M:%S").to_string(),
+        size: fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+        timestamp,
+    })
+}
+
+/// The backup `query` names: its path, its file name, or its place in
+/// `list_backups` (1 is the newest)
+pub fn find_backup(config_dir: &Path, query: &str) -> Result<BackupInfo> {
+    let backups = list_backups(config_dir);
+    if let Ok(n) = query.parse::<usize>() {
+        if let Some(backup) = n.checked_sub(1).and_then(|i| backups.get(i)) {
+            return Ok(backup.clone());
+        }
+    }
+    let query_path = Path::new(query);
+    backups
+        .into_iter()
+        .find(|b| b.name == query || Path::new(&b.path) == query_path || Path::new(&b.path).ends_with(query_path))
+        .with_context(|| format!("No backup named {}", query))
+}
+
+/// What restoring `backup` would change: the current file against the
+/// backup's content
+pub fn diff_backup(backup: &BackupInfo) -> Result<FileDiff> {
+    let content = fs::read_to_string(&backup.path)
+        .with_context(|| format!("Failed to read {}", backup.path))?;
+    let current = read_optional(Path::new(&backup.original))?;
+    Ok(FileDiff::new(&backup.original, current.as_deref(), &content))
+}
+
+/// Put `backup` back in place of its file. The current file is backed up
+/// first, so a restore can itself be undone; nothing is written when the
+/// file already matches the backup.
+pub fn restore_backup(backup: &BackupInfo, config_dir: &Path) -> Result<SaveResult> {
+    // Read before backing up the current file, which may add to the same folder
+    let content = fs::read_to_string(&backup.path)
+        .with_context(|| format!("Failed to read {}", backup.path))?;
+    let original = Path::new(&backup.original);
+    let mut result = SaveResult {
+        success: true,
+        files_written: vec![],
+        backups_created: vec![],
+        config_dir: config_dir.to_string_lossy().to_string(),
+    };
+
+    let current = read_optional(original)?;
+    if current.as_deref() == Some(content.as_str()) {
+        return Ok(result);
+    }
+    if current.is_some() {
+        let backup_path = create_backup(original, config_dir)?;
+        result.backups_created.push(backup_path.to_string_lossy().to_string());
+    }
+    if let Some(parent) = original.parent() {
+        fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    write_atomic(original, &content)?;
+    result.files_written.push(backup.original.clone());
+    Ok(result)
+}
+
+/// The backups in `backups` (newest first) that `policy` doesn't keep,
+/// as of `now`
+pub fn backups_to_prune(backups: &[BackupInfo], policy: &RetentionPolicy, now: NaiveDateTime) -> Vec<BackupInfo> {
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    backups
+        .iter()
+        .filter(|backup| {
+            let rank = seen.entry(backup.original.as_str()).or_default();
+            *rank += 1;
+            let by_count = policy.keep_last.is_some_and(|keep| *rank <= keep);
+            let by_age = policy
+                .keep_days
+                .is_some_and(|days| now - backup.timestamp < Duration::days(days.into()));
+            !by_count && !by_age
+        })
+        .cloned()
+        .collect()
+}
+
+/// Delete the backups `policy` doesn't keep and return them. With
+/// `dry_run` nothing is deleted.
+pub fn prune_backups(config_dir: &Path, policy: &RetentionPolicy, dry_run: bool) -> Result<Vec<BackupInfo>> {
+    let pruned = backups_to_prune(&list_backups(config_dir), policy, Local::now().naive_local());
+    if !dry_run {
+        for backup in &pruned {
+            fs::remove_file(&backup.path).with_context(|| format!("Failed to remove {}", backup.path))?;
+        }
+    }
+    Ok(pruned)
+}
+
+/// Create `.backups/` in the config directory, so later backups go there,
+/// and move the existing backups into it. Returns the new paths.
+pub fn use_backup_folder(config_dir: &Path) -> Result<Vec<PathBuf>> {
+    let folder = config_dir.join(BACKUP_FOLDER);
+    fs::create_dir_all(&folder).with_context(|| format!("Failed to create {}", folder.display()))?;
+
+    let mut moved = Vec::new();
+    for backup in list_backups(config_dir) {
+        let path = PathBuf::from(&backup.path);
+        if path.starts_with(&folder) {
+            continue;
+        }
+        let target = backup_dir_for(Path::new(&backup.original), config_dir).join(&backup.name);
+        if target.exists() {
+            bail!("{} already exists", target.display());
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        fs::rename(&path, &target).with_context(|| format!("Failed to move {}", path.display()))?;
+        moved.push(target);
+    }
+    Ok(moved)
+}
+
+/// A backup size for display (`512 B`, `1.5 KB`, `2.0 MB`)
+pub fn format_size(bytes: u64) -> String {
+    match bytes {
+        0..=1023 => format!("{} B", bytes),
+        1024..=1_048_575 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MB", bytes as f64 / 1_048_576.0),
+    }
+}
+
+fn read_optional(path: &Path) -> Result<Option<String>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    fs::read_to_string(path)
+        .map(Some)
+        .with_context(|| format!("Failed to read {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_config_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "wezterm-settings-backups-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_list_and_restore_backups() {
+        let dir = temp_config_dir("restore");
+        let config_file = dir.join("wezterm.lua");
+        fs::write(&config_file, "return { font_size = 14 }\n").unwrap();
+        fs::write(dir.join("wezterm.lua.backup.20240101_120000"), "return { font_size = 12 }\n").unwrap();
+        fs::write(dir.join("wezterm.lua.backup.20240102_120000"), "return { font_size = 13 }\n").unwrap();
+        fs::write(dir.join("wezterm.lua.backup.notes"), "").unwrap();
+
+        let backups = list_backups(&dir);
+        assert_eq!(backups.len(), 2);
+        assert_eq!(backups[0].name, "wezterm.lua.backup.20240102_120000");
+        assert_eq!(backups[0].created, "2024-01-02 12:00:00");
+        assert_eq!(backups[0].original, config_file.to_string_lossy());
+        assert_eq!(backups[1].size, 26);
+        assert_eq!(find_backup(&dir, "2").unwrap(), backups[1]);
+
+        let diff = diff_backup(&backups[1]).unwrap().to_unified();
+        assert!(diff.contains("-return { font_size = 14 }\n+return { font_size = 12 }\n"));
+
+        // Restoring backs up the file it replaces
+        let result = restore_backup(&backups[1], &dir).unwrap();
+        assert_eq!(fs::read_to_string(&config_file).unwrap(), "return { font_size = 12 }\n");
+        assert_eq!(result.backups_created.len(), 1);
+        assert_eq!(
+            fs::read_to_string(&result.backups_created[0]).unwrap(),
+            "return { font_size = 14 }\n"
+        );
+        assert_eq!(list_backups(&dir).len(), 3);
+        assert!(restore_backup(&backups[1], &dir).unwrap().files_written.is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_backup_folder() {
+        let dir = temp_config_dir("folder");
+        fs::create_dir_all(dir.join("config")).unwrap();
+        fs::write(dir.join("wezterm.lua"), "return {}\n").unwrap();
+        fs::write(dir.join("config/fonts.lua"), "return {}\n").unwrap();
+        fs::write(dir.join("config/fonts.lua.backup.20240101_120000"), "-- old\n").unwrap();
+        assert_eq!(backup_dir_for(&dir.join("wezterm.lua"), &dir), dir);
+
+        let moved = use_backup_folder(&dir).unwrap();
+        assert_eq!(moved, vec![dir.join(".backups/config/fonts.lua.backup.20240101_120000")]);
+        let backups = list_backups(&dir);
+        assert_eq!(backups.len(), 1);
+        assert_eq!(backups[0].original, dir.join("config/fonts.lua").to_string_lossy());
+
+        // New backups go to the folder, and one taken in the same second
+        // doesn't replace the last
+        let first = create_backup(&dir.join("wezterm.lua"), &dir).unwrap();
+        let second = create_backup(&dir.join("wezterm.lua"), &dir).unwrap();
+        assert_eq!(first.parent(), Some(dir.join(".backups").as_path()));
+        assert_ne!(first, second);
+        assert_eq!(list_backups(&dir).len(), 3);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_retention_policy() {
+        let dir = temp_config_dir("prune");
+        for day in 1..=5 {
+            fs::write(dir.join(format!("wezterm.lua.backup.202401{:02}_120000", day)), "").unwrap();
+        }
+        fs::write(dir.join("settings_ui.lua.backup.20240101_120000"), "").unwrap();
+        let backups = list_backups(&dir);
+        let now = NaiveDateTime::parse_from_str("20240106_120000", STAMP_FORMAT).unwrap();
+        let names = |pruned: Vec<BackupInfo>| pruned.into_iter().map(|b| b.name).collect::<Vec<_>>();
+
+        // Counted per file
+        let keep_two = RetentionPolicy { keep_last: Some(2), keep_days: None };
+        assert_eq!(
+            names(backups_to_prune(&backups, &keep_two, now)),
+            [
+                "wezterm.lua.backup.20240103_120000",
+                "wezterm.lua.backup.20240102_120000",
+                "wezterm.lua.backup.20240101_120000",
+            ]
+        );
+
+        // Either rule keeps a backup
+        let policy = RetentionPolicy { keep_last: Some(1), keep_days: Some(3) };
+        assert_eq!(
+            names(backups_to_prune(&backups, &policy, now)),
+            [
+                "wezterm.lua.backup.20240103_120000",
+                "wezterm.lua.backup.20240102_120000",
+                "wezterm.lua.backup.20240101_120000",
+            ]
+        );
+
+        let pruned = prune_backups(&dir, &keep_two, true).unwrap();
+        assert_eq!(pruned.len(), 3);
+        assert_eq!(list_backups(&dir).len(), 6);
+        prune_backups(&dir, &keep_two, false).unwrap();
+        assert_eq!(list_backups(&dir).len(), 3);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,56 @@
+// Backup commands - list, compare, restore and prune config backups
+
+use std::path::{Path, PathBuf};
+
+use crate::backups::{self, BackupInfo, RetentionPolicy};
+use crate::config::get_wezterm_config_file;
+use crate::diff::FileDiff;
+use crate::models::SaveResult;
+
+/// Directory holding the config file, where its backups are kept
+fn config_dir() -> Result<PathBuf, String> {
+    let config_file = get_wezterm_config_file(None)?;
+    Ok(config_file.parent().map(Path::to_path_buf).unwrap_or_default())
+}
+
+/// Tauri command: List backups of the config files, newest first
+#[tauri::command]
+pub fn list_config_backups() -> Result<Vec<BackupInfo>, String> {
+    Ok(backups::list_backups(&config_dir()?))
+}
+
+/// Tauri command: Show what restoring a backup would change
+/// `backup` is its path, file name or place in the list (1 is the newest).
+#[tauri::command]
+pub fn diff_config_backup(backup: String) -> Result<FileDiff, String> {
+    let backup = backups::find_backup(&config_dir()?, &backup).map_err(|e| e.to_string())?;
+    backups::diff_backup(&backup).map_err(|e| e.to_string())
+}
+
+/// Tauri command: Restore a backup, backing up the current file first
+#[tauri::command]
+pub fn restore_config_backup(backup: String) -> Result<SaveResult, String> {
+    let dir = config_dir()?;
+    let backup = backups::find_backup(&dir, &backup).map_err(|e| e.to_string())?;
+    backups::restore_backup(&backup, &dir).map_err(|e| e.to_string())
+}
+
+/// Tauri command: Delete the backups a retention policy doesn't keep
+/// `policy` defaults to keeping the newest 10 of each file and anything from
+/// the last 30 days. With `dry_run` nothing is deleted; either way the
+/// backups that go are returned.
+#[tauri::command]
+pub fn prune_config_backups(
+    policy: Option<RetentionPolicy>,
+    dry_run: Option<bool>,
+) -> Result<Vec<BackupInfo>, String> {
+    backups::prune_backups(&config_dir()?, &policy.unwrap_or_default(), dry_run.unwrap_or(false))
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command: Keep backups in a `.backups/` folder, moving the existing ones there
+#[tauri::command]
+pub fn use_config_backup_folder() -> Result<Vec<String>, String> {
+    let moved = backups::use_backup_folder(&config_dir()?).map_err(|e| e.to_string())?;
+    Ok(moved.iter().map(|p| p.to_string_lossy().to_string()).collect())
+}
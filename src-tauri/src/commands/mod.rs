@@ -1,11 +1,13 @@
 // Commands module - Tauri command handlers
 
 pub mod backdrops;
+pub mod backups;
 pub mod config;
 pub mod schemes;
 pub mod system;
 
 pub use backdrops::*;
+pub use backups::*;
 pub use config::*;
 pub use schemes::*;
 pub use system::*;
@@ -1,5 +1,6 @@
-// Color scheme commands - list available WezTerm color schemes
+// Color scheme commands - list available WezTerm color schemes and check colors
 
+use crate::models::Color;
 use serde::{Deserialize, Serialize};
 
 /// Information about a color scheme
@@ -446,3 +447,10 @@ pub fn get_builtin_color_schemes() -> Vec<ColorSchemeInfo> {
 
     schemes
 }
+
+/// Check a color as the user types it. Returns it as `#rrggbb` (or
+/// `#rrggbbaa`) for color pickers, or why it isn't a color.
+#[tauri::command]
+pub fn parse_color(text: String) -> Result<String, String> {
+    Color::parse(&text).map(|color| color.to_hex())
+}
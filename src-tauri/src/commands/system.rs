@@ -1,5 +1,6 @@
 // System commands - config path detection and system information
 
+use crate::atomic::write_atomic;
 use crate::config::{get_wezterm_config_dir, get_wezterm_config_file};
 use crate::models::{Platform, SystemInfo};
 
@@ -49,7 +50,7 @@ fn get_platform() -> Platform {
 /// Tauri command: Get the WezTerm config directory path
 #[tauri::command]
 pub fn get_config_path() -> Result<String, String> {
-    get_wezterm_config_dir()
+    get_wezterm_config_dir(None)
         .map(|p| p.to_string_lossy().to_string())
 }
 
@@ -57,7 +58,7 @@ pub fn get_config_path() -> Result<String, String> {
 /// Returns the path to the config file.
 #[tauri::command]
 pub fn ensure_config_exists() -> Result<String, String> {
-    let config_file = get_wezterm_config_file()?;
+    let config_file = get_wezterm_config_file(None)?;
 
     if !config_file.exists() {
         // Create default config
@@ -71,7 +72,7 @@ return config
             std::fs::create_dir_all(parent)
                 .map_err(|e| e.to_string())?;
         }
-        std::fs::write(&config_file, default_config)
+        write_atomic(&config_file, default_config)
             .map_err(|e| e.to_string())?;
     }
 
@@ -82,7 +83,7 @@ return config
 /// Tauri command: Get system information
 #[tauri::command]
 pub fn get_system_info() -> Result<SystemInfo, String> {
-    let config_dir = get_wezterm_config_dir()?;
+    let config_dir = get_wezterm_config_dir(None)?;
 
     let config_exists = config_dir.exists() && config_dir.join("wezterm.lua").exists();
 
@@ -101,7 +102,7 @@ mod tests {
     #[test]
     fn test_get_wezterm_config_dir() {
         // Should return Some path (won't necessarily exist in test env)
-        let result = get_wezterm_config_dir();
+        let result = get_wezterm_config_dir(None);
         assert!(result.is_ok());
     }
 
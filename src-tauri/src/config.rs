@@ -1,14 +1,35 @@
 // Config loading and saving
 
 use anyhow::{Context, Result};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use crate::lua::parser::parse_wezterm_config;
+use crate::atomic::write_atomic;
+use crate::backups::create_backup;
+use crate::conflict::{changed_files, merge_configs, FileStamp};
+use crate::lua::editor::{
+    apply_settings, edits_in_place, has_settings_hook, install_settings_hook, remove_settings_hook,
+};
+use crate::lua::generator::{
+    config_settings, generate_lua_config, generate_settings_module, LuaSetting, SETTINGS_MODULE,
+};
+use crate::lua::parser::{parse_lua_content, parse_wezterm_config, read_wezterm_config};
+use crate::validation::{format_errors, validate_config};
 
 /// Finds the WezTerm configuration directory, matching WezTerm's own search order.
+/// An explicit `config_dir` (e.g. from `--config-dir`) takes precedence over everything.
 /// Returns the first existing directory, or creates the default if none exist.
-pub fn get_wezterm_config_dir() -> Result<PathBuf, String> {
+pub fn get_wezterm_config_dir(config_dir: Option<&str>) -> Result<PathBuf, String> {
+    // An explicit location beats the environment and the default search order
+    if let Some(dir) = config_dir {
+        let config_file = explicit_config_file(dir);
+        return Ok(config_file
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default());
+    }
+
     // Check for explicit override via environment
     if let Ok(config_file) = std::env::var("WEZTERM_CONFIG_FILE") {
         let path = PathBuf::from(config_file);
@@ -77,7 +98,12 @@ fn get_default_config_dir() -> Result<PathBuf, String> {
 }
 
 /// Gets the path to the main wezterm.lua config file
-pub fn get_wezterm_config_file() -> Result<PathBuf, String> {
+pub fn get_wezterm_config_file(config_dir: Option<&str>) -> Result<PathBuf, String> {
+    // An explicit location wins over WEZTERM_CONFIG_FILE, XDG and ~/.wezterm.lua
+    if let Some(dir) = config_dir {
+        return Ok(explicit_config_file(dir));
+    }
+
     // Check WEZTERM_CONFIG_FILE first
     if let Ok(config_file) = std::env::var("WEZTERM_CONFIG_FILE") {
         let path = PathBuf::from(&config_file);
@@ -86,7 +112,7 @@ pub fn get_wezterm_config_file() -> Result<PathBuf, String> {
         }
     }
 
-    let config_dir = get_wezterm_config_dir()?;
+    let config_dir = get_wezterm_config_dir(None)?;
     let config_file = config_dir.join("wezterm.lua");
 
     // Also check for ~/.wezterm.lua (WezTerm's simple config location)
@@ -102,28 +128,38 @@ pub fn get_wezterm_config_file() -> Result<PathBuf, String> {
     Ok(config_file)
 }
 
-use crate::models::AppearanceConfig;
+/// Resolves an explicit config location to the config file it names.
+/// Accepts either a directory (uses `<dir>/wezterm.lua`) or a path to a `.lua` file.
+fn explicit_config_file(location: &str) -> PathBuf {
+    let path = match location.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir()
+            .map(|h| h.join(rest))
+            .unwrap_or_else(|| PathBuf::from(location)),
+        None => PathBuf::from(location),
+    };
 
-/// Load config from disk, parsing existing wezterm.lua if it exists
-pub fn load_config(_config_dir: Option<&str>) -> Result<AppearanceConfig> {
+    let is_lua_file = path.extension().is_some_and(|ext| ext == "lua");
+    if path.is_file() || (is_lua_file && !path.is_dir()) {
+        path
+    } else {
+        path.join("wezterm.lua")
+    }
+}
+
+use crate::models::{AppearanceConfig, Diagnostic, ImportMode, SaveResult, Severity, UnmanagedItem};
+
+/// Load config from disk, reading existing wezterm.lua (as `mode` says) if it exists.
+/// Whatever can't be read is left at its default; use
+/// `load_config_with_metadata` to get the diagnostics.
+pub fn load_config(config_dir: Option<&str>, mode: ImportMode) -> Result<AppearanceConfig> {
     // Try to find and parse existing config
-    match get_wezterm_config_file() {
+    match get_wezterm_config_file(config_dir) {
         Ok(config_path) if config_path.exists() => {
-            match parse_wezterm_config(&config_path) {
-                Ok(result) => {
-                    if !result.parse_errors.is_empty() {
-                        // Log parse errors but still return partial config
-                        for err in &result.parse_errors {
-                            eprintln!("Config parse warning: {}", err);
-                        }
-                    }
-                    Ok(result.config)
-                }
-                Err(e) => {
-                    eprintln!("Failed to parse config, using defaults: {}", e);
-                    Ok(AppearanceConfig::default())
-                }
-            }
+            // Managed settings live in the module when wezterm.lua loads it
+            let parse_path = active_settings_module(&config_path).unwrap_or(config_path);
+            Ok(read_wezterm_config(&parse_path, mode)
+                .map(|result| result.config)
+                .unwrap_or_default())
         }
         _ => {
             // No config file exists, return defaults
@@ -133,25 +169,44 @@ pub fn load_config(_config_dir: Option<&str>) -> Result<AppearanceConfig> {
 }
 
 /// Load config and return additional metadata about the parse
-pub fn load_config_with_metadata(_config_dir: Option<&str>) -> Result<ConfigLoadResult> {
-    match get_wezterm_config_file() {
+pub fn load_config_with_metadata(config_dir: Option<&str>, mode: ImportMode) -> Result<ConfigLoadResult> {
+    match get_wezterm_config_file(config_dir) {
         Ok(config_path) => {
             let config_exists = config_path.exists();
             if config_exists {
-                match parse_wezterm_config(&config_path) {
-                    Ok(result) => Ok(ConfigLoadResult {
-                        config: result.config,
-                        config_path: config_path.to_string_lossy().to_string(),
-                        config_exists: true,
-                        raw_content: Some(result.raw_content),
-                        parse_errors: result.parse_errors,
-                    }),
+                let module_file = active_settings_module(&config_path);
+                let settings_module = module_file.as_ref().map(|p| p.to_string_lossy().to_string());
+                match read_wezterm_config(module_file.as_ref().unwrap_or(&config_path), mode) {
+                    Ok(result) => {
+                        let mut read = vec![config_path.clone()];
+                        read.extend(module_file.clone());
+                        read.extend(result.modules.iter().cloned());
+                        Ok(ConfigLoadResult {
+                            config: result.config,
+                            config_path: config_path.to_string_lossy().to_string(),
+                            config_exists: true,
+                            raw_content: Some(result.raw_content),
+                            diagnostics: result.diagnostics,
+                            // The hand-written Lua is in wezterm.lua, not the generated module
+                            unmanaged: match module_file {
+                                Some(_) => parse_wezterm_config(&config_path)
+                                    .map(|r| r.unmanaged)
+                                    .unwrap_or_default(),
+                                None => result.unmanaged,
+                            },
+                            settings_module,
+                            stamps: read.iter().map(|path| FileStamp::read(path)).collect(),
+                        })
+                    }
                     Err(e) => Ok(ConfigLoadResult {
                         config: AppearanceConfig::default(),
                         config_path: config_path.to_string_lossy().to_string(),
                         config_exists: true,
                         raw_content: None,
-                        parse_errors: vec![e],
+                        diagnostics: vec![Diagnostic::for_file(&config_path, Severity::Error, e)],
+                        unmanaged: vec![],
+                        settings_module,
+                        stamps: vec![FileStamp::read(&config_path)],
                     }),
                 }
             } else {
@@ -160,7 +215,11 @@ pub fn load_config_with_metadata(_config_dir: Option<&str>) -> Result<ConfigLoad
                     config_path: config_path.to_string_lossy().to_string(),
                     config_exists: false,
                     raw_content: None,
-                    parse_errors: vec![],
+                    diagnostics: vec![],
+                    unmanaged: vec![],
+                    settings_module: None,
+                    // A file created after loading is a change too
+                    stamps: vec![FileStamp::read(&config_path)],
                 })
             }
         }
@@ -175,20 +234,825 @@ pub struct ConfigLoadResult {
     pub config_path: String,
     pub config_exists: bool,
     pub raw_content: Option<String>,
-    pub parse_errors: Vec<String>,
+    /// Problems found while parsing, each pointing at a file and line
+    pub diagnostics: Vec<Diagnostic>,
+    /// Settings, event handlers, plugins and functions the UI doesn't manage;
+    /// read-only, and kept as written on save
+    pub unmanaged: Vec<UnmanagedItem>,
+    /// Path of settings_ui.lua when wezterm.lua loads it; the config and
+    /// raw content then come from the module
+    pub settings_module: Option<String>,
+    /// Every file read, as it was; pass them to `save_config_if_unchanged`
+    /// so edits made elsewhere since aren't overwritten
+    pub stamps: Vec<FileStamp>,
+}
+
+/// Save config to disk, generating wezterm.lua and backing up the existing file.
+/// `mode` is how the config was loaded, so unchanged settings are left alone.
+pub fn save_config(config: &AppearanceConfig, config_dir: Option<&str>, mode: ImportMode) -> Result<SaveResult> {
+    let config_file = get_wezterm_config_file(config_dir).map_err(|e| anyhow::anyhow!(e))?;
+    write_config_file(config, &config_file, mode)
+}
+
+/// Save config like `save_config`, unless a file in `stamps` (from
+/// `load_config_with_metadata`) changed on disk since it was loaded. Then
+/// nothing is written and the error names the changed files; reload,
+/// `merge_external_changes`, or save with `save_config` to overwrite them.
+pub fn save_config_if_unchanged(
+    config: &AppearanceConfig,
+    config_dir: Option<&str>,
+    mode: ImportMode,
+    stamps: &[FileStamp],
+) -> Result<SaveResult> {
+    let changed = changed_files(stamps);
+    if !changed.is_empty() {
+        anyhow::bail!(
+            "{} changed on disk since it was loaded; reload, merge or overwrite it before saving",
+            changed.join(", ")
+        );
+    }
+    save_config(config, config_dir, mode)
+}
+
+/// Settings edited in the UI merged with the config as it is on disk now
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ConfigMergeResult {
+    /// The merged settings; nothing is saved yet
+    pub config: AppearanceConfig,
+    /// Settings changed both in the UI and on disk; the UI's value was kept
+    pub conflicts: Vec<String>,
+    /// The config as it is on disk now, with the stamps to save the merge against
+    pub loaded: ConfigLoadResult,
 }
 
-/// Save config to disk
-pub fn save_config(config: &AppearanceConfig, _config_dir: Option<&str>) -> Result<()> {
-    let dir = get_wezterm_config_dir().map_err(|e| anyhow::anyhow!(e))?;
+/// Read the config again and merge the managed settings three ways: `base`
+/// is what was loaded before, `ours` what the UI has made of it since. Each
+/// setting keeps whichever side changed it; see `conflict::merge_configs`.
+pub fn merge_external_changes(
+    base: &AppearanceConfig,
+    ours: &AppearanceConfig,
+    config_dir: Option<&str>,
+    mode: ImportMode,
+) -> Result<ConfigMergeResult> {
+    let loaded = load_config_with_metadata(config_dir, mode)?;
+    let merged = merge_configs(base, ours, &loaded.config).map_err(anyhow::Error::msg)?;
+    Ok(ConfigMergeResult {
+        config: merged.config,
+        conflicts: merged.conflicts,
+        loaded,
+    })
+}
+
+/// Write the config to the given file.
+///
+/// A new file gets the full generated wezterm.lua. An existing file that
+/// uses the `config.<key> = value` style is edited in place so comments and
+/// custom Lua survive; only settings that changed are rewritten. The previous
+/// file is copied to `<name>.backup.YYYYMMDD_HHMMSS` before it is modified
+/// (under `.backups/` when the config directory has one), and nothing is
+/// written when no setting changed. What counts as changed is decided
+/// against the file read with `mode`.
+///
+/// Settings read from a module wezterm.lua requires (`config/appearance.lua`)
+/// are written back to that module, each file with its own backup.
+///
+/// Nothing the settings don't manage (unknown keys, event handlers, plugins,
+/// helper functions) is ever dropped: a save that would lose any fails
+/// before writing.
+///
+/// If the file loads the managed settings module, the module is regenerated
+/// instead and wezterm.lua is left alone.
+///
+/// Settings that fail validation are never written.
+pub fn write_config_file(config: &AppearanceConfig, config_file: &Path, mode: ImportMode) -> Result<SaveResult> {
+    ensure_valid(config)?;
+    let config_dir = config_file
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
 
     // Ensure directories exist
-    std::fs::create_dir_all(&dir).context("Failed to create config directory")?;
+    fs::create_dir_all(&config_dir).context("Failed to create config directory")?;
+
+    let mut result = SaveResult {
+        success: true,
+        files_written: vec![],
+        backups_created: vec![],
+        config_dir: config_dir.to_string_lossy().to_string(),
+    };
+
+    for write in plan_config_file(config, config_file, mode)? {
+        write_if_changed(&write.path, &write.content, write.backup, &config_dir, &mut result)?;
+    }
+    Ok(result)
+}
+
+/// A file a save would write
+#[derive(Debug, Clone)]
+pub struct PendingWrite {
+    pub path: PathBuf,
+    /// What the file holds now; `None` when it doesn't exist yet
+    pub existing: Option<String>,
+    pub content: String,
+    /// Whether the previous file is backed up before it is replaced
+    backup: bool,
+}
+
+/// The files `save_config` would change and what it would write to each,
+/// without writing anything
+pub fn preview_save(config: &AppearanceConfig, config_dir: Option<&str>, mode: ImportMode) -> Result<Vec<PendingWrite>> {
+    let config_file = get_wezterm_config_file(config_dir).map_err(|e| anyhow::anyhow!(e))?;
+    preview_config_file(config, &config_file, mode)
+}
+
+/// The files `write_config_file` would change, as for `preview_save`
+pub fn preview_config_file(config: &AppearanceConfig, config_file: &Path, mode: ImportMode) -> Result<Vec<PendingWrite>> {
+    Ok(plan_config_file(config, config_file, mode)?
+        .into_iter()
+        .filter(|write| write.existing.as_deref() != Some(write.content.as_str()))
+        .collect())
+}
+
+/// Render every file a save of `config` touches, unchanged ones included,
+/// without writing any; see `write_config_file` for what goes where
+fn plan_config_file(config: &AppearanceConfig, config_file: &Path, mode: ImportMode) -> Result<Vec<PendingWrite>> {
+    let existing = read_existing(config_file)?;
+    if existing.as_deref().is_some_and(has_settings_hook) {
+        let module_file = settings_module_path(config_file);
+        return Ok(vec![PendingWrite {
+            existing: read_existing(&module_file)?,
+            path: module_file,
+            content: generate_settings_module(config),
+            backup: false,
+        }]);
+    }
+
+    let parsed = match existing {
+        Some(_) => read_wezterm_config(config_file, mode).ok(),
+        None => None,
+    };
+    let has_modules = parsed.as_ref().is_some_and(|r| !r.modules.is_empty());
+    let (current_config, sources) = parsed
+        .map(|r| (r.config, r.sources))
+        .unwrap_or_default();
+    let current = config_settings(&current_config);
+    let desired = config_settings(config);
+
+    // Render every file before writing any, so a failure leaves all of them alone
+    let mut outputs = Vec::new();
+    let mut modules: Vec<&PathBuf> = sources.values().collect();
+    modules.sort();
+    modules.dedup();
+    for module in modules {
+        let source = fs::read_to_string(module)
+            .with_context(|| format!("Failed to read {}", module.display()))?;
+        let edit = apply_settings(
+            &source,
+            &settings_in(&current, &sources, Some(module)),
+            &settings_in(&desired, &sources, Some(module)),
+        )
+        .map_err(|e| anyhow::anyhow!("Could not edit {}: {}", module.display(), e))?;
+        ensure_nothing_skipped(module, &edit.skipped)?;
+        ensure_unmanaged_kept(module, &source, &edit.content)?;
+        outputs.push(PendingWrite {
+            path: module.clone(),
+            existing: Some(source),
+            content: edit.content,
+            backup: true,
+        });
+    }
+
+    if !has_modules || settings_in(&current, &sources, None) != settings_in(&desired, &sources, None) {
+        if has_modules && !existing.as_deref().is_some_and(edits_in_place) {
+            anyhow::bail!(
+                "{} loads other modules but doesn't use `config.<key> = value` assignments, \
+                 so settings it owns can't be saved without rewriting it",
+                config_file.display()
+            );
+        }
+        let lua_content =
+            render_config_file(config, &current_config, existing.as_deref(), config_file, &sources)?;
+        if let Some(source) = &existing {
+            ensure_unmanaged_kept(config_file, source, &lua_content)?;
+        }
+        outputs.push(PendingWrite {
+            path: config_file.to_path_buf(),
+            existing,
+            content: lua_content,
+            backup: true,
+        });
+    }
+
+    Ok(outputs)
+}
+
+/// Produce the new wezterm.lua content for `config`, editing `existing` in place when possible.
+/// `current` is what `existing` was read as; settings listed in `sources`
+/// live in modules and are left out of the edit.
+fn render_config_file(
+    config: &AppearanceConfig,
+    current: &AppearanceConfig,
+    existing: Option<&str>,
+    config_file: &Path,
+    sources: &BTreeMap<String, PathBuf>,
+) -> Result<String> {
+    match existing {
+        Some(source) if edits_in_place(source) => {
+            let edit = apply_settings(
+                source,
+                &settings_in(&config_settings(current), sources, None),
+                &settings_in(&config_settings(config), sources, None),
+            )
+            .map_err(|e| anyhow::anyhow!("Could not edit {}: {}", config_file.display(), e))?;
+            ensure_nothing_skipped(config_file, &edit.skipped)?;
+            Ok(edit.content)
+        }
+        _ => Ok(generate_lua_config(config)),
+    }
+}
+
+/// The settings whose value lives in `module`, or in the main file for `None`
+fn settings_in(
+    settings: &[LuaSetting],
+    sources: &BTreeMap<String, PathBuf>,
+    module: Option<&PathBuf>,
+) -> Vec<LuaSetting> {
+    settings
+        .iter()
+        .filter(|s| sources.get(&s.key()) == module)
+        .cloned()
+        .collect()
+}
+
+/// Fail if an edit left changed settings out because `path` computes the
+/// table they live in (`config.colors = my_colors`), naming them
+fn ensure_nothing_skipped(path: &Path, skipped: &[String]) -> Result<()> {
+    if !skipped.is_empty() {
+        anyhow::bail!(
+            "{} computes the tables holding {}, so they can't be saved; change them in the file itself",
+            path.display(),
+            skipped.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Fail if `updated` no longer has every unmanaged item `original` defines,
+/// naming what would be lost
+fn ensure_unmanaged_kept(path: &Path, original: &str, updated: &str) -> Result<()> {
+    let unmanaged = |content: &str| {
+        parse_lua_content(content)
+            .map(|r| r.unmanaged)
+            .unwrap_or_default()
+    };
+    let mut kept = unmanaged(updated);
+    let mut lost = Vec::new();
+    for item in unmanaged(original) {
+        match kept.iter().position(|k| k.kind == item.kind && k.name == item.name) {
+            Some(index) => {
+                kept.remove(index);
+            }
+            None => lost.push(format!("{} `{}` (line {})", item.kind.label(), item.name, item.line)),
+        }
+    }
+    if !lost.is_empty() {
+        anyhow::bail!(
+            "Saving {} would drop Lua the settings don't manage: {}",
+            path.display(),
+            lost.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Refuse to save settings that fail validation, listing every problem
+fn ensure_valid(config: &AppearanceConfig) -> Result<()> {
+    let validation = validate_config(config);
+    if !validation.valid {
+        anyhow::bail!("Settings are not valid; nothing was saved:\n{}", format_errors(&validation));
+    }
+    Ok(())
+}
 
-    // TODO: Implement Lua generation to write config files.
-    // For now, this function is a placeholder.
-    println!("Saving config to: {:?}", dir);
-    println!("{:#?}", config);
+fn read_existing(path: &Path) -> Result<Option<String>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    fs::read_to_string(path)
+        .map(Some)
+        .with_context(|| format!("Failed to read {}", path.display()))
+}
 
+/// Write `content` to `path` unless it already holds exactly that,
+/// optionally backing up the previous file, and record it in `result`.
+/// The write is atomic and goes through symlinks; see `atomic`.
+fn write_if_changed(
+    path: &Path,
+    content: &str,
+    backup: bool,
+    config_dir: &Path,
+    result: &mut SaveResult,
+) -> Result<()> {
+    let existing = read_existing(path)?;
+    if existing.as_deref() == Some(content) {
+        return Ok(());
+    }
+
+    if backup && existing.is_some() {
+        let backup_path = create_backup(path, config_dir)?;
+        result
+            .backups_created
+            .push(backup_path.to_string_lossy().to_string());
+    }
+
+    write_atomic(path, content)?;
+    result.files_written.push(path.to_string_lossy().to_string());
     Ok(())
 }
+
+// ============================================================================
+// Managed Settings Module
+// ============================================================================
+
+/// Path of the managed settings module that sits next to wezterm.lua
+pub fn settings_module_path(config_file: &Path) -> PathBuf {
+    config_file.with_file_name(format!("{}.lua", SETTINGS_MODULE))
+}
+
+/// Whether wezterm.lua loads the managed settings module
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SettingsModuleStatus {
+    pub config_path: String,
+    pub module_path: String,
+    /// wezterm.lua contains `require('settings_ui').apply_to_config(config)`
+    pub hook_installed: bool,
+    pub module_exists: bool,
+}
+
+/// Check whether the settings module is installed for this config
+pub fn settings_module_status(config_dir: Option<&str>) -> Result<SettingsModuleStatus> {
+    let config_file = get_wezterm_config_file(config_dir).map_err(|e| anyhow::anyhow!(e))?;
+    let module_file = settings_module_path(&config_file);
+    let hook_installed = read_existing(&config_file)?
+        .as_deref()
+        .is_some_and(has_settings_hook);
+
+    Ok(SettingsModuleStatus {
+        config_path: config_file.to_string_lossy().to_string(),
+        module_exists: module_file.exists(),
+        module_path: module_file.to_string_lossy().to_string(),
+        hook_installed,
+    })
+}
+
+/// The settings module to parse instead of wezterm.lua, if the hook is installed
+fn active_settings_module(config_file: &Path) -> Option<PathBuf> {
+    let source = fs::read_to_string(config_file).ok()?;
+    let module_file = settings_module_path(config_file);
+    (has_settings_hook(&source) && module_file.exists()).then_some(module_file)
+}
+
+/// Move managed settings into `settings_ui.lua` and hook it into wezterm.lua.
+///
+/// Writes the module from `config`, then adds
+/// `require('settings_ui').apply_to_config(config)` before `return config`
+/// (creating a minimal wezterm.lua if there is none). Safe to run again.
+pub fn install_settings_module(config: &AppearanceConfig, config_dir: Option<&str>) -> Result<SaveResult> {
+    ensure_valid(config)?;
+    let config_file = get_wezterm_config_file(config_dir).map_err(|e| anyhow::anyhow!(e))?;
+    let dir = config_file
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    fs::create_dir_all(&dir).context("Failed to create config directory")?;
+
+    let mut result = SaveResult {
+        success: true,
+        files_written: vec![],
+        backups_created: vec![],
+        config_dir: dir.to_string_lossy().to_string(),
+    };
+
+    let source = read_existing(&config_file)?.unwrap_or_else(|| {
+        "local wezterm = require 'wezterm'\nlocal config = wezterm.config_builder()\n\nreturn config\n"
+            .to_string()
+    });
+    let hooked = install_settings_hook(&source).map_err(|e| anyhow::anyhow!(e))?;
+
+    let module_file = settings_module_path(&config_file);
+    write_if_changed(&module_file, &generate_settings_module(config), false, &dir, &mut result)?;
+    write_if_changed(&config_file, &hooked, true, &dir, &mut result)?;
+    Ok(result)
+}
+
+/// Remove the settings module hook and fold its settings back into wezterm.lua.
+///
+/// `config` is written into wezterm.lua the same way a normal save would, so
+/// the effective configuration is unchanged; `settings_ui.lua` is deleted.
+/// Settings that fail validation are never written.
+pub fn uninstall_settings_module(config: &AppearanceConfig, config_dir: Option<&str>) -> Result<SaveResult> {
+    ensure_valid(config)?;
+    let config_file = get_wezterm_config_file(config_dir).map_err(|e| anyhow::anyhow!(e))?;
+    let dir = config_file
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    let mut result = SaveResult {
+        success: true,
+        files_written: vec![],
+        backups_created: vec![],
+        config_dir: dir.to_string_lossy().to_string(),
+    };
+
+    if let Some(source) = read_existing(&config_file)? {
+        let unhooked = remove_settings_hook(&source).map_err(|e| anyhow::anyhow!(e))?;
+        let current = parse_lua_content(&unhooked)
+            .map(|r| r.config)
+            .unwrap_or_default();
+        let lua_content =
+            render_config_file(config, &current, Some(&unhooked), &config_file, &BTreeMap::new())?;
+        ensure_unmanaged_kept(&config_file, &unhooked, &lua_content)?;
+        write_if_changed(&config_file, &lua_content, true, &dir, &mut result)?;
+    }
+
+    let module_file = settings_module_path(&config_file);
+    if module_file.exists() {
+        fs::remove_file(&module_file)
+            .with_context(|| format!("Failed to remove {}", module_file.display()))?;
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_config_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "wezterm-settings-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_write_config_file_creates_file() {
+        let dir = temp_config_dir("write");
+        let config_file = dir.join("wezterm.lua");
+
+        let result = write_config_file(&AppearanceConfig::default(), &config_file, ImportMode::Static).unwrap();
+        assert!(result.success);
+        assert_eq!(result.files_written, vec![config_file.to_string_lossy().to_string()]);
+        assert!(result.backups_created.is_empty());
+
+        let content = fs::read_to_string(&config_file).unwrap();
+        assert!(content.contains("config.font_size = 12"));
+        assert!(content.trim_end().ends_with("return config"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_config_file_rejects_invalid_settings() {
+        let dir = temp_config_dir("invalid");
+        let config_file = dir.join("wezterm.lua");
+
+        let mut config = AppearanceConfig::default();
+        config.fonts.size = 200.0;
+        let err = write_config_file(&config, &config_file, ImportMode::Static).unwrap_err();
+        assert!(err.to_string().contains("fonts.size: must be between 8 and 72"), "{}", err);
+        assert!(!config_file.exists());
+    }
+
+    #[test]
+    fn test_write_config_file_backs_up_existing() {
+        let dir = temp_config_dir("backup");
+        let config_file = dir.join("wezterm.lua");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&config_file, "-- hand written\nreturn {}\n").unwrap();
+
+        let result = write_config_file(&AppearanceConfig::default(), &config_file, ImportMode::Static).unwrap();
+        assert_eq!(result.backups_created.len(), 1);
+
+        let backup = PathBuf::from(&result.backups_created[0]);
+        assert!(backup
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("wezterm.lua.backup."));
+        assert_eq!(fs::read_to_string(&backup).unwrap(), "-- hand written\nreturn {}\n");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_config_file_edits_in_place() {
+        let dir = temp_config_dir("surgical");
+        let config_file = dir.join("wezterm.lua");
+        fs::create_dir_all(&dir).unwrap();
+        let original = "-- my config\nlocal wezterm = require 'wezterm'\nlocal config = wezterm.config_builder()\n\nconfig.font_size = 13 -- keep me\n\nwezterm.on('update-status', function() end)\n\nreturn config\n";
+        fs::write(&config_file, original).unwrap();
+
+        // Saving what was loaded changes nothing
+        let mut config = parse_lua_content(original).unwrap().config;
+        let result = write_config_file(&config, &config_file, ImportMode::Static).unwrap();
+        assert!(result.files_written.is_empty());
+        assert!(result.backups_created.is_empty());
+
+        config.fonts.size = 15.0;
+        write_config_file(&config, &config_file, ImportMode::Static).unwrap();
+        let content = fs::read_to_string(&config_file).unwrap();
+        assert_eq!(content, original.replace("font_size = 13", "font_size = 15"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_config_file_through_symlink() {
+        let dir = temp_config_dir("symlink");
+        fs::create_dir_all(dir.join("dotfiles")).unwrap();
+        let real = dir.join("dotfiles/wezterm.lua");
+        let config_file = dir.join("wezterm.lua");
+        let original = "local wezterm = require 'wezterm'\nlocal config = wezterm.config_builder()\nconfig.font_size = 13\nreturn config\n";
+        fs::write(&real, original).unwrap();
+        std::os::unix::fs::symlink("dotfiles/wezterm.lua", &config_file).unwrap();
+
+        let mut config = parse_lua_content(original).unwrap().config;
+        config.fonts.size = 15.0;
+        let result = write_config_file(&config, &config_file, ImportMode::Static).unwrap();
+        assert_eq!(result.files_written.len(), 1);
+        assert!(fs::symlink_metadata(&config_file).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read_to_string(&real).unwrap(), original.replace("13", "15"));
+        // The backup holds the old content, not a copy of the link
+        assert_eq!(fs::read_to_string(&result.backups_created[0]).unwrap(), original);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_config_file_refuses_computed_tables() {
+        let dir = temp_config_dir("computed");
+        let config_file = dir.join("wezterm.lua");
+        fs::create_dir_all(&dir).unwrap();
+        let original = "local wezterm = require 'wezterm'\nlocal config = wezterm.config_builder()\nlocal my_colors = {}\nconfig.colors = my_colors\nconfig.font_size = 13\nreturn config\n";
+        fs::write(&config_file, original).unwrap();
+
+        let mut config = parse_lua_content(original).unwrap().config;
+        config.fonts.size = 15.0;
+        config.colors.foreground = crate::models::Color::parse("#ff0000").unwrap();
+        let err = write_config_file(&config, &config_file, ImportMode::Static).unwrap_err().to_string();
+        assert!(err.contains("colors.foreground"), "{}", err);
+        // Nothing is written, not even the settings that could be
+        assert_eq!(fs::read_to_string(&config_file).unwrap(), original);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_preview_config_file_writes_nothing() {
+        let dir = temp_config_dir("preview");
+        let config_file = dir.join("wezterm.lua");
+        fs::create_dir_all(&dir).unwrap();
+        let original = "local wezterm = require 'wezterm'\nlocal config = wezterm.config_builder()\nconfig.font_size = 13\nreturn config\n";
+        fs::write(&config_file, original).unwrap();
+
+        let mut config = parse_lua_content(original).unwrap().config;
+        assert!(preview_config_file(&config, &config_file, ImportMode::Static).unwrap().is_empty());
+
+        config.fonts.size = 15.0;
+        let writes = preview_config_file(&config, &config_file, ImportMode::Static).unwrap();
+        assert_eq!(writes.len(), 1);
+        assert_eq!(writes[0].existing.as_deref(), Some(original));
+        assert_eq!(writes[0].content, original.replace("13", "15"));
+        assert_eq!(fs::read_to_string(&config_file).unwrap(), original);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_config_file_keeps_unmanaged_lua() {
+        let dir = temp_config_dir("unmanaged");
+        let config_file = dir.join("wezterm.lua");
+        fs::create_dir_all(&dir).unwrap();
+        // No managed settings yet, so new ones are inserted before `return config`
+        let original = "local wezterm = require 'wezterm'\n\
+                        local config = wezterm.config_builder()\n\
+                        local function greet() return 'hi' end\n\
+                        wezterm.on('gui-startup', function() greet() end)\n\
+                        config.default_prog = { '/bin/zsh' }\n\
+                        return config\n";
+        fs::write(&config_file, original).unwrap();
+
+        let mut config = AppearanceConfig::default();
+        config.fonts.size = 15.0;
+        write_config_file(&config, &config_file, ImportMode::Static).unwrap();
+        let content = fs::read_to_string(&config_file).unwrap();
+        assert!(content.starts_with(original.trim_end_matches("return config\n")));
+        let loaded = load_config_with_metadata(Some(dir.to_str().unwrap()), ImportMode::Static).unwrap();
+        let names: Vec<_> = loaded.unmanaged.iter().map(|u| (u.name.as_str(), u.line)).collect();
+        assert_eq!(names, vec![("greet", 3), ("gui-startup", 4), ("default_prog", 5)]);
+        assert_eq!(loaded.config.fonts.size, 15.0);
+
+        // A file that would have to be regenerated is refused rather than lose them
+        let computed = "local cfg = {}\nwezterm.on('gui-startup', function() end)\nreturn cfg\n";
+        fs::write(&config_file, computed).unwrap();
+        let err = write_config_file(&config, &config_file, ImportMode::Static).unwrap_err();
+        assert!(err.to_string().contains("event handler `gui-startup` (line 2)"), "{}", err);
+        assert_eq!(fs::read_to_string(&config_file).unwrap(), computed);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_config_file_updates_required_modules() {
+        let dir = temp_config_dir("modules");
+        let config_file = dir.join("wezterm.lua");
+        fs::create_dir_all(dir.join("config")).unwrap();
+        let main = "local wezterm = require 'wezterm'\nlocal config = wezterm.config_builder()\n\nconfig.max_fps = 60\nrequire('config.fonts').apply_to_config(config)\nfor k, v in pairs(require('config.appearance')) do config[k] = v end\n\nreturn config\n";
+        let fonts = "local M = {}\n\nfunction M.apply_to_config(c)\n  c.font_size = 13 -- keep me\nend\n\nreturn M\n";
+        let appearance = "return {\n  window_background_opacity = 0.9,\n}\n";
+        fs::write(&config_file, main).unwrap();
+        fs::write(dir.join("config/fonts.lua"), fonts).unwrap();
+        fs::write(dir.join("config/appearance.lua"), appearance).unwrap();
+
+        let mut config = parse_wezterm_config(&config_file).unwrap().config;
+        assert!(write_config_file(&config, &config_file, ImportMode::Static).unwrap().files_written.is_empty());
+
+        // Each setting is written back to the file it came from
+        config.fonts.size = 15.0;
+        config.window.window_background_opacity = 0.75;
+        config.gpu.max_fps = 120;
+        let result = write_config_file(&config, &config_file, ImportMode::Static).unwrap();
+        assert_eq!(result.files_written.len(), 3);
+        assert_eq!(result.backups_created.len(), 3);
+        assert_eq!(
+            fs::read_to_string(&config_file).unwrap(),
+            main.replace("max_fps = 60", "max_fps = 120")
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("config/fonts.lua")).unwrap(),
+            fonts.replace("font_size = 13", "font_size = 15")
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("config/appearance.lua")).unwrap(),
+            appearance.replace("0.9", "0.75")
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_evaluate_mode_reads_and_saves_computed_values() {
+        let dir = temp_config_dir("evaluate");
+        let config_file = dir.join("wezterm.lua");
+        fs::create_dir_all(&dir).unwrap();
+        let original = "local wezterm = require 'wezterm'\nlocal config = wezterm.config_builder()\n\nlocal size = 10\nfor _ = 1, 3 do size = size + 1 end\nconfig.font_size = size\nconfig.color_scheme = 'Nord'\n\nreturn config\n";
+        fs::write(&config_file, original).unwrap();
+        let dir_str = dir.to_string_lossy().to_string();
+
+        let loaded = load_config_with_metadata(Some(&dir_str), ImportMode::Evaluate).unwrap();
+        assert_eq!(loaded.config.fonts.size, 13.0);
+        assert!(loaded.diagnostics.is_empty(), "{:?}", loaded.diagnostics);
+
+        // The loop is left alone; only what changed is written
+        let mut config = loaded.config;
+        assert!(write_config_file(&config, &config_file, ImportMode::Evaluate).unwrap().files_written.is_empty());
+        config.color_scheme = Some("Dracula".to_string());
+        write_config_file(&config, &config_file, ImportMode::Evaluate).unwrap();
+        assert_eq!(fs::read_to_string(&config_file).unwrap(), original.replace("Nord", "Dracula"));
+
+        // A config that can't run in the sandbox is read without running it
+        fs::write(&config_file, "local config = {}\nconfig.font_size = tonumber(os.getenv('SIZE'))\nreturn config\n").unwrap();
+        let loaded = load_config_with_metadata(Some(&dir_str), ImportMode::Evaluate).unwrap();
+        assert!(loaded.diagnostics.iter().any(|d| d.message.starts_with("Could not run the config")));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_save_refuses_to_overwrite_external_edits() {
+        let dir = temp_config_dir("external");
+        let dir_str = dir.to_string_lossy().to_string();
+        let config_file = dir.join("wezterm.lua");
+        fs::create_dir_all(&dir).unwrap();
+        let original = "local wezterm = require 'wezterm'\nlocal config = wezterm.config_builder()\nconfig.font_size = 13\nconfig.color_scheme = 'Nord'\nreturn config\n";
+        fs::write(&config_file, original).unwrap();
+
+        let loaded = load_config_with_metadata(Some(&dir_str), ImportMode::Static).unwrap();
+        assert_eq!(loaded.stamps.len(), 1);
+        let mut config = loaded.config.clone();
+        config.fonts.size = 15.0;
+
+        // Edited in another program meanwhile
+        let edited = original.replace("Nord", "Dracula");
+        fs::write(&config_file, &edited).unwrap();
+        let err = save_config_if_unchanged(&config, Some(&dir_str), ImportMode::Static, &loaded.stamps).unwrap_err();
+        assert!(err.to_string().contains("changed on disk"), "{}", err);
+        assert_eq!(fs::read_to_string(&config_file).unwrap(), edited);
+
+        // Merging keeps both edits and saves against the new stamps
+        let merged = merge_external_changes(&loaded.config, &config, Some(&dir_str), ImportMode::Static).unwrap();
+        assert!(merged.conflicts.is_empty());
+        save_config_if_unchanged(&merged.config, Some(&dir_str), ImportMode::Static, &merged.loaded.stamps).unwrap();
+        assert_eq!(fs::read_to_string(&config_file).unwrap(), edited.replace("13", "15"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_settings_module_install_save_uninstall() {
+        let dir = temp_config_dir("module");
+        let dir_str = dir.to_string_lossy().to_string();
+        let config_file = dir.join("wezterm.lua");
+        fs::create_dir_all(&dir).unwrap();
+        let original = "local wezterm = require 'wezterm'\nlocal config = wezterm.config_builder()\n\nconfig.font_size = 13\n\nreturn config\n";
+        fs::write(&config_file, original).unwrap();
+
+        let mut config = load_config(Some(&dir_str), ImportMode::Static).unwrap();
+        install_settings_module(&config, Some(&dir_str)).unwrap();
+        let status = settings_module_status(Some(&dir_str)).unwrap();
+        assert!(status.hook_installed && status.module_exists);
+
+        // Installing twice leaves wezterm.lua alone
+        let hooked = fs::read_to_string(&config_file).unwrap();
+        let again = install_settings_module(&config, Some(&dir_str)).unwrap();
+        assert!(again.files_written.is_empty());
+
+        // Saves go to the module; wezterm.lua is untouched
+        config.fonts.size = 16.0;
+        save_config(&config, Some(&dir_str), ImportMode::Static).unwrap();
+        assert_eq!(fs::read_to_string(&config_file).unwrap(), hooked);
+        assert!(fs::read_to_string(dir.join("settings_ui.lua"))
+            .unwrap()
+            .contains("  config.font_size = 16\n"));
+        let metadata = load_config_with_metadata(Some(&dir_str), ImportMode::Static).unwrap();
+        assert_eq!(metadata.config.fonts.size, 16.0);
+        assert!(metadata.settings_module.is_some());
+
+        // Invalid settings aren't folded back into wezterm.lua
+        let mut invalid = config.clone();
+        invalid.fonts.size = 200.0;
+        let err = uninstall_settings_module(&invalid, Some(&dir_str)).unwrap_err();
+        assert!(err.to_string().contains("fonts.size"), "{}", err);
+        assert_eq!(fs::read_to_string(&config_file).unwrap(), hooked);
+        assert!(dir.join("settings_ui.lua").exists());
+
+        // Uninstalling folds the settings back into wezterm.lua
+        uninstall_settings_module(&config, Some(&dir_str)).unwrap();
+        assert!(!dir.join("settings_ui.lua").exists());
+        assert_eq!(
+            fs::read_to_string(&config_file).unwrap(),
+            original.replace("font_size = 13", "font_size = 16")
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_explicit_config_dir_wins() {
+        let dir = temp_config_dir("explicit");
+        let dir_str = dir.to_string_lossy().to_string();
+
+        assert_eq!(get_wezterm_config_dir(Some(&dir_str)).unwrap(), dir);
+        assert_eq!(
+            get_wezterm_config_file(Some(&dir_str)).unwrap(),
+            dir.join("wezterm.lua")
+        );
+    }
+
+    #[test]
+    fn test_explicit_config_file_path() {
+        let dir = temp_config_dir("explicit-file");
+        let file = dir.join("dotfiles.lua");
+        let file_str = file.to_string_lossy().to_string();
+
+        assert_eq!(get_wezterm_config_file(Some(&file_str)).unwrap(), file);
+        assert_eq!(get_wezterm_config_dir(Some(&file_str)).unwrap(), dir);
+    }
+
+    #[test]
+    fn test_load_and_save_honor_config_dir() {
+        let dir = temp_config_dir("roundtrip");
+        let dir_str = dir.to_string_lossy().to_string();
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("wezterm.lua"), "config.font_size = 18\n").unwrap();
+
+        let mut config = load_config(Some(&dir_str), ImportMode::Static).unwrap();
+        assert_eq!(config.fonts.size, 18.0);
+
+        let metadata = load_config_with_metadata(Some(&dir_str), ImportMode::Static).unwrap();
+        assert!(metadata.config_exists);
+        assert_eq!(metadata.config_path, dir.join("wezterm.lua").to_string_lossy());
+
+        config.fonts.size = 20.0;
+        let result = save_config(&config, Some(&dir_str), ImportMode::Static).unwrap();
+        assert_eq!(result.config_dir, dir_str);
+        assert_eq!(load_config(Some(&dir_str), ImportMode::Static).unwrap().fonts.size, 20.0);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
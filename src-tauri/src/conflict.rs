@@ -0,0 +1,231 @@
+// Conflicts - noticing config files edited elsewhere since they were loaded
+//
+// Loading records a stamp of every file it read: a hash of its content and
+// its modification time. Before a save writes, the stamps are checked
+// against the files on disk; a file whose content changed was edited in
+// another program (or by another save) and writing over it would lose
+// those edits. The caller then reloads, overwrites, or merges: the managed
+// settings are merged three ways, taking each setting from whichever side
+// changed it since the load.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::models::AppearanceConfig;
+
+/// A config file as it was when it was loaded
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileStamp {
+    pub path: String,
+    /// Hash of the content in hex; `None` when the file didn't exist
+    pub hash: Option<String>,
+    /// Modification time in milliseconds since the Unix epoch; `None` when
+    /// it is too recent to tell a later write in the same tick from it
+    pub modified: Option<u64>,
+}
+
+/// How far apart two writes must be for their modification times to
+/// differ on every file system (FAT keeps two seconds)
+const MTIME_RESOLUTION: Duration = Duration::from_secs(2);
+
+impl FileStamp {
+    /// Stamp `path` as it is now
+    pub fn read(path: &Path) -> Self {
+        let modified = modified(path).filter(|time| {
+            SystemTime::now().duration_since(*time).is_ok_and(|age| age >= MTIME_RESOLUTION)
+        });
+        Self {
+            path: path.to_string_lossy().to_string(),
+            hash: fs::read(path).ok().as_deref().map(hash_content),
+            modified: modified.and_then(millis),
+        }
+    }
+
+    /// Whether the file's content is no longer what was stamped. A file
+    /// whose modification time is the stamped one isn't read again; one
+    /// that was touched (editors and sync tools do) is hashed, as only the
+    /// content counts.
+    pub fn is_stale(&self) -> bool {
+        let path = Path::new(&self.path);
+        if self.modified.is_some() && modified(path).and_then(millis) == self.modified {
+            return false;
+        }
+        fs::read(path).ok().as_deref().map(hash_content) != self.hash
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn millis(time: SystemTime) -> Option<u64> {
+    time.duration_since(UNIX_EPOCH).ok().map(|d| d.as_millis() as u64)
+}
+
+fn hash_content(content: &[u8]) -> String {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// The stamped files whose content changed since they were stamped
+pub fn changed_files(stamps: &[FileStamp]) -> Vec<String> {
+    stamps
+        .iter()
+        .filter(|stamp| stamp.is_stale())
+        .map(|stamp| stamp.path.clone())
+        .collect()
+}
+
+/// Settings merged from the UI's edits and the file's
+#[derive(Debug, Clone, Serialize)]
+pub struct MergeResult {
+    pub config: AppearanceConfig,
+    /// Dotted paths (`fonts.size`) both sides changed to different values;
+    /// the UI's value was kept
+    pub conflicts: Vec<String>,
+}
+
+/// Merge the settings edited in the UI (`ours`) with the ones now in the
+/// file (`theirs`), both changed from `base`, the settings as loaded. Each
+/// setting comes from the side that changed it; when both changed it to
+/// different values, `ours` wins and the setting is listed as a conflict.
+pub fn merge_configs(
+    base: &AppearanceConfig,
+    ours: &AppearanceConfig,
+    theirs: &AppearanceConfig,
+) -> Result<MergeResult, String> {
+    let json = |config: &AppearanceConfig| serde_json::to_value(config).unwrap_or(Value::Null);
+    let mut conflicts = Vec::new();
+    let merged = merge("", &json(base), &json(ours), &json(theirs), &mut conflicts);
+    let config = serde_json::from_value(merged).map_err(|e| format!("Couldn't merge settings: {}", e))?;
+    Ok(MergeResult { config, conflicts })
+}
+
+/// Objects and same-length arrays are merged member by member; anything
+/// else is taken whole from the side that changed it
+fn merge(path: &str, base: &Value, ours: &Value, theirs: &Value, conflicts: &mut Vec<String>) -> Value {
+    if ours == theirs || theirs == base {
+        return ours.clone();
+    }
+    if ours == base {
+        return theirs.clone();
+    }
+    let join = |key: &str| if path.is_empty() { key.to_string() } else { format!("{}.{}", path, key) };
+    match (base, ours, theirs) {
+        (Value::Object(b), Value::Object(o), Value::Object(t)) => {
+            let mut keys: Vec<&String> = o.keys().collect();
+            keys.extend(t.keys().filter(|key| !o.contains_key(*key)));
+            let mut merged = serde_json::Map::new();
+            for key in keys {
+                let get = |side: &serde_json::Map<String, Value>| side.get(key).cloned().unwrap_or(Value::Null);
+                let value = merge(&join(key), &get(b), &get(o), &get(t), conflicts);
+                merged.insert(key.clone(), value);
+            }
+            Value::Object(merged)
+        }
+        (Value::Array(b), Value::Array(o), Value::Array(t)) if b.len() == o.len() && o.len() == t.len() => Value::Array(
+            b.iter()
+                .zip(o)
+                .zip(t)
+                .enumerate()
+                .map(|(i, ((b, o), t))| merge(&join(&i.to_string()), b, o, t, conflicts))
+                .collect(),
+        ),
+        _ => {
+            conflicts.push(path.to_string());
+            ours.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn temp_file(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("wezterm-settings-conflict-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir.join("wezterm.lua")
+    }
+
+    #[test]
+    fn test_stamp_notices_edits() {
+        let file = temp_file("stamp");
+        let missing = FileStamp::read(&file);
+        assert_eq!(missing.hash, None);
+
+        fs::write(&file, "config.font_size = 12\n").unwrap();
+        assert_eq!(changed_files(&[missing]), vec![file.to_string_lossy().to_string()]);
+
+        let stamp = FileStamp::read(&file);
+        assert!(changed_files(std::slice::from_ref(&stamp)).is_empty());
+
+        // Rewriting the same content isn't a change; new content is
+        fs::write(&file, "config.font_size = 12\n").unwrap();
+        assert!(!stamp.is_stale());
+        fs::write(&file, "config.font_size = 14\n").unwrap();
+        assert!(stamp.is_stale());
+
+        fs::remove_dir_all(file.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_stamp_trusts_settled_modification_time() {
+        let file = temp_file("mtime");
+        let set_modified = |time: SystemTime| {
+            let handle = fs::File::options().write(true).open(&file).unwrap();
+            handle.set_modified(time).unwrap();
+        };
+
+        // A file written just now could be written again in the same tick
+        fs::write(&file, "config.font_size = 12\n").unwrap();
+        assert_eq!(FileStamp::read(&file).modified, None);
+
+        let an_hour_ago = SystemTime::now() - Duration::from_secs(3600);
+        set_modified(an_hour_ago);
+        let stamp = FileStamp::read(&file);
+        assert_eq!(stamp.modified, millis(an_hour_ago));
+
+        // With the time unchanged the file is taken as stamped, unread
+        fs::write(&file, "config.font_size = 14\n").unwrap();
+        set_modified(an_hour_ago);
+        assert!(!stamp.is_stale());
+        // A new time alone isn't a change; with new content it is
+        fs::write(&file, "config.font_size = 12\n").unwrap();
+        assert!(!stamp.is_stale());
+        fs::write(&file, "config.font_size = 16\n").unwrap();
+        assert!(stamp.is_stale());
+
+        fs::remove_dir_all(file.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_merge_takes_each_side_changes() {
+        let base = AppearanceConfig::default();
+        let mut ours = base.clone();
+        ours.fonts.size = 16.0;
+        ours.window.window_background_opacity = 0.8;
+        let mut theirs = base.clone();
+        theirs.color_scheme = Some("Nord".to_string());
+        theirs.window.window_background_opacity = 0.5;
+
+        let result = merge_configs(&base, &ours, &theirs).unwrap();
+        assert_eq!(result.config.fonts.size, 16.0);
+        assert_eq!(result.config.color_scheme.as_deref(), Some("Nord"));
+        // Both changed it: the UI's value is kept and the clash reported
+        assert_eq!(result.config.window.window_background_opacity, 0.8);
+        assert_eq!(result.conflicts, vec!["window.window_background_opacity"]);
+
+        // The same change on both sides is no conflict
+        theirs.window.window_background_opacity = 0.8;
+        assert!(merge_configs(&base, &ours, &theirs).unwrap().conflicts.is_empty());
+    }
+}
@@ -0,0 +1,249 @@
+// Line diffs - what a save would change, as unified diff hunks
+//
+// A save is previewed by diffing each file's current content against what
+// would be written. The diff is a longest-common-subsequence over lines,
+// which is plenty for config files of a few hundred lines, grouped into
+// hunks with surrounding context the way `diff -u` prints them.
+
+use serde::Serialize;
+
+/// Unchanged lines shown around each change
+pub const CONTEXT_LINES: usize = 3;
+
+/// Above this many line pairs the changed region is shown as removed and
+/// re-added whole rather than matched line by line
+const MAX_TABLE: usize = 4_000_000;
+
+/// A line of a hunk
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", content = "text", rename_all = "lowercase")]
+pub enum DiffLine {
+    Context(String),
+    Removed(String),
+    Added(String),
+}
+
+/// A run of changes with their context
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Hunk {
+    /// First line in the old file (1-based; the line before for an empty range)
+    pub old_start: usize,
+    pub old_len: usize,
+    pub new_start: usize,
+    pub new_len: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+impl Hunk {
+    /// `@@ -old_start,old_len +new_start,new_len @@`
+    pub fn header(&self) -> String {
+        format!(
+            "@@ -{},{} +{},{} @@",
+            self.old_start, self.old_len, self.new_start, self.new_len
+        )
+    }
+}
+
+/// The changes to one file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileDiff {
+    pub path: String,
+    /// The file doesn't exist yet
+    pub is_new: bool,
+    pub hunks: Vec<Hunk>,
+}
+
+impl FileDiff {
+    /// Diff `old` (`None` for a file that doesn't exist) against `new`
+    pub fn new(path: &str, old: Option<&str>, new: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            is_new: old.is_none(),
+            hunks: diff_lines(old.unwrap_or(""), new, CONTEXT_LINES),
+        }
+    }
+
+    /// The diff in unified format, as `diff -u` prints it
+    pub fn to_unified(&self) -> String {
+        let old_name = if self.is_new { "/dev/null" } else { self.path.as_str() };
+        let mut out = format!("--- {}\n+++ {}\n", old_name, self.path);
+        for hunk in &self.hunks {
+            out.push_str(&hunk.header());
+            out.push('\n');
+            for line in &hunk.lines {
+                let (sign, text) = match line {
+                    DiffLine::Context(text) => (' ', text),
+                    DiffLine::Removed(text) => ('-', text),
+                    DiffLine::Added(text) => ('+', text),
+                };
+                out.push(sign);
+                out.push_str(text);
+                out.push('\n');
+            }
+        }
+        out
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// Hunks turning `old` into `new`, each change with up to `context`
+/// unchanged lines around it; empty when the lines are the same
+pub fn diff_lines(old: &str, new: &str, context: usize) -> Vec<Hunk> {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+    let ops = line_ops(&a, &b);
+
+    // Line positions in each file before every op
+    let mut positions = Vec::with_capacity(ops.len() + 1);
+    let (mut i, mut j) = (0, 0);
+    for op in &ops {
+        positions.push((i, j));
+        match op {
+            Op::Equal => (i, j) = (i + 1, j + 1),
+            Op::Delete => i += 1,
+            Op::Insert => j += 1,
+        }
+    }
+    positions.push((i, j));
+
+    // Op ranges to show: each change widened by the context, merged when
+    // they touch
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for (k, _) in ops.iter().enumerate().filter(|(_, op)| **op != Op::Equal) {
+        let (start, end) = (k.saturating_sub(context), (k + context + 1).min(ops.len()));
+        match ranges.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+
+    ranges
+        .into_iter()
+        .map(|(start, end)| {
+            let lines: Vec<DiffLine> = (start..end)
+                .map(|k| {
+                    let (i, j) = positions[k];
+                    match ops[k] {
+                        Op::Equal => DiffLine::Context(a[i].to_string()),
+                        Op::Delete => DiffLine::Removed(a[i].to_string()),
+                        Op::Insert => DiffLine::Added(b[j].to_string()),
+                    }
+                })
+                .collect();
+            let (old_first, new_first) = positions[start];
+            let (old_end, new_end) = positions[end];
+            let (old_len, new_len) = (old_end - old_first, new_end - new_first);
+            Hunk {
+                old_start: if old_len == 0 { old_first } else { old_first + 1 },
+                old_len,
+                new_start: if new_len == 0 { new_first } else { new_first + 1 },
+                new_len,
+                lines,
+            }
+        })
+        .collect()
+}
+
+/// Equal/delete/insert steps from `a` to `b`, keeping the most lines equal
+fn line_ops(a: &[&str], b: &[&str]) -> Vec<Op> {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (a_mid, b_mid) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    let mut ops = vec![Op::Equal; prefix];
+    if a_mid.len() * b_mid.len() > MAX_TABLE {
+        ops.extend(std::iter::repeat_n(Op::Delete, a_mid.len()));
+        ops.extend(std::iter::repeat_n(Op::Insert, b_mid.len()));
+    } else {
+        ops.extend(lcs_ops(a_mid, b_mid));
+    }
+    ops.extend(std::iter::repeat_n(Op::Equal, suffix));
+    ops
+}
+
+fn lcs_ops(a: &[&str], b: &[&str]) -> Vec<Op> {
+    let (n, m) = (a.len(), b.len());
+    // lengths[i][j]: longest common subsequence of a[i..] and b[j..]
+    let mut lengths = vec![0u32; (n + 1) * (m + 1)];
+    let at = |i: usize, j: usize| i * (m + 1) + j;
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lengths[at(i, j)] = if a[i] == b[j] {
+                lengths[at(i + 1, j + 1)] + 1
+            } else {
+                lengths[at(i + 1, j)].max(lengths[at(i, j + 1)])
+            };
+        }
+    }
+
+    let mut ops = Vec::with_capacity(n + m);
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && a[i] == b[j] {
+            ops.push(Op::Equal);
+            i += 1;
+            j += 1;
+        } else if j < m && (i == n || lengths[at(i, j + 1)] >= lengths[at(i + 1, j)]) {
+            // Removed lines come before the added ones that replace them
+            if i < n && lengths[at(i + 1, j)] == lengths[at(i, j + 1)] {
+                ops.push(Op::Delete);
+                i += 1;
+            } else {
+                ops.push(Op::Insert);
+                j += 1;
+            }
+        } else {
+            ops.push(Op::Delete);
+            i += 1;
+        }
+    }
+    ops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_hunks_with_context() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk\n";
+        let hunks = diff_lines(old, new, 2);
+        assert_eq!(hunks.len(), 2);
+        assert_eq!(hunks[0].header(), "@@ -1,4 +1,4 @@");
+        assert_eq!(
+            hunks[0].lines[..3],
+            [
+                DiffLine::Context("a".into()),
+                DiffLine::Removed("b".into()),
+                DiffLine::Added("B".into()),
+            ]
+        );
+        assert_eq!(hunks[1].header(), "@@ -9,2 +9,3 @@");
+        assert_eq!(hunks[1].lines.last(), Some(&DiffLine::Added("k".into())));
+
+        // Close changes share a hunk
+        assert_eq!(diff_lines(old, "a\nB\nc\nD\ne\nf\ng\nh\ni\nj\n", 2).len(), 1);
+        assert!(diff_lines(old, old, 3).is_empty());
+    }
+
+    #[test]
+    fn test_unified_diff_of_new_file() {
+        let diff = FileDiff::new("wezterm.lua", None, "local wezterm = require 'wezterm'\nreturn {}\n");
+        assert_eq!(
+            diff.to_unified(),
+            "--- /dev/null\n+++ wezterm.lua\n@@ -0,0 +1,2 @@\n+local wezterm = require 'wezterm'\n+return {}\n"
+        );
+    }
+}
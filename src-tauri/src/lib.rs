@@ -1,13 +1,22 @@
 // WezTerm Settings GUI - Tauri backend
 
+pub mod atomic;
+pub mod backups;
 pub mod commands;
 pub mod config;
+pub mod conflict;
+pub mod diff;
 pub mod lua;
 pub mod models;
+pub mod validation;
 
 use commands::{
-    ensure_config_exists, get_builtin_color_schemes, get_config_path, get_default_config,
-    get_system_info, list_backdrop_images, load_wezterm_config, save_wezterm_config,
+    check_config_changes, diff_config_backup, ensure_config_exists, get_builtin_color_schemes,
+    get_config_path, get_default_config, get_settings_module_status, get_system_info,
+    install_wezterm_settings_module, list_backdrop_images, list_config_backups,
+    load_wezterm_config, merge_wezterm_config, parse_color, prune_config_backups,
+    restore_config_backup, save_wezterm_config, uninstall_wezterm_settings_module,
+    use_config_backup_folder, validate_config,
 };
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -23,11 +32,24 @@ pub fn run() {
             // Config commands
             load_wezterm_config,
             save_wezterm_config,
+            check_config_changes,
+            merge_wezterm_config,
+            validate_config,
             get_default_config,
+            get_settings_module_status,
+            install_wezterm_settings_module,
+            uninstall_wezterm_settings_module,
             // Color scheme commands
             get_builtin_color_schemes,
+            parse_color,
             // Backdrop commands
             list_backdrop_images,
+            // Backup commands
+            list_config_backups,
+            diff_config_backup,
+            restore_config_backup,
+            prune_config_backups,
+            use_config_backup_folder,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
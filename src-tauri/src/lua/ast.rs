@@ -0,0 +1,1044 @@
+// Lua AST - recursive-descent parser producing a syntax tree with source spans
+//
+// Covers the Lua 5.4 grammar so real-world configs (event handlers, helper
+// functions, plugin setup) parse completely. Callers such as the config
+// extractor only look at the parts they understand and ignore the rest.
+
+use super::lexer::{line_col, tokenize, Token, TokenKind};
+
+/// Byte range of a node in the source
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A sequence of statements (a chunk, function body or loop body)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    pub stats: Vec<Stat>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stat {
+    pub kind: StatKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StatKind {
+    /// `local a, b = x, y`
+    Local { names: Vec<String>, exprs: Vec<Expr> },
+    /// `a.b, c[d] = x, y`
+    Assign { targets: Vec<Expr>, exprs: Vec<Expr> },
+    /// A function or method call used as a statement
+    Call(Expr),
+    Do(Block),
+    While { cond: Expr, body: Block },
+    Repeat { body: Block, cond: Expr },
+    If {
+        branches: Vec<(Expr, Block)>,
+        otherwise: Option<Block>,
+    },
+    NumericFor {
+        var: String,
+        start: Expr,
+        limit: Expr,
+        step: Option<Expr>,
+        body: Block,
+    },
+    GenericFor {
+        names: Vec<String>,
+        exprs: Vec<Expr>,
+        body: Block,
+    },
+    /// `function a.b.c()` or `function a.b:c()`
+    Function {
+        name: Vec<String>,
+        method: Option<String>,
+        func: Function,
+    },
+    LocalFunction { name: String, func: Function },
+    Return(Vec<Expr>),
+    Break,
+    Goto(String),
+    Label(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    pub params: Vec<String>,
+    pub vararg: bool,
+    pub body: Block,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExprKind {
+    Nil,
+    Bool(bool),
+    Number(f64),
+    /// String literal with escapes decoded
+    String(String),
+    Vararg,
+    Function(Function),
+    Table(Vec<TableField>),
+    Name(String),
+    /// `object.name`
+    Field { object: Box<Expr>, name: String },
+    /// `object[key]`
+    Index { object: Box<Expr>, key: Box<Expr> },
+    Call { func: Box<Expr>, args: Vec<Expr> },
+    MethodCall {
+        object: Box<Expr>,
+        method: String,
+        args: Vec<Expr>,
+    },
+    Paren(Box<Expr>),
+    Unary { op: UnOp, expr: Box<Expr> },
+    Binary {
+        op: BinOp,
+        lhs: Box<Expr>,
+        rhs: Box<Expr>,
+    },
+}
+
+/// A field in a table constructor
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableField {
+    /// `None` for positional fields
+    pub key: Option<TableKey>,
+    pub value: Expr,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TableKey {
+    /// `name = value`
+    Name(String),
+    /// `[expr] = value`
+    Expr(Expr),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnOp {
+    Neg,
+    Not,
+    Len,
+    BNot,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    IDiv,
+    Mod,
+    Pow,
+    Concat,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+    BAnd,
+    BOr,
+    BXor,
+    Shl,
+    Shr,
+}
+
+impl Expr {
+    pub fn as_str(&self) -> Option<&str> {
+        match &self.kind {
+            ExprKind::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Numeric literal, including a negated one
+    pub fn as_number(&self) -> Option<f64> {
+        match &self.kind {
+            ExprKind::Number(n) => Some(*n),
+            ExprKind::Unary { op: UnOp::Neg, expr } => expr.as_number().map(|n| -n),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self.kind {
+            ExprKind::Bool(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    pub fn as_table(&self) -> Option<&[TableField]> {
+        match &self.kind {
+            ExprKind::Table(fields) => Some(fields),
+            _ => None,
+        }
+    }
+
+    /// `a.b["c"]` as `["a", "b", "c"]`, if the expression is such a path
+    pub fn dotted_path(&self) -> Option<Vec<&str>> {
+        match &self.kind {
+            ExprKind::Name(name) => Some(vec![name]),
+            ExprKind::Field { object, name } => {
+                let mut path = object.dotted_path()?;
+                path.push(name);
+                Some(path)
+            }
+            ExprKind::Index { object, key } => {
+                let mut path = object.dotted_path()?;
+                path.push(key.as_str()?);
+                Some(path)
+            }
+            _ => None,
+        }
+    }
+
+    /// Value of the last field named `key`, if this is a table constructor
+    pub fn table_get(&self, key: &str) -> Option<&Expr> {
+        self.as_table()?
+            .iter()
+            .rev()
+            .find(|f| f.name() == Some(key))
+            .map(|f| &f.value)
+    }
+
+    /// Positional (array) values, if this is a table constructor
+    pub fn table_items(&self) -> Option<Vec<&Expr>> {
+        Some(
+            self.as_table()?
+                .iter()
+                .filter(|f| f.key.is_none())
+                .map(|f| &f.value)
+                .collect(),
+        )
+    }
+}
+
+impl TableField {
+    /// The key as a string, for `name = v` and `['name'] = v` fields
+    pub fn name(&self) -> Option<&str> {
+        match &self.key {
+            Some(TableKey::Name(name)) => Some(name),
+            Some(TableKey::Expr(expr)) => expr.as_str(),
+            None => None,
+        }
+    }
+}
+
+/// Parse a Lua chunk
+pub fn parse_chunk(src: &str) -> Result<Block, String> {
+    let tokens = tokenize(src)?;
+    let mut parser = Parser {
+        src,
+        tokens,
+        pos: 0,
+    };
+    let block = parser.block()?;
+    if parser.pos < parser.tokens.len() {
+        return Err(parser.error("'<eof>' expected"));
+    }
+    Ok(block)
+}
+
+// ============================================================================
+// Parser
+// ============================================================================
+
+struct Parser<'a> {
+    src: &'a str,
+    tokens: Vec<Token<'a>>,
+    pos: usize,
+}
+
+/// Binary operator priorities (left, right), as in the reference implementation
+fn binary_op(t: &Token) -> Option<(BinOp, u8, u8)> {
+    let op = match (t.kind, t.text) {
+        (TokenKind::Name, "or") => (BinOp::Or, 1, 1),
+        (TokenKind::Name, "and") => (BinOp::And, 2, 2),
+        (TokenKind::Symbol, "<") => (BinOp::Lt, 3, 3),
+        (TokenKind::Symbol, ">") => (BinOp::Gt, 3, 3),
+        (TokenKind::Symbol, "<=") => (BinOp::Le, 3, 3),
+        (TokenKind::Symbol, ">=") => (BinOp::Ge, 3, 3),
+        (TokenKind::Symbol, "~=") => (BinOp::Ne, 3, 3),
+        (TokenKind::Symbol, "==") => (BinOp::Eq, 3, 3),
+        (TokenKind::Symbol, "|") => (BinOp::BOr, 4, 4),
+        (TokenKind::Symbol, "~") => (BinOp::BXor, 5, 5),
+        (TokenKind::Symbol, "&") => (BinOp::BAnd, 6, 6),
+        (TokenKind::Symbol, "<<") => (BinOp::Shl, 7, 7),
+        (TokenKind::Symbol, ">>") => (BinOp::Shr, 7, 7),
+        (TokenKind::Symbol, "..") => (BinOp::Concat, 9, 8),
+        (TokenKind::Symbol, "+") => (BinOp::Add, 10, 10),
+        (TokenKind::Symbol, "-") => (BinOp::Sub, 10, 10),
+        (TokenKind::Symbol, "*") => (BinOp::Mul, 11, 11),
+        (TokenKind::Symbol, "/") => (BinOp::Div, 11, 11),
+        (TokenKind::Symbol, "//") => (BinOp::IDiv, 11, 11),
+        (TokenKind::Symbol, "%") => (BinOp::Mod, 11, 11),
+        (TokenKind::Symbol, "^") => (BinOp::Pow, 14, 13),
+        _ => return None,
+    };
+    Some(op)
+}
+
+const UNARY_PRIORITY: u8 = 12;
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&Token<'a>> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&Token<'a>> {
+        self.tokens.get(self.pos + offset)
+    }
+
+    fn check_symbol(&self, s: &str) -> bool {
+        self.peek().is_some_and(|t| t.is_symbol(s))
+    }
+
+    fn check_keyword(&self, s: &str) -> bool {
+        self.peek().is_some_and(|t| t.is_name(s))
+    }
+
+    fn eat_symbol(&mut self, s: &str) -> bool {
+        let found = self.check_symbol(s);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn eat_keyword(&mut self, s: &str) -> bool {
+        let found = self.check_keyword(s);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect_symbol(&mut self, s: &str) -> Result<(), String> {
+        if self.eat_symbol(s) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("'{}' expected", s)))
+        }
+    }
+
+    fn expect_keyword(&mut self, s: &str) -> Result<(), String> {
+        if self.eat_keyword(s) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("'{}' expected", s)))
+        }
+    }
+
+    fn expect_name(&mut self) -> Result<String, String> {
+        match self.peek() {
+            Some(t) if t.kind == TokenKind::Name && !t.is_keyword() => {
+                let name = t.text.to_string();
+                self.pos += 1;
+                Ok(name)
+            }
+            _ => Err(self.error("<name> expected")),
+        }
+    }
+
+    /// Start offset of the next token
+    fn here(&self) -> usize {
+        self.peek().map_or(self.src.len(), |t| t.start)
+    }
+
+    /// End offset of the last consumed token
+    fn last_end(&self) -> usize {
+        self.pos
+            .checked_sub(1)
+            .and_then(|i| self.tokens.get(i))
+            .map_or(0, |t| t.end)
+    }
+
+    fn span_from(&self, start: usize) -> Span {
+        Span {
+            start,
+            end: self.last_end().max(start),
+        }
+    }
+
+    fn error(&self, msg: &str) -> String {
+        let (line, col) = line_col(self.src, self.here());
+        let near = self.peek().map_or("<eof>", |t| t.text);
+        format!("line {}:{}: {} near '{}'", line, col, msg, near)
+    }
+
+    // ------------------------------------------------------------------------
+    // Statements
+    // ------------------------------------------------------------------------
+
+    fn block_follows(&self) -> bool {
+        match self.peek() {
+            None => true,
+            Some(t) => ["else", "elseif", "end", "until"].iter().any(|k| t.is_name(k)),
+        }
+    }
+
+    fn block(&mut self) -> Result<Block, String> {
+        let start = self.here();
+        let mut stats = Vec::new();
+
+        while !self.block_follows() {
+            if self.check_keyword("return") {
+                stats.push(self.return_stat()?);
+                break;
+            }
+            if let Some(stat) = self.statement()? {
+                stats.push(stat);
+            }
+        }
+
+        Ok(Block {
+            stats,
+            span: self.span_from(start),
+        })
+    }
+
+    fn return_stat(&mut self) -> Result<Stat, String> {
+        let start = self.here();
+        self.expect_keyword("return")?;
+        let exprs = if self.block_follows() || self.check_symbol(";") {
+            Vec::new()
+        } else {
+            self.expr_list()?
+        };
+        self.eat_symbol(";");
+        Ok(Stat {
+            kind: StatKind::Return(exprs),
+            span: self.span_from(start),
+        })
+    }
+
+    fn statement(&mut self) -> Result<Option<Stat>, String> {
+        let start = self.here();
+        if self.eat_symbol(";") {
+            return Ok(None);
+        }
+
+        let kind = if self.eat_keyword("if") {
+            self.if_stat()?
+        } else if self.eat_keyword("while") {
+            let cond = self.expr()?;
+            self.expect_keyword("do")?;
+            let body = self.block()?;
+            self.expect_keyword("end")?;
+            StatKind::While { cond, body }
+        } else if self.eat_keyword("do") {
+            let body = self.block()?;
+            self.expect_keyword("end")?;
+            StatKind::Do(body)
+        } else if self.eat_keyword("for") {
+            self.for_stat()?
+        } else if self.eat_keyword("repeat") {
+            let body = self.block()?;
+            self.expect_keyword("until")?;
+            let cond = self.expr()?;
+            StatKind::Repeat { body, cond }
+        } else if self.eat_keyword("function") {
+            let mut name = vec![self.expect_name()?];
+            while self.eat_symbol(".") {
+                name.push(self.expect_name()?);
+            }
+            let method = if self.eat_symbol(":") {
+                Some(self.expect_name()?)
+            } else {
+                None
+            };
+            let func = self.function_body()?;
+            StatKind::Function { name, method, func }
+        } else if self.eat_keyword("local") {
+            if self.eat_keyword("function") {
+                let name = self.expect_name()?;
+                let func = self.function_body()?;
+                StatKind::LocalFunction { name, func }
+            } else {
+                self.local_stat()?
+            }
+        } else if self.eat_symbol("::") {
+            let label = self.expect_name()?;
+            self.expect_symbol("::")?;
+            StatKind::Label(label)
+        } else if self.eat_keyword("break") {
+            StatKind::Break
+        } else if self.eat_keyword("goto") {
+            StatKind::Goto(self.expect_name()?)
+        } else {
+            self.expr_stat()?
+        };
+
+        Ok(Some(Stat {
+            kind,
+            span: self.span_from(start),
+        }))
+    }
+
+    fn if_stat(&mut self) -> Result<StatKind, String> {
+        let mut branches = Vec::new();
+        let mut otherwise = None;
+
+        loop {
+            let cond = self.expr()?;
+            self.expect_keyword("then")?;
+            branches.push((cond, self.block()?));
+            if self.eat_keyword("elseif") {
+                continue;
+            }
+            if self.eat_keyword("else") {
+                otherwise = Some(self.block()?);
+            }
+            self.expect_keyword("end")?;
+            break;
+        }
+
+        Ok(StatKind::If {
+            branches,
+            otherwise,
+        })
+    }
+
+    fn for_stat(&mut self) -> Result<StatKind, String> {
+        let first = self.expect_name()?;
+
+        if self.eat_symbol("=") {
+            let start = self.expr()?;
+            self.expect_symbol(",")?;
+            let limit = self.expr()?;
+            let step = if self.eat_symbol(",") {
+                Some(self.expr()?)
+            } else {
+                None
+            };
+            self.expect_keyword("do")?;
+            let body = self.block()?;
+            self.expect_keyword("end")?;
+            return Ok(StatKind::NumericFor {
+                var: first,
+                start,
+                limit,
+                step,
+                body,
+            });
+        }
+
+        let mut names = vec![first];
+        while self.eat_symbol(",") {
+            names.push(self.expect_name()?);
+        }
+        self.expect_keyword("in")?;
+        let exprs = self.expr_list()?;
+        self.expect_keyword("do")?;
+        let body = self.block()?;
+        self.expect_keyword("end")?;
+        Ok(StatKind::GenericFor { names, exprs, body })
+    }
+
+    fn local_stat(&mut self) -> Result<StatKind, String> {
+        let mut names = Vec::new();
+        loop {
+            names.push(self.expect_name()?);
+            // Attributes: `local x <const> = 1`
+            if self.eat_symbol("<") {
+                self.expect_name()?;
+                self.expect_symbol(">")?;
+            }
+            if !self.eat_symbol(",") {
+                break;
+            }
+        }
+
+        let exprs = if self.eat_symbol("=") {
+            self.expr_list()?
+        } else {
+            Vec::new()
+        };
+        Ok(StatKind::Local { names, exprs })
+    }
+
+    fn expr_stat(&mut self) -> Result<StatKind, String> {
+        let first = self.suffixed_expr()?;
+
+        if self.check_symbol("=") || self.check_symbol(",") {
+            let mut targets = vec![first];
+            while self.eat_symbol(",") {
+                targets.push(self.suffixed_expr()?);
+            }
+            for target in &targets {
+                if !matches!(
+                    target.kind,
+                    ExprKind::Name(_) | ExprKind::Field { .. } | ExprKind::Index { .. }
+                ) {
+                    return Err(self.error("syntax error"));
+                }
+            }
+            self.expect_symbol("=")?;
+            let exprs = self.expr_list()?;
+            return Ok(StatKind::Assign { targets, exprs });
+        }
+
+        if matches!(first.kind, ExprKind::Call { .. } | ExprKind::MethodCall { .. }) {
+            Ok(StatKind::Call(first))
+        } else {
+            Err(self.error("syntax error"))
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    // Expressions
+    // ------------------------------------------------------------------------
+
+    fn expr_list(&mut self) -> Result<Vec<Expr>, String> {
+        let mut exprs = vec![self.expr()?];
+        while self.eat_symbol(",") {
+            exprs.push(self.expr()?);
+        }
+        Ok(exprs)
+    }
+
+    fn expr(&mut self) -> Result<Expr, String> {
+        self.sub_expr(0)
+    }
+
+    /// Parse an expression whose binary operators bind tighter than `limit`
+    fn sub_expr(&mut self, limit: u8) -> Result<Expr, String> {
+        let start = self.here();
+
+        let unary = self.peek().and_then(|t| match (t.kind, t.text) {
+            (TokenKind::Name, "not") => Some(UnOp::Not),
+            (TokenKind::Symbol, "-") => Some(UnOp::Neg),
+            (TokenKind::Symbol, "#") => Some(UnOp::Len),
+            (TokenKind::Symbol, "~") => Some(UnOp::BNot),
+            _ => None,
+        });
+
+        let mut lhs = match unary {
+            Some(op) => {
+                self.pos += 1;
+                let expr = self.sub_expr(UNARY_PRIORITY)?;
+                Expr {
+                    kind: ExprKind::Unary {
+                        op,
+                        expr: Box::new(expr),
+                    },
+                    span: self.span_from(start),
+                }
+            }
+            None => self.simple_expr()?,
+        };
+
+        while let Some((op, left, right)) = self.peek().and_then(binary_op) {
+            if left <= limit {
+                break;
+            }
+            self.pos += 1;
+            let rhs = self.sub_expr(right)?;
+            lhs = Expr {
+                kind: ExprKind::Binary {
+                    op,
+                    lhs: Box::new(lhs),
+                    rhs: Box::new(rhs),
+                },
+                span: self.span_from(start),
+            };
+        }
+
+        Ok(lhs)
+    }
+
+    fn simple_expr(&mut self) -> Result<Expr, String> {
+        let start = self.here();
+        let Some(t) = self.peek().copied() else {
+            return Err(self.error("unexpected symbol"));
+        };
+
+        let kind = match t.kind {
+            TokenKind::Number => {
+                self.pos += 1;
+                ExprKind::Number(
+                    parse_number(t.text).ok_or_else(|| self.error("malformed number"))?,
+                )
+            }
+            TokenKind::String => {
+                self.pos += 1;
+                ExprKind::String(decode_string(t.text).map_err(|e| self.error(&e))?)
+            }
+            TokenKind::Name if t.text == "nil" => {
+                self.pos += 1;
+                ExprKind::Nil
+            }
+            TokenKind::Name if t.text == "true" || t.text == "false" => {
+                self.pos += 1;
+                ExprKind::Bool(t.text == "true")
+            }
+            TokenKind::Name if t.text == "function" => {
+                self.pos += 1;
+                ExprKind::Function(self.function_body()?)
+            }
+            TokenKind::Symbol if t.text == "..." => {
+                self.pos += 1;
+                ExprKind::Vararg
+            }
+            TokenKind::Symbol if t.text == "{" => ExprKind::Table(self.table()?),
+            _ => return self.suffixed_expr(),
+        };
+
+        Ok(Expr {
+            kind,
+            span: self.span_from(start),
+        })
+    }
+
+    fn primary_expr(&mut self) -> Result<Expr, String> {
+        let start = self.here();
+        if self.eat_symbol("(") {
+            let inner = self.expr()?;
+            self.expect_symbol(")")?;
+            return Ok(Expr {
+                kind: ExprKind::Paren(Box::new(inner)),
+                span: self.span_from(start),
+            });
+        }
+
+        match self.peek() {
+            Some(t) if t.kind == TokenKind::Name && !t.is_keyword() => {
+                let name = self.expect_name()?;
+                Ok(Expr {
+                    kind: ExprKind::Name(name),
+                    span: self.span_from(start),
+                })
+            }
+            _ => Err(self.error("unexpected symbol")),
+        }
+    }
+
+    fn suffixed_expr(&mut self) -> Result<Expr, String> {
+        let start = self.here();
+        let mut expr = self.primary_expr()?;
+
+        loop {
+            let kind = if self.eat_symbol(".") {
+                ExprKind::Field {
+                    object: Box::new(expr),
+                    name: self.expect_name()?,
+                }
+            } else if self.eat_symbol("[") {
+                let key = self.expr()?;
+                self.expect_symbol("]")?;
+                ExprKind::Index {
+                    object: Box::new(expr),
+                    key: Box::new(key),
+                }
+            } else if self.eat_symbol(":") {
+                let method = self.expect_name()?;
+                let args = self.call_args()?;
+                ExprKind::MethodCall {
+                    object: Box::new(expr),
+                    method,
+                    args,
+                }
+            } else if self.check_symbol("(")
+                || self.check_symbol("{")
+                || self.peek().is_some_and(|t| t.kind == TokenKind::String)
+            {
+                let args = self.call_args()?;
+                ExprKind::Call {
+                    func: Box::new(expr),
+                    args,
+                }
+            } else {
+                return Ok(expr);
+            };
+
+            expr = Expr {
+                kind,
+                span: self.span_from(start),
+            };
+        }
+    }
+
+    /// `(a, b)`, `"str"` or `{ table }`
+    fn call_args(&mut self) -> Result<Vec<Expr>, String> {
+        if self.eat_symbol("(") {
+            if self.eat_symbol(")") {
+                return Ok(Vec::new());
+            }
+            let args = self.expr_list()?;
+            self.expect_symbol(")")?;
+            return Ok(args);
+        }
+
+        match self.peek() {
+            Some(t) if t.kind == TokenKind::String || t.is_symbol("{") => {
+                Ok(vec![self.simple_expr()?])
+            }
+            _ => Err(self.error("function arguments expected")),
+        }
+    }
+
+    fn function_body(&mut self) -> Result<Function, String> {
+        self.expect_symbol("(")?;
+        let mut params = Vec::new();
+        let mut vararg = false;
+
+        if !self.check_symbol(")") {
+            loop {
+                if self.eat_symbol("...") {
+                    vararg = true;
+                    break;
+                }
+                params.push(self.expect_name()?);
+                if !self.eat_symbol(",") {
+                    break;
+                }
+            }
+        }
+        self.expect_symbol(")")?;
+
+        let body = self.block()?;
+        self.expect_keyword("end")?;
+        Ok(Function {
+            params,
+            vararg,
+            body,
+        })
+    }
+
+    fn table(&mut self) -> Result<Vec<TableField>, String> {
+        self.expect_symbol("{")?;
+        let mut fields = Vec::new();
+
+        while !self.check_symbol("}") {
+            let start = self.here();
+            let key = if self.check_symbol("[") {
+                self.pos += 1;
+                let key = self.expr()?;
+                self.expect_symbol("]")?;
+                self.expect_symbol("=")?;
+                Some(TableKey::Expr(key))
+            } else if self.peek().is_some_and(|t| t.kind == TokenKind::Name && !t.is_keyword())
+                && self.peek_at(1).is_some_and(|t| t.is_symbol("="))
+            {
+                let name = self.expect_name()?;
+                self.pos += 1;
+                Some(TableKey::Name(name))
+            } else {
+                None
+            };
+
+            let value = self.expr()?;
+            fields.push(TableField {
+                key,
+                value,
+                span: self.span_from(start),
+            });
+
+            if !self.eat_symbol(",") && !self.eat_symbol(";") {
+                break;
+            }
+        }
+
+        self.expect_symbol("}")?;
+        Ok(fields)
+    }
+}
+
+// ============================================================================
+// Literals
+// ============================================================================
+
+fn parse_number(text: &str) -> Option<f64> {
+    let lower = text.to_ascii_lowercase();
+    match lower.strip_prefix("0x") {
+        Some(hex) => {
+            // Hex floats (`0x1p4`) are rare enough in configs to not support
+            u64::from_str_radix(hex, 16).ok().map(|n| n as f64)
+        }
+        None => lower.parse().ok(),
+    }
+}
+
+/// Decode a quoted or long-bracket string literal
+fn decode_string(text: &str) -> Result<String, String> {
+    if let Some(rest) = text.strip_prefix('[') {
+        let level = rest.chars().take_while(|&c| c == '=').count();
+        let inner = &text[level + 2..text.len() - level - 2];
+        // A newline right after the opening bracket is skipped
+        let inner = inner
+            .strip_prefix("\r\n")
+            .or_else(|| inner.strip_prefix('\n'))
+            .unwrap_or(inner);
+        return Ok(inner.to_string());
+    }
+
+    let inner = &text[1..text.len() - 1];
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        let Some(e) = chars.next() else {
+            return Err("unfinished string".to_string());
+        };
+        match e {
+            'n' => out.push('\n'),
+            't' => out.push('\t'),
+            'r' => out.push('\r'),
+            'a' => out.push('\x07'),
+            'b' => out.push('\x08'),
+            'f' => out.push('\x0c'),
+            'v' => out.push('\x0b'),
+            '\\' | '"' | '\'' | '\n' => out.push(e),
+            'x' => {
+                let hex: String = chars.by_ref().take(2).collect();
+                let byte = u8::from_str_radix(&hex, 16)
+                    .map_err(|_| "hexadecimal digit expected".to_string())?;
+                out.push(byte as char);
+            }
+            'z' => {
+                while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                    chars.next();
+                }
+            }
+            'u' => {
+                if chars.next() != Some('{') {
+                    return Err("missing '{' in \\u{xxxx}".to_string());
+                }
+                let hex: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                let ch = u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| "UTF-8 value too large".to_string())?;
+                out.push(ch);
+            }
+            d if d.is_ascii_digit() => {
+                let mut value = d.to_digit(10).unwrap_or(0);
+                for _ in 0..2 {
+                    match chars.peek().and_then(|c| c.to_digit(10)) {
+                        Some(digit) => {
+                            value = value * 10 + digit;
+                            chars.next();
+                        }
+                        None => break,
+                    }
+                }
+                let byte = u8::try_from(value).map_err(|_| "decimal escape too large".to_string())?;
+                out.push(byte as char);
+            }
+            _ => return Err("invalid escape sequence".to_string()),
+        }
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn single_expr(src: &str) -> Expr {
+        let block = parse_chunk(&format!("return {}", src)).unwrap();
+        match block.stats.into_iter().next().unwrap().kind {
+            StatKind::Return(mut exprs) => exprs.remove(0),
+            other => panic!("unexpected statement {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_operator_precedence() {
+        let expr = single_expr("1 + 2 * 3 .. 'x' .. 'y'");
+        let ExprKind::Binary { op: BinOp::Concat, lhs, rhs } = expr.kind else {
+            panic!("expected concat");
+        };
+        assert!(matches!(lhs.kind, ExprKind::Binary { op: BinOp::Add, .. }));
+        // `..` is right associative
+        assert!(matches!(rhs.kind, ExprKind::Binary { op: BinOp::Concat, .. }));
+
+        let expr = single_expr("-2 ^ 2");
+        assert!(matches!(expr.kind, ExprKind::Unary { op: UnOp::Neg, .. }));
+    }
+
+    #[test]
+    fn test_string_escapes() {
+        assert_eq!(single_expr(r#""a\tb\65\x41\u{263A}\\""#).as_str(), Some("a\tbAA\u{263A}\\"));
+        assert_eq!(single_expr("[==[\nraw ]] text]==]").as_str(), Some("raw ]] text"));
+    }
+
+    #[test]
+    fn test_table_and_spans() {
+        let src = "config.colors = { ansi = { 'a', 'b' }, ['tab_bar'] = { background = '#000' } }";
+        let block = parse_chunk(src).unwrap();
+        let StatKind::Assign { targets, exprs } = &block.stats[0].kind else {
+            panic!("expected assignment");
+        };
+        assert_eq!(targets[0].dotted_path(), Some(vec!["config", "colors"]));
+        let colors = &exprs[0];
+        assert_eq!(&src[colors.span.start..colors.span.end], &src[16..]);
+        assert_eq!(colors.table_get("ansi").unwrap().table_items().unwrap().len(), 2);
+        assert_eq!(
+            colors.table_get("tab_bar").unwrap().table_get("background").unwrap().as_str(),
+            Some("#000")
+        );
+    }
+
+    #[test]
+    fn test_full_config_parses() {
+        let src = r#"
+local wezterm = require 'wezterm'
+local act <const> = wezterm.action
+local config = wezterm.config_builder()
+
+local function is_windows()
+  return wezterm.target_triple:find('windows') ~= nil
+end
+
+if is_windows() then
+  config.default_prog = { 'pwsh.exe' }
+elseif #arg > 0 then
+  config.font_size = 10
+else
+  config.font_size = 12.5
+end
+
+for _, k in ipairs({ 'a', 'b' }) do
+  table.insert(config.keys, { key = k, mods = 'ALT', action = act.Nop })
+end
+
+wezterm.on('gui-startup', function(cmd, ...)
+  local tab, pane, window = wezterm.mux.spawn_window(cmd or {})
+  window:gui_window():maximize()
+end)
+
+return config
+"#;
+        let block = parse_chunk(src).unwrap();
+        assert_eq!(block.stats.len(), 8);
+        assert!(matches!(block.stats[7].kind, StatKind::Return(_)));
+    }
+
+    #[test]
+    fn test_syntax_error_location() {
+        let err = parse_chunk("local x = 1\nconfig.font_size = = 2\n").unwrap_err();
+        assert!(err.starts_with("line 2:20:"), "{}", err);
+    }
+}
@@ -0,0 +1,1068 @@
+// Lua editor - surgical, comment-preserving edits of an existing wezterm.lua
+//
+// Instead of regenerating the whole file, this finds the existing
+// `config.<key> = ...` assignments (and fields inside table constructors such
+// as `config.colors = { tab_bar = { active_tab = { ... } } }`) and rewrites
+// only the values that changed. New keys are inserted before `return config`.
+// A module that returns its settings as a table literal is edited the same
+// way, through the fields of the returned table.
+// Everything else - comments, event handlers, plugin requires - is left
+// byte-for-byte intact.
+
+use super::generator::{LuaSetting, SETTINGS_MODULE};
+use super::lexer::{tokenize, unquote, Token, TokenKind};
+use std::collections::HashMap;
+
+/// Result of applying settings to an existing config file
+#[derive(Debug, Clone)]
+pub struct EditResult {
+    /// The edited file content
+    pub content: String,
+    /// Dotted keys that were updated, inserted or removed
+    pub changed: Vec<String>,
+    /// Dotted keys that could not be written because the file computes
+    /// the surrounding table (e.g. `config.colors = my_colors`)
+    pub skipped: Vec<String>,
+}
+
+/// Apply `desired` settings to `source`.
+///
+/// `current` is what the file holds today (as understood by the parser); only
+/// settings whose value differs from it are touched. Settings present in
+/// `current` but missing from `desired` are removed from the file.
+pub fn apply_settings(
+    source: &str,
+    current: &[LuaSetting],
+    desired: &[LuaSetting],
+) -> Result<EditResult, String> {
+    let tokens = tokenize(source)?;
+    let mut assignments = find_assignments(&tokens);
+    assignments.extend(returned_table(&tokens));
+    let current_values: HashMap<&[&str], &str> = current
+        .iter()
+        .map(|s| (s.path.as_slice(), s.value.as_str()))
+        .collect();
+
+    let mut edits = Vec::new();
+    let mut changed = Vec::new();
+    let mut skipped = Vec::new();
+    let mut constructor_inserts: Vec<(usize, InsertTree)> = Vec::new();
+    let mut new_statements = Vec::new();
+    let mut whole_tops: Vec<&str> = Vec::new();
+
+    for setting in desired {
+        let path = setting.path.as_slice();
+        if current_values.get(path) == Some(&setting.value.as_str()) {
+            continue;
+        }
+
+        match locate(&tokens, &assignments, path) {
+            Location::Value { value, .. } => {
+                let (start, end) = (tokens[value.0].start, tokens[value.1 - 1].end);
+                if source[start..end] != setting.value {
+                    edits.push(Edit::new(start, end, setting.value.clone()).for_key(setting.key()));
+                    changed.push(setting.key());
+                }
+            }
+            Location::MissingField { constructor, rest } => {
+                let tree = match constructor_inserts.iter_mut().find(|(open, _)| *open == constructor) {
+                    Some((_, tree)) => tree,
+                    None => {
+                        constructor_inserts.push((constructor, InsertTree::default()));
+                        &mut constructor_inserts.last_mut().unwrap().1
+                    }
+                };
+                if rest.len() == 1 {
+                    tree.insert(rest, &setting.value);
+                    changed.push(setting.key());
+                } else if !tree.contains(rest[0]) {
+                    // A new nested table gets every field under it, not just this one
+                    let depth = path.len() - rest.len();
+                    for s in desired.iter().filter(|s| s.path.starts_with(&path[..=depth])) {
+                        tree.insert(&s.path[depth..], &s.value);
+                        changed.push(s.key());
+                    }
+                }
+            }
+            Location::TopMissing => {
+                if !whole_tops.contains(&path[0]) {
+                    whole_tops.push(path[0]);
+                }
+            }
+            Location::DottedMissing => {
+                new_statements.push(format!("config.{} = {}", setting.key(), setting.value));
+                changed.push(setting.key());
+            }
+            Location::Computed => skipped.push(setting.key()),
+        }
+    }
+
+    // A key the file never assigns is written out in full, so the table
+    // WezTerm sees matches what the settings UI shows.
+    for top in &whole_tops {
+        let mut tree = InsertTree::default();
+        for setting in desired.iter().filter(|s| s.path[0] == *top) {
+            changed.push(setting.key());
+            if setting.path.len() == 1 {
+                new_statements.push(format!("config.{} = {}", top, setting.value));
+            } else {
+                tree.insert(&setting.path[1..], &setting.value);
+            }
+        }
+        if !tree.entries.is_empty() {
+            new_statements.push(format!("config.{} = {}", top, tree.render("", "  ")));
+        }
+    }
+
+    // Removals: whole keys that are no longer generated, or single fields
+    let desired_paths: Vec<&[&str]> = desired.iter().map(|s| s.path.as_slice()).collect();
+    let mut removed_tops: Vec<&str> = Vec::new();
+    for setting in current {
+        let path = setting.path.as_slice();
+        if desired_paths.contains(&path) {
+            continue;
+        }
+        if !desired.iter().any(|s| s.path[0] == path[0]) {
+            if removed_tops.contains(&path[0]) {
+                continue;
+            }
+            removed_tops.push(path[0]);
+            let dotted: Vec<&Assignment> = assignments
+                .iter()
+                .filter(|a| a.path.first().is_some_and(|p| p == path[0]))
+                .collect();
+            for assignment in &dotted {
+                let key = assignment.path.join(".");
+                edits.push(removal_edit(source, assignment.span).for_key(&key));
+                changed.push(key);
+            }
+            if dotted.is_empty() {
+                if let Location::Value { remove, .. } = locate(&tokens, &assignments, &path[..1]) {
+                    edits.push(removal_edit(source, remove).for_key(path[0]));
+                    changed.push(path[0].to_string());
+                }
+            }
+        } else if let Location::Value { remove, .. } = locate(&tokens, &assignments, path) {
+            edits.push(removal_edit(source, remove).for_key(setting.key()));
+            changed.push(setting.key());
+        }
+    }
+
+    for (open, tree) in &constructor_inserts {
+        let keys: Vec<&str> = tree.entries.iter().map(|(key, _)| key.as_str()).collect();
+        let inserts = constructor_insert_edits(source, &tokens, *open, tree);
+        edits.extend(inserts.into_iter().map(|edit| edit.for_key(keys.join(", "))));
+    }
+
+    if !new_statements.is_empty() {
+        let keys: Vec<&str> = new_statements
+            .iter()
+            .filter_map(|s| s.split(" = ").next())
+            .map(|s| s.trim_start_matches("config."))
+            .collect();
+        let edit = statement_insert_edit(source, &tokens, &new_statements);
+        edits.push(edit.for_key(keys.join(", ")));
+    }
+
+    Ok(EditResult {
+        content: apply_edits(source, edits)?,
+        changed,
+        skipped,
+    })
+}
+
+/// Whether `source` is written in a style this editor understands:
+/// `config.<key> = value` assignments, a chunk ending in `return config` that
+/// new assignments can go before, or a returned table literal
+/// (`return { ... }`). Anything else is regenerated instead.
+pub fn edits_in_place(source: &str) -> bool {
+    tokenize(source).is_ok_and(|tokens| {
+        let returns_config = top_level_return(&tokens)
+            .and_then(|at| tokens.get(at + 1))
+            .is_some_and(|t| t.is_name("config"));
+        !find_assignments(&tokens).is_empty() || returns_config || returned_table(&tokens).is_some()
+    })
+}
+
+// ============================================================================
+// Finding Assignments
+// ============================================================================
+
+/// A `config.a.b = value` statement
+#[derive(Debug)]
+struct Assignment {
+    path: Vec<String>,
+    /// Token index of the `config` name
+    first: usize,
+    /// Token range `[start, end)` of the value expression
+    value: (usize, usize),
+    /// Byte span of the whole statement, including a trailing `;`
+    span: (usize, usize),
+}
+
+fn find_assignments(tokens: &[Token]) -> Vec<Assignment> {
+    let mut assignments = Vec::new();
+    let config_names = config_names(tokens);
+
+    for i in 0..tokens.len() {
+        if !config_names.iter().any(|name| tokens[i].is_name(name)) {
+            continue;
+        }
+        if i > 0 && (tokens[i - 1].is_symbol(".") || tokens[i - 1].is_symbol(":")) {
+            continue;
+        }
+
+        let mut path = Vec::new();
+        let mut j = i + 1;
+        loop {
+            match (tokens.get(j), tokens.get(j + 1), tokens.get(j + 2)) {
+                (Some(dot), Some(name), _)
+                    if dot.is_symbol(".") && name.kind == TokenKind::Name =>
+                {
+                    path.push(name.text.to_string());
+                    j += 2;
+                }
+                (Some(open), Some(key), Some(close))
+                    if open.is_symbol("[") && key.kind == TokenKind::String && close.is_symbol("]") =>
+                {
+                    match unquote(key.text) {
+                        Some(k) => path.push(k),
+                        None => break,
+                    }
+                    j += 3;
+                }
+                _ => break,
+            }
+        }
+
+        if path.is_empty() || !tokens.get(j).is_some_and(|t| t.is_symbol("=")) {
+            continue;
+        }
+
+        let value_start = j + 1;
+        let value_end = expr_end(tokens, value_start);
+        if value_end == value_start {
+            continue;
+        }
+        let mut end = tokens[value_end - 1].end;
+        if tokens.get(value_end).is_some_and(|t| t.is_symbol(";")) {
+            end = tokens[value_end].end;
+        }
+
+        assignments.push(Assignment {
+            path,
+            first: i,
+            value: (value_start, value_end),
+            span: (tokens[i].start, end),
+        });
+    }
+
+    assignments
+}
+
+/// Names the config table goes by: `config`, and the parameter of a module's
+/// `function M.apply_to_config(c)`
+fn config_names<'a>(tokens: &[Token<'a>]) -> Vec<&'a str> {
+    let mut names = vec!["config"];
+    for (i, t) in tokens.iter().enumerate() {
+        if !t.is_name("function") {
+            continue;
+        }
+        let mut j = i + 1;
+        while tokens.get(j).is_some_and(|t| {
+            (t.kind == TokenKind::Name && !t.is_keyword()) || t.is_symbol(".") || t.is_symbol(":")
+        }) {
+            j += 1;
+        }
+        let defines_apply = j > i + 1 && tokens[j - 1].is_name("apply_to_config");
+        if let (true, Some(open), Some(param)) = (defines_apply, tokens.get(j), tokens.get(j + 1)) {
+            if open.is_symbol("(") && param.kind == TokenKind::Name && !names.contains(&param.text) {
+                names.push(param.text);
+            }
+        }
+    }
+    names
+}
+
+/// A module that returns its settings (`return { font_size = 12 }`) as an
+/// assignment to the whole config, so its fields are found like
+/// `config.font_size` would be
+fn returned_table(tokens: &[Token]) -> Option<Assignment> {
+    let at = top_level_return(tokens)?;
+    let value = (at + 1, expr_end(tokens, at + 1));
+    value_constructor(tokens, value)?;
+    Some(Assignment {
+        path: Vec::new(),
+        first: at,
+        value,
+        span: (tokens[at].start, tokens[value.1 - 1].end),
+    })
+}
+
+/// Find the end (exclusive token index) of the expression starting at `start`
+fn expr_end(tokens: &[Token], start: usize) -> usize {
+    let mut depth = 0usize;
+    let mut i = start;
+
+    while i < tokens.len() {
+        let t = &tokens[i];
+        if depth == 0 {
+            if [",", ";", ")", "}", "]"].iter().any(|s| t.is_symbol(s)) {
+                break;
+            }
+            if i > start && starts_statement(&tokens[i - 1], t) {
+                break;
+            }
+        }
+
+        if opens_block(t) {
+            depth += 1;
+        } else if closes_block(t) {
+            depth = depth.saturating_sub(1);
+        }
+        i += 1;
+    }
+
+    i
+}
+
+fn opens_block(t: &Token) -> bool {
+    ["(", "{", "["].iter().any(|s| t.is_symbol(s))
+        || ["function", "if", "do", "repeat"].iter().any(|s| t.is_name(s))
+}
+
+fn closes_block(t: &Token) -> bool {
+    [")", "}", "]"].iter().any(|s| t.is_symbol(s)) || t.is_name("end") || t.is_name("until")
+}
+
+/// Whether `next` starts a new statement rather than continuing the expression ending in `prev`
+fn starts_statement(prev: &Token, next: &Token) -> bool {
+    const BINARY_OPS: &[&str] = &[
+        "..", "+", "-", "*", "/", "//", "%", "^", "==", "~=", "<", ">", "<=", ">=", "&", "|",
+        "~", "<<", ">>",
+    ];
+    if BINARY_OPS.iter().any(|s| next.is_symbol(s)) || next.is_name("and") || next.is_name("or") {
+        return false;
+    }
+
+    let prev_ends_operand = match prev.kind {
+        TokenKind::Number | TokenKind::String => true,
+        TokenKind::Name => !prev.is_keyword() || ["true", "false", "nil", "end"].contains(&prev.text),
+        TokenKind::Symbol => [")", "]", "}", "..."].contains(&prev.text),
+    };
+    if !prev_ends_operand {
+        return false;
+    }
+
+    // Field access and call syntax continue the expression
+    let continues = [".", ":", "[", "(", "{"].iter().any(|s| next.is_symbol(s))
+        || next.kind == TokenKind::String;
+    !continues
+}
+
+// ============================================================================
+// Table Constructors
+// ============================================================================
+
+#[derive(Debug)]
+struct Field {
+    key: Option<String>,
+    /// Token index where the field starts (key or positional value)
+    first: usize,
+    value: (usize, usize),
+    /// Token index of the trailing `,` or `;`
+    separator: Option<usize>,
+}
+
+#[derive(Debug)]
+struct Constructor {
+    open: usize,
+    close: usize,
+    fields: Vec<Field>,
+}
+
+fn parse_constructor(tokens: &[Token], open: usize) -> Option<Constructor> {
+    if !tokens.get(open)?.is_symbol("{") {
+        return None;
+    }
+
+    let mut fields = Vec::new();
+    let mut i = open + 1;
+    loop {
+        let t = tokens.get(i)?;
+        if t.is_symbol("}") {
+            return Some(Constructor { open, close: i, fields });
+        }
+
+        let first = i;
+        let (key, value_start) = if t.kind == TokenKind::Name
+            && !t.is_keyword()
+            && tokens.get(i + 1).is_some_and(|n| n.is_symbol("="))
+        {
+            (Some(t.text.to_string()), i + 2)
+        } else if t.is_symbol("[") {
+            let close = expr_end(tokens, i + 1);
+            if !tokens.get(close)?.is_symbol("]") || !tokens.get(close + 1)?.is_symbol("=") {
+                return None;
+            }
+            let key = if close == i + 2 { unquote(tokens[i + 1].text) } else { None };
+            (key, close + 2)
+        } else {
+            (None, i)
+        };
+
+        let value_end = expr_end(tokens, value_start);
+        if value_end == value_start {
+            return None;
+        }
+
+        let separator = tokens
+            .get(value_end)
+            .filter(|t| t.is_symbol(",") || t.is_symbol(";"))
+            .map(|_| value_end);
+        i = separator.map_or(value_end, |s| s + 1);
+
+        fields.push(Field {
+            key,
+            first,
+            value: (value_start, value_end),
+            separator,
+        });
+    }
+}
+
+/// If the value tokens are exactly one table constructor, parse it
+fn value_constructor(tokens: &[Token], value: (usize, usize)) -> Option<Constructor> {
+    parse_constructor(tokens, value.0).filter(|c| c.close + 1 == value.1)
+}
+
+// ============================================================================
+// Locating Settings
+// ============================================================================
+
+enum Location<'p> {
+    /// The value exists; `value` is its token range and `remove` the byte
+    /// span to delete when removing the whole statement or field
+    Value {
+        value: (usize, usize),
+        remove: (usize, usize),
+    },
+    /// A table constructor exists but lacks the remaining fields
+    MissingField { constructor: usize, rest: &'p [&'p str] },
+    /// Nothing in the file assigns `config.<top>`
+    TopMissing,
+    /// `config.<top>` is set elsewhere, but no assignment covers this path
+    DottedMissing,
+    /// The value is inside a table the file computes rather than writes out
+    Computed,
+}
+
+fn locate<'p>(tokens: &[Token], assignments: &[Assignment], path: &'p [&'p str]) -> Location<'p> {
+    let is_prefix = |a: &Assignment| {
+        a.path.len() <= path.len() && a.path.iter().zip(path).all(|(a, b)| a == b)
+    };
+
+    // The last assignment wins, as it does when WezTerm runs the file
+    match assignments.iter().filter(|a| is_prefix(a)).max_by_key(|a| a.first) {
+        None if assignments.iter().any(|a| a.path.first().is_some_and(|p| p == path[0])) => {
+            Location::DottedMissing
+        }
+        None => Location::TopMissing,
+        Some(a) if a.path.len() == path.len() => Location::Value {
+            value: a.value,
+            remove: a.span,
+        },
+        Some(a) => descend(tokens, a.value, &path[a.path.len()..]),
+    }
+}
+
+fn descend<'p>(tokens: &[Token], value: (usize, usize), rest: &'p [&'p str]) -> Location<'p> {
+    let Some(constructor) = value_constructor(tokens, value) else {
+        return Location::Computed;
+    };
+
+    let field = constructor
+        .fields
+        .iter()
+        .rev()
+        .find(|f| f.key.as_deref() == Some(rest[0]));
+
+    match field {
+        None => Location::MissingField {
+            constructor: constructor.open,
+            rest,
+        },
+        Some(f) if rest.len() == 1 => {
+            let end = f.separator.unwrap_or(f.value.1 - 1);
+            Location::Value {
+                value: f.value,
+                remove: (tokens[f.first].start, tokens[end].end),
+            }
+        }
+        Some(f) => descend(tokens, f.value, &rest[1..]),
+    }
+}
+
+// ============================================================================
+// Building Edits
+// ============================================================================
+
+/// A replacement of `source[start..end]` with `text`
+#[derive(Debug)]
+struct Edit {
+    start: usize,
+    end: usize,
+    text: String,
+    /// The setting the edit writes, to name it when edits clash
+    key: String,
+}
+
+impl Edit {
+    fn new(start: usize, end: usize, text: String) -> Self {
+        Self { start, end, text, key: String::new() }
+    }
+
+    fn insert(at: usize, text: String) -> Self {
+        Self::new(at, at, text)
+    }
+
+    fn for_key(mut self, key: impl Into<String>) -> Self {
+        self.key = key.into();
+        self
+    }
+}
+
+/// Apply `edits` to `source`. Edits that overlap would lose one of the
+/// changes, so they are an error naming both settings.
+fn apply_edits(source: &str, mut edits: Vec<Edit>) -> Result<String, String> {
+    edits.sort_by_key(|e| e.start);
+
+    let mut out = String::with_capacity(source.len());
+    let mut cursor = 0;
+    let mut previous: Option<&Edit> = None;
+    for edit in &edits {
+        if let Some(previous) = previous.filter(|_| edit.start < cursor) {
+            return Err(format!(
+                "the changes to {} and {} overlap in the file, so {} can't be saved",
+                previous.key, edit.key, edit.key
+            ));
+        }
+        out.push_str(&source[cursor..edit.start]);
+        out.push_str(&edit.text);
+        cursor = edit.end;
+        previous = Some(edit);
+    }
+    out.push_str(&source[cursor..]);
+    Ok(out)
+}
+
+/// Missing fields to insert, as a nested tree of `key = value` pairs
+#[derive(Debug, Default)]
+struct InsertTree {
+    entries: Vec<(String, InsertNode)>,
+}
+
+#[derive(Debug)]
+enum InsertNode {
+    Value(String),
+    Table(InsertTree),
+}
+
+impl InsertTree {
+    fn insert(&mut self, path: &[&str], value: &str) {
+        let Some((first, rest)) = path.split_first() else {
+            return;
+        };
+        if rest.is_empty() {
+            self.entries.push((first.to_string(), InsertNode::Value(value.to_string())));
+            return;
+        }
+        let index = match self.entries.iter().position(|(k, _)| k == first) {
+            Some(i) => i,
+            None => {
+                self.entries
+                    .push((first.to_string(), InsertNode::Table(InsertTree::default())));
+                self.entries.len() - 1
+            }
+        };
+        if let InsertNode::Table(tree) = &mut self.entries[index].1 {
+            tree.insert(rest, value);
+        }
+    }
+
+    fn contains(&self, key: &str) -> bool {
+        self.entries.iter().any(|(k, _)| k == key)
+    }
+
+    fn render_entries(&self, indent: &str, step: &str) -> Vec<String> {
+        self.entries
+            .iter()
+            .map(|(key, node)| format!("{} = {}", key, node.render(indent, step)))
+            .collect()
+    }
+
+    /// Render as a multi-line table constructor whose closing brace sits at `indent`
+    fn render(&self, indent: &str, step: &str) -> String {
+        let inner = format!("{}{}", indent, step);
+        let mut s = String::from("{\n");
+        for entry in self.render_entries(&inner, step) {
+            s.push_str(&format!("{}{},\n", inner, entry));
+        }
+        s.push_str(indent);
+        s.push('}');
+        s
+    }
+}
+
+impl InsertNode {
+    fn render(&self, indent: &str, step: &str) -> String {
+        match self {
+            InsertNode::Value(v) => v.clone(),
+            InsertNode::Table(tree) => tree.render(indent, step),
+        }
+    }
+}
+
+/// Byte offset of the start of the line containing `pos`
+fn line_start(source: &str, pos: usize) -> usize {
+    source[..pos].rfind('\n').map_or(0, |i| i + 1)
+}
+
+/// Leading whitespace of the line containing `pos`
+fn line_indent(source: &str, pos: usize) -> &str {
+    let start = line_start(source, pos);
+    let rest = &source[start..];
+    &rest[..rest.len() - rest.trim_start_matches([' ', '\t']).len()]
+}
+
+/// Whether only whitespace precedes `pos` on its line
+fn first_on_line(source: &str, pos: usize) -> bool {
+    source[line_start(source, pos)..pos].trim().is_empty()
+}
+
+fn constructor_insert_edits(
+    source: &str,
+    tokens: &[Token],
+    open: usize,
+    tree: &InsertTree,
+) -> Vec<Edit> {
+    let Some(constructor) = parse_constructor(tokens, open) else {
+        return Vec::new();
+    };
+    let close = &tokens[constructor.close];
+    let close_indent = line_indent(source, close.start);
+    let mut edits = Vec::new();
+
+    if first_on_line(source, close.start) && constructor.close > open + 1 {
+        // Multi-line constructor: add each field on its own line above `}`
+        let field_indent = constructor
+            .fields
+            .first()
+            .map(|f| tokens[f.first].start)
+            .filter(|&pos| first_on_line(source, pos))
+            .map(|pos| line_indent(source, pos).to_string())
+            .unwrap_or_else(|| format!("{}  ", close_indent));
+        let step = field_indent
+            .strip_prefix(close_indent)
+            .filter(|s| !s.is_empty())
+            .unwrap_or("  ")
+            .to_string();
+
+        if let Some(last) = constructor.fields.last() {
+            if last.separator.is_none() {
+                edits.push(Edit::insert(tokens[last.value.1 - 1].end, ",".to_string()));
+            }
+        }
+
+        let mut text = String::new();
+        for entry in tree.render_entries(&field_indent, &step) {
+            text.push_str(&format!("{}{},\n", field_indent, entry));
+        }
+        edits.push(Edit::insert(line_start(source, close.start), text));
+    } else {
+        // Single-line constructor: `{ a = 1 }` becomes `{ a = 1, b = 2 }`
+        let entries = tree.render_entries(close_indent, "  ").join(", ");
+        match constructor.fields.last() {
+            None => edits.push(Edit::insert(tokens[open].end, format!(" {} ", entries))),
+            Some(last) => match last.separator {
+                Some(sep) => edits.push(Edit::insert(tokens[sep].end, format!(" {},", entries))),
+                None => edits.push(Edit::insert(
+                    tokens[last.value.1 - 1].end,
+                    format!(", {}", entries),
+                )),
+            },
+        }
+    }
+
+    edits
+}
+
+/// Insert new statements before the top-level `return`, or at the end of the file
+fn statement_insert_edit(source: &str, tokens: &[Token], statements: &[String]) -> Edit {
+    let block = statements.join("\n");
+
+    match top_level_return(tokens).map(|i| tokens[i].start) {
+        Some(pos) => {
+            // Append to a run of `config.` lines, keeping the blank line
+            // that usually separates `return config`
+            let at = line_start(source, pos);
+            let before = source[..at].trim_end_matches([' ', '\t', '\n']);
+            let follows_setting = source[line_start(source, before.len())..]
+                .trim_start()
+                .starts_with("config.");
+            match source[before.len()..at].find('\n') {
+                Some(i) if follows_setting && before.len() + i + 1 < at => {
+                    Edit::insert(before.len() + i + 1, format!("{}\n", block))
+                }
+                _ => Edit::insert(at, format!("{}\n\n", block)),
+            }
+        }
+        None => {
+            let sep = if source.is_empty() || source.ends_with('\n') { "" } else { "\n" };
+            Edit::insert(source.len(), format!("{}\n{}\n", sep, block))
+        }
+    }
+}
+
+/// Token index of the last `return` outside any function or block
+fn top_level_return(tokens: &[Token]) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut return_at = None;
+    for (i, t) in tokens.iter().enumerate() {
+        if ["function", "if", "do", "repeat"].iter().any(|s| t.is_name(s)) {
+            depth += 1;
+        } else if t.is_name("end") || t.is_name("until") {
+            depth = depth.saturating_sub(1);
+        } else if depth == 0 && t.is_name("return") {
+            return_at = Some(i);
+        }
+    }
+    return_at
+}
+
+/// Delete a statement or field, taking its whole line when nothing else is on it
+fn removal_edit(source: &str, (start, end): (usize, usize)) -> Edit {
+    let line_begin = line_start(source, start);
+    let line_end = source[end..].find('\n').map_or(source.len(), |i| end + i + 1);
+
+    if source[line_begin..start].trim().is_empty() && source[end..line_end].trim().is_empty() {
+        Edit::new(line_begin, line_end, String::new())
+    } else {
+        Edit::new(start, end, String::new())
+    }
+}
+
+// ============================================================================
+// Settings Module Hook
+// ============================================================================
+
+/// Comment written above the hook so users know where it came from
+const HOOK_COMMENT: &str = "-- Settings managed by WezTerm Settings UI (settings_ui.lua)";
+
+/// The statement that applies the managed settings module to `config_var`
+pub fn settings_hook(config_var: &str) -> String {
+    format!("require('{}').apply_to_config({})", SETTINGS_MODULE, config_var)
+}
+
+/// Byte span of the `require('settings_ui').apply_to_config(...)` call, if present
+fn find_settings_hook(tokens: &[Token]) -> Option<(usize, usize)> {
+    for i in 0..tokens.len() {
+        if !tokens[i].is_name("require")
+            || (i > 0 && (tokens[i - 1].is_symbol(".") || tokens[i - 1].is_symbol(":")))
+        {
+            continue;
+        }
+
+        // `require('settings_ui')` or `require 'settings_ui'`
+        let mut j = i + 1;
+        let paren = tokens.get(j).is_some_and(|t| t.is_symbol("("));
+        if paren {
+            j += 1;
+        }
+        let is_module = tokens
+            .get(j)
+            .filter(|t| t.kind == TokenKind::String)
+            .and_then(|t| unquote(t.text))
+            .is_some_and(|name| name == SETTINGS_MODULE);
+        if !is_module {
+            continue;
+        }
+        j += 1;
+        if paren {
+            if !tokens.get(j).is_some_and(|t| t.is_symbol(")")) {
+                continue;
+            }
+            j += 1;
+        }
+
+        // `.apply_to_config(config)`
+        let is_apply = tokens.get(j).is_some_and(|t| t.is_symbol("."))
+            && tokens.get(j + 1).is_some_and(|t| t.is_name("apply_to_config"))
+            && tokens.get(j + 2).is_some_and(|t| t.is_symbol("("));
+        if !is_apply {
+            continue;
+        }
+        let close = expr_end(tokens, j + 3);
+        if tokens.get(close).is_some_and(|t| t.is_symbol(")")) {
+            return Some((tokens[i].start, tokens[close].end));
+        }
+    }
+    None
+}
+
+/// Whether wezterm.lua already loads the managed settings module
+pub fn has_settings_hook(source: &str) -> bool {
+    tokenize(source).is_ok_and(|tokens| find_settings_hook(&tokens).is_some())
+}
+
+/// Insert the settings module hook just before `return config`, so managed
+/// settings are applied last. Returns the source unchanged if the hook is
+/// already there.
+pub fn install_settings_hook(source: &str) -> Result<String, String> {
+    let tokens = tokenize(source)?;
+    if find_settings_hook(&tokens).is_some() {
+        return Ok(source.to_string());
+    }
+
+    // The hook needs the name of the table the file returns
+    let missing_return = "wezterm.lua must end with `return config` to load the settings module";
+    let ret = top_level_return(&tokens).ok_or(missing_return)?;
+    let config_var = tokens
+        .get(ret + 1)
+        .filter(|t| t.kind == TokenKind::Name && !t.is_keyword())
+        .filter(|_| tokens.get(ret + 2).is_none_or(|t| t.is_symbol(";")))
+        .ok_or(missing_return)?
+        .text;
+
+    let at = line_start(source, tokens[ret].start);
+    let block = format!("{}\n{}\n\n", HOOK_COMMENT, settings_hook(config_var));
+    apply_edits(source, vec![Edit::insert(at, block)])
+}
+
+/// Remove the settings module hook (and the comment written with it)
+pub fn remove_settings_hook(source: &str) -> Result<String, String> {
+    let tokens = tokenize(source)?;
+    let Some(span) = find_settings_hook(&tokens) else {
+        return Ok(source.to_string());
+    };
+
+    let mut edit = removal_edit(source, span);
+    if edit.start > 0 && edit.start == line_start(source, span.0) {
+        let comment_start = line_start(source, edit.start - 1);
+        if source[comment_start..edit.start].trim() == HOOK_COMMENT {
+            edit.start = comment_start;
+        }
+        // Drop the blank line written after the hook when one already precedes it
+        if source[..edit.start].ends_with("\n\n") && source[edit.end..].starts_with('\n') {
+            edit.end += 1;
+        }
+    }
+    apply_edits(source, vec![edit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setting(path: &[&'static str], value: &str) -> LuaSetting {
+        LuaSetting {
+            path: path.to_vec(),
+            value: value.to_string(),
+        }
+    }
+
+    const SOURCE: &str = r#"-- My hand-written config
+local wezterm = require 'wezterm'
+local config = wezterm.config_builder()
+
+config.font_size = 12 -- readable
+config.colors = {
+  foreground = '#cdd6f4',
+  tab_bar = {
+    active_tab = { bg_color = '#585b70', fg_color = '#cdd6f4' },
+  },
+}
+
+wezterm.on('gui-startup', function(cmd)
+  local tab, pane, window = wezterm.mux.spawn_window(cmd or {})
+end)
+
+return config
+"#;
+
+    #[test]
+    fn test_unchanged_settings_leave_file_intact() {
+        let settings = vec![setting(&["font_size"], "12")];
+        let result = apply_settings(SOURCE, &settings, &settings).unwrap();
+        assert_eq!(result.content, SOURCE);
+        assert!(result.changed.is_empty());
+    }
+
+    #[test]
+    fn test_replace_top_level_value() {
+        let result = apply_settings(
+            SOURCE,
+            &[setting(&["font_size"], "12")],
+            &[setting(&["font_size"], "14")],
+        )
+        .unwrap();
+        assert_eq!(result.content, SOURCE.replace("font_size = 12", "font_size = 14"));
+        assert_eq!(result.changed, vec!["font_size"]);
+    }
+
+    #[test]
+    fn test_replace_nested_field() {
+        let path = &["colors", "tab_bar", "active_tab", "bg_color"];
+        let result = apply_settings(
+            SOURCE,
+            &[setting(path, "'#585b70'")],
+            &[setting(path, "'#ff0000'")],
+        )
+        .unwrap();
+        assert_eq!(
+            result.content,
+            SOURCE.replace("bg_color = '#585b70'", "bg_color = '#ff0000'")
+        );
+    }
+
+    #[test]
+    fn test_insert_missing_field_into_table() {
+        let result = apply_settings(
+            SOURCE,
+            &[],
+            &[setting(&["colors", "background"], "'#1f1f28'")],
+        )
+        .unwrap();
+        assert!(result
+            .content
+            .contains("  foreground = '#cdd6f4',\n  tab_bar = {\n    active_tab = { bg_color = '#585b70', fg_color = '#cdd6f4' },\n  },\n  background = '#1f1f28',\n}"));
+    }
+
+    #[test]
+    fn test_insert_into_single_line_table() {
+        let path = &["colors", "tab_bar", "active_tab", "italic"];
+        let result = apply_settings(SOURCE, &[], &[setting(path, "true")]).unwrap();
+        assert!(result
+            .content
+            .contains("active_tab = { bg_color = '#585b70', fg_color = '#cdd6f4', italic = true },"));
+    }
+
+    #[test]
+    fn test_insert_new_key_before_return() {
+        let result = apply_settings(
+            SOURCE,
+            &[],
+            &[
+                setting(&["scrollback_lines"], "5000"),
+                setting(&["window_padding", "left"], "2"),
+                setting(&["window_padding", "right"], "4"),
+            ],
+        )
+        .unwrap();
+        assert!(result.content.ends_with(
+            "end)\n\nconfig.scrollback_lines = 5000\nconfig.window_padding = {\n  left = 2,\n  right = 4,\n}\n\nreturn config\n"
+        ));
+        assert!(result.content.starts_with("-- My hand-written config\n"));
+    }
+
+    #[test]
+    fn test_remove_key() {
+        let source = "config.color_scheme = 'Nord'\nconfig.font_size = 12\nreturn config\n";
+        let result = apply_settings(
+            source,
+            &[setting(&["color_scheme"], "'Nord'")],
+            &[],
+        )
+        .unwrap();
+        assert_eq!(result.content, "config.font_size = 12\nreturn config\n");
+    }
+
+    #[test]
+    fn test_overlapping_edits_are_refused() {
+        let source = "config.font_size = 12\n";
+        let edits = vec![
+            Edit::new(19, 21, "14".to_string()).for_key("font_size"),
+            Edit::new(19, 21, "16".to_string()).for_key("fonts.size"),
+        ];
+        let err = apply_edits(source, edits).unwrap_err();
+        assert!(err.contains("font_size and fonts.size overlap"), "{}", err);
+
+        // Edits next to each other, and inserts at the same place, are fine
+        let edits = vec![
+            Edit::new(0, 6, "c".to_string()).for_key("a"),
+            Edit::insert(6, "x".to_string()).for_key("b"),
+            Edit::insert(6, "y".to_string()).for_key("c"),
+        ];
+        assert_eq!(apply_edits(source, edits).unwrap(), "cxy.font_size = 12\n");
+    }
+
+    #[test]
+    fn test_computed_table_is_skipped() {
+        let source = "local c = {}\nconfig.colors = c\nreturn config\n";
+        let result = apply_settings(
+            source,
+            &[],
+            &[setting(&["colors", "foreground"], "'#ffffff'")],
+        )
+        .unwrap();
+        assert_eq!(result.content, source);
+        assert_eq!(result.skipped, vec!["colors.foreground"]);
+    }
+
+    #[test]
+    fn test_assignment_expression_extent() {
+        let source = "config.font = wezterm.font_with_fallback {\n  'A',\n  'B',\n}\nconfig.x = 1 .. \n 'y'\nreturn config\n";
+        let tokens = tokenize(source).unwrap();
+        let assignments = find_assignments(&tokens);
+        assert_eq!(assignments.len(), 2);
+        let (s, e) = assignments[0].span;
+        assert!(source[s..e].ends_with("'B',\n}"));
+        let (s, e) = assignments[1].span;
+        assert_eq!(&source[s..e], "config.x = 1 .. \n 'y'");
+    }
+
+    #[test]
+    fn test_edit_returned_table_module() {
+        let module = "-- appearance\nreturn {\n  font_size = 12,\n  max_fps = 60,\n}\n";
+        let result = apply_settings(
+            module,
+            &[setting(&["font_size"], "12"), setting(&["max_fps"], "60")],
+            &[setting(&["font_size"], "14"), setting(&["front_end"], "'WebGpu'")],
+        )
+        .unwrap();
+        assert_eq!(
+            result.content,
+            "-- appearance\nreturn {\n  font_size = 14,\n  front_end = 'WebGpu',\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_settings_hook_install_is_idempotent() {
+        let installed = install_settings_hook(SOURCE).unwrap();
+        assert!(has_settings_hook(&installed));
+        assert!(installed.ends_with(
+            "end)\n\n-- Settings managed by WezTerm Settings UI (settings_ui.lua)\nrequire('settings_ui').apply_to_config(config)\n\nreturn config\n"
+        ));
+        assert_eq!(install_settings_hook(&installed).unwrap(), installed);
+    }
+
+    #[test]
+    fn test_settings_hook_detection() {
+        assert!(has_settings_hook("require 'settings_ui'.apply_to_config(cfg)"));
+        assert!(has_settings_hook("require(\"settings_ui\").apply_to_config(config)\n"));
+        assert!(!has_settings_hook("-- require('settings_ui').apply_to_config(config)\n"));
+        assert!(!has_settings_hook("require('other').apply_to_config(config)\n"));
+    }
+
+    #[test]
+    fn test_settings_hook_uninstall_restores_file() {
+        let installed = install_settings_hook(SOURCE).unwrap();
+        assert_eq!(remove_settings_hook(&installed).unwrap(), SOURCE);
+        assert_eq!(remove_settings_hook(SOURCE).unwrap(), SOURCE);
+    }
+
+    #[test]
+    fn test_settings_hook_requires_returned_config() {
+        assert!(install_settings_hook("return { font_size = 12 }\n").is_err());
+    }
+}
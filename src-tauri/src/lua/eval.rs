@@ -0,0 +1,351 @@
+// Lua evaluator - constant folding over the syntax tree
+//
+// Works out what an expression evaluates to without running it: literals,
+// locals bound to known values, arithmetic, string concatenation, comparisons,
+// `and`/`or`, a few string methods, and the `wezterm.*` constants that are
+// fixed on a given machine (`wezterm.home_dir`, `wezterm.config_dir`).
+// Anything that depends on run time - a function call, an unknown global,
+// `wezterm.gui`, `wezterm.target_triple` - comes out as `Value::Dynamic`
+// instead of a guess.
+
+use super::ast::{BinOp, Expr, ExprKind, UnOp};
+
+/// The value of an expression, as far as it is known before WezTerm runs
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value<'a> {
+    Nil,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    /// A table constructor; its fields are evaluated when they are read
+    Table(&'a Expr),
+    /// Only known at run time; says what it depends on (`` `os.getenv()` ``)
+    Dynamic(String),
+}
+
+impl<'a> Value<'a> {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            Value::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// Lua truthiness: everything but `nil` and `false` is true
+    fn truthy(&self) -> Option<bool> {
+        match self {
+            Value::Nil | Value::Bool(false) => Some(false),
+            Value::Dynamic(_) => None,
+            _ => Some(true),
+        }
+    }
+
+    /// The number arithmetic sees; numeric strings are converted like Lua does
+    fn to_number(&self) -> Option<f64> {
+        match self {
+            Value::Number(n) => Some(*n),
+            Value::String(s) => s.trim().parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// The string concatenation sees
+    fn to_concat(&self) -> Option<String> {
+        match self {
+            Value::String(s) => Some(s.clone()),
+            Value::Number(n) => Some(format_number(*n)),
+            _ => None,
+        }
+    }
+}
+
+/// Where the names in an expression get their values
+pub trait Scope<'a> {
+    /// The expression local `name` is bound to where `at` refers to it
+    fn local(&self, name: &str, at: &'a Expr) -> Option<&'a Expr>;
+    /// `wezterm.<name>`, when it is the same every time WezTerm runs here
+    fn wezterm(&self, name: &str) -> Option<Value<'a>>;
+}
+
+/// Fold `expr` to a value
+pub fn eval<'a>(expr: &'a Expr, scope: &impl Scope<'a>) -> Value<'a> {
+    match &expr.kind {
+        ExprKind::Nil => Value::Nil,
+        ExprKind::Bool(b) => Value::Bool(*b),
+        ExprKind::Number(n) => Value::Number(*n),
+        ExprKind::String(s) => Value::String(s.clone()),
+        ExprKind::Table(_) => Value::Table(expr),
+        ExprKind::Paren(inner) => eval(inner, scope),
+        ExprKind::Name(name) => match scope.local(name, expr) {
+            Some(bound) => eval(bound, scope),
+            None => Value::Dynamic(format!("`{}`", name)),
+        },
+        ExprKind::Field { object, name } => {
+            if matches!(&object.kind, ExprKind::Name(n) if n == "wezterm") {
+                return scope
+                    .wezterm(name)
+                    .unwrap_or_else(|| Value::Dynamic(format!("`wezterm.{}`", name)));
+            }
+            field(eval(object, scope), &Value::String(name.clone()), scope)
+        }
+        ExprKind::Index { object, key } => field(eval(object, scope), &eval(key, scope), scope),
+        ExprKind::Unary { op, expr: operand } => unary(*op, eval(operand, scope)),
+        ExprKind::Binary { op: BinOp::And, lhs, rhs } => {
+            let lhs = eval(lhs, scope);
+            match lhs.truthy() {
+                Some(true) => eval(rhs, scope),
+                _ => lhs,
+            }
+        }
+        ExprKind::Binary { op: BinOp::Or, lhs, rhs } => {
+            let lhs = eval(lhs, scope);
+            match lhs.truthy() {
+                Some(false) => eval(rhs, scope),
+                _ => lhs,
+            }
+        }
+        ExprKind::Binary { op, lhs, rhs } => binary(*op, eval(lhs, scope), eval(rhs, scope)),
+        ExprKind::MethodCall { object, method, args } => {
+            let object = eval(object, scope);
+            let args: Vec<Value> = args.iter().map(|a| eval(a, scope)).collect();
+            match &object {
+                Value::String(s) => string_method(s, method, &args)
+                    .unwrap_or_else(|| Value::Dynamic(format!("`:{}()`", method))),
+                Value::Dynamic(_) => object,
+                _ => Value::Dynamic(format!("`:{}()`", method)),
+            }
+        }
+        ExprKind::Call { func, .. } => {
+            let name = func.dotted_path().map(|p| p.join(".")).unwrap_or_else(|| "a function".to_string());
+            Value::Dynamic(format!("`{}()`", name))
+        }
+        ExprKind::Function(_) => Value::Dynamic("a function".to_string()),
+        ExprKind::Vararg => Value::Dynamic("`...`".to_string()),
+    }
+}
+
+/// `table[key]` on a table constructor
+fn field<'a>(table: Value<'a>, key: &Value<'a>, scope: &impl Scope<'a>) -> Value<'a> {
+    let constructor = match table {
+        Value::Table(constructor) => constructor,
+        Value::Dynamic(_) => return table,
+        // Indexing anything else is an error in Lua
+        _ => return Value::Nil,
+    };
+    let found = match key {
+        Value::String(name) => constructor.table_get(name),
+        Value::Number(n) if n.fract() == 0.0 && *n >= 1.0 => constructor
+            .table_items()
+            .and_then(|items| items.get(*n as usize - 1).copied()),
+        Value::Dynamic(_) => return key.clone(),
+        _ => None,
+    };
+    found.map_or(Value::Nil, |value| eval(value, scope))
+}
+
+fn unary(op: UnOp, value: Value) -> Value {
+    if let Value::Dynamic(_) = value {
+        return value;
+    }
+    match op {
+        UnOp::Not => Value::Bool(value.truthy() == Some(false)),
+        UnOp::Neg => value.to_number().map_or(Value::Nil, |n| Value::Number(-n)),
+        UnOp::Len => match &value {
+            Value::String(s) => Value::Number(s.len() as f64),
+            Value::Table(t) => Value::Number(t.table_items().map_or(0, |i| i.len()) as f64),
+            _ => Value::Nil,
+        },
+        UnOp::BNot => integer(&value).map_or(Value::Nil, |n| Value::Number(!n as f64)),
+    }
+}
+
+fn binary<'a>(op: BinOp, lhs: Value<'a>, rhs: Value<'a>) -> Value<'a> {
+    for value in [&lhs, &rhs] {
+        if let Value::Dynamic(_) = value {
+            return value.clone();
+        }
+    }
+    let numbers = lhs.to_number().zip(rhs.to_number());
+    let integers = integer(&lhs).zip(integer(&rhs));
+    let result = match op {
+        BinOp::Add => numbers.map(|(a, b)| Value::Number(a + b)),
+        BinOp::Sub => numbers.map(|(a, b)| Value::Number(a - b)),
+        BinOp::Mul => numbers.map(|(a, b)| Value::Number(a * b)),
+        BinOp::Div => numbers.map(|(a, b)| Value::Number(a / b)),
+        BinOp::IDiv => numbers.map(|(a, b)| Value::Number((a / b).floor())),
+        BinOp::Mod => numbers.map(|(a, b)| Value::Number(a - (a / b).floor() * b)),
+        BinOp::Pow => numbers.map(|(a, b)| Value::Number(a.powf(b))),
+        BinOp::Concat => lhs
+            .to_concat()
+            .zip(rhs.to_concat())
+            .map(|(a, b)| Value::String(a + &b)),
+        BinOp::Eq => Some(Value::Bool(equal(&lhs, &rhs))),
+        BinOp::Ne => Some(Value::Bool(!equal(&lhs, &rhs))),
+        BinOp::Lt => compare(&lhs, &rhs).map(|o| Value::Bool(o.is_lt())),
+        BinOp::Le => compare(&lhs, &rhs).map(|o| Value::Bool(o.is_le())),
+        BinOp::Gt => compare(&lhs, &rhs).map(|o| Value::Bool(o.is_gt())),
+        BinOp::Ge => compare(&lhs, &rhs).map(|o| Value::Bool(o.is_ge())),
+        BinOp::BAnd => integers.map(|(a, b)| Value::Number((a & b) as f64)),
+        BinOp::BOr => integers.map(|(a, b)| Value::Number((a | b) as f64)),
+        BinOp::BXor => integers.map(|(a, b)| Value::Number((a ^ b) as f64)),
+        BinOp::Shl => integers.map(|(a, b)| Value::Number(shift(a, b) as f64)),
+        BinOp::Shr => integers.map(|(a, b)| Value::Number(shift(a, -b) as f64)),
+        // Short-circuiting operators are handled in `eval`
+        BinOp::And | BinOp::Or => None,
+    };
+    // What's left would be a runtime error in Lua
+    result.unwrap_or(Value::Nil)
+}
+
+fn equal(lhs: &Value, rhs: &Value) -> bool {
+    match (lhs, rhs) {
+        (Value::Table(a), Value::Table(b)) => std::ptr::eq(*a, *b),
+        _ => lhs == rhs,
+    }
+}
+
+fn compare(lhs: &Value, rhs: &Value) -> Option<std::cmp::Ordering> {
+    match (lhs, rhs) {
+        (Value::Number(a), Value::Number(b)) => a.partial_cmp(b),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        _ => None,
+    }
+}
+
+/// A number with an exact integer representation, as bitwise operators need
+fn integer(value: &Value) -> Option<i64> {
+    value
+        .to_number()
+        .filter(|n| n.fract() == 0.0 && n.abs() < 2f64.powi(63))
+        .map(|n| n as i64)
+}
+
+/// Lua's logical shift: negative amounts shift the other way, 64 or more gives 0
+fn shift(value: i64, by: i64) -> i64 {
+    match by {
+        0 => value,
+        1..=63 => ((value as u64) << by) as i64,
+        -63..=-1 => ((value as u64) >> -by) as i64,
+        _ => 0,
+    }
+}
+
+/// How Lua prints a number in `..`: whole numbers without a fraction
+fn format_number(n: f64) -> String {
+    if n.fract() == 0.0 && n.abs() < 2f64.powi(53) {
+        format!("{}", n as i64)
+    } else {
+        format!("{}", n)
+    }
+}
+
+/// `s:lower()`, `s:upper()`, `s:len()` and `s:find(text)`. `find` is only
+/// folded for plain text; Lua patterns are left to run time.
+fn string_method<'a>(s: &str, method: &str, args: &[Value<'a>]) -> Option<Value<'a>> {
+    if let Some(dynamic) = args.iter().find(|a| matches!(a, Value::Dynamic(_))) {
+        return Some(dynamic.clone());
+    }
+    match method {
+        "lower" => Some(Value::String(s.to_lowercase())),
+        "upper" => Some(Value::String(s.to_uppercase())),
+        "len" => Some(Value::Number(s.len() as f64)),
+        "find" => {
+            let needle = args.first()?.as_str()?;
+            let init = args.get(1).and_then(Value::as_number).unwrap_or(1.0);
+            let plain = args.get(2).and_then(Value::truthy).unwrap_or(false);
+            const MAGIC: &[char] = &['^', '$', '(', ')', '%', '.', '[', ']', '*', '+', '-', '?'];
+            if init < 1.0 || (!plain && needle.contains(MAGIC)) {
+                return None;
+            }
+            let from = (init as usize - 1).min(s.len());
+            Some(match s.get(from..)?.find(needle) {
+                // Lua returns the 1-based start (and end) of the match
+                Some(at) => Value::Number((from + at + 1) as f64),
+                None => Value::Nil,
+            })
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lua::ast::{parse_chunk, Block, StatKind};
+
+    /// Locals resolve to the last declaration before the use, like the parser's scope
+    struct Locals<'a>(&'a Block);
+
+    impl<'a> Scope<'a> for Locals<'a> {
+        fn local(&self, name: &str, at: &'a Expr) -> Option<&'a Expr> {
+            self.0.stats.iter().rev().find_map(|stat| match &stat.kind {
+                StatKind::Local { names, exprs } if stat.span.end <= at.span.start => {
+                    names.iter().zip(exprs).find(|(n, _)| *n == name).map(|(_, e)| e)
+                }
+                _ => None,
+            })
+        }
+
+        fn wezterm(&self, name: &str) -> Option<Value<'a>> {
+            match name {
+                "home_dir" => Some(Value::String("/home/me".to_string())),
+                _ => None,
+            }
+        }
+    }
+
+    /// Evaluate the value of the last `local` in `source`
+    fn eval_last(source: &str) -> Value<'static> {
+        let chunk: &'static Block = Box::leak(Box::new(parse_chunk(source).unwrap()));
+        let Some(StatKind::Local { exprs, .. }) = chunk.stats.last().map(|s| &s.kind) else {
+            panic!("no local in {}", source);
+        };
+        eval(&exprs[0], &Locals(chunk))
+    }
+
+    #[test]
+    fn test_fold_constants() {
+        assert_eq!(eval_last("local x = 10 * 1.5"), Value::Number(15.0));
+        assert_eq!(eval_last("local x = 7 // 2 + 2 ^ 3 - 9 % 4"), Value::Number(10.0));
+        assert_eq!(eval_last("local x = 'font ' .. 12"), Value::String("font 12".to_string()));
+        assert_eq!(eval_last("local x = #'abc' == 3 and not nil"), Value::Bool(true));
+        assert_eq!(eval_last("local x = 1 << 4 | 1"), Value::Number(17.0));
+    }
+
+    #[test]
+    fn test_fold_locals_and_wezterm_constants() {
+        let source = "local bg = '#1e1e2e'\nlocal c = { background = bg, ansi = { 'red' } }\nlocal x = c.background .. c.ansi[1]";
+        assert_eq!(eval_last(source), Value::String("#1e1e2ered".to_string()));
+
+        let source = "local path = wezterm.home_dir .. '/pics/bg.png'";
+        assert_eq!(eval_last(source), Value::String("/home/me/pics/bg.png".to_string()));
+    }
+
+    #[test]
+    fn test_runtime_values_are_dynamic() {
+        assert_eq!(eval_last("local x = os.getenv('HOME') .. '/x'"), Value::Dynamic("`os.getenv()`".to_string()));
+        assert_eq!(eval_last("local x = wezterm.gui and 1 or 2"), Value::Dynamic("`wezterm.gui`".to_string()));
+        assert_eq!(eval_last("local x = unknown + 1"), Value::Dynamic("`unknown`".to_string()));
+        // The same config often runs on several platforms
+        let source = "local is_mac = wezterm.target_triple:find('darwin') ~= nil\nlocal x = is_mac and 14 or 12";
+        assert_eq!(eval_last(source), Value::Dynamic("`wezterm.target_triple`".to_string()));
+        // A Lua pattern is not folded
+        assert_eq!(eval_last("local x = ('abc'):find('a.c')"), Value::Dynamic("`:find()`".to_string()));
+        // Short-circuiting skips the dynamic side
+        assert_eq!(eval_last("local x = false and os.time() or 3"), Value::Number(3.0));
+    }
+}
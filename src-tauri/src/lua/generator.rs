@@ -1,5 +1,6 @@
 // Lua generator - generates WezTerm Lua config files using Tera templates
 
+use crate::models::{AppearanceConfig, Color};
 use std::path::Path;
 use tera::{Context, Tera};
 
@@ -80,6 +81,1117 @@ pub fn create_context<T: serde::Serialize>(value: &T) -> Result<Context, LuaGenE
     })
 }
 
+// ============================================================================
+// wezterm.lua Generation
+// ============================================================================
+
+/// Generate a WezTerm Lua configuration file from the config struct
+pub fn generate_lua_config(config: &AppearanceConfig) -> String {
+    let mut lua = String::new();
+    
+    lua.push_str("-- WezTerm configuration\n");
+    lua.push_str("-- Generated by WezTerm Settings GUI\n\n");
+    lua.push_str("local wezterm = require 'wezterm'\n");
+    lua.push_str("local act = wezterm.action\n");
+    lua.push_str("local config = wezterm.config_builder()\n\n");
+    lua.push_str(&generate_settings_lua(config));
+    lua.push_str("return config\n");
+    
+    lua
+}
+
+/// Module name of the managed settings file (`<config_dir>/settings_ui.lua`)
+pub const SETTINGS_MODULE: &str = "settings_ui";
+
+/// Generate the managed settings module.
+///
+/// The module exposes `apply_to_config(config)` and is loaded from wezterm.lua
+/// with `require('settings_ui').apply_to_config(config)`, so this file can be
+/// regenerated on every save while hand-written Lua stays in wezterm.lua.
+pub fn generate_settings_module(config: &AppearanceConfig) -> String {
+    let mut lua = String::new();
+    
+    lua.push_str("-- WezTerm settings module\n");
+    lua.push_str("-- Generated by WezTerm Settings GUI. This file is rewritten on every save;\n");
+    lua.push_str("-- keep hand-written configuration in wezterm.lua.\n\n");
+    lua.push_str("local wezterm = require 'wezterm'\n");
+    lua.push_str("local act = wezterm.action\n\n");
+    lua.push_str("local M = {}\n\n");
+    lua.push_str("function M.apply_to_config(config)\n");
+    for line in generate_settings_lua(config).trim_end().lines() {
+        if !line.is_empty() {
+            lua.push_str("  ");
+            lua.push_str(line);
+        }
+        lua.push('\n');
+    }
+    lua.push_str("end\n\n");
+    lua.push_str("return M\n");
+    
+    lua
+}
+
+/// Top-level `config.<key>` settings the generator writes. Any other key in a
+/// config is left to the user and preserved as written.
+pub const MANAGED_KEYS: &[&str] = &[
+    "adjust_window_size_when_changing_font_size",
+    "animation_fps",
+    "audible_bell",
+    "automatically_reload_config",
+    "color_scheme",
+    "colors",
+    "command_palette_bg_color",
+    "command_palette_fg_color",
+    "command_palette_font_size",
+    "cursor_blink_ease_in",
+    "cursor_blink_ease_out",
+    "cursor_blink_rate",
+    "default_cursor_style",
+    "disable_default_key_bindings",
+    "enable_scroll_bar",
+    "enable_tab_bar",
+    "exit_behavior",
+    "font",
+    "font_size",
+    "freetype_load_target",
+    "freetype_render_target",
+    "front_end",
+    "hide_tab_bar_if_only_one_tab",
+    "inactive_pane_hsb",
+    "initial_cols",
+    "initial_rows",
+    "key_tables",
+    "keys",
+    "leader",
+    "max_fps",
+    "mouse_bindings",
+    "scrollback_lines",
+    "show_tab_index_in_tab_bar",
+    "switch_to_last_active_tab_when_closing_tab",
+    "tab_max_width",
+    "text_background_opacity",
+    "use_fancy_tab_bar",
+    "visual_bell",
+    "webgpu_power_preference",
+    "window_background_image",
+    "window_background_image_hsb",
+    "window_background_opacity",
+    "window_close_confirmation",
+    "window_decorations",
+    "window_padding",
+];
+
+/// The `config.<key> = value` statements shared by wezterm.lua and the settings module
+fn generate_settings_lua(config: &AppearanceConfig) -> String {
+    let mut lua = String::new();
+    
+    // Font settings
+    lua.push_str("-- Font settings\n");
+    lua.push_str(&format!("config.font = {}\n", font_lua(&config.fonts)));
+    lua.push_str(&format!("config.font_size = {}\n", config.fonts.size));
+    if let Some(ref target) = config.fonts.freetype_load_target {
+        lua.push_str(&format!(
+            "config.freetype_load_target = '{}'\n",
+            format_freetype_target(target)
+        ));
+    }
+    if let Some(ref target) = config.fonts.freetype_render_target {
+        lua.push_str(&format!(
+            "config.freetype_render_target = '{}'\n",
+            format_freetype_target(target)
+        ));
+    }
+    lua.push('\n');
+    
+    // Window settings
+    lua.push_str("-- Window settings\n");
+    lua.push_str(&format!(
+        "config.window_background_opacity = {}\n",
+        config.window.window_background_opacity
+    ));
+    lua.push_str(&format!(
+        "config.window_decorations = '{}'\n",
+        format_window_decorations(&config.window.window_decorations)
+    ));
+    lua.push_str(&format!(
+        "config.enable_tab_bar = {}\n",
+        config.window.enable_tab_bar
+    ));
+    lua.push_str(&format!(
+        "config.hide_tab_bar_if_only_one_tab = {}\n",
+        config.window.hide_tab_bar_if_only_one_tab
+    ));
+    lua.push_str(&format!(
+        "config.use_fancy_tab_bar = {}\n",
+        config.window.use_fancy_tab_bar
+    ));
+    lua.push_str(&format!(
+        "config.tab_max_width = {}\n",
+        config.window.tab_max_width
+    ));
+    lua.push_str(&format!(
+        "config.show_tab_index_in_tab_bar = {}\n",
+        config.window.show_tab_index_in_tab_bar
+    ));
+    
+    // Window padding
+    lua.push_str(&format!(
+        "config.window_padding = {{\n  left = {},\n  right = {},\n  top = {},\n  bottom = {},\n}}\n",
+        config.window.window_padding.left,
+        config.window.window_padding.right,
+        config.window.window_padding.top,
+        config.window.window_padding.bottom
+    ));
+    
+    // Inactive pane dimming
+    lua.push_str(&format!(
+        "config.inactive_pane_hsb = {{\n  hue = {},\n  saturation = {},\n  brightness = {},\n}}\n",
+        config.window.inactive_pane_hsb.hue,
+        config.window.inactive_pane_hsb.saturation,
+        config.window.inactive_pane_hsb.brightness
+    ));
+    
+    // Window close confirmation
+    lua.push_str(&format!(
+        "config.window_close_confirmation = '{}'\n",
+        format_close_confirmation(&config.window.window_close_confirmation)
+    ));
+    lua.push('\n');
+    
+    // Cursor settings
+    lua.push_str("-- Cursor settings\n");
+    lua.push_str(&format!(
+        "config.default_cursor_style = '{}'\n",
+        format_cursor_style(&config.cursor.default_cursor_style)
+    ));
+    lua.push_str(&format!(
+        "config.cursor_blink_rate = {}\n",
+        config.cursor.cursor_blink_rate
+    ));
+    lua.push_str(&format!(
+        "config.cursor_blink_ease_in = '{}'\n",
+        format_ease_function(&config.cursor.cursor_blink_ease_in)
+    ));
+    lua.push_str(&format!(
+        "config.cursor_blink_ease_out = '{}'\n",
+        format_ease_function(&config.cursor.cursor_blink_ease_out)
+    ));
+    lua.push_str(&format!(
+        "config.animation_fps = {}\n",
+        config.cursor.animation_fps
+    ));
+    lua.push('\n');
+    
+    // GPU settings
+    lua.push_str("-- GPU settings\n");
+    lua.push_str(&format!(
+        "config.front_end = '{}'\n",
+        format_front_end(&config.gpu.front_end)
+    ));
+    lua.push_str(&format!(
+        "config.webgpu_power_preference = '{}'\n",
+        format_power_preference(&config.gpu.webgpu_power_preference)
+    ));
+    lua.push_str(&format!("config.max_fps = {}\n", config.gpu.max_fps));
+    lua.push('\n');
+    
+    // Color scheme
+    lua.push_str("-- Color scheme\n");
+    
+    // If using a built-in color scheme, use that instead of custom colors
+    if let Some(ref scheme_name) = config.color_scheme {
+        lua.push_str(&format!("config.color_scheme = {}\n\n", lua_string(scheme_name)));
+    } else {
+        // Use custom colors
+        lua.push_str("config.colors = {\n");
+        lua.push_str(&format!("  foreground = {},\n", lua_string(config.colors.foreground.as_str())));
+        lua.push_str(&format!("  background = {},\n", lua_string(config.colors.background.as_str())));
+        lua.push_str(&format!("  cursor_bg = {},\n", lua_string(config.colors.cursor_bg.as_str())));
+        lua.push_str(&format!("  cursor_border = {},\n", lua_string(config.colors.cursor_border.as_str())));
+        lua.push_str(&format!("  cursor_fg = {},\n", lua_string(config.colors.cursor_fg.as_str())));
+        lua.push_str(&format!("  selection_bg = {},\n", lua_string(config.colors.selection_bg.as_str())));
+        lua.push_str(&format!("  selection_fg = {},\n", lua_string(config.colors.selection_fg.as_str())));
+        for (key, color) in optional_colors(&config.colors) {
+            lua.push_str(&format!("  {} = {},\n", key, lua_string(color.as_str())));
+        }
+        
+        // ANSI colors
+        lua.push_str("  ansi = {\n");
+        for color in &config.colors.ansi {
+            lua.push_str(&format!("    {},\n", lua_string(color.as_str())));
+        }
+        lua.push_str("  },\n");
+        
+        // Bright colors
+        lua.push_str("  brights = {\n");
+        for color in &config.colors.brights {
+            lua.push_str(&format!("    {},\n", lua_string(color.as_str())));
+        }
+        lua.push_str("  },\n");
+        
+        // Tab bar colors
+        lua.push_str("  tab_bar = {\n");
+        lua.push_str(&format!("    background = {},\n", lua_string(config.colors.tab_bar.background.as_str())));
+        lua.push_str(&format_tab_colors("    active_tab", &config.colors.tab_bar.active_tab));
+        lua.push_str(&format_tab_colors("    inactive_tab", &config.colors.tab_bar.inactive_tab));
+        lua.push_str(&format_tab_colors("    inactive_tab_hover", &config.colors.tab_bar.inactive_tab_hover));
+        lua.push_str(&format_tab_colors("    new_tab", &config.colors.tab_bar.new_tab));
+        lua.push_str(&format_tab_colors("    new_tab_hover", &config.colors.tab_bar.new_tab_hover));
+        lua.push_str("  },\n");
+        
+        lua.push_str("}\n\n");
+    }
+    
+    // Backdrop/Background Image settings
+    if config.backdrop.enabled && !config.backdrop.images.is_empty() {
+        lua.push_str("-- Background image settings\n");
+        
+        if config.backdrop.images.len() == 1 && !config.backdrop.random_on_start {
+            lua.push_str(&format!(
+                "config.window_background_image = {}\n",
+                lua_string(&config.backdrop.images[0])
+            ));
+        } else {
+            // Keep the whole list so it can be read back, then pick the current
+            // image or a random one on start
+            lua.push_str("local backdrops = {\n");
+            for image in &config.backdrop.images {
+                lua.push_str(&format!("  {},\n", lua_string(image)));
+            }
+            lua.push_str("}\n");
+            if config.backdrop.random_on_start {
+                lua.push_str("config.window_background_image = backdrops[math.random(#backdrops)]\n");
+            } else {
+                let index = config.backdrop.current_index.min(config.backdrop.images.len() - 1);
+                lua.push_str(&format!("config.window_background_image = backdrops[{}]\n", index + 1));
+            }
+        }
+        
+        // Background image HSB for overlay effect (using overlay_opacity to control brightness)
+        lua.push_str(&format!(
+            "config.window_background_image_hsb = {{\n  hue = 1.0,\n  saturation = 1.0,\n  brightness = {},\n}}\n",
+            1.0 - config.backdrop.overlay_opacity + 0.04  // Convert overlay opacity to brightness
+        ));
+        
+        // Set the text background opacity for the focus color effect
+        lua.push_str(&format!(
+            "config.text_background_opacity = {}\n",
+            config.backdrop.overlay_opacity
+        ));
+        
+        lua.push('\n');
+    }
+    
+    // General settings
+    lua.push_str("-- General settings\n");
+    lua.push_str(&format!(
+        "config.automatically_reload_config = {}\n",
+        config.general.automatically_reload_config
+    ));
+    lua.push_str(&format!(
+        "config.scrollback_lines = {}\n",
+        config.general.scrollback_lines
+    ));
+    lua.push_str(&format!(
+        "config.initial_rows = {}\n",
+        config.general.initial_rows
+    ));
+    lua.push_str(&format!(
+        "config.initial_cols = {}\n",
+        config.general.initial_cols
+    ));
+    lua.push_str(&format!(
+        "config.exit_behavior = '{}'\n",
+        format_exit_behavior(&config.general.exit_behavior)
+    ));
+    lua.push_str(&format!(
+        "config.audible_bell = '{}'\n",
+        format_audible_bell(&config.general.audible_bell)
+    ));
+    lua.push_str(&format!(
+        "config.enable_scroll_bar = {}\n",
+        config.general.enable_scroll_bar
+    ));
+    lua.push_str(&format!(
+        "config.switch_to_last_active_tab_when_closing_tab = {}\n",
+        config.general.switch_to_last_active_tab_when_closing_tab
+    ));
+    lua.push_str(&format!(
+        "config.adjust_window_size_when_changing_font_size = {}\n",
+        config.general.adjust_window_size_when_changing_font_size
+    ));
+    lua.push('\n');
+    
+    // Command palette settings
+    lua.push_str("-- Command palette settings\n");
+    lua.push_str(&format!(
+        "config.command_palette_fg_color = {}\n",
+        lua_string(config.command_palette.fg_color.as_str())
+    ));
+    lua.push_str(&format!(
+        "config.command_palette_bg_color = {}\n",
+        lua_string(config.command_palette.bg_color.as_str())
+    ));
+    lua.push_str(&format!(
+        "config.command_palette_font_size = {}\n",
+        config.command_palette.font_size
+    ));
+    lua.push('\n');
+    
+    // Visual bell settings
+    lua.push_str("-- Visual bell settings\n");
+    lua.push_str("config.visual_bell = {\n");
+    lua.push_str(&format!(
+        "  fade_in_duration_ms = {},\n",
+        config.visual_bell.fade_in_duration_ms
+    ));
+    lua.push_str(&format!(
+        "  fade_out_duration_ms = {},\n",
+        config.visual_bell.fade_out_duration_ms
+    ));
+    lua.push_str(&format!(
+        "  fade_in_function = '{}',\n",
+        format_ease_function(&config.visual_bell.fade_in_function)
+    ));
+    lua.push_str(&format!(
+        "  fade_out_function = '{}',\n",
+        format_ease_function(&config.visual_bell.fade_out_function)
+    ));
+    lua.push_str(&format!(
+        "  target = {},\n",
+        lua_string(&config.visual_bell.target)
+    ));
+    lua.push_str("}\n\n");
+    
+    // Generate keybindings
+    lua.push_str(&generate_keybindings_lua(&config.keybindings));
+    
+    lua
+}
+
+/// `wezterm.font('Family')`, with the weight when one is set
+fn font_lua(fonts: &crate::models::FontConfig) -> String {
+    match fonts.weight {
+        Some(ref weight) => format!(
+            "wezterm.font({}, {{ weight = '{}' }})",
+            lua_string(&fonts.family),
+            format_font_weight(weight)
+        ),
+        None => format!("wezterm.font({})", lua_string(&fonts.family)),
+    }
+}
+
+/// The optional `colors` entries that are set, by key
+fn optional_colors(colors: &crate::models::ColorScheme) -> Vec<(&'static str, &Color)> {
+    [
+        ("visual_bell", &colors.visual_bell),
+        ("scrollbar_thumb", &colors.scrollbar_thumb),
+        ("split", &colors.split),
+    ]
+    .into_iter()
+    .filter_map(|(key, color)| color.as_ref().map(|c| (key, c)))
+    .collect()
+}
+
+fn format_tab_colors(name: &str, colors: &crate::models::TabColors) -> String {
+    let mut s = format!("{} = {{\n", name);
+    s.push_str(&format!("      bg_color = {},\n", lua_string(colors.bg_color.as_str())));
+    s.push_str(&format!("      fg_color = {},\n", lua_string(colors.fg_color.as_str())));
+    if let Some(italic) = colors.italic {
+        s.push_str(&format!("      italic = {},\n", italic));
+    }
+    s.push_str("    },\n");
+    s
+}
+
+fn format_window_decorations(d: &crate::models::WindowDecorations) -> &'static str {
+    use crate::models::WindowDecorations::*;
+    match d {
+        Full => "FULL",
+        Resize => "RESIZE",
+        None => "NONE",
+        Title => "TITLE",
+        IntegratedButtonsResize => "INTEGRATED_BUTTONS|RESIZE",
+    }
+}
+
+fn format_close_confirmation(c: &crate::models::CloseConfirmation) -> &'static str {
+    use crate::models::CloseConfirmation::*;
+    match c {
+        AlwaysPrompt => "AlwaysPrompt",
+        NeverPrompt => "NeverPrompt",
+    }
+}
+
+fn format_cursor_style(s: &crate::models::CursorStyle) -> &'static str {
+    use crate::models::CursorStyle::*;
+    match s {
+        SteadyBlock => "SteadyBlock",
+        BlinkingBlock => "BlinkingBlock",
+        SteadyUnderline => "SteadyUnderline",
+        BlinkingUnderline => "BlinkingUnderline",
+        SteadyBar => "SteadyBar",
+        BlinkingBar => "BlinkingBar",
+    }
+}
+
+fn format_ease_function(e: &crate::models::EaseFunction) -> &'static str {
+    use crate::models::EaseFunction::*;
+    match e {
+        Linear => "Linear",
+        EaseIn => "EaseIn",
+        EaseOut => "EaseOut",
+        EaseInOut => "EaseInOut",
+        Constant => "Constant",
+    }
+}
+
+fn format_font_weight(w: &crate::models::FontWeight) -> &'static str {
+    use crate::models::FontWeight::*;
+    match w {
+        Thin => "Thin",
+        ExtraLight => "ExtraLight",
+        Light => "Light",
+        Regular => "Regular",
+        Medium => "Medium",
+        DemiBold => "DemiBold",
+        Bold => "Bold",
+        ExtraBold => "ExtraBold",
+        Black => "Black",
+    }
+}
+
+fn format_freetype_target(t: &crate::models::FreetypeTarget) -> &'static str {
+    use crate::models::FreetypeTarget::*;
+    match t {
+        Normal => "Normal",
+        Light => "Light",
+        Mono => "Mono",
+        HorizontalLcd => "HorizontalLcd",
+    }
+}
+
+fn format_front_end(f: &crate::models::FrontEnd) -> &'static str {
+    use crate::models::FrontEnd::*;
+    match f {
+        WebGpu => "WebGpu",
+        OpenGL => "OpenGL",
+        Software => "Software",
+    }
+}
+
+fn format_power_preference(p: &crate::models::PowerPreference) -> &'static str {
+    use crate::models::PowerPreference::*;
+    match p {
+        LowPower => "LowPower",
+        HighPerformance => "HighPerformance",
+    }
+}
+
+fn format_exit_behavior(e: &crate::models::ExitBehavior) -> &'static str {
+    use crate::models::ExitBehavior::*;
+    match e {
+        Close => "Close",
+        CloseOnCleanExit => "CloseOnCleanExit",
+        Hold => "Hold",
+    }
+}
+
+fn format_audible_bell(a: &crate::models::AudibleBell) -> &'static str {
+    use crate::models::AudibleBell::*;
+    match a {
+        SystemBeep => "SystemBeep",
+        Disabled => "Disabled",
+    }
+}
+
+/// Generate complete keybindings Lua code
+fn generate_keybindings_lua(kb: &crate::models::KeyBindingsConfig) -> String {
+    let mut lua = String::new();
+    
+    lua.push_str("-- ============================================================================\n");
+    lua.push_str("-- Keybindings Configuration\n");
+    lua.push_str("-- Generated by WezTerm Settings TUI\n");
+    lua.push_str("-- ============================================================================\n\n");
+    
+    // Disable default keybindings if configured
+    if kb.disable_defaults {
+        lua.push_str("config.disable_default_key_bindings = true\n\n");
+    }
+    
+    // Leader key
+    if kb.leader.enabled {
+        lua.push_str(&format!(
+            "config.leader = {{ key = {}, mods = {}, timeout_milliseconds = {} }}\n\n",
+            lua_string(&kb.leader.key), lua_string(&kb.leader.mods), kb.leader.timeout_ms
+        ));
+    }
+    
+    lua.push_str("config.keys = ");
+    lua.push_str(&keys_table_lua(kb));
+    lua.push_str("\n\n");
+    
+    lua.push_str("config.key_tables = ");
+    lua.push_str(&key_tables_lua(kb));
+    lua.push_str("\n\n");
+    
+    lua.push_str("config.mouse_bindings = ");
+    lua.push_str(&mouse_bindings_lua(kb));
+    lua.push_str("\n\n");
+    
+    // Custom command palette entries
+    if kb.custom_commands.settings_tui || kb.custom_commands.rename_tab {
+        lua.push_str("-- Add custom commands to command palette (Ctrl+Shift+P)\n");
+        lua.push_str("wezterm.on('augment-command-palette', function(window, pane)\n");
+        lua.push_str("   return {\n");
+        
+        if kb.custom_commands.settings_tui {
+            lua.push_str("      {\n");
+            lua.push_str("         brief = 'Settings-TUI',\n");
+            lua.push_str("         icon = 'md_cog',\n");
+            lua.push_str("         action = wezterm.action.SpawnCommandInNewWindow {\n");
+            lua.push_str("            args = { 'wezterm-settings-tui' },\n");
+            lua.push_str("         },\n");
+            lua.push_str("      },\n");
+        }
+        
+        if kb.custom_commands.rename_tab {
+            lua.push_str("      {\n");
+            lua.push_str("         brief = 'Rename Current Tab',\n");
+            lua.push_str("         icon = 'md_rename_box',\n");
+            lua.push_str("         action = wezterm.action.EmitEvent('Rename Current Tab'),\n");
+            lua.push_str("      },\n");
+            lua.push_str("      {\n");
+            lua.push_str("         brief = 'Reset Tab Title',\n");
+            lua.push_str("         icon = 'md_undo',\n");
+            lua.push_str("         action = wezterm.action.EmitEvent('Reset Tab Title'),\n");
+            lua.push_str("      },\n");
+        }
+        
+        lua.push_str("   }\n");
+        lua.push_str("end)\n\n");
+    }
+    
+    // Generate event handlers for tab renaming
+    // These are needed when using EmitEvent('Rename Current Tab') / EmitEvent('Reset Tab Title')
+    if kb.tabs.rename_tab.enabled || kb.tabs.manual_update_title.enabled || kb.custom_commands.rename_tab {
+        lua.push_str("-- ============================================================================\n");
+        lua.push_str("-- Tab Title Event Handlers\n");
+        lua.push_str("-- These events show in the command palette with human-readable names\n");
+        lua.push_str("-- ============================================================================\n\n");
+        
+        lua.push_str("-- Track tab titles for persistence\n");
+        lua.push_str("local tab_titles = {}\n\n");
+        
+        lua.push_str("-- Event: Rename Current Tab\n");
+        lua.push_str("-- Opens a prompt to rename the current tab. Title persists until reset.\n");
+        lua.push_str("wezterm.on('Rename Current Tab', function(window, pane)\n");
+        lua.push_str("   window:perform_action(\n");
+        lua.push_str("      wezterm.action.PromptInputLine({\n");
+        lua.push_str("         description = wezterm.format({\n");
+        lua.push_str("            { Foreground = { Color = '#FFFFFF' } },\n");
+        lua.push_str("            { Text = 'Enter new tab name: ' },\n");
+        lua.push_str("         }),\n");
+        lua.push_str("         action = wezterm.action_callback(function(inner_window, inner_pane, line)\n");
+        lua.push_str("            if line and line ~= '' then\n");
+        lua.push_str("               local tab = inner_window:active_tab()\n");
+        lua.push_str("               local id = tab:tab_id()\n");
+        lua.push_str("               tab_titles[id] = { title = line, locked = true }\n");
+        lua.push_str("               tab:set_title(line)\n");
+        lua.push_str("            end\n");
+        lua.push_str("         end),\n");
+        lua.push_str("      }),\n");
+        lua.push_str("      pane\n");
+        lua.push_str("   )\n");
+        lua.push_str("end)\n\n");
+        
+        lua.push_str("-- Event: Reset Tab Title\n");
+        lua.push_str("-- Removes custom tab title and restores automatic naming.\n");
+        lua.push_str("wezterm.on('Reset Tab Title', function(window, _pane)\n");
+        lua.push_str("   local tab = window:active_tab()\n");
+        lua.push_str("   local id = tab:tab_id()\n");
+        lua.push_str("   tab_titles[id] = nil\n");
+        lua.push_str("   tab:set_title('')  -- Empty string triggers automatic title\n");
+        lua.push_str("end)\n\n");
+    }
+    
+    lua
+}
+
+/// Sections of the generated `config.keys` table: a comment, then the bindings
+/// under it with the Lua action each one runs. Bindings are named as in
+/// `KeyBindingsConfig::bindings`, and the parser matches existing entries
+/// against these actions when importing a config.
+pub const KEY_ACTIONS: &[(&str, &[(&str, &str)])] = &[
+    ("misc/useful", &[
+        ("misc.copy_mode", "'ActivateCopyMode'"),
+        ("misc.command_palette", "act.ActivateCommandPalette"),
+        ("misc.command_palette_alt", "act.ActivateCommandPalette"),
+        ("misc.show_launcher", "act.ShowLauncher"),
+        ("misc.show_tab_launcher", "act.ShowLauncherArgs({ flags = 'FUZZY|TABS' })"),
+        ("misc.show_workspace_launcher", "act.ShowLauncherArgs({ flags = 'FUZZY|WORKSPACES' })"),
+        ("misc.toggle_fullscreen", "act.ToggleFullScreen"),
+        ("misc.show_debug_overlay", "act.ShowDebugOverlay"),
+        ("misc.search", "act.Search({ CaseInSensitiveString = '' })"),
+        ("misc.quick_select_url", r"wezterm.action.QuickSelectArgs({
+         label = 'open url',
+         patterns = {
+            '\\((https?://\\S+)\\)',
+            '\\[(https?://\\S+)\\]',
+            '\\{(https?://\\S+)\\}',
+            '<(https?://\\S+)>',
+            '\\bhttps?://\\S+[)/a-zA-Z0-9-]+'
+         },
+         action = wezterm.action_callback(function(window, pane)
+            local url = window:get_selection_text_for_pane(pane)
+            wezterm.log_info('opening: ' .. url)
+            wezterm.open_with(url)
+         end),
+      })"),
+    ]),
+    ("cursor movement", &[
+        ("cursor.home", r"act.SendString '\u{1b}OH'"),
+        ("cursor.end", r"act.SendString '\u{1b}OF'"),
+        ("cursor.delete_line", r"act.SendString '\u{15}'"),
+        ("cursor.newline", r"act.SendString('\n')"),
+    ]),
+    ("copy/paste", &[
+        ("copy_paste.copy", "act.CopyTo('Clipboard')"),
+        ("copy_paste.paste", "act.PasteFrom('Clipboard')"),
+        ("copy_paste.copy_simple", "act.CopyTo('Clipboard')"),
+        ("copy_paste.paste_simple", "act.PasteFrom('Clipboard')"),
+    ]),
+    ("tabs: spawn+close", &[
+        ("tabs.spawn_tab", "act.SpawnTab('DefaultDomain')"),
+        ("tabs.spawn_tab_wsl", "act.SpawnTab({ DomainName = 'wsl:ubuntu-fish' })"),
+        ("tabs.close_tab", "act.CloseCurrentTab({ confirm = false })"),
+    ]),
+    ("tabs: navigation", &[
+        ("tabs.prev_tab", "act.ActivateTabRelative(-1)"),
+        ("tabs.next_tab", "act.ActivateTabRelative(1)"),
+        ("tabs.move_tab_back", "act.MoveTabRelative(-1)"),
+        ("tabs.move_tab_forward", "act.MoveTabRelative(1)"),
+    ]),
+    // Rename uses the event pattern for compatibility with custom tab-title handlers
+    ("tab: title", &[
+        ("tabs.rename_tab", "act.EmitEvent('Rename Current Tab')"),
+        ("tabs.manual_update_title", "act.EmitEvent('Rename Current Tab')"),
+        ("tabs.reset_title", "act.EmitEvent('Reset Tab Title')"),
+        ("tabs.toggle_tab_bar", "act.EmitEvent('tabs.toggle-tab-bar')"),
+    ]),
+    ("window", &[
+        ("windows.spawn_window", "act.SpawnWindow"),
+        ("windows.shrink_window", "wezterm.action_callback(function(window, _pane)
+         local dimensions = window:get_dimensions()
+         if dimensions.is_full_screen then return end
+         local new_width = dimensions.pixel_width - 50
+         local new_height = dimensions.pixel_height - 50
+         window:set_inner_size(new_width, new_height)
+      end)"),
+        ("windows.grow_window", "wezterm.action_callback(function(window, _pane)
+         local dimensions = window:get_dimensions()
+         if dimensions.is_full_screen then return end
+         local new_width = dimensions.pixel_width + 50
+         local new_height = dimensions.pixel_height + 50
+         window:set_inner_size(new_width, new_height)
+      end)"),
+        ("windows.maximize_window", "wezterm.action_callback(function(window, _pane)
+         window:maximize()
+      end)"),
+    ]),
+    ("background controls", &[
+        ("backdrops.random", "wezterm.action_callback(function(window, _pane)
+         backdrops:random(window)
+      end)"),
+        ("backdrops.cycle_back", "wezterm.action_callback(function(window, _pane)
+         backdrops:cycle_back(window)
+      end)"),
+        ("backdrops.cycle_forward", "wezterm.action_callback(function(window, _pane)
+         backdrops:cycle_forward(window)
+      end)"),
+        ("backdrops.select", "act.InputSelector({
+         title = 'InputSelector: Select Background',
+         choices = backdrops:choices(),
+         fuzzy = true,
+         fuzzy_description = 'Select Background: ',
+         action = wezterm.action_callback(function(window, _pane, idx)
+            if not idx then return end
+            backdrops:set_img(window, tonumber(idx))
+         end),
+      })"),
+        ("backdrops.toggle_focus", "wezterm.action_callback(function(window, _pane)
+         backdrops:toggle_focus(window)
+      end)"),
+    ]),
+    ("panes: split panes", &[
+        ("panes.split_vertical", "act.SplitVertical({ domain = 'CurrentPaneDomain' })"),
+        ("panes.split_horizontal", "act.SplitHorizontal({ domain = 'CurrentPaneDomain' })"),
+    ]),
+    ("panes: zoom+close pane", &[
+        ("panes.toggle_zoom", "act.TogglePaneZoomState"),
+        ("panes.close_pane", "act.CloseCurrentPane({ confirm = false })"),
+    ]),
+    ("panes: navigation", &[
+        ("panes.nav_up", "act.ActivatePaneDirection('Up')"),
+        ("panes.nav_down", "act.ActivatePaneDirection('Down')"),
+        ("panes.nav_left", "act.ActivatePaneDirection('Left')"),
+        ("panes.nav_right", "act.ActivatePaneDirection('Right')"),
+        ("panes.swap_pane", "act.PaneSelect({ alphabet = '1234567890', mode = 'SwapWithActiveKeepFocus' })"),
+    ]),
+    ("panes: scroll pane", &[
+        ("panes.scroll_up", "act.ScrollByLine(-5)"),
+        ("panes.scroll_down", "act.ScrollByLine(5)"),
+        ("panes.page_up", "act.ScrollByPage(-0.75)"),
+        ("panes.page_down", "act.ScrollByPage(0.75)"),
+    ]),
+    ("key-tables", &[
+        ("key_tables.resize_font_mode", "act.ActivateKeyTable({
+         name = 'resize_font',
+         one_shot = false,
+         timeout_milliseconds = 1000,
+      })"),
+        ("key_tables.resize_pane_mode", "act.ActivateKeyTable({
+         name = 'resize_pane',
+         one_shot = false,
+         timeout_milliseconds = 1000,
+      })"),
+    ]),
+];
+
+/// The Lua action generated for a binding, by `group.name`
+pub fn key_action(name: &str) -> Option<&'static str> {
+    KEY_ACTIONS
+        .iter()
+        .flat_map(|(_, bindings)| bindings.iter())
+        .find(|(n, _)| *n == name)
+        .map(|(_, action)| *action)
+}
+
+/// Generate the `config.keys` table constructor for the enabled bindings
+fn keys_table_lua(kb: &crate::models::KeyBindingsConfig) -> String {
+    let bindings = kb.bindings();
+    let mut lua = String::from("{\n");
+    let mut group = "";
+
+    for (comment, actions) in KEY_ACTIONS {
+        // Blank line between top-level groups (misc, tabs, panes, ...)
+        let next_group = actions[0].0.split('.').next().unwrap_or_default();
+        if !group.is_empty() && group != next_group {
+            lua.push('\n');
+        }
+        group = next_group;
+
+        lua.push_str(&format!("   -- {} --\n", comment));
+        for (name, action) in actions.iter() {
+            let Some((_, binding)) = bindings.iter().find(|(n, _)| n == name) else {
+                continue;
+            };
+            if !binding.enabled {
+                continue;
+            }
+            if action.contains('\n') {
+                lua.push_str(&format!(
+                    "   {{\n      key = {},\n      mods = {},\n      action = {},\n   }},\n",
+                    lua_string(&binding.key), lua_string(&binding.mods), action
+                ));
+            } else {
+                lua.push_str(&format!(
+                    "   {{ key = {}, mods = {}, action = {} }},\n",
+                    lua_string(&binding.key), lua_string(&binding.mods), action
+                ));
+            }
+        }
+    }
+
+    if !kb.extra_keys.is_empty() {
+        lua.push_str("\n   -- custom --\n");
+        for entry in &kb.extra_keys {
+            lua.push_str(&format!("   {},\n", entry));
+        }
+    }
+
+    lua.push('}');
+    lua
+}
+
+/// Key tables activated by the resize_font / resize_pane bindings
+const KEY_TABLES_LUA: &str = "   resize_font = {
+      { key = 'k',      action = act.IncreaseFontSize },
+      { key = 'j',      action = act.DecreaseFontSize },
+      { key = 'r',      action = act.ResetFontSize },
+      { key = 'Escape', action = 'PopKeyTable' },
+      { key = 'q',      action = 'PopKeyTable' },
+   },
+   resize_pane = {
+      { key = 'k',      action = act.AdjustPaneSize({ 'Up', 1 }) },
+      { key = 'j',      action = act.AdjustPaneSize({ 'Down', 1 }) },
+      { key = 'h',      action = act.AdjustPaneSize({ 'Left', 1 }) },
+      { key = 'l',      action = act.AdjustPaneSize({ 'Right', 1 }) },
+      { key = 'Escape', action = 'PopKeyTable' },
+      { key = 'q',      action = 'PopKeyTable' },
+   },
+";
+
+/// Names of the key tables in `KEY_TABLES_LUA`
+pub const MANAGED_KEY_TABLES: &[&str] = &["resize_font", "resize_pane"];
+
+/// Generate the `config.key_tables` table constructor
+fn key_tables_lua(kb: &crate::models::KeyBindingsConfig) -> String {
+    let mut lua = String::from("{\n");
+    lua.push_str(KEY_TABLES_LUA);
+    for table in &kb.extra_key_tables {
+        lua.push_str(&format!("   {},\n", table));
+    }
+    lua.push('}');
+    lua
+}
+
+/// Generate the `config.mouse_bindings` table constructor
+fn mouse_bindings_lua(kb: &crate::models::KeyBindingsConfig) -> String {
+    let mut lua = String::from("{\n");
+    lua.push_str(&MOUSE_ACTIONS
+        .iter()
+        .filter(|(name, _)| match *name {
+            "ctrl_click_open_link" => kb.mouse.ctrl_click_open_link,
+            "right_click_command_palette" => kb.mouse.right_click_command_palette,
+            _ => false,
+        })
+        .map(|(_, entry)| format!("   {},\n", entry))
+        .collect::<String>());
+    for entry in &kb.extra_mouse_bindings {
+        lua.push_str(&format!("   {},\n", entry));
+    }
+    lua.push('}');
+    lua
+}
+
+/// Mouse bindings managed by `MouseBindings`, by field name
+pub const MOUSE_ACTIONS: &[(&str, &str)] = &[
+    ("ctrl_click_open_link", "{
+      event = { Up = { streak = 1, button = 'Left' } },
+      mods = 'CTRL',
+      action = act.OpenLinkAtMouseCursor,
+   }"),
+    ("right_click_command_palette", "{
+      event = { Down = { streak = 1, button = 'Right' } },
+      mods = 'NONE',
+      action = act.ActivateCommandPalette,
+   }"),
+];
+
+// ============================================================================
+// Per-Setting Values (used for surgical edits of existing files)
+// ============================================================================
+
+/// A single managed setting: a path below `config` and its Lua value.
+/// e.g. `["colors", "tab_bar", "active_tab", "bg_color"]` = `'#585b70'`
+#[derive(Debug, Clone, PartialEq)]
+pub struct LuaSetting {
+    pub path: Vec<&'static str>,
+    pub value: String,
+}
+
+impl LuaSetting {
+    fn new(path: &[&'static str], value: impl Into<String>) -> Self {
+        Self {
+            path: path.to_vec(),
+            value: value.into(),
+        }
+    }
+
+    /// Dotted key, e.g. `colors.tab_bar.background`
+    pub fn key(&self) -> String {
+        self.path.join(".")
+    }
+}
+
+/// Quote a string as a single-quoted Lua literal
+pub fn lua_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('\'');
+    for c in s.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '\'' => quoted.push_str("\\'"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_ascii_control() => quoted.push_str(&format!("\\{:03}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('\'');
+    quoted
+}
+
+/// Flatten a config into the `config.*` settings that `generate_lua_config` manages.
+/// Nested tables are split into leaves so they can be edited field by field;
+/// lists and key tables stay whole.
+pub fn config_settings(config: &AppearanceConfig) -> Vec<LuaSetting> {
+    let mut settings = Vec::new();
+    let mut push = |path: &[&'static str], value: String| settings.push(LuaSetting::new(path, value));
+
+    // Font settings
+    push(&["font"], font_lua(&config.fonts));
+    push(&["font_size"], config.fonts.size.to_string());
+    if let Some(ref target) = config.fonts.freetype_load_target {
+        push(&["freetype_load_target"], lua_string(format_freetype_target(target)));
+    }
+    if let Some(ref target) = config.fonts.freetype_render_target {
+        push(&["freetype_render_target"], lua_string(format_freetype_target(target)));
+    }
+
+    // Window settings
+    let window = &config.window;
+    push(&["window_background_opacity"], window.window_background_opacity.to_string());
+    push(&["window_decorations"], lua_string(format_window_decorations(&window.window_decorations)));
+    push(&["enable_tab_bar"], window.enable_tab_bar.to_string());
+    push(&["hide_tab_bar_if_only_one_tab"], window.hide_tab_bar_if_only_one_tab.to_string());
+    push(&["use_fancy_tab_bar"], window.use_fancy_tab_bar.to_string());
+    push(&["tab_max_width"], window.tab_max_width.to_string());
+    push(&["show_tab_index_in_tab_bar"], window.show_tab_index_in_tab_bar.to_string());
+    push(&["window_padding", "left"], window.window_padding.left.to_string());
+    push(&["window_padding", "right"], window.window_padding.right.to_string());
+    push(&["window_padding", "top"], window.window_padding.top.to_string());
+    push(&["window_padding", "bottom"], window.window_padding.bottom.to_string());
+    push(&["inactive_pane_hsb", "hue"], window.inactive_pane_hsb.hue.to_string());
+    push(&["inactive_pane_hsb", "saturation"], window.inactive_pane_hsb.saturation.to_string());
+    push(&["inactive_pane_hsb", "brightness"], window.inactive_pane_hsb.brightness.to_string());
+    push(
+        &["window_close_confirmation"],
+        lua_string(format_close_confirmation(&window.window_close_confirmation)),
+    );
+
+    // Cursor settings
+    let cursor = &config.cursor;
+    push(&["default_cursor_style"], lua_string(format_cursor_style(&cursor.default_cursor_style)));
+    push(&["cursor_blink_rate"], cursor.cursor_blink_rate.to_string());
+    push(&["cursor_blink_ease_in"], lua_string(format_ease_function(&cursor.cursor_blink_ease_in)));
+    push(&["cursor_blink_ease_out"], lua_string(format_ease_function(&cursor.cursor_blink_ease_out)));
+    push(&["animation_fps"], cursor.animation_fps.to_string());
+
+    // GPU settings
+    push(&["front_end"], lua_string(format_front_end(&config.gpu.front_end)));
+    push(
+        &["webgpu_power_preference"],
+        lua_string(format_power_preference(&config.gpu.webgpu_power_preference)),
+    );
+    push(&["max_fps"], config.gpu.max_fps.to_string());
+
+    // Color scheme: a built-in scheme replaces the custom colors entirely
+    if let Some(ref scheme_name) = config.color_scheme {
+        push(&["color_scheme"], lua_string(scheme_name));
+    } else {
+        let colors = &config.colors;
+        push(&["colors", "foreground"], lua_string(colors.foreground.as_str()));
+        push(&["colors", "background"], lua_string(colors.background.as_str()));
+        push(&["colors", "cursor_bg"], lua_string(colors.cursor_bg.as_str()));
+        push(&["colors", "cursor_border"], lua_string(colors.cursor_border.as_str()));
+        push(&["colors", "cursor_fg"], lua_string(colors.cursor_fg.as_str()));
+        push(&["colors", "selection_bg"], lua_string(colors.selection_bg.as_str()));
+        push(&["colors", "selection_fg"], lua_string(colors.selection_fg.as_str()));
+        for (key, color) in optional_colors(colors) {
+            push(&["colors", key], lua_string(color.as_str()));
+        }
+        push(&["colors", "ansi"], lua_colors(&colors.ansi));
+        push(&["colors", "brights"], lua_colors(&colors.brights));
+        push(&["colors", "tab_bar", "background"], lua_string(colors.tab_bar.background.as_str()));
+        let tabs: [(&'static str, &crate::models::TabColors); 5] = [
+            ("active_tab", &colors.tab_bar.active_tab),
+            ("inactive_tab", &colors.tab_bar.inactive_tab),
+            ("inactive_tab_hover", &colors.tab_bar.inactive_tab_hover),
+            ("new_tab", &colors.tab_bar.new_tab),
+            ("new_tab_hover", &colors.tab_bar.new_tab_hover),
+        ];
+        for (name, tab) in tabs {
+            push(&["colors", "tab_bar", name, "bg_color"], lua_string(tab.bg_color.as_str()));
+            push(&["colors", "tab_bar", name, "fg_color"], lua_string(tab.fg_color.as_str()));
+            if let Some(italic) = tab.italic {
+                push(&["colors", "tab_bar", name, "italic"], italic.to_string());
+            }
+        }
+    }
+
+    // Backdrop settings. A random pick between several images needs a local
+    // table, so only the single-image form is managed here.
+    let backdrop = &config.backdrop;
+    if backdrop.enabled && !backdrop.images.is_empty() {
+        if !(backdrop.random_on_start && backdrop.images.len() > 1) {
+            let image = backdrop
+                .images
+                .get(backdrop.current_index)
+                .unwrap_or(&backdrop.images[0]);
+            push(&["window_background_image"], lua_string(image));
+        }
+        push(&["window_background_image_hsb", "hue"], "1.0".to_string());
+        push(&["window_background_image_hsb", "saturation"], "1.0".to_string());
+        push(
+            &["window_background_image_hsb", "brightness"],
+            (1.0 - backdrop.overlay_opacity + 0.04).to_string(),
+        );
+        push(&["text_background_opacity"], backdrop.overlay_opacity.to_string());
+    }
+
+    // General settings
+    let general = &config.general;
+    push(&["automatically_reload_config"], general.automatically_reload_config.to_string());
+    push(&["scrollback_lines"], general.scrollback_lines.to_string());
+    push(&["initial_rows"], general.initial_rows.to_string());
+    push(&["initial_cols"], general.initial_cols.to_string());
+    push(&["exit_behavior"], lua_string(format_exit_behavior(&general.exit_behavior)));
+    push(&["audible_bell"], lua_string(format_audible_bell(&general.audible_bell)));
+    push(&["enable_scroll_bar"], general.enable_scroll_bar.to_string());
+    push(
+        &["switch_to_last_active_tab_when_closing_tab"],
+        general.switch_to_last_active_tab_when_closing_tab.to_string(),
+    );
+    push(
+        &["adjust_window_size_when_changing_font_size"],
+        general.adjust_window_size_when_changing_font_size.to_string(),
+    );
+
+    // Command palette settings
+    push(&["command_palette_fg_color"], lua_string(config.command_palette.fg_color.as_str()));
+    push(&["command_palette_bg_color"], lua_string(config.command_palette.bg_color.as_str()));
+    push(&["command_palette_font_size"], config.command_palette.font_size.to_string());
+
+    // Visual bell settings
+    let bell = &config.visual_bell;
+    push(&["visual_bell", "fade_in_duration_ms"], bell.fade_in_duration_ms.to_string());
+    push(&["visual_bell", "fade_out_duration_ms"], bell.fade_out_duration_ms.to_string());
+    push(&["visual_bell", "fade_in_function"], lua_string(format_ease_function(&bell.fade_in_function)));
+    push(&["visual_bell", "fade_out_function"], lua_string(format_ease_function(&bell.fade_out_function)));
+    push(&["visual_bell", "target"], lua_string(&bell.target));
+
+    // Keybindings
+    let kb = &config.keybindings;
+    if kb.disable_defaults {
+        push(&["disable_default_key_bindings"], "true".to_string());
+    }
+    if kb.leader.enabled {
+        push(
+            &["leader"],
+            format!(
+                "{{ key = {}, mods = {}, timeout_milliseconds = {} }}",
+                lua_string(&kb.leader.key),
+                lua_string(&kb.leader.mods),
+                kb.leader.timeout_ms
+            ),
+        );
+    }
+    push(&["keys"], keys_table_lua(kb));
+    push(&["key_tables"], key_tables_lua(kb));
+    push(&["mouse_bindings"], mouse_bindings_lua(kb));
+
+    settings
+}
+
+/// Format a list of colors as a single-line Lua array
+fn lua_colors(colors: &[Color]) -> String {
+    let quoted: Vec<String> = colors.iter().map(|c| lua_string(c.as_str())).collect();
+    format!("{{ {} }}", quoted.join(", "))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -125,4 +1237,23 @@ return M
         let context = create_context(&data);
         assert!(context.is_ok());
     }
+
+    #[test]
+    fn test_managed_keys_cover_generated_settings() {
+        let mut config = AppearanceConfig::default();
+        config.fonts.freetype_load_target = Some(crate::models::FreetypeTarget::Light);
+        config.fonts.freetype_render_target = Some(crate::models::FreetypeTarget::HorizontalLcd);
+        config.backdrop.enabled = true;
+        config.backdrop.images = vec!["/tmp/a.png".to_string()];
+        config.keybindings.leader.enabled = true;
+
+        let lua = generate_lua_config(&config);
+        for line in lua.lines().filter(|l| l.starts_with("config.")) {
+            let key = line["config.".len()..].split([' ', '.']).next().unwrap();
+            assert!(MANAGED_KEYS.contains(&key), "{} is generated but not managed", key);
+        }
+        for setting in config_settings(&config) {
+            assert!(MANAGED_KEYS.contains(&setting.path[0]), "{}", setting.key());
+        }
+    }
 }
@@ -0,0 +1,247 @@
+// Lua lexer - splits Lua source into tokens with byte spans
+//
+// Comments and whitespace are skipped, but every token keeps its exact
+// position in the source so callers can rewrite individual values without
+// disturbing anything around them.
+
+/// Kind of a Lua token
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// Identifier or keyword (`config`, `local`, `end`, ...)
+    Name,
+    /// Numeric literal
+    Number,
+    /// Quoted or long-bracket string literal, including delimiters
+    String,
+    /// Operator or punctuation (`=`, `..`, `{`, ...)
+    Symbol,
+}
+
+/// A single token and its byte span in the source
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Token<'_> {
+    pub fn is_symbol(&self, s: &str) -> bool {
+        self.kind == TokenKind::Symbol && self.text == s
+    }
+
+    pub fn is_name(&self, s: &str) -> bool {
+        self.kind == TokenKind::Name && self.text == s
+    }
+
+    pub fn is_keyword(&self) -> bool {
+        self.kind == TokenKind::Name && is_keyword(self.text)
+    }
+}
+
+/// Returns true for Lua reserved words
+pub fn is_keyword(s: &str) -> bool {
+    matches!(
+        s,
+        "and" | "break" | "do" | "else" | "elseif" | "end" | "false" | "for" | "function"
+            | "goto" | "if" | "in" | "local" | "nil" | "not" | "or" | "repeat" | "return"
+            | "then" | "true" | "until" | "while"
+    )
+}
+
+/// 1-based line and column of a byte offset
+pub fn line_col(src: &str, pos: usize) -> (usize, usize) {
+    let before = &src[..pos.min(src.len())];
+    let line = before.matches('\n').count() + 1;
+    let col = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+    (line, col)
+}
+
+/// Strip the quotes from a simple string literal (no escapes)
+pub fn unquote(text: &str) -> Option<String> {
+    let inner = text
+        .strip_prefix('\'')
+        .and_then(|t| t.strip_suffix('\''))
+        .or_else(|| text.strip_prefix('"').and_then(|t| t.strip_suffix('"')))?;
+    if inner.contains('\\') {
+        None
+    } else {
+        Some(inner.to_string())
+    }
+}
+
+/// Multi-character symbols, longest first so `...` wins over `..`
+const SYMBOLS: &[&str] = &[
+    "...", "..", "==", "~=", "<=", ">=", "//", "::", "<<", ">>",
+];
+
+/// Tokenize Lua source, skipping whitespace and comments
+pub fn tokenize(src: &str) -> Result<Vec<Token<'_>>, String> {
+    let bytes = src.as_bytes();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        let c = bytes[pos];
+
+        if c.is_ascii_whitespace() {
+            pos += 1;
+            continue;
+        }
+
+        // Comments: `-- line` or `--[[ long ]]`
+        if src[pos..].starts_with("--") {
+            pos += 2;
+            if let Some(level) = long_bracket_level(&bytes[pos..]) {
+                pos = skip_long_bracket(src, pos, level)
+                    .ok_or_else(|| error_at(src, pos, "unfinished long comment"))?;
+            } else {
+                while pos < bytes.len() && bytes[pos] != b'\n' {
+                    pos += 1;
+                }
+            }
+            continue;
+        }
+
+        let start = pos;
+        let kind = if c.is_ascii_alphabetic() || c == b'_' {
+            while pos < bytes.len() && (bytes[pos].is_ascii_alphanumeric() || bytes[pos] == b'_') {
+                pos += 1;
+            }
+            TokenKind::Name
+        } else if c.is_ascii_digit() || (c == b'.' && bytes.get(pos + 1).is_some_and(u8::is_ascii_digit)) {
+            pos = scan_number(bytes, pos);
+            TokenKind::Number
+        } else if c == b'"' || c == b'\'' {
+            pos = scan_short_string(bytes, pos)
+                .ok_or_else(|| error_at(src, start, "unfinished string"))?;
+            TokenKind::String
+        } else if let Some(level) = long_bracket_level(&bytes[pos..]) {
+            pos = skip_long_bracket(src, pos, level)
+                .ok_or_else(|| error_at(src, start, "unfinished long string"))?;
+            TokenKind::String
+        } else {
+            let len = SYMBOLS
+                .iter()
+                .find(|s| src[pos..].starts_with(*s))
+                .map(|s| s.len())
+                .unwrap_or_else(|| src[pos..].chars().next().map_or(1, char::len_utf8));
+            pos += len;
+            TokenKind::Symbol
+        };
+
+        tokens.push(Token {
+            kind,
+            text: &src[start..pos],
+            start,
+            end: pos,
+        });
+    }
+
+    Ok(tokens)
+}
+
+fn error_at(src: &str, pos: usize, msg: &str) -> String {
+    let (line, col) = line_col(src, pos);
+    format!("line {}:{}: {}", line, col, msg)
+}
+
+/// If `bytes` starts with `[[` or `[==[`, returns the number of `=` signs
+fn long_bracket_level(bytes: &[u8]) -> Option<usize> {
+    if bytes.first() != Some(&b'[') {
+        return None;
+    }
+    let level = bytes[1..].iter().take_while(|&&b| b == b'=').count();
+    if bytes.get(1 + level) == Some(&b'[') {
+        Some(level)
+    } else {
+        None
+    }
+}
+
+/// Skips a long bracket starting at `pos`, returning the position after its close
+fn skip_long_bracket(src: &str, pos: usize, level: usize) -> Option<usize> {
+    let close = format!("]{}]", "=".repeat(level));
+    let body = pos + level + 2;
+    src[body..].find(&close).map(|i| body + i + close.len())
+}
+
+fn scan_short_string(bytes: &[u8], pos: usize) -> Option<usize> {
+    let quote = bytes[pos];
+    let mut i = pos + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'\n' => return None,
+            b if b == quote => return Some(i + 1),
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+fn scan_number(bytes: &[u8], pos: usize) -> usize {
+    let mut i = pos;
+    let hex = bytes[i] == b'0' && matches!(bytes.get(i + 1), Some(b'x') | Some(b'X'));
+    if hex {
+        i += 2;
+    }
+    while i < bytes.len() {
+        let b = bytes[i];
+        let is_exponent = if hex {
+            matches!(b, b'p' | b'P')
+        } else {
+            matches!(b, b'e' | b'E')
+        };
+        if is_exponent && matches!(bytes.get(i + 1), Some(b'+') | Some(b'-')) {
+            i += 2;
+        } else if b.is_ascii_alphanumeric() || b == b'.' {
+            i += 1;
+        } else {
+            break;
+        }
+    }
+    i
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(src: &str) -> Vec<&str> {
+        tokenize(src).unwrap().iter().map(|t| t.text).collect()
+    }
+
+    #[test]
+    fn test_tokenize_assignment() {
+        assert_eq!(
+            texts("config.font_size = 14.5 -- big\n"),
+            vec!["config", ".", "font_size", "=", "14.5"]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_strings_and_comments() {
+        let src = r#"--[==[ long
+comment ]==] local s = 'it\'s' .. [[raw ]] "x" ]]"#;
+        assert_eq!(
+            texts(src),
+            vec!["local", "s", "=", r"'it\'s'", "..", "[[raw ]]", r#""x""#, "]", "]"]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_spans() {
+        let src = "a = { 0x1F, 1e-3 }";
+        let tokens = tokenize(src).unwrap();
+        assert_eq!(tokens[3].text, "0x1F");
+        assert_eq!(&src[tokens[5].start..tokens[5].end], "1e-3");
+        assert_eq!(tokens[3].kind, TokenKind::Number);
+    }
+
+    #[test]
+    fn test_unterminated_string() {
+        assert_eq!(tokenize("x = 1\ny = 'oops\n").unwrap_err(), "line 2:5: unfinished string");
+    }
+}
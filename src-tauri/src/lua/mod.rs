@@ -1,7 +1,16 @@
-// Lua module - Lua code generation and parsing
+// Lua module - Lua code generation, parsing, evaluation and in-place editing
 
+pub mod ast;
+pub mod editor;
+pub mod eval;
 pub mod generator;
+pub mod lexer;
 pub mod parser;
+pub mod runtime;
 
+pub use editor::*;
 pub use generator::*;
 pub use parser::*;
+
+#[cfg(test)]
+mod round_trip;
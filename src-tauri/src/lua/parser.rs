@@ -1,189 +1,1037 @@
 // Lua parser - extracts WezTerm configuration values from Lua files
 //
-// WezTerm configs can be complex Lua scripts. This parser uses regex patterns
-// to extract common configuration values. It's not a full Lua parser, but
-// handles the most common config patterns.
+// The file is parsed into a Lua syntax tree (see `ast`), then every
+// `config.<key> = value` assignment and `config_builder` table field is
+// resolved by path. Modules loaded with `require('config.appearance')` are
+// parsed too, so configs split across a directory are read as one. Values
+// are folded with `eval` (locals, arithmetic, `..`, `wezterm.home_dir`);
+// anything only known at run time is reported and left at its default.
+// Key and mouse bindings are matched back to the bindings the generator
+// writes; entries that match none are reported and kept verbatim so saving
+// doesn't drop them. Everything else (unknown keys, event handlers, plugins
+// and helper functions) is listed as unmanaged.
 
+use super::ast::{parse_chunk, Block, Expr, ExprKind, Function, Span, StatKind, UnOp};
+use super::eval::{eval, Scope, Value};
+use super::generator::{
+    config_settings, key_action, MANAGED_KEYS, MANAGED_KEY_TABLES, MOUSE_ACTIONS,
+};
+use super::lexer::{line_col, tokenize, unquote, TokenKind};
 use crate::models::{
-    AppearanceConfig, CloseConfirmation, ColorScheme, CursorConfig, CursorStyle,
-    EaseFunction, FontConfig, FontWeight, FreetypeTarget, FrontEnd, GPUConfig, PowerPreference,
-    TabBarColors, WindowConfig, WindowDecorations, HSB, Padding,
+    AppearanceConfig, AudibleBell, BackdropConfig, CloseConfirmation, ColorScheme,
+    Color, CommandPaletteConfig, CursorConfig, CursorStyle, Diagnostic, EaseFunction, ExitBehavior, FontConfig,
+    FontWeight, FreetypeTarget, FrontEnd, GPUConfig, GeneralConfig, ImportMode, KeyBindingsConfig,
+    PowerPreference, Severity, TabBarColors, TabColors, UnmanagedItem, UnmanagedKind,
+    VisualBellConfig, WindowConfig, WindowDecorations, HSB, Padding,
 };
-use regex::Regex;
-use std::path::Path;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Part of every warning about a value that needs the config to run, so
+/// those warnings can be dropped once it has run
+const RUNTIME_ONLY: &str = "is only known when WezTerm runs";
 
 /// Result of parsing a WezTerm config file
 #[derive(Debug)]
 pub struct ParseResult {
     pub config: AppearanceConfig,
     pub raw_content: String,
-    pub parse_errors: Vec<String>,
+    /// Everything that couldn't be read or imported, with where it is
+    pub diagnostics: Vec<Diagnostic>,
+    /// Files loaded with `require()`, in the order they were found
+    pub modules: Vec<PathBuf>,
+    /// Settings (by dotted key, e.g. `colors.foreground`) whose value comes
+    /// from one of `modules`. Anything not listed belongs to the main file.
+    pub sources: BTreeMap<String, PathBuf>,
+    /// What the settings don't cover, in file and line order
+    pub unmanaged: Vec<UnmanagedItem>,
 }
 
-/// Parse a WezTerm Lua config file and extract configuration values
+/// Parse a WezTerm Lua config file and extract configuration values.
+/// Modules it requires from the same directory are parsed along with it.
 pub fn parse_wezterm_config(path: &Path) -> Result<ParseResult, String> {
     let content = std::fs::read_to_string(path)
         .map_err(|e| format!("Failed to read config file: {}", e))?;
 
-    parse_lua_content(&content)
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+    parse_files(content, path.to_path_buf(), Some(base_dir))
+}
+
+/// Read a WezTerm config file the way `mode` says. `Evaluate` runs the
+/// config (see `runtime`) and reads the values it produces; diagnostics,
+/// modules and unmanaged items still come from parsing it, and if it can't
+/// be run the parsed values are used instead.
+pub fn read_wezterm_config(path: &Path, mode: ImportMode) -> Result<ParseResult, String> {
+    let mut result = parse_wezterm_config(path)?;
+    let has_errors = result.diagnostics.iter().any(|d| d.severity == Severity::Error);
+    if mode == ImportMode::Static || has_errors {
+        return Ok(result);
+    }
+
+    match super::runtime::run_config(path).and_then(|values| parse_lua_content(&values)) {
+        Ok(evaluated) => {
+            result.config = evaluated.config;
+            result.diagnostics.retain(|d| !d.message.contains(RUNTIME_ONLY));
+        }
+        Err(e) => result.diagnostics.push(Diagnostic::for_file(
+            path,
+            Severity::Warning,
+            format!("Could not run the config ({}); values were read without running it", e),
+        )),
+    }
+    Ok(result)
 }
 
 /// Parse Lua content string and extract configuration values
 pub fn parse_lua_content(content: &str) -> Result<ParseResult, String> {
+    parse_files(content.to_string(), PathBuf::new(), None)
+}
+
+/// Parse the main file and, when `base_dir` is given, every module it requires
+fn parse_files(content: String, path: PathBuf, base_dir: Option<&Path>) -> Result<ParseResult, String> {
     let mut config = AppearanceConfig::default();
-    let mut errors = Vec::new();
+    let mut diagnostics = Vec::new();
+    let constants = Constants {
+        home_dir: dirs::home_dir().map(|p| p.to_string_lossy().to_string()),
+        config_dir: base_dir.map(|p| p.to_string_lossy().to_string()),
+        config_file: base_dir.map(|_| path.to_string_lossy().to_string()),
+    };
+
+    let chunk = match parse_chunk(&content) {
+        Ok(chunk) => chunk,
+        Err(e) => {
+            diagnostics.push(syntax_error(&path, &e));
+            return Ok(ParseResult {
+                config,
+                raw_content: content,
+                diagnostics,
+                modules: Vec::new(),
+                sources: BTreeMap::new(),
+                unmanaged: Vec::new(),
+            });
+        }
+    };
+    let mut files = vec![LuaFile {
+        path,
+        module: String::new(),
+        content,
+        chunk,
+    }];
+    if let Some(dir) = base_dir {
+        load_modules(dir, &mut files, &mut diagnostics);
+    }
+
+    let values = ConfigValues::collect(&files, constants);
 
     // Parse each section
-    if let Err(e) = parse_colors(content, &mut config.colors) {
-        errors.push(format!("Colors: {}", e));
+    config.color_scheme = values.string(&["color_scheme"]);
+    if let Err(e) = parse_colors(&values, &mut config.colors) {
+        diagnostics.push(values.warning(&["colors"], format!("Colors: {}", e)));
+    }
+    if let Err(e) = parse_fonts(&values, &mut config.fonts) {
+        diagnostics.push(values.warning(&["font"], format!("Fonts: {}", e)));
+    }
+    if let Err(e) = parse_window(&values, &mut config.window) {
+        diagnostics.push(values.warning(&[], format!("Window: {}", e)));
+    }
+    if let Err(e) = parse_cursor(&values, &mut config.cursor) {
+        diagnostics.push(values.warning(&[], format!("Cursor: {}", e)));
+    }
+    if let Err(e) = parse_gpu(&values, &mut config.gpu) {
+        diagnostics.push(values.warning(&[], format!("GPU: {}", e)));
     }
-    if let Err(e) = parse_fonts(content, &mut config.fonts) {
-        errors.push(format!("Fonts: {}", e));
+    if let Err(e) = parse_backdrop(&values, &mut config.backdrop) {
+        diagnostics.push(values.warning(&["window_background_image"], format!("Backdrop: {}", e)));
     }
-    if let Err(e) = parse_window(content, &mut config.window) {
-        errors.push(format!("Window: {}", e));
+    if let Err(e) = parse_general(&values, &mut config.general) {
+        diagnostics.push(values.warning(&[], format!("General: {}", e)));
     }
-    if let Err(e) = parse_cursor(content, &mut config.cursor) {
-        errors.push(format!("Cursor: {}", e));
+    if let Err(e) = parse_command_palette(&values, &mut config.command_palette) {
+        diagnostics.push(values.warning(&[], format!("Command palette: {}", e)));
     }
-    if let Err(e) = parse_gpu(content, &mut config.gpu) {
-        errors.push(format!("GPU: {}", e));
+    if let Err(e) = parse_visual_bell(&values, &mut config.visual_bell) {
+        diagnostics.push(values.warning(&["visual_bell"], format!("Visual bell: {}", e)));
     }
+    diagnostics.extend(parse_keybindings(&values, &mut config.keybindings));
+    diagnostics.extend(values.dynamic_warnings());
+    diagnostics.extend(values.invalid_warnings());
 
+    let sources = values.sources(&config);
+    let unmanaged = values.unmanaged();
+    let mut files = files.into_iter();
+    let main = files.next().expect("the main file is always loaded");
     Ok(ParseResult {
         config,
-        raw_content: content.to_string(),
-        parse_errors: errors,
+        raw_content: main.content,
+        diagnostics,
+        modules: files.map(|f| f.path).collect(),
+        sources,
+        unmanaged,
     })
 }
 
 // ============================================================================
-// Color Parsing
+// Modules
 // ============================================================================
 
-fn parse_colors(content: &str, colors: &mut ColorScheme) -> Result<(), String> {
-    // Parse simple color assignments like: config.foreground = "#cdd6f4"
-    if let Some(val) = extract_string_value(content, r#"(?:config\.)?foreground\s*=\s*["']([^"']+)["']"#) {
-        colors.foreground = val;
+/// A Lua file the config is read from: wezterm.lua or a module it requires
+struct LuaFile {
+    path: PathBuf,
+    /// The name it was required as (`config.appearance`); empty for the main file
+    module: String,
+    content: String,
+    chunk: Block,
+}
+
+impl LuaFile {
+    /// A warning about `expr` in this file, concerning the setting `key`
+    fn warning(&self, expr: &Expr, key: &str, message: impl Into<String>) -> Diagnostic {
+        let (line, column) = line_col(&self.content, expr.span.start);
+        Diagnostic {
+            line: Some(line),
+            column: Some(column),
+            key: Some(key.to_string()),
+            ..Diagnostic::for_file(&self.path, Severity::Warning, message)
+        }
     }
-    if let Some(val) = extract_string_value(content, r#"(?:config\.)?background\s*=\s*["']([^"']+)["']"#) {
-        colors.background = val;
+}
+
+/// A syntax error from the lexer or parser, which report `line L:C: message`
+fn syntax_error(path: &Path, error: &str) -> Diagnostic {
+    let position = error.strip_prefix("line ").and_then(|rest| {
+        let (position, message) = rest.split_once(": ")?;
+        let (line, column) = position.split_once(':')?;
+        Some((line.parse().ok()?, column.parse().ok()?, message))
+    });
+    let (line, column, message) = match position {
+        Some((line, column, message)) => (Some(line), Some(column), message),
+        None => (None, None, error),
+    };
+    Diagnostic {
+        line,
+        column,
+        ..Diagnostic::for_file(path, Severity::Error, format!("Syntax error: {}", message))
     }
-    if let Some(val) = extract_string_value(content, r#"(?:config\.)?cursor_bg\s*=\s*["']([^"']+)["']"#) {
-        colors.cursor_bg = val;
+}
+
+/// Load every module required by the files loaded so far, following the
+/// requires of each module in turn. Names are resolved the way WezTerm's
+/// `package.path` does for the config directory: `a.b` is `<dir>/a/b.lua` or
+/// `<dir>/a/b/init.lua`. Anything else (plugins, Lua libraries) is skipped,
+/// as is `wezterm` itself, which is built in even next to a `wezterm.lua`.
+fn load_modules(base_dir: &Path, files: &mut Vec<LuaFile>, diagnostics: &mut Vec<Diagnostic>) {
+    let mut seen: Vec<String> = Vec::new();
+    let mut next = 0;
+    while next < files.len() {
+        for name in required_names(&files[next].content) {
+            if seen.contains(&name) {
+                continue;
+            }
+            seen.push(name.clone());
+
+            let Some(path) = module_path(base_dir, &name) else {
+                continue;
+            };
+            let content = match std::fs::read_to_string(&path) {
+                Ok(content) => content,
+                Err(e) => {
+                    let message = format!("Failed to read module {}: {}", name, e);
+                    diagnostics.push(Diagnostic::for_file(&path, Severity::Error, message));
+                    continue;
+                }
+            };
+            match parse_chunk(&content) {
+                Ok(chunk) => files.push(LuaFile {
+                    path,
+                    module: name,
+                    content,
+                    chunk,
+                }),
+                Err(e) => diagnostics.push(syntax_error(&path, &e)),
+            }
+        }
+        next += 1;
     }
-    if let Some(val) = extract_string_value(content, r#"(?:config\.)?cursor_border\s*=\s*["']([^"']+)["']"#) {
-        colors.cursor_border = val;
+}
+
+/// Names passed to `require 'x'` or `require('x')` anywhere in the source
+fn required_names(source: &str) -> Vec<String> {
+    let Ok(tokens) = tokenize(source) else {
+        return Vec::new();
+    };
+    let mut names = Vec::new();
+    for (i, token) in tokens.iter().enumerate() {
+        if !token.is_name("require") || (i > 0 && tokens[i - 1].is_symbol(".")) {
+            continue;
+        }
+        let arg = match tokens.get(i + 1) {
+            Some(open) if open.is_symbol("(") => tokens.get(i + 2),
+            other => other,
+        };
+        if let Some(name) = arg.filter(|t| t.kind == TokenKind::String).and_then(|t| unquote(t.text)) {
+            names.push(name);
+        }
     }
-    if let Some(val) = extract_string_value(content, r#"(?:config\.)?cursor_fg\s*=\s*["']([^"']+)["']"#) {
-        colors.cursor_fg = val;
+    names
+}
+
+/// The file a module name refers to, if it exists under `base_dir`
+pub(super) fn module_path(base_dir: &Path, name: &str) -> Option<PathBuf> {
+    if name == "wezterm" || name.split('.').any(|part| part.is_empty() || part == "..") {
+        return None;
     }
-    if let Some(val) = extract_string_value(content, r#"(?:config\.)?selection_bg\s*=\s*["']([^"']+)["']"#) {
-        colors.selection_bg = val;
+    let relative: PathBuf = name.split('.').collect();
+    let file = base_dir.join(&relative).with_extension("lua");
+    let init = base_dir.join(&relative).join("init.lua");
+    [file, init].into_iter().find(|p| p.is_file())
+}
+
+/// The module name of a `require('name')` call
+fn required_module(expr: &Expr) -> Option<&str> {
+    let ExprKind::Call { func, args } = &expr.kind else {
+        return None;
+    };
+    match (&func.kind, args.as_slice()) {
+        (ExprKind::Name(name), [arg]) if name == "require" => arg.as_str(),
+        _ => None,
     }
-    if let Some(val) = extract_string_value(content, r#"(?:config\.)?selection_fg\s*=\s*["']([^"']+)["']"#) {
-        colors.selection_fg = val;
+}
+
+/// The `wezterm.plugin.require('url')` call's plugin URL
+fn plugin_url(expr: &Expr) -> Option<&str> {
+    let ExprKind::Call { func, args } = &expr.kind else {
+        return None;
+    };
+    match (func.dotted_path()?.as_slice(), args.first()) {
+        (["wezterm", "plugin", "require"], Some(arg)) => arg.as_str(),
+        _ => None,
     }
+}
 
-    // Parse ANSI colors array
-    if let Some(ansi) = extract_color_array(content, "ansi") {
-        if ansi.len() == 8 {
-            colors.ansi = ansi.try_into().unwrap_or(colors.ansi.clone());
+/// Calls in an expression, outside function bodies, and whether each one's
+/// result is passed to another call (`Config:append(require('x'))`)
+fn find_calls<'e>(expr: &'e Expr, as_argument: bool, found: &mut Vec<(&'e Expr, bool)>) {
+    match &expr.kind {
+        ExprKind::Call { func, args } => {
+            found.push((expr, as_argument));
+            find_calls(func, false, found);
+            for arg in args {
+                find_calls(arg, true, found);
+            }
+        }
+        ExprKind::MethodCall { object, args, .. } => {
+            found.push((expr, as_argument));
+            find_calls(object, false, found);
+            for arg in args {
+                find_calls(arg, true, found);
+            }
+        }
+        ExprKind::Paren(inner) => find_calls(inner, as_argument, found),
+        ExprKind::Field { object, .. } => find_calls(object, false, found),
+        ExprKind::Index { object, key } => {
+            find_calls(object, false, found);
+            find_calls(key, false, found);
+        }
+        ExprKind::Unary { expr, .. } => find_calls(expr, false, found),
+        ExprKind::Binary { lhs, rhs, .. } => {
+            find_calls(lhs, false, found);
+            find_calls(rhs, false, found);
+        }
+        ExprKind::Table(fields) => {
+            for field in fields {
+                find_calls(&field.value, false, found);
+            }
         }
+        _ => {}
     }
+}
 
-    // Parse bright colors array
-    if let Some(brights) = extract_color_array(content, "brights") {
-        if brights.len() == 8 {
-            colors.brights = brights.try_into().unwrap_or(colors.brights.clone());
-        }
+/// The expression a chunk returns at the top level
+fn returned_expr(chunk: &Block) -> Option<&Expr> {
+    match chunk.stats.last().map(|s| &s.kind) {
+        Some(StatKind::Return(exprs)) => exprs.first(),
+        _ => None,
     }
+}
 
-    // Parse tab bar colors from colors block
-    parse_tab_bar_colors(content, &mut colors.tab_bar);
+// ============================================================================
+// Config Value Resolution
+// ============================================================================
 
-    Ok(())
+/// Every value assigned to the config table, in the order WezTerm runs it
+struct ConfigValues<'a> {
+    files: &'a [LuaFile],
+    /// Assigned path, value and the index of the file it is in
+    entries: Vec<(Vec<&'a str>, &'a Expr, usize)>,
+    /// Indexes of the entries made in a branch, a loop or a function other
+    /// than `apply_to_config`; whether they run is only known at run time
+    conditional: Vec<usize>,
+    /// `local name = value` declarations, the file they are in and where
+    /// the block declaring them ends, for values that refer to a local
+    locals: Vec<(&'a str, &'a Expr, usize, usize)>,
+    /// Locals assigned again after their declaration (`size = 20`), with
+    /// their file; which value they hold is only known at run time
+    reassigned: Vec<(&'a str, usize)>,
+    /// `wezterm.on('event', function ... end)` handlers
+    handlers: Vec<(&'a str, &'a Function)>,
+    /// Handlers, plugins and functions the settings don't manage, with the
+    /// file and byte offset they are defined at
+    definitions: Vec<(UnmanagedKind, String, usize, usize)>,
+    /// Modules already run; like Lua, `require` runs each one once
+    loaded: Vec<usize>,
+    constants: Constants,
+    /// Settings read so far whose value is only known at run time, with
+    /// what they depend on
+    dynamic: RefCell<Vec<(String, String)>>,
+    /// Settings read so far whose value isn't valid for them, with why
+    invalid: RefCell<Vec<(String, String)>>,
 }
 
-fn parse_tab_bar_colors(content: &str, tab_bar: &mut TabBarColors) {
-    // Look for tab_bar block within colors
-    if let Some(val) = extract_nested_string(content, &["colors", "tab_bar", "background"]) {
-        tab_bar.background = val;
+impl<'a> ConfigValues<'a> {
+    /// Collect `config.a.b = v` assignments and the fields of tables used as the
+    /// config (`local config = { ... }` or `return { ... }`). The config table is
+    /// `config`, any local initialised with `wezterm.config_builder()`, and
+    /// whatever name the chunk returns.
+    ///
+    /// Modules are read where they are required, so a later assignment wins
+    /// whichever file it is in. A module's settings are its `config.x = v`
+    /// assignments (usually in `apply_to_config(config)`), or the table it
+    /// returns when that is passed to a call such as `Config:append(...)`.
+    fn collect(files: &'a [LuaFile], constants: Constants) -> Self {
+        let chunk = &files[0].chunk;
+        let mut config_vars = vec!["config"];
+        collect_config_vars(chunk, &mut config_vars);
+        if let Some(ExprKind::Name(name)) = returned_expr(chunk).map(|e| &e.kind) {
+            config_vars.push(name);
+        }
+
+        let mut values = Self {
+            files,
+            entries: Vec::new(),
+            conditional: Vec::new(),
+            locals: Vec::new(),
+            reassigned: Vec::new(),
+            handlers: Vec::new(),
+            definitions: Vec::new(),
+            loaded: Vec::new(),
+            constants,
+            dynamic: RefCell::new(Vec::new()),
+            invalid: RefCell::new(Vec::new()),
+        };
+        values.walk(chunk, &config_vars, true, 0);
+        values
+    }
+
+    fn walk(&mut self, block: &'a Block, config_vars: &[&'a str], top_level: bool, file: usize) {
+        for stat in &block.stats {
+            match &stat.kind {
+                StatKind::Local { names, exprs } => {
+                    self.run_calls(exprs, file);
+                    for (name, expr) in names.iter().zip(exprs) {
+                        if config_vars.contains(&name.as_str()) {
+                            self.push_fields(&[], expr, file);
+                        }
+                        if let ExprKind::Function(_) = expr.kind {
+                            self.define(UnmanagedKind::Function, name, file, stat.span);
+                        }
+                        self.locals.push((name, expr, file, block.span.end));
+                    }
+                }
+                StatKind::Assign { targets, exprs } => {
+                    self.run_calls(targets.iter().chain(exprs), file);
+                    for (target, expr) in targets.iter().zip(exprs) {
+                        match config_path(target, config_vars) {
+                            Some(path) if path.is_empty() => self.push_fields(&[], expr, file),
+                            Some(path) => self.entries.push((path, expr, file)),
+                            None => {
+                                if let ExprKind::Name(name) = &target.kind {
+                                    self.reassigned.push((name, file));
+                                }
+                            }
+                        }
+                    }
+                }
+                StatKind::Call(call) => {
+                    self.run_calls([call], file);
+                    if let Some((event, handler)) = event_handler(call) {
+                        if !is_generated_handler(event, handler) {
+                            self.define(UnmanagedKind::EventHandler, event, file, stat.span);
+                        }
+                        self.handlers.push((event, handler));
+                    }
+                }
+                StatKind::Return(exprs) => {
+                    self.run_calls(exprs, file);
+                    // `return config` returns what was collected already
+                    let returned = exprs.first().filter(|e| !matches!(e.kind, ExprKind::Name(_)));
+                    if let (true, Some(expr)) = (top_level, returned) {
+                        self.push_fields(&[], expr, file);
+                    }
+                }
+                // `for k, v in pairs(require('config.appearance')) do config[k] = v end`
+                StatKind::GenericFor { exprs, body, .. } => {
+                    self.run_calls(exprs, file);
+                    self.walk_conditional(body, config_vars, file)
+                }
+                StatKind::Do(body)
+                | StatKind::While { body, .. }
+                | StatKind::Repeat { body, .. }
+                | StatKind::NumericFor { body, .. } => self.walk_conditional(body, config_vars, file),
+                StatKind::If { branches, otherwise } => {
+                    for (_, body) in branches {
+                        self.walk_conditional(body, config_vars, file);
+                    }
+                    if let Some(body) = otherwise {
+                        self.walk_conditional(body, config_vars, file);
+                    }
+                }
+                // Helpers like `function M.apply_to_config(config)` set values too;
+                // the config is apply_to_config's first parameter whatever its name
+                StatKind::Function { name, method, func } => {
+                    let mut vars = config_vars.to_vec();
+                    let applies = method.as_deref().or(name.last().map(String::as_str));
+                    match (applies, func.params.first()) {
+                        (Some("apply_to_config"), Some(param)) => {
+                            vars.push(param);
+                            self.walk(&func.body, &vars, false, file)
+                        }
+                        _ => {
+                            let mut full_name = name.join(".");
+                            if let Some(method) = method {
+                                full_name = format!("{}:{}", full_name, method);
+                            }
+                            self.define(UnmanagedKind::Function, &full_name, file, stat.span);
+                            self.walk_conditional(&func.body, &vars, file)
+                        }
+                    }
+                }
+                StatKind::LocalFunction { name, func } => {
+                    self.define(UnmanagedKind::Function, name, file, stat.span);
+                    self.walk_conditional(&func.body, config_vars, file)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Walk a block that may not run, or run more than once, marking the
+    /// values assigned in it as only known at run time
+    fn walk_conditional(&mut self, block: &'a Block, config_vars: &[&'a str], file: usize) {
+        let first = self.entries.len();
+        self.walk(block, config_vars, false, file);
+        self.conditional.extend(first..self.entries.len());
+    }
+
+    /// Run the modules required by a statement before its own assignments,
+    /// and note the plugins it loads. A module's returned table is merged into
+    /// the config when it is passed straight to another call.
+    fn run_calls(&mut self, exprs: impl IntoIterator<Item = &'a Expr>, file: usize) {
+        let mut found = Vec::new();
+        for expr in exprs {
+            find_calls(expr, false, &mut found);
+        }
+        for (call, as_argument) in found {
+            if let Some(url) = plugin_url(call) {
+                self.define(UnmanagedKind::Plugin, url, file, call.span);
+            }
+            let Some(index) = required_module(call).and_then(|name| self.module(name)) else {
+                continue;
+            };
+            if !self.loaded.contains(&index) {
+                self.loaded.push(index);
+                let chunk = &self.files[index].chunk;
+                let mut config_vars = vec!["config"];
+                collect_config_vars(chunk, &mut config_vars);
+                self.walk(chunk, &config_vars, false, index);
+            }
+            if as_argument {
+                if let Some(expr) = returned_expr(&self.files[index].chunk) {
+                    self.push_fields(&[], expr, index);
+                }
+            }
+        }
+    }
+
+    /// Note an unmanaged definition at `span` in `file`
+    fn define(&mut self, kind: UnmanagedKind, name: &str, file: usize, span: Span) {
+        self.definitions.push((kind, name.to_string(), file, span.start));
+    }
+
+    /// Everything the settings don't manage: config keys outside
+    /// `MANAGED_KEYS` (where they are last assigned) and the handlers,
+    /// plugins and functions found while walking the files
+    fn unmanaged(&self) -> Vec<UnmanagedItem> {
+        let mut found: Vec<(usize, usize, UnmanagedKind, &str)> = Vec::new();
+        for (path, expr, file) in self.entries.iter().rev() {
+            let key = path[0];
+            let seen = found.iter().any(|(_, _, kind, name)| *kind == UnmanagedKind::Setting && *name == key);
+            if !MANAGED_KEYS.contains(&key) && !seen {
+                found.push((*file, expr.span.start, UnmanagedKind::Setting, key));
+            }
+        }
+        for (kind, name, file, offset) in &self.definitions {
+            found.push((*file, *offset, *kind, name));
+        }
+        found.sort_by_key(|(file, offset, _, _)| (*file, *offset));
+
+        found
+            .into_iter()
+            .map(|(file, offset, kind, name)| {
+                let file = &self.files[file];
+                UnmanagedItem {
+                    kind,
+                    name: name.to_string(),
+                    file: file.path.to_string_lossy().to_string(),
+                    line: line_col(&file.content, offset).0,
+                }
+            })
+            .collect()
+    }
+
+    /// Index of the file loaded for a module name
+    fn module(&self, name: &str) -> Option<usize> {
+        self.files.iter().skip(1).position(|f| f.module == name).map(|i| i + 1)
+    }
+
+    /// Follow locals to the value they are bound to, and `require('module')`
+    /// to the value the module returns
+    fn resolve(&self, mut expr: &'a Expr, mut file: usize) -> (&'a Expr, usize) {
+        // Bounded so modules that return each other can't loop forever
+        for _ in 0..self.files.len() + self.locals.len() {
+            if let ExprKind::Name(name) = &expr.kind {
+                match self.binding(name, file, expr) {
+                    Some(bound) => expr = bound,
+                    None => break,
+                }
+                continue;
+            }
+            let Some(index) = required_module(expr).and_then(|name| self.module(name)) else {
+                break;
+            };
+            let Some(returned) = returned_expr(&self.files[index].chunk) else {
+                break;
+            };
+            (expr, file) = (returned, index);
+        }
+        (expr, file)
+    }
+
+    /// The value of the last `local <name> = value` in `file` before `at`
+    /// and still in scope there. A local assigned again anywhere (in a
+    /// branch, a loop or a function) has no single value.
+    fn binding(&self, name: &str, file: usize, at: &Expr) -> Option<&'a Expr> {
+        if self.reassigned.contains(&(name, file)) {
+            return None;
+        }
+        self.locals
+            .iter()
+            .rev()
+            .find(|(n, value, f, end)| {
+                *n == name && *f == file && value.span.end <= at.span.start && at.span.start < *end
+            })
+            .map(|(_, value, _, _)| *value)
+    }
+
+    /// Fold an expression written in `file`
+    fn eval(&self, expr: &'a Expr, file: usize) -> Value<'a> {
+        eval(expr, &FileScope { values: self, file })
+    }
+
+    /// Record each named field of a table constructor as `prefix.<field>`
+    fn push_fields(&mut self, prefix: &[&'a str], expr: &'a Expr, file: usize) {
+        let (expr, file) = self.resolve(expr, file);
+        let Some(fields) = expr.as_table() else {
+            return;
+        };
+        for field in fields {
+            if let Some(name) = field.name() {
+                let mut path = prefix.to_vec();
+                path.push(name);
+                self.entries.push((path, &field.value, file));
+            }
+        }
+    }
+
+    /// The value at `path` and the file it is written in, following nested
+    /// table constructors and required modules. The last assignment covering
+    /// the path wins, as it does in Lua.
+    fn lookup(&self, path: &[&str]) -> Option<(&'a Expr, &'a LuaFile)> {
+        self.lookup_at(path).map(|(expr, file)| (expr, &self.files[file]))
+    }
+
+    /// `lookup`, giving the index of the file
+    fn lookup_at(&self, path: &[&str]) -> Option<(&'a Expr, usize)> {
+        let (assigned, expr, file) = &self.entries[self.entry(path)?];
+
+        let (expr, file) = path[assigned.len()..]
+            .iter()
+            .try_fold(self.resolve(expr, *file), |(expr, file), key| {
+                Some(self.resolve(expr.table_get(key)?, file))
+            })?;
+        Some((expr, file))
+    }
+
+    /// Index of the last entry covering `path`
+    fn entry(&self, path: &[&str]) -> Option<usize> {
+        self.entries
+            .iter()
+            .rposition(|(p, _, _)| p.len() <= path.len() && p[..] == path[..p.len()])
+    }
+
+    fn get(&self, path: &[&str]) -> Option<&'a Expr> {
+        self.lookup(path).map(|(expr, _)| expr)
+    }
+
+    /// The folded value at `path`. One only known at run time, or assigned
+    /// where it may not run, is noted so it can be reported, and reads as
+    /// missing.
+    fn value(&self, path: &[&str]) -> Option<Value<'a>> {
+        let (expr, file) = self.lookup_at(path)?;
+        let value = if self.entry(path).is_some_and(|i| self.conditional.contains(&i)) {
+            Value::Dynamic("a branch, loop or function".to_string())
+        } else {
+            self.eval(expr, file)
+        };
+        match value {
+            Value::Dynamic(reason) => {
+                self.dynamic.borrow_mut().push((path.join("."), reason));
+                None
+            }
+            value => Some(value),
+        }
+    }
+
+    /// The value at `path` if it is the expected type. One of another type
+    /// is noted so it can be reported, and reads as missing; so does `nil`,
+    /// without a report, as it leaves the setting unset.
+    fn typed<T>(&self, path: &[&str], kind: &str, get: impl Fn(&Value<'a>) -> Option<T>) -> Option<T> {
+        let value = self.value(path)?;
+        let typed = get(&value);
+        if typed.is_none() && value != Value::Nil {
+            self.reject(path, &format!("is not {}", kind));
+        }
+        typed
+    }
+
+    /// Note the value at `path` as invalid, quoting it before `reason`
+    /// (`` `'big'` is not a number ``)
+    fn reject(&self, path: &[&str], reason: &str) {
+        let text = self
+            .lookup(path)
+            .map(|(expr, file)| file.content[expr.span.start..expr.span.end].to_string())
+            .unwrap_or_default();
+        self.invalid.borrow_mut().push((path.join("."), format!("`{}` {}", text, reason)));
+    }
+
+    fn string(&self, path: &[&str]) -> Option<String> {
+        self.typed(path, "a string", |v| v.as_str().map(str::to_string))
+    }
+
+    fn number(&self, path: &[&str]) -> Option<f32> {
+        let n = self.typed(path, "a number", Value::as_number)? as f32;
+        if !n.is_finite() {
+            self.reject(path, "is out of range");
+            return None;
+        }
+        Some(n)
+    }
+
+    /// A whole number of 0 or more, read at full precision rather than
+    /// through `f32`
+    fn integer(&self, path: &[&str]) -> Option<u32> {
+        let n = self.typed(path, "a number", Value::as_number)?;
+        if n < 0.0 || n.fract() != 0.0 {
+            self.reject(path, "is not a whole number of 0 or more");
+            return None;
+        }
+        if n > u32::MAX as f64 {
+            self.reject(path, &format!("is out of range (at most {})", u32::MAX));
+            return None;
+        }
+        Some(n as u32)
+    }
+
+    fn bool(&self, path: &[&str]) -> Option<bool> {
+        self.typed(path, "true or false", Value::as_bool)
+    }
+
+    /// A color; text that isn't one is noted so it can be reported, and
+    /// reads as missing
+    fn color(&self, path: &[&str]) -> Option<Color> {
+        let text = self.string(path)?;
+        Color::parse(&text)
+            .map_err(|e| self.invalid.borrow_mut().push((path.join("."), e)))
+            .ok()
     }
 
-    // Parse active_tab
-    if let Some(bg) = extract_nested_string(content, &["colors", "tab_bar", "active_tab", "bg_color"]) {
-        tab_bar.active_tab.bg_color = bg;
+    /// A warning for each setting that was read but is only known at run time
+    fn dynamic_warnings(&self) -> Vec<Diagnostic> {
+        let mut seen: Vec<&str> = Vec::new();
+        let dynamic = self.dynamic.borrow();
+        let mut warnings = Vec::new();
+        for (key, reason) in dynamic.iter() {
+            if seen.contains(&key.as_str()) {
+                continue;
+            }
+            seen.push(key);
+            let message = format!("{} depends on {} and {}; the default is shown", key, reason, RUNTIME_ONLY);
+            warnings.push(self.warning(&key.split('.').collect::<Vec<_>>(), message));
+        }
+        warnings
     }
-    if let Some(fg) = extract_nested_string(content, &["colors", "tab_bar", "active_tab", "fg_color"]) {
-        tab_bar.active_tab.fg_color = fg;
+
+    /// A warning for each setting whose value was read but isn't valid
+    fn invalid_warnings(&self) -> Vec<Diagnostic> {
+        self.invalid
+            .borrow()
+            .iter()
+            .map(|(key, reason)| {
+                let message = format!("{}: {}; the default is shown", key, reason);
+                self.warning(&key.split('.').collect::<Vec<_>>(), message)
+            })
+            .collect()
     }
 
-    // Parse inactive_tab
-    if let Some(bg) = extract_nested_string(content, &["colors", "tab_bar", "inactive_tab", "bg_color"]) {
-        tab_bar.inactive_tab.bg_color = bg;
+    /// A warning about the value at `path`, located where it is assigned
+    fn warning(&self, path: &[&str], message: String) -> Diagnostic {
+        let key = path.join(".");
+        match self.lookup(path).filter(|_| !path.is_empty()) {
+            Some((expr, file)) => file.warning(expr, &key, message),
+            None => Diagnostic {
+                key: (!key.is_empty()).then_some(key),
+                ..Diagnostic::for_file(&self.files[0].path, Severity::Warning, message)
+            },
+        }
     }
-    if let Some(fg) = extract_nested_string(content, &["colors", "tab_bar", "inactive_tab", "fg_color"]) {
-        tab_bar.inactive_tab.fg_color = fg;
+
+    /// The module each generated setting is read from. A setting the files
+    /// don't assign belongs to the file holding its closest enclosing table,
+    /// so new fields are written next to their siblings.
+    fn sources(&self, config: &AppearanceConfig) -> BTreeMap<String, PathBuf> {
+        let mut sources = BTreeMap::new();
+        for setting in config_settings(config) {
+            let file = (1..=setting.path.len())
+                .rev()
+                .find_map(|len| self.lookup(&setting.path[..len]))
+                .map(|(_, file)| file);
+            if let Some(file) = file.filter(|f| !f.module.is_empty()) {
+                sources.insert(setting.key(), file.path.clone());
+            }
+        }
+        sources
     }
 }
 
-fn extract_color_array(content: &str, name: &str) -> Option<Vec<String>> {
-    // Match patterns like: ansi = { "#000", "#111", ... }
-    let pattern = format!(r#"{}\s*=\s*\{{\s*([^}}]+)\s*\}}"#, name);
-    let re = Regex::new(&pattern).ok()?;
-    let caps = re.captures(content)?;
-    let array_content = caps.get(1)?.as_str();
+/// Locals and `wezterm.*` constants as seen from one file
+struct FileScope<'v, 'a> {
+    values: &'v ConfigValues<'a>,
+    file: usize,
+}
 
-    // Extract quoted strings from array
-    let string_re = Regex::new(r#"["']([^"']+)["']"#).ok()?;
-    let colors: Vec<String> = string_re
-        .captures_iter(array_content)
-        .filter_map(|c| c.get(1).map(|m| m.as_str().to_string()))
-        .collect();
+impl<'a> Scope<'a> for FileScope<'_, 'a> {
+    fn local(&self, name: &str, at: &'a Expr) -> Option<&'a Expr> {
+        self.values.binding(name, self.file, at)
+    }
 
-    if colors.is_empty() {
-        None
-    } else {
-        Some(colors)
+    fn wezterm(&self, name: &str) -> Option<Value<'a>> {
+        let constants = &self.values.constants;
+        let value = match name {
+            "home_dir" => constants.home_dir.clone(),
+            "config_dir" => constants.config_dir.clone(),
+            "config_file" => constants.config_file.clone(),
+            _ => None,
+        };
+        value.map(Value::String)
+    }
+}
+
+/// `wezterm.*` values that are the same every time WezTerm runs the config
+/// on this machine. Unknown ones (no file when parsing a string) are dynamic,
+/// as is `wezterm.target_triple`: the same config often runs on several.
+struct Constants {
+    home_dir: Option<String>,
+    config_dir: Option<String>,
+    config_file: Option<String>,
+}
+
+/// Names of locals initialised with `wezterm.config_builder()`
+fn collect_config_vars<'a>(block: &'a Block, vars: &mut Vec<&'a str>) {
+    for stat in &block.stats {
+        if let StatKind::Local { names, exprs } = &stat.kind {
+            for (name, expr) in names.iter().zip(exprs) {
+                if let ExprKind::Call { func, .. } = &expr.kind {
+                    if func.dotted_path().is_some_and(|p| p == ["wezterm", "config_builder"]) {
+                        vars.push(name);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The event name and function of a `wezterm.on('event', function ... end)` call
+fn event_handler(call: &Expr) -> Option<(&str, &Function)> {
+    let ExprKind::Call { func, args } = &call.kind else {
+        return None;
+    };
+    if func.dotted_path()? != ["wezterm", "on"] {
+        return None;
+    }
+    match (args.first()?.as_str()?, &args.get(1)?.kind) {
+        (event, ExprKind::Function(handler)) => Some((event, handler)),
+        _ => None,
     }
 }
 
+/// `config.a["b"]` as `["a", "b"]` if the expression is rooted at a config table
+fn config_path<'a>(expr: &'a Expr, config_vars: &[&str]) -> Option<Vec<&'a str>> {
+    let path = expr.dotted_path()?;
+    config_vars.contains(&path[0]).then(|| path[1..].to_vec())
+}
+
 // ============================================================================
-// Font Parsing
+// Color Parsing
 // ============================================================================
 
-fn parse_fonts(content: &str, fonts: &mut FontConfig) -> Result<(), String> {
-    // Parse font_size = 12
-    if let Some(val) = extract_number_value(content, r#"(?:config\.)?font_size\s*=\s*(\d+(?:\.\d+)?)"#) {
-        fonts.size = val;
+fn parse_colors(values: &ConfigValues, colors: &mut ColorScheme) -> Result<(), String> {
+    let fields: [(&str, &mut Color); 7] = [
+        ("foreground", &mut colors.foreground),
+        ("background", &mut colors.background),
+        ("cursor_bg", &mut colors.cursor_bg),
+        ("cursor_border", &mut colors.cursor_border),
+        ("cursor_fg", &mut colors.cursor_fg),
+        ("selection_bg", &mut colors.selection_bg),
+        ("selection_fg", &mut colors.selection_fg),
+    ];
+    for (key, field) in fields {
+        if let Some(val) = values.color(&["colors", key]) {
+            *field = val;
+        }
     }
 
-    // Parse font = wezterm.font("JetBrainsMono Nerd Font")
-    if let Some(val) = extract_string_value(content, r#"wezterm\.font\s*\(\s*["']([^"']+)["']"#) {
-        fonts.family = val;
+    // The optional colors are only written when set, so a `colors` table
+    // without them clears them
+    if values.get(&["colors"]).is_some() {
+        colors.visual_bell = values.color(&["colors", "visual_bell"]);
+        colors.scrollbar_thumb = values.color(&["colors", "scrollbar_thumb"]);
+        colors.split = values.color(&["colors", "split"]);
     }
-    // Also try: font = wezterm.font { family = "..." }
-    if let Some(val) = extract_string_value(content, r#"wezterm\.font\s*\{\s*family\s*=\s*["']([^"']+)["']"#) {
-        fonts.family = val;
+
+    // Parse ANSI colors array
+    if let Some(ansi) = extract_color_array(values, "ansi") {
+        colors.ansi = ansi;
     }
 
-    // Parse font weight
-    if let Some(val) = extract_string_value(content, r#"(?:weight|font_weight)\s*=\s*["']([^"']+)["']"#) {
-        fonts.weight = parse_font_weight(&val);
+    // Parse bright colors array
+    if let Some(brights) = extract_color_array(values, "brights") {
+        colors.brights = brights;
     }
 
-    // Parse freetype targets
-    if let Some(val) = extract_string_value(content, r#"freetype_load_target\s*=\s*["']([^"']+)["']"#) {
-        fonts.freetype_load_target = parse_freetype_target(&val);
+    // Parse tab bar colors from colors block
+    parse_tab_bar_colors(values, &mut colors.tab_bar);
+
+    Ok(())
+}
+
+fn parse_tab_bar_colors(values: &ConfigValues, tab_bar: &mut TabBarColors) {
+    if let Some(val) = values.color(&["colors", "tab_bar", "background"]) {
+        tab_bar.background = val;
     }
-    if let Some(val) = extract_string_value(content, r#"freetype_render_target\s*=\s*["']([^"']+)["']"#) {
-        fonts.freetype_render_target = parse_freetype_target(&val);
+
+    let tabs: [(&str, &mut TabColors); 5] = [
+        ("active_tab", &mut tab_bar.active_tab),
+        ("inactive_tab", &mut tab_bar.inactive_tab),
+        ("inactive_tab_hover", &mut tab_bar.inactive_tab_hover),
+        ("new_tab", &mut tab_bar.new_tab),
+        ("new_tab_hover", &mut tab_bar.new_tab_hover),
+    ];
+    for (name, tab) in tabs {
+        if let Some(bg) = values.color(&["colors", "tab_bar", name, "bg_color"]) {
+            tab.bg_color = bg;
+        }
+        if let Some(fg) = values.color(&["colors", "tab_bar", name, "fg_color"]) {
+            tab.fg_color = fg;
+        }
+        if values.get(&["colors", "tab_bar", name]).is_some() {
+            tab.italic = values.bool(&["colors", "tab_bar", name, "italic"]);
+        }
     }
+}
+
+/// `colors.<name> = { '#000', ... }` with exactly 8 color entries
+fn extract_color_array(values: &ConfigValues, name: &str) -> Option<[Color; 8]> {
+    let (array, file) = values.lookup_at(&["colors", name])?;
+    let texts: Option<Vec<String>> = array
+        .table_items()?
+        .iter()
+        .map(|e| values.eval(e, file).as_str().map(str::to_string))
+        .collect();
+    let colors: Result<Vec<Color>, String> = texts?.iter().map(|t| Color::parse(t)).collect();
+    match colors {
+        Ok(colors) => colors.try_into().ok(),
+        Err(e) => {
+            values.invalid.borrow_mut().push((format!("colors.{}", name), e));
+            None
+        }
+    }
+}
+
+// ============================================================================
+// Font Parsing
+// ============================================================================
+
+fn parse_fonts(values: &ConfigValues, fonts: &mut FontConfig) -> Result<(), String> {
+    if let Some(val) = values.number(&["font_size"]) {
+        fonts.size = val;
+    }
+
+    // font = wezterm.font('Name'), wezterm.font { family = 'Name', weight = 'Bold' },
+    // wezterm.font('Name', { weight = 'Bold' }) or wezterm.font_with_fallback { ... }
+    if let Some((font, file)) = values.lookup_at(&["font"]) {
+        if let ExprKind::Call { func, args } = &font.kind {
+            let name = func.dotted_path().unwrap_or_default();
+            let text = |expr: &Expr| values.eval(expr, file).as_str().map(str::to_string);
+            fonts.weight = None;
+            if name == ["wezterm", "font"] {
+                if let Some(spec) = args.first() {
+                    parse_font_spec(spec, text, fonts);
+                }
+                if let Some(weight) = args.get(1).and_then(|a| a.table_get("weight")) {
+                    fonts.weight = text(weight).as_deref().and_then(parse_font_weight);
+                }
+            } else if name == ["wezterm", "font_with_fallback"] {
+                if let Some(first) = args.first().and_then(|a| a.table_items()).and_then(|i| i.first().copied()) {
+                    parse_font_spec(first, text, fonts);
+                }
+            }
+        }
+    }
+
+    // Parse freetype targets; without one WezTerm uses its own default
+    fonts.freetype_load_target = values
+        .string(&["freetype_load_target"])
+        .and_then(|val| parse_freetype_target(&val));
+    fonts.freetype_render_target = values
+        .string(&["freetype_render_target"])
+        .and_then(|val| parse_freetype_target(&val));
 
     Ok(())
 }
 
+/// A font family string or a `{ family = ..., weight = ... }` table, with
+/// `text` folding each string
+fn parse_font_spec(spec: &Expr, text: impl Fn(&Expr) -> Option<String>, fonts: &mut FontConfig) {
+    if let Some(family) = text(spec) {
+        fonts.family = family;
+    } else if let Some(family) = spec.table_get("family").and_then(&text) {
+        fonts.family = family;
+        if let Some(weight) = spec.table_get("weight").and_then(&text) {
+            fonts.weight = parse_font_weight(&weight);
+        }
+    }
+}
+
 fn parse_font_weight(s: &str) -> Option<FontWeight> {
     match s.to_lowercase().as_str() {
         "thin" => Some(FontWeight::Thin),
@@ -213,72 +1061,68 @@ fn parse_freetype_target(s: &str) -> Option<FreetypeTarget> {
 // Window Parsing
 // ============================================================================
 
-fn parse_window(content: &str, window: &mut WindowConfig) -> Result<(), String> {
-    // Parse window_background_opacity
-    if let Some(val) = extract_number_value(content, r#"(?:config\.)?window_background_opacity\s*=\s*(\d+(?:\.\d+)?)"#) {
+fn parse_window(values: &ConfigValues, window: &mut WindowConfig) -> Result<(), String> {
+    if let Some(val) = values.number(&["window_background_opacity"]) {
         window.window_background_opacity = val;
     }
-
-    // Parse window_decorations
-    if let Some(val) = extract_string_value(content, r#"(?:config\.)?window_decorations\s*=\s*["']([^"']+)["']"#) {
+    if let Some(val) = values.string(&["window_decorations"]) {
         window.window_decorations = parse_window_decorations(&val);
     }
 
     // Parse tab bar settings
-    if let Some(val) = extract_bool_value(content, r#"(?:config\.)?enable_tab_bar\s*=\s*(true|false)"#) {
+    if let Some(val) = values.bool(&["enable_tab_bar"]) {
         window.enable_tab_bar = val;
     }
-    if let Some(val) = extract_bool_value(content, r#"(?:config\.)?hide_tab_bar_if_only_one_tab\s*=\s*(true|false)"#) {
+    if let Some(val) = values.bool(&["hide_tab_bar_if_only_one_tab"]) {
         window.hide_tab_bar_if_only_one_tab = val;
     }
-    if let Some(val) = extract_bool_value(content, r#"(?:config\.)?use_fancy_tab_bar\s*=\s*(true|false)"#) {
+    if let Some(val) = values.bool(&["use_fancy_tab_bar"]) {
         window.use_fancy_tab_bar = val;
     }
-    if let Some(val) = extract_number_value(content, r#"(?:config\.)?tab_max_width\s*=\s*(\d+)"#) {
-        window.tab_max_width = val as u32;
+    if let Some(val) = values.integer(&["tab_max_width"]) {
+        window.tab_max_width = val;
     }
-    if let Some(val) = extract_bool_value(content, r#"(?:config\.)?show_tab_index_in_tab_bar\s*=\s*(true|false)"#) {
+    if let Some(val) = values.bool(&["show_tab_index_in_tab_bar"]) {
         window.show_tab_index_in_tab_bar = val;
     }
 
     // Parse window_padding
-    parse_window_padding(content, &mut window.window_padding);
+    parse_window_padding(values, &mut window.window_padding);
 
     // Parse inactive_pane_hsb
-    parse_hsb(content, &mut window.inactive_pane_hsb);
+    parse_hsb(values, &mut window.inactive_pane_hsb);
 
-    // Parse window_close_confirmation
-    if let Some(val) = extract_string_value(content, r#"(?:config\.)?window_close_confirmation\s*=\s*["']([^"']+)["']"#) {
+    if let Some(val) = values.string(&["window_close_confirmation"]) {
         window.window_close_confirmation = parse_close_confirmation(&val);
     }
 
     Ok(())
 }
 
-fn parse_window_padding(content: &str, padding: &mut Padding) {
-    // Look for window_padding = { left = 0, right = 0, top = 10, bottom = 7.5 }
-    if let Some(val) = extract_nested_number(content, &["window_padding", "left"]) {
+fn parse_window_padding(values: &ConfigValues, padding: &mut Padding) {
+    // window_padding = { left = 0, right = 0, top = 10, bottom = 7.5 }
+    if let Some(val) = values.number(&["window_padding", "left"]) {
         padding.left = val;
     }
-    if let Some(val) = extract_nested_number(content, &["window_padding", "right"]) {
+    if let Some(val) = values.number(&["window_padding", "right"]) {
         padding.right = val;
     }
-    if let Some(val) = extract_nested_number(content, &["window_padding", "top"]) {
+    if let Some(val) = values.number(&["window_padding", "top"]) {
         padding.top = val;
     }
-    if let Some(val) = extract_nested_number(content, &["window_padding", "bottom"]) {
+    if let Some(val) = values.number(&["window_padding", "bottom"]) {
         padding.bottom = val;
     }
 }
 
-fn parse_hsb(content: &str, hsb: &mut HSB) {
-    if let Some(val) = extract_nested_number(content, &["inactive_pane_hsb", "hue"]) {
+fn parse_hsb(values: &ConfigValues, hsb: &mut HSB) {
+    if let Some(val) = values.number(&["inactive_pane_hsb", "hue"]) {
         hsb.hue = val;
     }
-    if let Some(val) = extract_nested_number(content, &["inactive_pane_hsb", "saturation"]) {
+    if let Some(val) = values.number(&["inactive_pane_hsb", "saturation"]) {
         hsb.saturation = val;
     }
-    if let Some(val) = extract_nested_number(content, &["inactive_pane_hsb", "brightness"]) {
+    if let Some(val) = values.number(&["inactive_pane_hsb", "brightness"]) {
         hsb.brightness = val;
     }
 }
@@ -306,21 +1150,21 @@ fn parse_close_confirmation(s: &str) -> CloseConfirmation {
 // Cursor Parsing
 // ============================================================================
 
-fn parse_cursor(content: &str, cursor: &mut CursorConfig) -> Result<(), String> {
-    if let Some(val) = extract_string_value(content, r#"(?:config\.)?default_cursor_style\s*=\s*["']([^"']+)["']"#) {
+fn parse_cursor(values: &ConfigValues, cursor: &mut CursorConfig) -> Result<(), String> {
+    if let Some(val) = values.string(&["default_cursor_style"]) {
         cursor.default_cursor_style = parse_cursor_style(&val);
     }
-    if let Some(val) = extract_number_value(content, r#"(?:config\.)?cursor_blink_rate\s*=\s*(\d+)"#) {
-        cursor.cursor_blink_rate = val as u32;
+    if let Some(val) = values.integer(&["cursor_blink_rate"]) {
+        cursor.cursor_blink_rate = val;
     }
-    if let Some(val) = extract_string_value(content, r#"(?:config\.)?cursor_blink_ease_in\s*=\s*["']([^"']+)["']"#) {
+    if let Some(val) = values.string(&["cursor_blink_ease_in"]) {
         cursor.cursor_blink_ease_in = parse_ease_function(&val);
     }
-    if let Some(val) = extract_string_value(content, r#"(?:config\.)?cursor_blink_ease_out\s*=\s*["']([^"']+)["']"#) {
+    if let Some(val) = values.string(&["cursor_blink_ease_out"]) {
         cursor.cursor_blink_ease_out = parse_ease_function(&val);
     }
-    if let Some(val) = extract_number_value(content, r#"(?:config\.)?animation_fps\s*=\s*(\d+)"#) {
-        cursor.animation_fps = val as u32;
+    if let Some(val) = values.integer(&["animation_fps"]) {
+        cursor.animation_fps = val;
     }
 
     Ok(())
@@ -353,15 +1197,15 @@ fn parse_ease_function(s: &str) -> EaseFunction {
 // GPU Parsing
 // ============================================================================
 
-fn parse_gpu(content: &str, gpu: &mut GPUConfig) -> Result<(), String> {
-    if let Some(val) = extract_string_value(content, r#"(?:config\.)?front_end\s*=\s*["']([^"']+)["']"#) {
+fn parse_gpu(values: &ConfigValues, gpu: &mut GPUConfig) -> Result<(), String> {
+    if let Some(val) = values.string(&["front_end"]) {
         gpu.front_end = parse_front_end(&val);
     }
-    if let Some(val) = extract_string_value(content, r#"(?:config\.)?webgpu_power_preference\s*=\s*["']([^"']+)["']"#) {
+    if let Some(val) = values.string(&["webgpu_power_preference"]) {
         gpu.webgpu_power_preference = parse_power_preference(&val);
     }
-    if let Some(val) = extract_number_value(content, r#"(?:config\.)?max_fps\s*=\s*(\d+)"#) {
-        gpu.max_fps = val as u32;
+    if let Some(val) = values.integer(&["max_fps"]) {
+        gpu.max_fps = val;
     }
 
     Ok(())
@@ -385,43 +1229,423 @@ fn parse_power_preference(s: &str) -> PowerPreference {
 }
 
 // ============================================================================
-// Helper Functions
+// Backdrop Parsing
+// ============================================================================
+
+/// `window_background_image = 'path'`, or a pick from a local list as
+/// generated for several images: `backdrops[2]`, or
+/// `backdrops[math.random(#backdrops)]` for `random_on_start`.
+/// Without an image the backdrop is off.
+fn parse_backdrop(values: &ConfigValues, backdrop: &mut BackdropConfig) -> Result<(), String> {
+    let Some((image, file)) = values.lookup_at(&["window_background_image"]) else {
+        backdrop.enabled = false;
+        return Ok(());
+    };
+
+    // The generated `backdrops[...]` pick is recognised before folding, since
+    // a random pick is only known at run time
+    let picked = match &image.kind {
+        ExprKind::Index { object, key } => values
+            .resolve(object, file)
+            .0
+            .table_items()
+            .map(|items| (items, key)),
+        _ => None,
+    };
+
+    if let Some((items, key)) = picked {
+        let images: Option<Vec<String>> = items
+            .iter()
+            .map(|e| values.eval(e, file).as_str().map(str::to_string))
+            .collect();
+        let Some(images) = images.filter(|i| !i.is_empty()) else {
+            return Err("window_background_image is computed; backdrop settings were not imported".to_string());
+        };
+        // `backdrops[2]` picks a fixed image, anything else is a random pick
+        let index = key.as_number().filter(|n| *n >= 1.0 && (*n as usize) <= images.len());
+        backdrop.enabled = true;
+        backdrop.random_on_start = index.is_none();
+        backdrop.current_index = index.map_or(0, |n| n as usize - 1);
+        backdrop.images = images;
+    } else if let Some(path) = values.string(&["window_background_image"]) {
+        let path = path.as_str();
+        backdrop.enabled = true;
+        backdrop.random_on_start = false;
+        match backdrop.images.iter().position(|i| i == path) {
+            Some(index) => backdrop.current_index = index,
+            None => {
+                backdrop.images = vec![path.to_string()];
+                backdrop.current_index = 0;
+            }
+        }
+    } else if let Value::Dynamic(_) = values.eval(image, file) {
+        // Already reported as only known at run time
+        return Ok(());
+    } else {
+        return Err("window_background_image is not a path; backdrop settings were not imported".to_string());
+    }
+
+    if backdrop.images_dir.is_empty() {
+        if let Some(dir) = Path::new(&backdrop.images[0]).parent() {
+            backdrop.images_dir = dir.to_string_lossy().to_string();
+        }
+    }
+    if let Some(val) = values.number(&["text_background_opacity"]) {
+        backdrop.overlay_opacity = val;
+    }
+
+    Ok(())
+}
+
+// ============================================================================
+// General Parsing
+// ============================================================================
+
+fn parse_general(values: &ConfigValues, general: &mut GeneralConfig) -> Result<(), String> {
+    let flags: [(&str, &mut bool); 4] = [
+        ("automatically_reload_config", &mut general.automatically_reload_config),
+        ("enable_scroll_bar", &mut general.enable_scroll_bar),
+        (
+            "switch_to_last_active_tab_when_closing_tab",
+            &mut general.switch_to_last_active_tab_when_closing_tab,
+        ),
+        (
+            "adjust_window_size_when_changing_font_size",
+            &mut general.adjust_window_size_when_changing_font_size,
+        ),
+    ];
+    for (key, field) in flags {
+        if let Some(val) = values.bool(&[key]) {
+            *field = val;
+        }
+    }
+
+    if let Some(val) = values.integer(&["scrollback_lines"]) {
+        general.scrollback_lines = val;
+    }
+    if let Some(val) = values.integer(&["initial_rows"]) {
+        general.initial_rows = val;
+    }
+    if let Some(val) = values.integer(&["initial_cols"]) {
+        general.initial_cols = val;
+    }
+    if let Some(val) = values.string(&["exit_behavior"]) {
+        general.exit_behavior = parse_exit_behavior(&val);
+    }
+    if let Some(val) = values.string(&["audible_bell"]) {
+        general.audible_bell = parse_audible_bell(&val);
+    }
+
+    Ok(())
+}
+
+fn parse_exit_behavior(s: &str) -> ExitBehavior {
+    match s {
+        "Close" => ExitBehavior::Close,
+        "CloseOnCleanExit" => ExitBehavior::CloseOnCleanExit,
+        "Hold" => ExitBehavior::Hold,
+        _ => ExitBehavior::CloseOnCleanExit,
+    }
+}
+
+fn parse_audible_bell(s: &str) -> AudibleBell {
+    match s {
+        "SystemBeep" => AudibleBell::SystemBeep,
+        "Disabled" => AudibleBell::Disabled,
+        _ => AudibleBell::SystemBeep,
+    }
+}
+
+// ============================================================================
+// Command Palette / Visual Bell Parsing
 // ============================================================================
 
-fn extract_string_value(content: &str, pattern: &str) -> Option<String> {
-    let re = Regex::new(pattern).ok()?;
-    let caps = re.captures(content)?;
-    caps.get(1).map(|m| m.as_str().to_string())
+fn parse_command_palette(values: &ConfigValues, palette: &mut CommandPaletteConfig) -> Result<(), String> {
+    if let Some(val) = values.color(&["command_palette_fg_color"]) {
+        palette.fg_color = val;
+    }
+    if let Some(val) = values.color(&["command_palette_bg_color"]) {
+        palette.bg_color = val;
+    }
+    if let Some(val) = values.number(&["command_palette_font_size"]) {
+        palette.font_size = val;
+    }
+
+    Ok(())
 }
 
-fn extract_number_value(content: &str, pattern: &str) -> Option<f32> {
-    let re = Regex::new(pattern).ok()?;
-    let caps = re.captures(content)?;
-    caps.get(1)?.as_str().parse().ok()
+fn parse_visual_bell(values: &ConfigValues, bell: &mut VisualBellConfig) -> Result<(), String> {
+    if let Some(val) = values.integer(&["visual_bell", "fade_in_duration_ms"]) {
+        bell.fade_in_duration_ms = val;
+    }
+    if let Some(val) = values.integer(&["visual_bell", "fade_out_duration_ms"]) {
+        bell.fade_out_duration_ms = val;
+    }
+    if let Some(val) = values.string(&["visual_bell", "fade_in_function"]) {
+        bell.fade_in_function = parse_ease_function(&val);
+    }
+    if let Some(val) = values.string(&["visual_bell", "fade_out_function"]) {
+        bell.fade_out_function = parse_ease_function(&val);
+    }
+    if let Some(val) = values.string(&["visual_bell", "target"]) {
+        bell.target = val;
+    }
+
+    Ok(())
+}
+
+// ============================================================================
+// Keybinding Parsing
+// ============================================================================
+
+/// Import `config.keys`, `leader`, `key_tables` and `mouse_bindings`.
+/// Each binding the generator writes is only emitted when enabled, so a
+/// binding missing from the file is imported as disabled. Returns a warning for
+/// every entry that could not be mapped; those are kept in the `extra_*` lists.
+fn parse_keybindings(values: &ConfigValues, kb: &mut KeyBindingsConfig) -> Vec<Diagnostic> {
+    let mut unmapped = Vec::new();
+
+    kb.disable_defaults = values.bool(&["disable_default_key_bindings"]).unwrap_or(false);
+
+    kb.leader.enabled = values.get(&["leader"]).is_some();
+    if let Some(key) = values.string(&["leader", "key"]) {
+        kb.leader.key = key;
+    }
+    if let Some(mods) = values.string(&["leader", "mods"]) {
+        kb.leader.mods = mods;
+    }
+    if let Some(timeout) = values.integer(&["leader", "timeout_milliseconds"]) {
+        kb.leader.timeout_ms = timeout;
+    }
+
+    match values.lookup(&["keys"]).map(|(keys, file)| (keys, file, keys.table_items())) {
+        Some((_, file, Some(entries))) => import_keys(&entries, file, kb, &mut unmapped),
+        Some((keys, file, None)) => unmapped.push(file.warning(
+            keys,
+            "keys",
+            "config.keys is not a table; bindings were not imported",
+        )),
+        None => {
+            for (_, binding) in kb.bindings_mut() {
+                binding.enabled = false;
+            }
+        }
+    }
+
+    if let Some((fields, file)) = values
+        .lookup(&["key_tables"])
+        .and_then(|(tables, file)| Some((tables.as_table()?, file)))
+    {
+        for field in fields {
+            if field.name().is_some_and(|name| MANAGED_KEY_TABLES.contains(&name)) {
+                continue;
+            }
+            let key = format!("key_tables.{}", field.name().unwrap_or("?"));
+            unmapped.push(file.warning(&field.value, &key, "key table is not managed; kept as-is"));
+            kb.extra_key_tables.push(source_text(&file.content, field.span).to_string());
+        }
+    }
+
+    parse_custom_commands(values, kb);
+
+    match values.lookup(&["mouse_bindings"]).map(|(m, file)| (m, file, m.table_items())) {
+        Some((_, file, Some(entries))) => import_mouse_bindings(&entries, file, kb, &mut unmapped),
+        Some((mouse, file, None)) => unmapped.push(file.warning(
+            mouse,
+            "mouse_bindings",
+            "config.mouse_bindings is not a table; bindings were not imported",
+        )),
+        None => {
+            kb.mouse.ctrl_click_open_link = false;
+            kb.mouse.right_click_command_palette = false;
+        }
+    }
+
+    unmapped
+}
+
+/// Command palette entries added by the generated `augment-command-palette`
+/// handler, recognised by their `brief`
+/// Briefs of the palette commands the generator writes
+const CUSTOM_COMMAND_BRIEFS: &[&str] = &["Settings-TUI", "Rename Current Tab"];
+
+fn parse_custom_commands(values: &ConfigValues, kb: &mut KeyBindingsConfig) {
+    let briefs: Vec<&str> = values
+        .handlers
+        .iter()
+        .filter(|(event, _)| *event == "augment-command-palette")
+        .flat_map(|(_, handler)| palette_briefs(handler))
+        .collect();
+
+    kb.custom_commands.settings_tui = briefs.contains(&CUSTOM_COMMAND_BRIEFS[0]);
+    kb.custom_commands.rename_tab = briefs.contains(&CUSTOM_COMMAND_BRIEFS[1]);
+}
+
+/// The `brief` of each entry an `augment-command-palette` handler returns
+fn palette_briefs(handler: &Function) -> Vec<&str> {
+    handler
+        .body
+        .stats
+        .iter()
+        .filter_map(|stat| match &stat.kind {
+            StatKind::Return(exprs) => exprs.first().and_then(Expr::table_items),
+            _ => None,
+        })
+        .flatten()
+        .filter_map(|entry| entry.table_get("brief").and_then(Expr::as_str))
+        .collect()
+}
+
+/// Whether a handler is the command palette one the generator writes, adding
+/// only the custom commands the settings manage
+fn is_generated_handler(event: &str, handler: &Function) -> bool {
+    let briefs = palette_briefs(handler);
+    event == "augment-command-palette"
+        && !briefs.is_empty()
+        && briefs.iter().all(|brief| CUSTOM_COMMAND_BRIEFS.contains(brief))
+}
+
+/// Map each `{ key = ..., mods = ..., action = ... }` entry onto the binding
+/// whose generated action matches. When several bindings share an action
+/// (copy and copy_simple), the one whose default mods and key match is preferred.
+fn import_keys(entries: &[&Expr], file: &LuaFile, kb: &mut KeyBindingsConfig, unmapped: &mut Vec<Diagnostic>) {
+    let mut bindings = kb.bindings_mut();
+    let expected: Vec<Option<String>> = bindings
+        .iter()
+        .map(|(name, _)| key_action(name).and_then(action_signature_of))
+        .collect();
+    let defaults: Vec<(String, String)> = bindings
+        .iter()
+        .map(|(_, b)| (b.key.clone(), b.mods.clone()))
+        .collect();
+    for (_, binding) in bindings.iter_mut() {
+        binding.enabled = false;
+    }
+
+    let mut extra = Vec::new();
+    for entry in entries {
+        let key = entry.table_get("key").and_then(Expr::as_str);
+        let mods = entry.table_get("mods").and_then(Expr::as_str).unwrap_or("NONE");
+        let action = entry.table_get("action").map(|a| action_signature(a, &file.content));
+
+        let slot = key.zip(action.as_ref()).and_then(|(key, action)| {
+            (0..bindings.len())
+                .filter(|&i| !bindings[i].1.enabled && expected[i].as_ref() == Some(action))
+                .max_by_key(|&i| {
+                    let (default_key, default_mods) = &defaults[i];
+                    (default_mods == mods, default_key == key, std::cmp::Reverse(i))
+                })
+        });
+
+        match (slot, key) {
+            (Some(i), Some(key)) => {
+                let binding = &mut bindings[i].1;
+                binding.enabled = true;
+                binding.key = key.to_string();
+                binding.mods = mods.to_string();
+            }
+            _ => {
+                unmapped.push(file.warning(
+                    entry,
+                    "keys",
+                    format!(
+                        "binding {}+{} has no matching setting; kept as a custom binding",
+                        mods,
+                        key.unwrap_or("?")
+                    ),
+                ));
+                extra.push(source_text(&file.content, entry.span).to_string());
+            }
+        }
+    }
+    kb.extra_keys = extra;
+}
+
+fn import_mouse_bindings(entries: &[&Expr], file: &LuaFile, kb: &mut KeyBindingsConfig, unmapped: &mut Vec<Diagnostic>) {
+    kb.mouse.ctrl_click_open_link = false;
+    kb.mouse.right_click_command_palette = false;
+
+    for entry in entries {
+        let signature = action_signature(entry, &file.content);
+        let known = MOUSE_ACTIONS
+            .iter()
+            .find(|(_, lua)| action_signature_of(lua).as_ref() == Some(&signature));
+        match known.map(|(name, _)| *name) {
+            Some("ctrl_click_open_link") => kb.mouse.ctrl_click_open_link = true,
+            Some("right_click_command_palette") => kb.mouse.right_click_command_palette = true,
+            _ => {
+                unmapped.push(file.warning(
+                    entry,
+                    "mouse_bindings",
+                    "mouse binding has no matching setting; kept as-is",
+                ));
+                kb.extra_mouse_bindings.push(source_text(&file.content, entry.span).to_string());
+            }
+        }
+    }
 }
 
-fn extract_bool_value(content: &str, pattern: &str) -> Option<bool> {
-    let re = Regex::new(pattern).ok()?;
-    let caps = re.captures(content)?;
-    match caps.get(1)?.as_str() {
-        "true" => Some(true),
-        "false" => Some(false),
+/// Signature of a standalone Lua action expression, as written by the generator
+fn action_signature_of(lua: &str) -> Option<String> {
+    let src = format!("return {}", lua);
+    let chunk = parse_chunk(&src).ok()?;
+    match chunk.stats.first().map(|s| &s.kind) {
+        Some(StatKind::Return(exprs)) => exprs.first().map(|e| action_signature(e, &src)),
         _ => None,
     }
 }
 
-fn extract_nested_string(content: &str, keys: &[&str]) -> Option<String> {
-    // Build a pattern for nested table access
-    // e.g., colors.tab_bar.background = "#000"
-    let key_path = keys.join(r#"\s*[\.\[\]"']*\s*"#);
-    let pattern = format!(r#"{}\s*=\s*["']([^"']+)["']"#, key_path);
-    extract_string_value(content, &pattern)
+/// A normalised form of an action so equivalent spellings compare equal:
+/// `act.X`, `wezterm.action.X` and `'X'` are the same action, call and
+/// quoting style don't matter, and named table fields are order-independent.
+/// Function bodies are compared by their source with whitespace removed.
+fn action_signature(expr: &Expr, source: &str) -> String {
+    match &expr.kind {
+        ExprKind::String(s) => s.clone(),
+        ExprKind::Number(n) => n.to_string(),
+        ExprKind::Bool(b) => b.to_string(),
+        ExprKind::Nil => "nil".to_string(),
+        ExprKind::Unary { op: UnOp::Neg, expr } => format!("-{}", action_signature(expr, source)),
+        ExprKind::Paren(inner) => action_signature(inner, source),
+        ExprKind::Name(_) | ExprKind::Field { .. } => match expr.dotted_path() {
+            Some(path) => {
+                let path = match path.as_slice() {
+                    ["act", rest @ ..] | ["wezterm", "action", rest @ ..] if !rest.is_empty() => rest,
+                    all => all,
+                };
+                path.join(".")
+            }
+            None => compact_source(source, expr),
+        },
+        ExprKind::Call { func, args } => format!(
+            "{}({})",
+            action_signature(func, source),
+            args.iter().map(|a| action_signature(a, source)).collect::<Vec<_>>().join(",")
+        ),
+        ExprKind::Table(fields) => {
+            let mut named = Vec::new();
+            let mut items = Vec::new();
+            for field in fields {
+                let value = action_signature(&field.value, source);
+                match field.name() {
+                    Some(name) => named.push(format!("{}={}", name, value)),
+                    None => items.push(value),
+                }
+            }
+            named.sort();
+            items.extend(named);
+            format!("{{{}}}", items.join(","))
+        }
+        _ => compact_source(source, expr),
+    }
+}
+
+fn compact_source(source: &str, expr: &Expr) -> String {
+    source_text(source, expr.span).split_whitespace().collect()
 }
 
-fn extract_nested_number(content: &str, keys: &[&str]) -> Option<f32> {
-    let key_path = keys.join(r#"\s*[\.\[\]"']*\s*"#);
-    let pattern = format!(r#"{}\s*=\s*(\d+(?:\.\d+)?)"#, key_path);
-    extract_number_value(content, &pattern)
+fn source_text(source: &str, span: Span) -> &str {
+    &source[span.start..span.end]
 }
 
 #[cfg(test)]
@@ -452,13 +1676,421 @@ mod tests {
     #[test]
     fn test_parse_color_array() {
         let content = r##"
-            ansi = { "#000000", "#ff0000", "#00ff00", "#ffff00", "#0000ff", "#ff00ff", "#00ffff", "#ffffff" }
+            config.colors = {
+                ansi = { "#000000", "#ff0000", "#00ff00", "#ffff00", "#0000ff", "#ff00ff", "#00ffff", "#ffffff" },
+            }
+        "##;
+
+        let colors = parse_lua_content(content).unwrap().config.colors;
+        assert_eq!(colors.ansi[0], "#000000");
+        assert_eq!(colors.ansi[1], "#ff0000");
+    }
+
+    #[test]
+    fn test_invalid_colors_are_reported() {
+        let content = r##"local config = {}
+config.colors = {
+  foreground = 'rgb(300, 0, 0)',
+  background = 'Navy',
+  ansi = { '#000', '#111', '#222', 'nope', '#444', '#555', '#666', '#777' },
+}
+return config
+"##;
+
+        let result = parse_lua_content(content).unwrap();
+        let defaults = ColorScheme::default();
+        assert_eq!(result.config.colors.foreground, defaults.foreground);
+        assert_eq!(result.config.colors.background, "Navy");
+        assert_eq!(result.config.colors.ansi, defaults.ansi);
+        let warnings: Vec<_> = result.diagnostics.iter().map(|d| (d.line, d.message.as_str())).collect();
+        assert_eq!(
+            warnings,
+            vec![
+                (Some(3), "colors.foreground: `rgb(300, 0, 0)` is not a color: 300 is out of range 0-255; the default is shown"),
+                (Some(5), "colors.ansi: `nope` is not a color (use #rrggbb, rgb(), rgba(), hsl() or a color name); the default is shown"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_numbers_are_reported() {
+        let content = r##"local config = {}
+config.font_size = 'big'
+config.initial_rows = -5
+config.initial_cols = 80.5
+config.scrollback_lines = 1e12
+config.max_fps = nil
+config.enable_tab_bar = 'yes'
+config.window_background_opacity = 2 ^ 1024
+return config
+"##;
+
+        let result = parse_lua_content(content).unwrap();
+        let defaults = AppearanceConfig::default();
+        assert_eq!(result.config.fonts.size, defaults.fonts.size);
+        assert_eq!(result.config.general, defaults.general);
+        assert_eq!(result.config.gpu.max_fps, defaults.gpu.max_fps);
+        assert_eq!(result.config.window, defaults.window);
+        let warnings: Vec<_> = result.diagnostics.iter().map(|d| (d.line, d.message.as_str())).collect();
+        assert_eq!(
+            warnings,
+            vec![
+                (Some(2), "font_size: `'big'` is not a number; the default is shown"),
+                (Some(8), "window_background_opacity: `2 ^ 1024` is out of range; the default is shown"),
+                (Some(7), "enable_tab_bar: `'yes'` is not true or false; the default is shown"),
+                (Some(5), "scrollback_lines: `1e12` is out of range (at most 4294967295); the default is shown"),
+                (Some(3), "initial_rows: `-5` is not a whole number of 0 or more; the default is shown"),
+                (Some(4), "initial_cols: `80.5` is not a whole number of 0 or more; the default is shown"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_nested_keys_do_not_leak() {
+        let content = r##"
+            local config = wezterm.config_builder()
+            -- config.font_size = 99
+            config.colors = {
+                tab_bar = { background = "#111111", active_tab = { bg_color = "#222222", fg_color = "#333333" } },
+                background = '#444444',
+                brights = { '#000', '#111', '#222', '#333', '#444', '#555', '#666', '#777' },
+            }
+            return config
+        "##;
+
+        let config = parse_lua_content(content).unwrap().config;
+        assert_eq!(config.fonts.size, AppearanceConfig::default().fonts.size);
+        assert_eq!(config.colors.background, "#444444");
+        assert_eq!(config.colors.tab_bar.background, "#111111");
+        assert_eq!(config.colors.tab_bar.active_tab.fg_color, "#333333");
+        assert_eq!(config.colors.brights[7], "#777");
+    }
+
+    #[test]
+    fn test_last_assignment_wins() {
+        let content = r##"
+            local config = {}
+            config.colors = { foreground = '#aaaaaa' }
+            config.colors.foreground = "#bbbbbb"
+            config.window_padding = { left = 1 }
+            config.window_padding = { right = 2 }
+            return config
         "##;
 
-        let colors = extract_color_array(content, "ansi").unwrap();
-        assert_eq!(colors.len(), 8);
-        assert_eq!(colors[0], "#000000");
-        assert_eq!(colors[1], "#ff0000");
+        let config = parse_lua_content(content).unwrap().config;
+        assert_eq!(config.colors.foreground, "#bbbbbb");
+        // The second table replaces the first, so `left` keeps its default
+        assert_eq!(config.window.window_padding.left, AppearanceConfig::default().window.window_padding.left);
+        assert_eq!(config.window.window_padding.right, 2.0);
+    }
+
+    #[test]
+    fn test_parse_returned_table_and_module() {
+        let content = r#"
+            local wezterm = require 'wezterm'
+            return {
+              font = wezterm.font_with_fallback { { family = 'Fira Code', weight = 'Medium' }, 'Noto' },
+              max_fps = 144,
+            }
+        "#;
+        let config = parse_lua_content(content).unwrap().config;
+        assert_eq!(config.fonts.family, "Fira Code");
+        assert!(matches!(config.fonts.weight, Some(FontWeight::Medium)));
+        assert_eq!(config.gpu.max_fps, 144);
+
+        let module = "local M = {}\nfunction M.apply_to_config(config)\n  config.font_size = 15\nend\nreturn M\n";
+        assert_eq!(parse_lua_content(module).unwrap().config.fonts.size, 15.0);
+    }
+
+    #[test]
+    fn test_syntax_error_is_reported() {
+        let result = parse_lua_content("config.font_size = \n").unwrap();
+        assert_eq!(result.diagnostics.len(), 1);
+        let error = &result.diagnostics[0];
+        assert_eq!(error.severity, Severity::Error);
+        assert_eq!(error.line, Some(2));
+        assert!(error.message.starts_with("Syntax error: "), "{}", error.message);
+    }
+
+    #[test]
+    fn test_generated_keybindings_round_trip() {
+        let mut config = AppearanceConfig::default();
+        let kb = &mut config.keybindings;
+        kb.disable_defaults = false;
+        kb.leader.enabled = false;
+        kb.panes.nav_up.enabled = false;
+        kb.copy_paste.copy.enabled = false;
+        kb.copy_paste.copy_simple.key = "y".to_string();
+        kb.windows.grow_window.mods = "SUPER".to_string();
+        kb.mouse.right_click_command_palette = false;
+
+        let lua = crate::lua::generate_lua_config(&config);
+        let result = parse_lua_content(&lua).unwrap();
+        assert!(result.diagnostics.is_empty(), "{:?}", result.diagnostics);
+        assert_eq!(result.config.keybindings, config.keybindings);
+    }
+
+    #[test]
+    fn test_import_hand_written_keybindings() {
+        let content = r#"
+            local wezterm = require 'wezterm'
+            local config = wezterm.config_builder()
+            config.leader = { key = 'a', mods = 'CTRL', timeout_milliseconds = 500 }
+            config.keys = {
+              { key = 'LeftArrow', mods = 'CTRL|SHIFT', action = wezterm.action.ActivatePaneDirection 'Left' },
+              { key = 'F1', action = 'ActivateCopyMode' },
+              { key = 'x', mods = 'CTRL', action = wezterm.action.Nop },
+            }
+            config.mouse_bindings = {
+              { event = { Up = { button = 'Left', streak = 1 } }, mods = 'CTRL', action = wezterm.action.OpenLinkAtMouseCursor },
+            }
+            return config
+        "#;
+
+        let result = parse_lua_content(content).unwrap();
+        let kb = &result.config.keybindings;
+        assert!(!kb.disable_defaults);
+        assert!(kb.leader.enabled);
+        assert_eq!((kb.leader.key.as_str(), kb.leader.timeout_ms), ("a", 500));
+        assert!(kb.panes.nav_left.enabled);
+        assert_eq!(kb.panes.nav_left.key, "LeftArrow");
+        assert_eq!(kb.panes.nav_left.mods, "CTRL|SHIFT");
+        assert_eq!(kb.misc.copy_mode.mods, "NONE");
+        assert!(!kb.panes.nav_right.enabled);
+        assert!(kb.mouse.ctrl_click_open_link);
+        assert!(!kb.mouse.right_click_command_palette);
+
+        assert_eq!(kb.extra_keys, vec!["{ key = 'x', mods = 'CTRL', action = wezterm.action.Nop }"]);
+        assert_eq!(result.diagnostics.len(), 1);
+        let warning = &result.diagnostics[0];
+        assert_eq!((warning.line, warning.column), (Some(8), Some(15)));
+        assert_eq!(warning.key.as_deref(), Some("keys"));
+        assert_eq!(
+            warning.to_string(),
+            "8:15: warning: binding CTRL+x has no matching setting; kept as a custom binding"
+        );
+
+        // Unmapped bindings survive a regenerate
+        let lua = crate::lua::generate_lua_config(&result.config);
+        assert!(lua.contains("   { key = 'x', mods = 'CTRL', action = wezterm.action.Nop },\n"));
+    }
+
+    #[test]
+    fn test_generated_settings_round_trip() {
+        let mut config = AppearanceConfig {
+            color_scheme: Some("Tokyo Night".to_string()),
+            ..Default::default()
+        };
+        config.general.scrollback_lines = 10000;
+        config.general.exit_behavior = ExitBehavior::Hold;
+        config.general.audible_bell = AudibleBell::SystemBeep;
+        config.general.enable_scroll_bar = true;
+        config.command_palette.bg_color = Color::parse("#000000").unwrap();
+        config.command_palette.font_size = 16.5;
+        config.visual_bell.fade_in_duration_ms = 20;
+        config.visual_bell.fade_out_function = EaseFunction::Linear;
+        config.visual_bell.target = "CursorColor".to_string();
+        config.backdrop.enabled = true;
+        config.backdrop.images = vec!["/pics/a.png".to_string(), "C:\\pics\\it's.png".to_string()];
+        config.backdrop.random_on_start = true;
+        config.backdrop.overlay_opacity = 0.9;
+
+        for lua in [
+            crate::lua::generate_lua_config(&config),
+            crate::lua::generate_settings_module(&config),
+        ] {
+            let result = parse_lua_content(&lua).unwrap();
+            assert!(result.diagnostics.is_empty(), "{:?}", result.diagnostics);
+            let parsed = result.config;
+            assert_eq!(parsed.color_scheme.as_deref(), Some("Tokyo Night"));
+            assert_eq!(parsed.general, config.general);
+            assert_eq!(parsed.command_palette, config.command_palette);
+            assert_eq!(parsed.visual_bell, config.visual_bell);
+            assert!(parsed.backdrop.enabled && parsed.backdrop.random_on_start);
+            assert_eq!(parsed.backdrop.images, config.backdrop.images);
+            assert_eq!(parsed.backdrop.overlay_opacity, 0.9);
+        }
+    }
+
+    #[test]
+    fn test_follow_required_modules() {
+        let dir = std::env::temp_dir().join(format!("wezterm-parser-modules-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("config")).unwrap();
+        std::fs::create_dir_all(dir.join("colors/custom")).unwrap();
+        let files = [
+            (
+                "wezterm.lua",
+                "local wezterm = require 'wezterm'\n\
+                 local config = wezterm.config_builder()\n\
+                 config.font_size = 11\n\
+                 config.max_fps = 30\n\
+                 require('config.fonts').apply_to_config(config)\n\
+                 for k, v in pairs(require('config.appearance')) do config[k] = v end\n\
+                 config.max_fps = 90\n\
+                 return config\n",
+            ),
+            (
+                "config/fonts.lua",
+                "local M = {}\nfunction M.apply_to_config(c)\n  c.font_size = 14\nend\nreturn M\n",
+            ),
+            (
+                "config/appearance.lua",
+                "return {\n  max_fps = 60,\n  window_background_opacity = 0.8,\n  colors = require('colors.custom'),\n}\n",
+            ),
+            ("colors/custom/init.lua", "return { foreground = '#c0c0c0' }\n"),
+        ];
+        for (name, content) in files {
+            std::fs::write(dir.join(name), content).unwrap();
+        }
+
+        let result = parse_wezterm_config(&dir.join("wezterm.lua")).unwrap();
+        assert!(result.diagnostics.is_empty(), "{:?}", result.diagnostics);
+        assert_eq!(result.modules.len(), 3);
+        // Later assignments win, whichever file they are in
+        assert_eq!(result.config.fonts.size, 14.0);
+        assert_eq!(result.config.gpu.max_fps, 90);
+        assert_eq!(result.config.window.window_background_opacity, 0.8);
+        assert_eq!(result.config.colors.foreground, "#c0c0c0");
+
+        assert_eq!(result.sources["font_size"], dir.join("config/fonts.lua"));
+        assert_eq!(result.sources["window_background_opacity"], dir.join("config/appearance.lua"));
+        assert_eq!(result.sources["colors.foreground"], dir.join("colors/custom/init.lua"));
+        // Siblings the module doesn't set yet are written next to it
+        assert_eq!(result.sources["colors.background"], dir.join("colors/custom/init.lua"));
+        assert!(!result.sources.contains_key("max_fps"));
+        assert!(result.unmanaged.is_empty(), "{:?}", result.unmanaged);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_evaluate_computed_values() {
+        let content = r#"local wezterm = require 'wezterm'
+local config = wezterm.config_builder()
+local bg = '#1e1e2e'
+local base = 10
+local palette = { background = bg, foreground = '#' .. string.rep('c', 6) }
+config.colors = palette
+config.font_size = base * 1.5
+config.initial_cols = base * 12
+config.window_background_opacity = wezterm.gui and 0.9 or 1.0
+config.font = wezterm.font(bg == '#1e1e2e' and 'Iosevka' or 'Hack')
+config.window_background_image = wezterm.config_dir .. '/pics/bg.png'
+return config
+"#;
+        let dir = std::env::temp_dir().join(format!("wezterm-parser-eval-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("wezterm.lua"), content).unwrap();
+        let result = parse_wezterm_config(&dir.join("wezterm.lua")).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let config = result.config;
+        assert_eq!(config.colors.background, "#1e1e2e");
+        assert_eq!(config.fonts.size, 15.0);
+        assert_eq!(config.general.initial_cols, 120);
+        assert_eq!(config.fonts.family, "Iosevka");
+        assert_eq!(config.backdrop.images, vec![dir.join("pics/bg.png").to_string_lossy().to_string()]);
+
+        // Values only known at run time keep their default and are reported
+        assert_eq!(config.colors.foreground, AppearanceConfig::default().colors.foreground);
+        assert_eq!(config.window.window_background_opacity, AppearanceConfig::default().window.window_background_opacity);
+        let messages: Vec<_> = result.diagnostics.iter().map(|d| (d.line, d.message.as_str())).collect();
+        assert_eq!(
+            messages,
+            vec![
+                (Some(5), "colors.foreground depends on `string.rep()` and is only known when WezTerm runs; the default is shown"),
+                (Some(9), "window_background_opacity depends on `wezterm.gui` and is only known when WezTerm runs; the default is shown"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reassigned_locals_are_dynamic() {
+        let content = r#"local wezterm = require 'wezterm'
+local config = wezterm.config_builder()
+local size = 14
+if os.getenv('LARGE_FONT') then
+  size = 20
+end
+local cols = 100
+for _, arg in ipairs(wezterm.GLOBAL.args or {}) do
+  local cols = 80
+  config.initial_rows = cols / 2
+end
+config.font_size = size
+config.initial_cols = cols
+return config
+"#;
+        let result = parse_lua_content(content).unwrap();
+        let config = result.config;
+
+        // `size` is 14 or 20 depending on the environment
+        assert_eq!(config.fonts.size, AppearanceConfig::default().fonts.size);
+        assert!(result
+            .diagnostics
+            .iter()
+            .any(|d| d.message.starts_with("font_size depends on `size`")), "{:?}", result.diagnostics);
+        // The loop's own `cols` doesn't leak out of it
+        assert_eq!(config.general.initial_cols, 100);
+        // The loop may run any number of times
+        assert_eq!(config.general.initial_rows, AppearanceConfig::default().general.initial_rows);
+        assert!(result
+            .diagnostics
+            .iter()
+            .any(|d| d.line == Some(10) && d.message.starts_with("initial_rows depends on a branch, loop or function")), "{:?}", result.diagnostics);
+    }
+
+    #[test]
+    fn test_list_unmanaged_items() {
+        let content = r#"local wezterm = require 'wezterm'
+local config = wezterm.config_builder()
+local tabline = wezterm.plugin.require('https://github.com/michaelbrusegard/tabline.wez')
+
+local function basename(s)
+  return string.gsub(s, '(.*[/\\])(.*)', '%2')
+end
+
+wezterm.on('format-tab-title', function(tab)
+  return basename(tab.active_pane.title)
+end)
+wezterm.on('augment-command-palette', function(window, pane)
+  return {
+    { brief = 'Settings-TUI', action = wezterm.action.SpawnCommandInNewTab { args = { 'wezterm-settings-tui' } } },
+  }
+end)
+
+config.font_size = 12
+config.default_prog = { '/bin/zsh', '-l' }
+config.set_environment_variables = { EDITOR = 'nvim' }
+config.default_prog = { '/usr/bin/fish' }
+tabline.setup()
+return config
+"#;
+        let unmanaged = parse_lua_content(content).unwrap().unmanaged;
+        let found: Vec<_> = unmanaged.iter().map(|u| (u.kind, u.name.as_str(), u.line)).collect();
+        assert_eq!(
+            found,
+            vec![
+                (UnmanagedKind::Plugin, "https://github.com/michaelbrusegard/tabline.wez", 3),
+                (UnmanagedKind::Function, "basename", 5),
+                (UnmanagedKind::EventHandler, "format-tab-title", 9),
+                (UnmanagedKind::Setting, "set_environment_variables", 20),
+                (UnmanagedKind::Setting, "default_prog", 21),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_single_backdrop_image() {
+        let content = "config.window_background_image = '/home/me/walls/sea.jpg'\n";
+        let backdrop = parse_lua_content(content).unwrap().config.backdrop;
+        assert!(backdrop.enabled && !backdrop.random_on_start);
+        assert_eq!(backdrop.images, vec!["/home/me/walls/sea.jpg"]);
+        assert_eq!(backdrop.images_dir, "/home/me/walls");
+
+        let backdrop = parse_lua_content("config.font_size = 12\n").unwrap().config.backdrop;
+        assert!(!backdrop.enabled);
     }
 
     #[test]
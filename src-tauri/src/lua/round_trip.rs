@@ -0,0 +1,408 @@
+// Round-trip property tests: generated Lua must parse back to the config it
+// was generated from, and regenerating from that must give identical bytes.
+
+use super::{generate_lua_config, generate_settings_module, key_action, parse_lua_content};
+use crate::models::*;
+use proptest::prelude::*;
+use std::path::Path;
+
+/// Plain names plus strings that need escaping or look like Lua syntax
+fn text() -> impl Strategy<Value = String> {
+    prop_oneof![
+        3 => "[a-zA-Z0-9 #._-]{0,16}",
+        1 => prop::sample::select(vec![
+            "it's",
+            "C:\\Users\\me\\bg.png",
+            "line\nbreak",
+            "tab\there\r",
+            "]] --[[",
+            "\"double\"",
+            "ünïcödé ✓",
+            "\u{1}\u{7f}",
+            "",
+        ])
+        .prop_map(str::to_string),
+    ]
+}
+
+/// Finite values covering fractions, negatives and large magnitudes
+fn number() -> impl Strategy<Value = f32> {
+    prop_oneof![
+        (-1000i32..1000).prop_map(|n| n as f32 / 4.0),
+        -1.0e9f32..1.0e9f32,
+        0.0f32..1.0f32,
+    ]
+}
+
+/// Colors in every notation `Color` accepts, written as users write them
+fn color() -> BoxedStrategy<Color> {
+    prop_oneof![
+        "#([0-9a-fA-F]{3}|[0-9a-fA-F]{4}|[0-9a-fA-F]{6}|[0-9a-fA-F]{8})",
+        (any::<[u8; 3]>(), 0.0f32..=1.0)
+            .prop_map(|([r, g, b], a)| format!("rgba({}, {}, {}, {:.2})", r, g, b, a)),
+        (any::<[u8; 3]>()).prop_map(|[r, g, b]| format!("rgb({} {} {})", r, g, b)),
+        (0u16..360, 0u8..=100, 0u8..=100).prop_map(|(h, s, l)| format!("hsl({}, {}%, {}%)", h, s, l)),
+        prop::sample::select(vec!["Navy", "light slate gray", "transparent"]).prop_map(str::to_string),
+    ]
+    .prop_map(|text| Color::parse(&text).unwrap())
+    .boxed()
+}
+
+fn ease() -> impl Strategy<Value = EaseFunction> {
+    prop_oneof![
+        Just(EaseFunction::Linear),
+        Just(EaseFunction::EaseIn),
+        Just(EaseFunction::EaseOut),
+        Just(EaseFunction::EaseInOut),
+        Just(EaseFunction::Constant),
+    ]
+}
+
+fn freetype_target() -> impl Strategy<Value = Option<FreetypeTarget>> {
+    prop::option::of(prop_oneof![
+        Just(FreetypeTarget::Normal),
+        Just(FreetypeTarget::Light),
+        Just(FreetypeTarget::Mono),
+        Just(FreetypeTarget::HorizontalLcd),
+    ])
+}
+
+fn tab_colors() -> impl Strategy<Value = TabColors> {
+    (color(), color(), any::<Option<bool>>()).prop_map(|(bg_color, fg_color, italic)| TabColors {
+        bg_color,
+        fg_color,
+        italic,
+    })
+}
+
+prop_compose! {
+    fn colors()(
+        core in prop::collection::vec(color(), 7),
+        ansi in prop::array::uniform8(color()),
+        brights in prop::array::uniform8(color()),
+        background in color(),
+        tabs in prop::collection::vec(tab_colors(), 5),
+        optional in prop::collection::vec(prop::option::of(color()), 3),
+    ) -> ColorScheme {
+        let mut tabs = tabs.into_iter();
+        let mut tab = || tabs.next().unwrap();
+        ColorScheme {
+            foreground: core[0].clone(),
+            background: core[1].clone(),
+            cursor_bg: core[2].clone(),
+            cursor_border: core[3].clone(),
+            cursor_fg: core[4].clone(),
+            selection_bg: core[5].clone(),
+            selection_fg: core[6].clone(),
+            ansi,
+            brights,
+            tab_bar: TabBarColors {
+                background,
+                active_tab: tab(),
+                inactive_tab: tab(),
+                inactive_tab_hover: tab(),
+                new_tab: tab(),
+                new_tab_hover: tab(),
+            },
+            visual_bell: optional[0].clone(),
+            scrollbar_thumb: optional[1].clone(),
+            split: optional[2].clone(),
+        }
+    }
+}
+
+prop_compose! {
+    fn fonts()(
+        family in text(),
+        size in number(),
+        weight in prop::option::of(prop_oneof![
+            Just(FontWeight::Thin),
+            Just(FontWeight::ExtraLight),
+            Just(FontWeight::Light),
+            Just(FontWeight::Regular),
+            Just(FontWeight::Medium),
+            Just(FontWeight::DemiBold),
+            Just(FontWeight::Bold),
+            Just(FontWeight::ExtraBold),
+            Just(FontWeight::Black),
+        ]),
+        freetype_load_target in freetype_target(),
+        freetype_render_target in freetype_target(),
+    ) -> FontConfig {
+        FontConfig { family, size, weight, freetype_load_target, freetype_render_target }
+    }
+}
+
+prop_compose! {
+    fn window()(
+        padding in prop::array::uniform4(number()),
+        window_background_opacity in number(),
+        window_decorations in prop_oneof![
+            Just(WindowDecorations::Full),
+            Just(WindowDecorations::Resize),
+            Just(WindowDecorations::None),
+            Just(WindowDecorations::Title),
+            Just(WindowDecorations::IntegratedButtonsResize),
+        ],
+        flags in prop::array::uniform4(any::<bool>()),
+        tab_max_width in any::<u32>(),
+        hsb in prop::array::uniform3(number()),
+        window_close_confirmation in prop_oneof![
+            Just(CloseConfirmation::AlwaysPrompt),
+            Just(CloseConfirmation::NeverPrompt),
+        ],
+    ) -> WindowConfig {
+        WindowConfig {
+            window_padding: Padding { left: padding[0], right: padding[1], top: padding[2], bottom: padding[3] },
+            window_background_opacity,
+            window_decorations,
+            enable_tab_bar: flags[0],
+            hide_tab_bar_if_only_one_tab: flags[1],
+            use_fancy_tab_bar: flags[2],
+            tab_max_width,
+            show_tab_index_in_tab_bar: flags[3],
+            inactive_pane_hsb: HSB { hue: hsb[0], saturation: hsb[1], brightness: hsb[2] },
+            window_close_confirmation,
+        }
+    }
+}
+
+prop_compose! {
+    fn cursor()(
+        default_cursor_style in prop_oneof![
+            Just(CursorStyle::SteadyBlock),
+            Just(CursorStyle::BlinkingBlock),
+            Just(CursorStyle::SteadyUnderline),
+            Just(CursorStyle::BlinkingUnderline),
+            Just(CursorStyle::SteadyBar),
+            Just(CursorStyle::BlinkingBar),
+        ],
+        cursor_blink_rate in any::<u32>(),
+        cursor_blink_ease_in in ease(),
+        cursor_blink_ease_out in ease(),
+        animation_fps in any::<u32>(),
+    ) -> CursorConfig {
+        CursorConfig { default_cursor_style, cursor_blink_rate, cursor_blink_ease_in, cursor_blink_ease_out, animation_fps }
+    }
+}
+
+prop_compose! {
+    fn gpu()(
+        front_end in prop_oneof![Just(FrontEnd::WebGpu), Just(FrontEnd::OpenGL), Just(FrontEnd::Software)],
+        webgpu_power_preference in prop_oneof![
+            Just(PowerPreference::LowPower),
+            Just(PowerPreference::HighPerformance),
+        ],
+        max_fps in any::<u32>(),
+    ) -> GPUConfig {
+        GPUConfig { front_end, webgpu_power_preference, max_fps }
+    }
+}
+
+/// Only what the generated Lua carries: the image list, which one is shown and
+/// the overlay. `images_dir` is derived from the first image when parsing.
+fn backdrop() -> impl Strategy<Value = BackdropConfig> {
+    prop_oneof![
+        Just(BackdropConfig::default()),
+        (prop::collection::vec(text(), 1..4), any::<bool>(), any::<prop::sample::Index>(), number())
+            .prop_map(|(images, random_on_start, index, overlay_opacity)| {
+                let current_index = if random_on_start { 0 } else { index.index(images.len()) };
+                BackdropConfig {
+                    enabled: true,
+                    images_dir: Path::new(&images[0])
+                        .parent()
+                        .map(|dir| dir.to_string_lossy().to_string())
+                        .unwrap_or_default(),
+                    images,
+                    current_index,
+                    overlay_opacity,
+                    random_on_start,
+                    ..BackdropConfig::default()
+                }
+            }),
+    ]
+}
+
+prop_compose! {
+    fn general()(
+        flags in prop::array::uniform4(any::<bool>()),
+        sizes in prop::array::uniform3(any::<u32>()),
+        exit_behavior in prop_oneof![
+            Just(ExitBehavior::Close),
+            Just(ExitBehavior::CloseOnCleanExit),
+            Just(ExitBehavior::Hold),
+        ],
+        audible_bell in prop_oneof![Just(AudibleBell::SystemBeep), Just(AudibleBell::Disabled)],
+    ) -> GeneralConfig {
+        GeneralConfig {
+            automatically_reload_config: flags[0],
+            scrollback_lines: sizes[0],
+            initial_rows: sizes[1],
+            initial_cols: sizes[2],
+            exit_behavior,
+            audible_bell,
+            enable_scroll_bar: flags[1],
+            switch_to_last_active_tab_when_closing_tab: flags[2],
+            adjust_window_size_when_changing_font_size: flags[3],
+        }
+    }
+}
+
+prop_compose! {
+    fn command_palette()(fg_color in color(), bg_color in color(), font_size in number()) -> CommandPaletteConfig {
+        CommandPaletteConfig { fg_color, bg_color, font_size }
+    }
+}
+
+prop_compose! {
+    fn visual_bell()(
+        fade_in_duration_ms in any::<u32>(),
+        fade_out_duration_ms in any::<u32>(),
+        fade_in_function in ease(),
+        fade_out_function in ease(),
+        target in text(),
+    ) -> VisualBellConfig {
+        VisualBellConfig { fade_in_duration_ms, fade_out_duration_ms, fade_in_function, fade_out_function, target }
+    }
+}
+
+/// Disabled bindings are not written, so they keep their default key. Bindings
+/// that share an action with another (copy / copy_simple) are told apart by
+/// their default key, so those keep it too.
+fn keybindings() -> impl Strategy<Value = KeyBindingsConfig> {
+    let count = KeyBindingsConfig::default().bindings().len();
+    (
+        prop::collection::vec((any::<bool>(), text(), text()), count),
+        (any::<bool>(), any::<bool>(), text(), text(), any::<u32>()),
+        prop::array::uniform4(any::<bool>()),
+        prop::collection::vec(
+            prop::sample::select(vec![
+                "{ key = 'z', mods = 'CTRL', action = act.Nop }",
+                "{ key = 'q', action = wezterm.action.QuitApplication }",
+            ]),
+            0..3,
+        ),
+        prop::option::of(Just("vim = { { key = 'Escape', action = 'PopKeyTable' } }")),
+        prop::option::of(Just(
+            "{ event = { Down = { streak = 3, button = 'Left' } }, action = act.SelectTextAtMouseCursor('Line') }",
+        )),
+    )
+        .prop_map(|(bindings, leader, flags, extra_keys, extra_table, extra_mouse)| {
+            let mut kb = KeyBindingsConfig::default();
+            let shared: Vec<bool> = kb
+                .bindings()
+                .iter()
+                .map(|(name, _)| {
+                    let action = key_action(name);
+                    kb.bindings().iter().filter(|(n, _)| key_action(n) == action).count() > 1
+                })
+                .collect();
+            for (((_, binding), (enabled, key, mods)), shared) in
+                kb.bindings_mut().into_iter().zip(bindings).zip(shared)
+            {
+                binding.enabled = enabled;
+                if enabled && !shared {
+                    binding.key = key;
+                    binding.mods = mods;
+                }
+            }
+
+            let (disable_defaults, leader_enabled, key, mods, timeout_ms) = leader;
+            kb.disable_defaults = disable_defaults;
+            if leader_enabled {
+                kb.leader = LeaderKeyConfig { enabled: true, key, mods, timeout_ms };
+            } else {
+                kb.leader.enabled = false;
+            }
+            kb.mouse.ctrl_click_open_link = flags[0];
+            kb.mouse.right_click_command_palette = flags[1];
+            kb.custom_commands.settings_tui = flags[2];
+            kb.custom_commands.rename_tab = flags[3];
+            kb.extra_keys = extra_keys.into_iter().map(str::to_string).collect();
+            kb.extra_key_tables = extra_table.into_iter().map(str::to_string).collect();
+            kb.extra_mouse_bindings = extra_mouse.into_iter().map(str::to_string).collect();
+            kb
+        })
+}
+
+/// A built-in scheme replaces the custom colors, which are then not written
+fn appearance() -> impl Strategy<Value = AppearanceConfig> {
+    (
+        prop::option::of(text()),
+        colors(),
+        fonts(),
+        window(),
+        cursor(),
+        backdrop(),
+        gpu(),
+        general(),
+        command_palette(),
+        visual_bell(),
+        keybindings(),
+    )
+        .prop_map(
+            |(color_scheme, colors, fonts, window, cursor, backdrop, gpu, general, command_palette, visual_bell, keybindings)| {
+                AppearanceConfig {
+                    colors: if color_scheme.is_some() { ColorScheme::default() } else { colors },
+                    color_scheme,
+                    fonts,
+                    window,
+                    cursor,
+                    backdrop,
+                    gpu,
+                    general,
+                    command_palette,
+                    visual_bell,
+                    keybindings,
+                }
+            },
+        )
+}
+
+/// Values no whole-number setting accepts, as written in Lua
+fn invalid_integer() -> impl Strategy<Value = String> {
+    prop_oneof![
+        (1u32..100_000).prop_map(|n| format!("-{}", n)),
+        (0u32..100_000, 1u32..100).prop_map(|(n, f)| format!("{}.{:02}", n, f)),
+        (u32::MAX as u64 + 1..u64::MAX / 2).prop_map(|n| n.to_string()),
+        text().prop_map(|t| format!("{:?}", t.replace(|c: char| !c.is_ascii_alphanumeric(), ""))),
+        Just("true".to_string()),
+    ]
+}
+
+/// Entries kept verbatim are reported once each; nothing else may be
+fn expected_warnings(config: &AppearanceConfig) -> usize {
+    let kb = &config.keybindings;
+    kb.extra_keys.len() + kb.extra_key_tables.len() + kb.extra_mouse_bindings.len()
+}
+
+proptest! {
+    #[test]
+    fn wezterm_lua_round_trips(config in appearance()) {
+        let lua = generate_lua_config(&config);
+        let parsed = parse_lua_content(&lua).unwrap();
+        prop_assert_eq!(parsed.diagnostics.len(), expected_warnings(&config), "{:?}", parsed.diagnostics);
+        prop_assert_eq!(&parsed.config, &config);
+        prop_assert_eq!(generate_lua_config(&parsed.config), lua);
+    }
+
+    #[test]
+    fn invalid_integers_are_reported(config in appearance(), bad in invalid_integer()) {
+        let assignment = format!("config.initial_rows = {}\nreturn config\n", bad);
+        let lua = generate_lua_config(&config).replace("return config\n", &assignment);
+        let parsed = parse_lua_content(&lua).unwrap();
+        prop_assert_eq!(parsed.config.general.initial_rows, GeneralConfig::default().initial_rows);
+        prop_assert_eq!(parsed.diagnostics.len(), expected_warnings(&config) + 1, "{:?}", parsed.diagnostics);
+        let message = format!("initial_rows: `{}` ", bad);
+        prop_assert!(parsed.diagnostics.iter().any(|d| d.message.starts_with(&message)), "{:?}", parsed.diagnostics);
+    }
+
+    #[test]
+    fn settings_module_round_trips(config in appearance()) {
+        let lua = generate_settings_module(&config);
+        let parsed = parse_lua_content(&lua).unwrap();
+        prop_assert_eq!(parsed.diagnostics.len(), expected_warnings(&config), "{:?}", parsed.diagnostics);
+        prop_assert_eq!(&parsed.config, &config);
+        prop_assert_eq!(generate_settings_module(&parsed.config), lua);
+    }
+}
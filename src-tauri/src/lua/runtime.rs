@@ -0,0 +1,431 @@
+// Lua runtime - runs a config in a sandboxed Lua VM
+//
+// The static parser reads what is written; this reads what the config
+// produces, so loops, helper functions and conditionals are followed. The
+// config runs against a stub `wezterm` module, and the table it returns is
+// written out as plain Lua (`return { font_size = 14, ... }`) for the parser
+// to read like any other file. The VM has no `os`, `io` or `debug` library,
+// can only `require` modules from the config directory, and is stopped after
+// `TIME_LIMIT` or `MEMORY_LIMIT`.
+
+use super::ast::{parse_chunk, ExprKind, StatKind};
+use super::parser::module_path;
+use mlua::{Function, HookTriggers, Lua, LuaOptions, StdLib};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::path::Path;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// How long a config may run before it is stopped
+const TIME_LIMIT: Duration = Duration::from_secs(2);
+
+/// How much memory the VM may allocate
+const MEMORY_LIMIT: usize = 64 * 1024 * 1024;
+
+/// The stub `wezterm` module, `require`, and the function that runs the
+/// config and writes out its result. Called with the host table from
+/// `run_config`; returns the runner.
+const PRELUDE: &str = r#"
+local host = ...
+
+-- Values the stub creates, by kind, so they can be written back as Lua
+local markers = setmetatable({}, { __mode = 'k' })
+local function mark(kind, value)
+  markers[value] = kind
+  return value
+end
+
+-- Anything the stub doesn't model: it can be called, indexed and combined,
+-- and is written out as nil so the setting keeps its default
+local unknown
+local function get_unknown() return unknown end
+unknown = mark('unknown', setmetatable({}, {
+  __index = get_unknown, __call = get_unknown, __concat = get_unknown,
+  __add = get_unknown, __sub = get_unknown, __mul = get_unknown, __div = get_unknown,
+  __mod = get_unknown, __unm = get_unknown, __len = function() return 0 end,
+  __tostring = function() return '' end,
+}))
+
+local action_mt = {}
+action_mt.__call = function(self, args)
+  return mark('action', setmetatable({ name = self.name, args = args }, action_mt))
+end
+local function action(name)
+  return mark('action', setmetatable({ name = name }, action_mt))
+end
+
+local color_mt = {
+  __index = function() return get_unknown end,
+  __tostring = function(self) return self[1] end,
+}
+
+local handlers = {}
+local wezterm = setmetatable({
+  home_dir = host.home_dir,
+  config_dir = host.config_dir,
+  config_file = host.config_file,
+  target_triple = host.target_triple,
+  config_builder = function() return {} end,
+  font = function(family, attrs)
+    return mark('font', { name = 'font', args = { family, attrs } })
+  end,
+  font_with_fallback = function(families, attrs)
+    return mark('font', { name = 'font_with_fallback', args = { families, attrs } })
+  end,
+  action = setmetatable({}, {
+    __index = function(_, name) return action(name) end,
+    __call = function(_, spec) return mark('action_table', { spec }) end,
+  }),
+  action_callback = function(f) return mark('callback', { f }) end,
+  on = function(event, f)
+    handlers[event] = handlers[event] or {}
+    table.insert(handlers[event], f)
+  end,
+  log_info = function() end,
+  log_warn = function() end,
+  log_error = function() end,
+  plugin = { require = get_unknown },
+  color = setmetatable({
+    parse = function(s) return mark('color', setmetatable({ s }, color_mt)) end,
+    get_builtin_schemes = function() return {} end,
+  }, { __index = get_unknown }),
+}, { __index = get_unknown })
+
+-- Only text chunks may be loaded, and only modules from the config directory
+local raw_load, raw_pcall, raw_xpcall = load, pcall, xpcall
+load = function(chunk, name, _, env) return raw_load(chunk, name, 't', env) end
+dofile, loadfile, string.dump = nil, nil, nil
+print = function() end
+
+-- The time limit can't be caught and ignored
+local function rethrow_timeout(ok, ...)
+  if not ok and host.expired() then error((...), 0) end
+  return ok, ...
+end
+pcall = function(...) return rethrow_timeout(raw_pcall(...)) end
+xpcall = function(...) return rethrow_timeout(raw_xpcall(...)) end
+
+local loaded = { wezterm = wezterm }
+require = function(name)
+  if loaded[name] ~= nil then return loaded[name] end
+  local source, chunkname = host.read_module(name)
+  if not source then
+    error("module '" .. tostring(name) .. "' not found in the config directory", 2)
+  end
+  local result = assert(raw_load(source, chunkname, 't'))(name)
+  if result == nil then result = true end
+  loaded[name] = result
+  return result
+end
+
+local keywords = {}
+for word in ([[and break do else elseif end false for function goto if in
+    local nil not or repeat return then true until while]]):gmatch('%a+') do
+  keywords[word] = true
+end
+
+local serialize
+
+local function serialize_table(t, indent, seen)
+  local inner = indent .. '  '
+  local parts = {}
+  local count = #t
+  for i = 1, count do
+    parts[#parts + 1] = serialize(t[i], inner, seen)
+  end
+  local keys = {}
+  for key in pairs(t) do
+    local is_item = math.type(key) == 'integer' and key >= 1 and key <= count
+    if not is_item and (type(key) == 'string' or type(key) == 'number') then
+      keys[#keys + 1] = key
+    end
+  end
+  table.sort(keys, function(a, b) return tostring(a) < tostring(b) end)
+  for _, key in ipairs(keys) do
+    local name
+    if type(key) == 'string' and key:match('^[%a_][%w_]*$') and not keywords[key] then
+      name = key
+    else
+      name = '[' .. serialize(key, inner, seen) .. ']'
+    end
+    local text = serialize(t[key], inner, seen)
+    if text ~= 'nil' then parts[#parts + 1] = name .. ' = ' .. text end
+  end
+  if #parts == 0 then return '{}' end
+  local line = '{ ' .. table.concat(parts, ', ') .. ' }'
+  if #line <= 80 and not line:find('\n') then return line end
+  return '{\n' .. inner .. table.concat(parts, ',\n' .. inner) .. ',\n' .. indent .. '}'
+end
+
+serialize = function(value, indent, seen)
+  local kind = type(value)
+  if kind == 'nil' or kind == 'boolean' then return tostring(value) end
+  if kind == 'number' then
+    if math.type(value) == 'integer' then return tostring(value) end
+    if value ~= value or value == math.huge or value == -math.huge then return 'nil' end
+    return string.format('%.14g', value)
+  end
+  if kind == 'string' then return (string.format('%q', value):gsub('\\\n', '\\n')) end
+  if kind ~= 'table' then return 'nil' end
+
+  local marker = markers[value]
+  if marker == 'unknown' then return 'nil' end
+  if marker == 'color' then return serialize(value[1], indent, seen) end
+  if marker == 'font' then
+    local args = { serialize(value.args[1], indent, seen) }
+    if value.args[2] ~= nil then args[2] = serialize(value.args[2], indent, seen) end
+    return 'wezterm.' .. value.name .. '(' .. table.concat(args, ', ') .. ')'
+  end
+  if marker == 'action' then
+    if value.args == nil then return 'act.' .. value.name end
+    return 'act.' .. value.name .. '(' .. serialize(value.args, indent, seen) .. ')'
+  end
+  if marker == 'action_table' then
+    return 'wezterm.action(' .. serialize(value[1], indent, seen) .. ')'
+  end
+  if marker == 'callback' then
+    local source = host.function_source(value[1])
+    return source and ('wezterm.action_callback(' .. source .. ')') or 'nil'
+  end
+
+  if seen[value] then return 'nil' end
+  seen[value] = true
+  local text = serialize_table(value, indent, seen)
+  seen[value] = nil
+  return text
+end
+
+-- Run the config and write out what it returns, with the command palette
+-- entries its `augment-command-palette` handlers add
+return function(main)
+  local config = main()
+  if type(config) ~= 'table' then
+    error('the config returned ' .. type(config) .. ' instead of a table', 0)
+  end
+
+  local palette = {}
+  for _, handler in ipairs(handlers['augment-command-palette'] or {}) do
+    local ok, entries = pcall(handler, unknown, unknown)
+    if ok and type(entries) == 'table' then
+      for _, entry in ipairs(entries) do palette[#palette + 1] = entry end
+    end
+  end
+
+  local out = { "local wezterm = require 'wezterm'", 'local act = wezterm.action' }
+  if #palette > 0 then
+    out[#out + 1] = "wezterm.on('augment-command-palette', function(window, pane)"
+    out[#out + 1] = '  return ' .. serialize(palette, '  ', {})
+    out[#out + 1] = 'end)'
+  end
+  out[#out + 1] = 'return ' .. serialize(config, '', {})
+  return table.concat(out, '\n') .. '\n'
+end
+"#;
+
+/// Run the config at `path` and return what it evaluates to as a Lua chunk
+/// of literal values
+pub fn run_config(path: &Path) -> Result<String, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read config file: {}", e))?;
+    let base_dir = path.parent().unwrap_or(Path::new("")).to_path_buf();
+
+    let lua = Lua::new_with(
+        StdLib::TABLE | StdLib::STRING | StdLib::UTF8 | StdLib::MATH,
+        LuaOptions::new(),
+    )
+    .map_err(|e| e.to_string())?;
+    lua.set_memory_limit(MEMORY_LIMIT).map_err(|e| e.to_string())?;
+
+    let started = Instant::now();
+    let expired = Rc::new(Cell::new(false));
+    let timer = expired.clone();
+    lua.set_hook(HookTriggers::new().every_nth_instruction(10_000), move |_, _| {
+        if started.elapsed() > TIME_LIMIT {
+            timer.set(true);
+            return Err(mlua::Error::runtime(format!(
+                "stopped after {} seconds",
+                TIME_LIMIT.as_secs()
+            )));
+        }
+        Ok(())
+    });
+
+    // Source of every chunk loaded, so action callbacks can be written back
+    // as they are written in the file
+    let sources: Rc<RefCell<HashMap<String, String>>> = Rc::default();
+    sources.borrow_mut().insert(chunk_name(path), content.clone());
+
+    let host = lua.create_table().map_err(|e| e.to_string())?;
+    let lua_err = |e: mlua::Error| first_line(&e.to_string());
+    let home_dir = dirs::home_dir().map(|p| p.to_string_lossy().to_string());
+    host.set("home_dir", home_dir).map_err(lua_err)?;
+    host.set("config_dir", base_dir.to_string_lossy().to_string()).map_err(lua_err)?;
+    host.set("config_file", path.to_string_lossy().to_string()).map_err(lua_err)?;
+    host.set("target_triple", target_triple()).map_err(lua_err)?;
+    let expired_flag = expired.clone();
+    host.set(
+        "expired",
+        lua.create_function(move |_, ()| Ok(expired_flag.get())).map_err(lua_err)?,
+    )
+    .map_err(lua_err)?;
+    let module_sources = sources.clone();
+    host.set(
+        "read_module",
+        lua.create_function(move |_, name: String| {
+            let Some(path) = module_path(&base_dir, &name) else {
+                return Ok((None, None));
+            };
+            let Ok(source) = std::fs::read_to_string(&path) else {
+                return Ok((None, None));
+            };
+            let chunk = chunk_name(&path);
+            module_sources.borrow_mut().insert(chunk.clone(), source.clone());
+            Ok((Some(source), Some(chunk)))
+        })
+        .map_err(lua_err)?,
+    )
+    .map_err(lua_err)?;
+    let callback_sources = sources.clone();
+    host.set(
+        "function_source",
+        lua.create_function(move |_, f: Function| {
+            let info = f.info();
+            let sources = callback_sources.borrow();
+            let source = info.source.and_then(|name| sources.get(&name));
+            Ok(match (source, info.line_defined, info.last_line_defined) {
+                (Some(source), Some(first), Some(last)) => function_text(source, first, last),
+                _ => None,
+            })
+        })
+        .map_err(lua_err)?,
+    )
+    .map_err(lua_err)?;
+
+    let run: Function = lua
+        .load(PRELUDE)
+        .set_name("=settings-ui")
+        .call(host)
+        .map_err(lua_err)?;
+    let main = lua
+        .load(&content)
+        .set_name(chunk_name(path))
+        .into_function()
+        .map_err(lua_err)?;
+    run.call(main).map_err(lua_err)
+}
+
+/// `wezterm.target_triple` for the platform this was built for
+fn target_triple() -> String {
+    let platform = match std::env::consts::OS {
+        "macos" => "apple-darwin",
+        "windows" => "pc-windows-msvc",
+        "linux" => "unknown-linux-gnu",
+        other => other,
+    };
+    format!("{}-{}", std::env::consts::ARCH, platform)
+}
+
+/// Chunk name Lua reports for a file, as in `@/home/me/.config/wezterm/wezterm.lua`
+fn chunk_name(path: &Path) -> String {
+    format!("@{}", path.display())
+}
+
+/// The first line of a Lua error, without the traceback
+fn first_line(message: &str) -> String {
+    let message = message.strip_prefix("runtime error: ").unwrap_or(message);
+    message.lines().next().unwrap_or_default().to_string()
+}
+
+/// The text of a function defined on lines `first..=last` of `source`, from
+/// its `function` keyword to its closing `end`, if it can be cut out cleanly
+fn function_text(source: &str, first: usize, last: usize) -> Option<String> {
+    let lines: Vec<&str> = source.lines().collect();
+    let text = lines.get(first.checked_sub(1)?..last)?.join("\n");
+    let start = text.find("function")?;
+    let end = text.rfind("end")? + "end".len();
+    let text = text.get(start..end)?;
+
+    // Only accept it if it is exactly one function expression
+    let chunk = parse_chunk(&format!("return {}", text)).ok()?;
+    match chunk.stats.first().map(|s| &s.kind) {
+        Some(StatKind::Return(exprs)) if chunk.stats.len() == 1 => match exprs.as_slice() {
+            [expr] if matches!(expr.kind, ExprKind::Function(_)) => Some(text.to_string()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(name: &str, files: &[(&str, &str)]) -> Result<String, String> {
+        let dir = std::env::temp_dir().join(format!("wezterm-runtime-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        for (file, content) in files {
+            let path = dir.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+        let result = run_config(&dir.join("wezterm.lua"));
+        std::fs::remove_dir_all(&dir).unwrap();
+        result
+    }
+
+    #[test]
+    fn test_run_config_with_loops_and_helpers() {
+        let config = r#"local wezterm = require 'wezterm'
+local act = wezterm.action
+local helpers = require 'helpers'
+local config = wezterm.config_builder()
+
+config.font = wezterm.font(helpers.font_name(), { weight = 'Bold' })
+config.font_size = helpers.scale(10)
+config.keys = {}
+for i = 1, 2 do
+  table.insert(config.keys, { key = tostring(i), mods = 'ALT', action = act.ActivateTab(i - 1) })
+end
+table.insert(config.keys, {
+  key = 'r',
+  mods = 'LEADER',
+  action = wezterm.action_callback(function(window, pane)
+    window:perform_action(act.ReloadConfiguration, pane)
+  end),
+})
+if config.font_size > 12 then
+  config.color_scheme = 'Dracula'
+end
+config.window_background_opacity = wezterm.gui.get_appearance()
+return config
+"#;
+        let helpers = "local M = {}\nfunction M.font_name() return 'Iosevka' end\nfunction M.scale(n) return n * 1.5 end\nreturn M\n";
+        let snapshot = run("loops", &[("wezterm.lua", config), ("helpers.lua", helpers)]).unwrap();
+
+        assert!(snapshot.contains(r#"font = wezterm.font("Iosevka", { weight = "Bold" })"#), "{}", snapshot);
+        assert!(snapshot.contains("font_size = 15"), "{}", snapshot);
+        assert!(snapshot.contains(r#"{ action = act.ActivateTab(1), key = "2", mods = "ALT" }"#), "{}", snapshot);
+        assert!(
+            snapshot.contains("wezterm.action_callback(function(window, pane)\n    window:perform_action"),
+            "{}",
+            snapshot
+        );
+        assert!(snapshot.contains(r#"color_scheme = "Dracula""#), "{}", snapshot);
+        // Only known when WezTerm runs, so left out
+        assert!(!snapshot.contains("window_background_opacity"), "{}", snapshot);
+    }
+
+    #[test]
+    fn test_run_config_is_sandboxed() {
+        let err = run("io", &[("wezterm.lua", "return { font_size = io.read('n') }")]).unwrap_err();
+        assert!(err.contains("io"), "{}", err);
+
+        let err = run("loop", &[("wezterm.lua", "while true do pcall(function() while true do end end) end")])
+            .unwrap_err();
+        assert!(err.contains("stopped after"), "{}", err);
+
+        let err = run("require", &[("wezterm.lua", "return require('../secrets')")]).unwrap_err();
+        assert!(err.contains("not found in the config directory"), "{}", err);
+    }
+}
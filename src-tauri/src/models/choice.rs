@@ -0,0 +1,157 @@
+// Choice trait - the variants of a settings enum, for pickers
+//
+// Every enum setting lists its variants with a display name and a
+// one-line description, so a UI can offer all of them without knowing the
+// type. The value a picker hands back is the variant's serialized name.
+
+use serde::Serialize;
+
+use super::config::{
+    AudibleBell, CloseConfirmation, CursorStyle, EaseFunction, ExitBehavior, FontWeight,
+    FreetypeTarget, FrontEnd, PowerPreference, WindowDecorations,
+};
+
+/// A settings enum whose variants can be listed
+pub trait Choice: Sized + Serialize + 'static {
+    /// Every variant, in the order a picker shows them
+    const ALL: &'static [Self];
+
+    /// Name shown to the user
+    fn label(&self) -> &'static str;
+
+    /// What choosing the variant does
+    fn description(&self) -> &'static str;
+}
+
+/// One variant of a `Choice` enum, without its type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChoiceOption {
+    /// The variant as it serializes (`BlinkingBlock`, `INTEGRATED_BUTTONS|RESIZE`)
+    pub value: String,
+    pub label: &'static str,
+    pub description: &'static str,
+}
+
+/// Every variant of `T`, in order
+pub fn choice_options<T: Choice>() -> Vec<ChoiceOption> {
+    T::ALL
+        .iter()
+        .map(|variant| ChoiceOption {
+            value: serde_json::to_value(variant)
+                .ok()
+                .and_then(|v| v.as_str().map(str::to_string))
+                .unwrap_or_default(),
+            label: variant.label(),
+            description: variant.description(),
+        })
+        .collect()
+}
+
+/// `impl Choice` from `Variant => "Label", "Description";` lines
+macro_rules! choices {
+    ($ty:ident { $($variant:ident => $label:literal, $description:literal;)* }) => {
+        impl Choice for $ty {
+            const ALL: &'static [Self] = &[$($ty::$variant),*];
+
+            fn label(&self) -> &'static str {
+                match self {
+                    $($ty::$variant => $label),*
+                }
+            }
+
+            fn description(&self) -> &'static str {
+                match self {
+                    $($ty::$variant => $description),*
+                }
+            }
+        }
+    };
+}
+
+choices!(FontWeight {
+    Thin => "Thin", "Weight 100";
+    ExtraLight => "Extra Light", "Weight 200";
+    Light => "Light", "Weight 300";
+    Regular => "Regular", "Weight 400, the usual text weight";
+    Medium => "Medium", "Weight 500";
+    DemiBold => "Demi Bold", "Weight 600";
+    Bold => "Bold", "Weight 700";
+    ExtraBold => "Extra Bold", "Weight 800";
+    Black => "Black", "Weight 900";
+});
+
+choices!(FreetypeTarget {
+    Normal => "Normal", "Default hinting, tuned for most displays";
+    Light => "Light", "Lighter vertical-only hinting; keeps glyph shapes closer to the design";
+    Mono => "Mono", "Strong hinting for monochrome output";
+    HorizontalLcd => "Horizontal LCD", "Subpixel hinting for horizontal RGB LCD panels";
+});
+
+choices!(WindowDecorations {
+    Full => "Full", "Title bar and resizable border";
+    Resize => "Resize", "Resizable border only, no title bar";
+    None => "None", "No title bar or border";
+    Title => "Title", "Title bar only, no resizable border";
+    IntegratedButtonsResize => "Integrated Buttons", "Window buttons in the tab bar, with a resizable border";
+});
+
+choices!(CloseConfirmation {
+    AlwaysPrompt => "Always Prompt", "Ask before closing a window";
+    NeverPrompt => "Never Prompt", "Close windows without asking";
+});
+
+choices!(CursorStyle {
+    SteadyBlock => "Steady Block", "Solid block that doesn't blink";
+    BlinkingBlock => "Blinking Block", "Block that blinks at the blink rate";
+    SteadyUnderline => "Steady Underline", "Underline that doesn't blink";
+    BlinkingUnderline => "Blinking Underline", "Underline that blinks at the blink rate";
+    SteadyBar => "Steady Bar", "Thin vertical bar that doesn't blink";
+    BlinkingBar => "Blinking Bar", "Thin vertical bar that blinks at the blink rate";
+});
+
+choices!(EaseFunction {
+    Linear => "Linear", "Constant speed";
+    EaseIn => "Ease In", "Starts slow, ends fast";
+    EaseOut => "Ease Out", "Starts fast, ends slow";
+    EaseInOut => "Ease In/Out", "Slow at both ends";
+    Constant => "Constant", "No fade; switches at once";
+});
+
+choices!(ExitBehavior {
+    Close => "Close", "Close the pane when its program exits";
+    CloseOnCleanExit => "Close On Clean Exit", "Close only when the program exits successfully";
+    Hold => "Hold", "Keep the pane open until closed by hand";
+});
+
+choices!(AudibleBell {
+    SystemBeep => "System Beep", "Play the system beep";
+    Disabled => "Disabled", "Stay silent";
+});
+
+choices!(FrontEnd {
+    WebGpu => "WebGpu", "Modern GPU renderer (Vulkan, Metal or DirectX 12)";
+    OpenGL => "OpenGL", "OpenGL renderer";
+    Software => "Software", "CPU rendering; slow, but works without a GPU";
+});
+
+choices!(PowerPreference {
+    LowPower => "Low Power", "Prefer the integrated GPU";
+    HighPerformance => "High Performance", "Prefer the discrete GPU";
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_choice_options_use_serialized_names() {
+        let options = choice_options::<WindowDecorations>();
+        assert_eq!(options.len(), 5);
+        assert_eq!(options[4].value, "INTEGRATED_BUTTONS|RESIZE");
+        assert_eq!(options[4].label, "Integrated Buttons");
+
+        let weights: Vec<String> = choice_options::<FontWeight>().into_iter().map(|o| o.value).collect();
+        assert_eq!(weights[5], "DemiBold");
+        assert!(choice_options::<CursorStyle>().iter().all(|o| !o.value.is_empty()));
+    }
+}
@@ -0,0 +1,536 @@
+// Color value type - parses the color notations WezTerm accepts
+//
+// A `Color` keeps the text it was written as, so a config saved back
+// unchanged reads the same, along with the RGBA it stands for. It
+// serializes as that text and refuses anything that isn't a color when
+// deserialized.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// A color as written in the config (`#1e1e2e`, `rgba(0, 0, 0, 0.4)`,
+/// `hsl(240 20% 15%)`, `Navy`), with the RGBA it stands for
+#[derive(Debug, Clone, PartialEq)]
+pub struct Color {
+    text: String,
+    /// Red, green, blue and alpha, each 0.0-1.0
+    rgba: [f32; 4],
+}
+
+impl Color {
+    /// Parse hex (`#rgb`, `#rgba`, `#rrggbb`, `#rrggbbaa`), `rgb()`,
+    /// `rgba()`, `hsl()`, `hsla()` or a named color
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let trimmed = text.trim();
+        let rgba = parse_rgba(trimmed).map_err(|reason| match reason {
+            Some(reason) => format!("`{}` is not a color: {}", text, reason),
+            None => format!(
+                "`{}` is not a color (use #rrggbb, rgb(), rgba(), hsl() or a color name)",
+                text
+            ),
+        })?;
+        Ok(Self {
+            text: trimmed.to_string(),
+            rgba,
+        })
+    }
+
+    /// An opaque color written as `#rrggbb`
+    pub fn from_rgb8(r: u8, g: u8, b: u8) -> Self {
+        Self::from_rgba8([r, g, b, 255])
+    }
+
+    /// A color written as `#rrggbb`, or `#rrggbbaa` when it isn't opaque
+    pub fn from_rgba8(rgba: [u8; 4]) -> Self {
+        let rgba = rgba.map(|c| c as f32 / 255.0);
+        let mut color = Self { text: String::new(), rgba };
+        color.text = color.to_hex();
+        color
+    }
+
+    /// A color from hue in degrees, saturation and lightness 0.0-1.0 and
+    /// alpha 0.0-1.0, written as hex
+    pub fn from_hsla(hue: f32, saturation: f32, lightness: f32, alpha: f32) -> Self {
+        let [r, g, b] = hsl_to_rgb(
+            hue.rem_euclid(360.0),
+            saturation.clamp(0.0, 1.0),
+            lightness.clamp(0.0, 1.0),
+        );
+        Self::from_rgba8([r, g, b, alpha.clamp(0.0, 1.0)].map(|c| (c * 255.0).round() as u8))
+    }
+
+    /// Hue in degrees (0 for grays), saturation and lightness 0.0-1.0
+    pub fn to_hsl(&self) -> [f32; 3] {
+        let [r, g, b, _] = self.rgba;
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let lightness = (max + min) / 2.0;
+        let chroma = max - min;
+        if chroma == 0.0 {
+            return [0.0, 0.0, lightness];
+        }
+        let saturation = chroma / (1.0 - (2.0 * lightness - 1.0).abs());
+        let sector = if max == r {
+            ((g - b) / chroma).rem_euclid(6.0)
+        } else if max == g {
+            (b - r) / chroma + 2.0
+        } else {
+            (r - g) / chroma + 4.0
+        };
+        [sector * 60.0, saturation, lightness]
+    }
+
+    /// The color as it was written
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Red, green, blue and alpha, each 0.0-1.0
+    pub fn rgba(&self) -> [f32; 4] {
+        self.rgba
+    }
+
+    /// Red, green, blue and alpha, each 0-255
+    pub fn to_rgba8(&self) -> [u8; 4] {
+        self.rgba.map(|c| (c * 255.0).round() as u8)
+    }
+
+    /// `#rrggbb`, or `#rrggbbaa` when the color isn't opaque
+    pub fn to_hex(&self) -> String {
+        let [r, g, b, a] = self.to_rgba8();
+        if a == 255 {
+            format!("#{:02x}{:02x}{:02x}", r, g, b)
+        } else {
+            format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, a)
+        }
+    }
+}
+
+impl FromStr for Color {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl PartialEq<str> for Color {
+    fn eq(&self, other: &str) -> bool {
+        self.text == other
+    }
+}
+
+impl PartialEq<&str> for Color {
+    fn eq(&self, other: &&str) -> bool {
+        self.text == *other
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(&self.text)
+    }
+}
+
+impl Serialize for Color {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.text)
+    }
+}
+
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        Self::parse(&text).map_err(serde::de::Error::custom)
+    }
+}
+
+/// RGBA for `text`. `Err(None)` means it isn't any known notation;
+/// `Err(Some(reason))` that it is one but is malformed.
+fn parse_rgba(text: &str) -> Result<[f32; 4], Option<String>> {
+    if let Some(hex) = text.strip_prefix('#') {
+        return parse_hex(hex).ok_or_else(|| {
+            Some("hex colors have 3, 4, 6 or 8 hex digits".to_string())
+        });
+    }
+
+    let lower = text.to_ascii_lowercase();
+    if let Some((name, args)) = lower.strip_suffix(')').and_then(|s| s.split_once('(')) {
+        let args = split_args(args);
+        return match name.trim() {
+            "rgb" | "rgba" => parse_rgb_args(&args),
+            "hsl" | "hsla" => parse_hsl_args(&args),
+            _ => Err(None),
+        };
+    }
+
+    let name: String = lower.chars().filter(|c| !c.is_whitespace()).collect();
+    if name == "transparent" {
+        return Ok([0.0, 0.0, 0.0, 0.0]);
+    }
+    NAMED_COLORS
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|&(_, rgb)| {
+            let [_, r, g, b] = rgb.to_be_bytes();
+            [r, g, b, 255].map(|c| c as f32 / 255.0)
+        })
+        .ok_or(None)
+}
+
+fn parse_hex(hex: &str) -> Option<[f32; 4]> {
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let digits: Vec<u8> = match hex.len() {
+        // Short forms repeat each digit: #abc is #aabbcc
+        3 | 4 => hex
+            .chars()
+            .map(|c| u8::from_str_radix(&c.to_string().repeat(2), 16).ok())
+            .collect::<Option<_>>()?,
+        6 | 8 => (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+            .collect::<Option<_>>()?,
+        _ => return None,
+    };
+    let alpha = digits.get(3).copied().unwrap_or(255);
+    Some([digits[0], digits[1], digits[2], alpha].map(|c| c as f32 / 255.0))
+}
+
+/// The arguments of `rgb(1, 2, 3)` or `rgb(1 2 3 / 0.5)`
+fn split_args(args: &str) -> Vec<&str> {
+    args.split(|c: char| c == ',' || c == '/' || c.is_whitespace())
+        .filter(|a| !a.is_empty())
+        .collect()
+}
+
+fn parse_rgb_args(args: &[&str]) -> Result<[f32; 4], Option<String>> {
+    let [r, g, b, alpha @ ..] = args else {
+        return Err(Some("rgb() takes red, green, blue and an optional alpha".to_string()));
+    };
+    if alpha.len() > 1 {
+        return Err(Some("rgb() takes red, green, blue and an optional alpha".to_string()));
+    }
+    let channel = |arg: &str| match arg.strip_suffix('%') {
+        Some(percent) => in_range(percent, 0.0, 100.0).map(|p| p / 100.0),
+        None => in_range(arg, 0.0, 255.0).map(|c| c / 255.0),
+    };
+    Ok([
+        channel(r)?,
+        channel(g)?,
+        channel(b)?,
+        alpha.first().map_or(Ok(1.0), |a| parse_alpha(a))?,
+    ])
+}
+
+fn parse_hsl_args(args: &[&str]) -> Result<[f32; 4], Option<String>> {
+    let [h, s, l, alpha @ ..] = args else {
+        return Err(Some("hsl() takes hue, saturation, lightness and an optional alpha".to_string()));
+    };
+    if alpha.len() > 1 {
+        return Err(Some("hsl() takes hue, saturation, lightness and an optional alpha".to_string()));
+    }
+    let hue = h.strip_suffix("deg").unwrap_or(h);
+    let hue = hue
+        .parse::<f32>()
+        .ok()
+        .filter(|h| h.is_finite())
+        .ok_or_else(|| Some(format!("{} is not a hue", h)))?
+        .rem_euclid(360.0);
+    let percent = |arg: &str| in_range(arg.strip_suffix('%').unwrap_or(arg), 0.0, 100.0).map(|p| p / 100.0);
+    let [r, g, b] = hsl_to_rgb(hue, percent(s)?, percent(l)?);
+    Ok([r, g, b, alpha.first().map_or(Ok(1.0), |a| parse_alpha(a))?])
+}
+
+/// Alpha as 0.0-1.0 or a percentage
+fn parse_alpha(arg: &str) -> Result<f32, Option<String>> {
+    match arg.strip_suffix('%') {
+        Some(percent) => in_range(percent, 0.0, 100.0).map(|p| p / 100.0),
+        None => in_range(arg, 0.0, 1.0),
+    }
+}
+
+fn in_range(arg: &str, min: f32, max: f32) -> Result<f32, Option<String>> {
+    match arg.parse::<f32>() {
+        Ok(n) if (min..=max).contains(&n) => Ok(n),
+        Ok(_) => Err(Some(format!("{} is out of range {}-{}", arg, min, max))),
+        Err(_) => Err(Some(format!("{} is not a number", arg))),
+    }
+}
+
+/// Hue in degrees, saturation and lightness 0.0-1.0, to RGB 0.0-1.0
+fn hsl_to_rgb(hue: f32, saturation: f32, lightness: f32) -> [f32; 3] {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let sector = hue / 60.0;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = lightness - chroma / 2.0;
+    [r + m, g + m, b + m]
+}
+
+/// Named colors WezTerm accepts (the CSS/X11 set), lowercase without spaces
+const NAMED_COLORS: &[(&str, u32)] = &[
+    ("aliceblue", 0xf0f8ff),
+    ("antiquewhite", 0xfaebd7),
+    ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2),
+    ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887),
+    ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50),
+    ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc),
+    ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b),
+    ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f),
+    ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff),
+    ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xadff2f),
+    ("grey", 0x808080),
+    ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4),
+    ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c),
+    ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd),
+    ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0),
+    ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db),
+    ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead),
+    ("navy", 0x000080),
+    ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500),
+    ("orangered", 0xff4500),
+    ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa),
+    ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f),
+    ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460),
+    ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0),
+    ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c),
+    ("teal", 0x008080),
+    ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347),
+    ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3),
+    ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00),
+    ("yellowgreen", 0x9acd32),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_color_notations() {
+        let rgba8 = |text: &str| Color::parse(text).unwrap().to_rgba8();
+        assert_eq!(rgba8("#1e1e2e"), [0x1e, 0x1e, 0x2e, 255]);
+        assert_eq!(rgba8("#ABC"), [0xaa, 0xbb, 0xcc, 255]);
+        assert_eq!(rgba8("#11223380"), [0x11, 0x22, 0x33, 0x80]);
+        assert_eq!(rgba8("rgba(0, 0, 0, 0.4)"), [0, 0, 0, 102]);
+        assert_eq!(rgba8("rgb(100% 50% 0% / 50%)"), [255, 128, 0, 128]);
+        assert_eq!(rgba8("hsl(120, 100%, 25%)"), [0, 128, 0, 255]);
+        assert_eq!(rgba8("hsla(-120deg 100% 50% / 1)"), [0, 0, 255, 255]);
+        assert_eq!(rgba8("Light Slate Gray"), [0x77, 0x88, 0x99, 255]);
+        assert_eq!(rgba8("transparent")[3], 0);
+
+        // The original notation is kept
+        let color = Color::parse(" rgba(0, 0, 0, 0.4) ").unwrap();
+        assert_eq!(color.as_str(), "rgba(0, 0, 0, 0.4)");
+        assert_eq!(color.to_hex(), "#00000066");
+        assert_eq!(Color::from_rgb8(255, 0, 16).as_str(), "#ff0010");
+    }
+
+    #[test]
+    fn test_hsl_round_trip() {
+        let color = Color::parse("#1e1e2e").unwrap();
+        let [h, s, l] = color.to_hsl();
+        assert_eq!(h.round(), 240.0);
+        assert_eq!(Color::from_hsla(h, s, l, 1.0).as_str(), "#1e1e2e");
+        assert_eq!(Color::parse("gray").unwrap().to_hsl()[..2], [0.0, 0.0]);
+        assert_eq!(Color::from_hsla(0.0, 1.0, 0.5, 0.5).as_str(), "#ff000080");
+        assert_eq!(Color::from_rgba8([1, 2, 3, 255]).as_str(), "#010203");
+    }
+
+    #[test]
+    fn test_rgb_hsl_hex_round_trips() {
+        // Every 17th step of each channel, so grays and pure hues are in it
+        for r in (0..=255).step_by(17) {
+            for g in (0..=255).step_by(17) {
+                for b in (0..=255).step_by(17) {
+                    let color = Color::from_rgb8(r, g, b);
+                    let [h, s, l] = color.to_hsl();
+                    assert!((0.0..360.0).contains(&h), "{} has hue {}", color, h);
+                    assert_eq!(Color::from_hsla(h, s, l, 1.0).to_rgba8(), [r, g, b, 255], "{}", color);
+                    assert_eq!(Color::parse(&color.to_hex()).unwrap().to_rgba8(), [r, g, b, 255]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_hsl_edge_hues() {
+        assert_eq!(Color::parse("#ff0000").unwrap().to_hsl(), [0.0, 1.0, 0.5]);
+        // 360 is 0 again, and hues wrap both ways
+        for hue in [0.0, 360.0, 720.0, -360.0] {
+            assert_eq!(Color::from_hsla(hue, 1.0, 0.5, 1.0).as_str(), "#ff0000", "hue {}", hue);
+        }
+        assert_eq!(Color::from_hsla(-120.0, 1.0, 0.5, 1.0).as_str(), "#0000ff");
+        assert_eq!(Color::from_hsla(300.0, 1.0, 0.5, 1.0).as_str(), "#ff00ff");
+        assert!(Color::parse("#ff0001").unwrap().to_hsl()[0] > 359.0);
+    }
+
+    #[test]
+    fn test_zero_saturation_is_gray() {
+        for hue in [0.0, 120.0, 359.0, 360.0] {
+            assert_eq!(Color::from_hsla(hue, 0.0, 0.5, 1.0).as_str(), "#808080", "hue {}", hue);
+        }
+        assert_eq!(Color::from_hsla(200.0, 0.0, 0.0, 1.0).as_str(), "#000000");
+        assert_eq!(Color::from_hsla(200.0, 0.0, 1.0, 1.0).as_str(), "#ffffff");
+        assert_eq!(Color::parse("#808080").unwrap().to_hsl()[..2], [0.0, 0.0]);
+        assert_eq!(Color::parse("white").unwrap().to_hsl(), [0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn test_hex_with_and_without_alpha() {
+        assert_eq!(Color::parse("#0f0").unwrap().to_hex(), "#00ff00");
+        assert_eq!(Color::parse("#0f08").unwrap().to_hex(), "#00ff0088");
+        assert_eq!(Color::parse("#00ff0080").unwrap().to_rgba8(), [0, 255, 0, 128]);
+        // Opaque colors drop the alpha; any other keeps it, even none at all
+        assert_eq!(Color::parse("#00ff00ff").unwrap().to_hex(), "#00ff00");
+        assert_eq!(Color::from_rgba8([0, 255, 0, 0]).as_str(), "#00ff0000");
+        assert_eq!(Color::from_hsla(120.0, 1.0, 0.5, 0.5).as_str(), "#00ff0080");
+        let [h, s, l] = Color::parse("#00ff0080").unwrap().to_hsl();
+        assert_eq!(Color::from_hsla(h, s, l, 128.0 / 255.0).as_str(), "#00ff0080");
+    }
+
+    #[test]
+    fn test_reject_invalid_colors() {
+        for text in ["", "#12", "#ggg", "rgb(300, 0, 0)", "rgb(1, 2)", "hsl(1, 2%, 3%, 4, 5)", "blurple"] {
+            assert!(Color::parse(text).is_err(), "{} parsed", text);
+        }
+        assert_eq!(
+            Color::parse("rgb(300, 0, 0)").unwrap_err(),
+            "`rgb(300, 0, 0)` is not a color: 300 is out of range 0-255"
+        );
+    }
+
+    #[test]
+    fn test_color_serde_keeps_notation() {
+        let color: Color = serde_json::from_str("\"hsl(0, 0%, 0%)\"").unwrap();
+        assert_eq!(serde_json::to_string(&color).unwrap(), "\"hsl(0, 0%, 0%)\"");
+        assert!(serde_json::from_str::<Color>("\"#12\"").is_err());
+    }
+}
@@ -1,23 +1,24 @@
 // Rust config structs for WezTerm configuration
 // Mirrors TypeScript interfaces in src/types/config.ts
 
+use super::Color;
 use serde::{Deserialize, Serialize};
 
 // ============================================================================
 // Color Types
 // ============================================================================
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TabColors {
-    pub bg_color: String,
-    pub fg_color: String,
+    pub bg_color: Color,
+    pub fg_color: Color,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub italic: Option<bool>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TabBarColors {
-    pub background: String,
+    pub background: Color,
     pub active_tab: TabColors,
     pub inactive_tab: TabColors,
     pub inactive_tab_hover: TabColors,
@@ -25,38 +26,38 @@ pub struct TabBarColors {
     pub new_tab_hover: TabColors,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ColorScheme {
     // Core colors
-    pub foreground: String,
-    pub background: String,
-    pub cursor_bg: String,
-    pub cursor_border: String,
-    pub cursor_fg: String,
-    pub selection_bg: String,
-    pub selection_fg: String,
+    pub foreground: Color,
+    pub background: Color,
+    pub cursor_bg: Color,
+    pub cursor_border: Color,
+    pub cursor_fg: Color,
+    pub selection_bg: Color,
+    pub selection_fg: Color,
 
     // ANSI palette (16 colors split into 8 + 8)
-    pub ansi: [String; 8],
-    pub brights: [String; 8],
+    pub ansi: [Color; 8],
+    pub brights: [Color; 8],
 
     // Tab bar colors
     pub tab_bar: TabBarColors,
 
     // Optional colors
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub visual_bell: Option<String>,
+    pub visual_bell: Option<Color>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub scrollbar_thumb: Option<String>,
+    pub scrollbar_thumb: Option<Color>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub split: Option<String>,
+    pub split: Option<Color>,
 }
 
 // ============================================================================
 // Font Types
 // ============================================================================
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub enum FontWeight {
     Thin,
@@ -70,7 +71,7 @@ pub enum FontWeight {
     Black,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub enum FreetypeTarget {
     Normal,
@@ -79,7 +80,7 @@ pub enum FreetypeTarget {
     HorizontalLcd,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FontConfig {
     pub family: String,
     pub size: f32,
@@ -95,7 +96,7 @@ pub struct FontConfig {
 // Window Types
 // ============================================================================
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Padding {
     pub left: f32,
     pub right: f32,
@@ -103,14 +104,14 @@ pub struct Padding {
     pub bottom: f32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct HSB {
     pub hue: f32,
     pub saturation: f32,
     pub brightness: f32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum WindowDecorations {
     #[serde(rename = "FULL")]
     Full,
@@ -124,13 +125,13 @@ pub enum WindowDecorations {
     IntegratedButtonsResize,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum CloseConfirmation {
     AlwaysPrompt,
     NeverPrompt,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct WindowConfig {
     pub window_padding: Padding,
     pub window_background_opacity: f32,
@@ -148,7 +149,7 @@ pub struct WindowConfig {
 // Cursor Types
 // ============================================================================
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum CursorStyle {
     SteadyBlock,
     BlinkingBlock,
@@ -158,7 +159,7 @@ pub enum CursorStyle {
     BlinkingBar,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum EaseFunction {
     Linear,
     EaseIn,
@@ -167,7 +168,7 @@ pub enum EaseFunction {
     Constant,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CursorConfig {
     pub default_cursor_style: CursorStyle,
     pub cursor_blink_rate: u32,
@@ -180,13 +181,13 @@ pub struct CursorConfig {
 // Backdrop Types
 // ============================================================================
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct BackdropConfig {
     pub enabled: bool,
     pub images_dir: String,
     pub images: Vec<String>,
     pub current_index: usize,
-    pub focus_color: String,
+    pub focus_color: Color,
     pub overlay_opacity: f32,
     pub random_on_start: bool,
 }
@@ -195,10 +196,10 @@ pub struct BackdropConfig {
 // Command Palette Types
 // ============================================================================
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CommandPaletteConfig {
-    pub fg_color: String,
-    pub bg_color: String,
+    pub fg_color: Color,
+    pub bg_color: Color,
     pub font_size: f32,
 }
 
@@ -206,7 +207,7 @@ pub struct CommandPaletteConfig {
 // Visual Bell Types
 // ============================================================================
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct VisualBellConfig {
     pub fade_in_duration_ms: u32,
     pub fade_out_duration_ms: u32,
@@ -219,20 +220,20 @@ pub struct VisualBellConfig {
 // General Types
 // ============================================================================
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ExitBehavior {
     Close,
     CloseOnCleanExit,
     Hold,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum AudibleBell {
     SystemBeep,
     Disabled,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct GeneralConfig {
     pub automatically_reload_config: bool,
     pub scrollback_lines: u32,
@@ -250,7 +251,7 @@ pub struct GeneralConfig {
 // ============================================================================
 
 /// A single keybinding configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct KeyBinding {
     pub enabled: bool,
     pub key: String,
@@ -276,7 +277,7 @@ impl KeyBinding {
 }
 
 /// Misc/utility keybindings
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct MiscBindings {
     pub copy_mode: KeyBinding,           // F1 - ActivateCopyMode
     pub command_palette: KeyBinding,     // F2 - ActivateCommandPalette
@@ -291,7 +292,7 @@ pub struct MiscBindings {
 }
 
 /// Copy/paste keybindings
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CopyPasteBindings {
     pub copy: KeyBinding,          // Ctrl+Shift+c - CopyTo Clipboard
     pub paste: KeyBinding,         // Ctrl+Shift+v - PasteFrom Clipboard
@@ -300,7 +301,7 @@ pub struct CopyPasteBindings {
 }
 
 /// Tab-related keybindings
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TabBindings {
     pub spawn_tab: KeyBinding,           // Super+t - SpawnTab DefaultDomain
     pub spawn_tab_wsl: KeyBinding,       // Super_Rev+t - SpawnTab WSL
@@ -316,7 +317,7 @@ pub struct TabBindings {
 }
 
 /// Window-related keybindings
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct WindowBindings {
     pub spawn_window: KeyBinding,   // Super+n - SpawnWindow
     pub shrink_window: KeyBinding,  // Super+- - Shrink window by 50px
@@ -325,7 +326,7 @@ pub struct WindowBindings {
 }
 
 /// Pane-related keybindings
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PaneBindings {
     pub split_vertical: KeyBinding,   // Super+\ - SplitVertical
     pub split_horizontal: KeyBinding, // Super_Rev+\ - SplitHorizontal
@@ -343,7 +344,7 @@ pub struct PaneBindings {
 }
 
 /// Backdrop/background keybindings
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct BackdropBindings {
     pub random: KeyBinding,       // Super+/ - Random backdrop
     pub cycle_back: KeyBinding,   // Super+, - Cycle backdrop back
@@ -353,7 +354,7 @@ pub struct BackdropBindings {
 }
 
 /// Cursor movement keybindings
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CursorBindings {
     pub home: KeyBinding,      // Super+LeftArrow - Send Home
     pub end: KeyBinding,       // Super+RightArrow - Send End
@@ -362,14 +363,14 @@ pub struct CursorBindings {
 }
 
 /// Key table bindings (Leader key activated modes)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct KeyTableBindings {
     pub resize_font_mode: KeyBinding, // Leader+f - Activate resize_font key table
     pub resize_pane_mode: KeyBinding, // Leader+p - Activate resize_pane key table
 }
 
 /// Leader key configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct LeaderKeyConfig {
     pub enabled: bool,
     pub key: String,
@@ -378,21 +379,21 @@ pub struct LeaderKeyConfig {
 }
 
 /// Mouse binding configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct MouseBindings {
     pub ctrl_click_open_link: bool,     // Ctrl+Click opens link
     pub right_click_command_palette: bool, // Right-click opens command palette
 }
 
 /// Custom commands for command palette
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CustomCommands {
     pub settings_tui: bool,  // Add Settings-TUI to command palette
     pub rename_tab: bool,    // Add Rename Tab to command palette (redundant with keybinding but appears in palette)
 }
 
 /// Complete keybindings configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct KeyBindingsConfig {
     pub disable_defaults: bool,
     pub leader: LeaderKeyConfig,
@@ -406,26 +407,83 @@ pub struct KeyBindingsConfig {
     pub key_tables: KeyTableBindings,
     pub mouse: MouseBindings,
     pub custom_commands: CustomCommands,
+    /// `config.keys` entries from an existing file that don't map to a binding
+    /// above, kept verbatim as Lua table constructors
+    #[serde(default)]
+    pub extra_keys: Vec<String>,
+    /// Unmanaged `config.key_tables` fields, kept verbatim (`name = { ... }`)
+    #[serde(default)]
+    pub extra_key_tables: Vec<String>,
+    /// Unmanaged `config.mouse_bindings` entries, kept verbatim
+    #[serde(default)]
+    pub extra_mouse_bindings: Vec<String>,
+}
+
+/// `vec![("group.name", &kb.group.name), ...]` for the listed bindings
+macro_rules! named_bindings {
+    (ref $kb:ident, $($group:ident.$name:ident),* $(,)?) => {
+        vec![$((concat!(stringify!($group), ".", stringify!($name)), &$kb.$group.$name)),*]
+    };
+    (mut $kb:ident, $($group:ident.$name:ident),* $(,)?) => {
+        vec![$((concat!(stringify!($group), ".", stringify!($name)), &mut $kb.$group.$name)),*]
+    };
+}
+
+/// Every binding in `KeyBindingsConfig`, in generated order
+macro_rules! all_bindings {
+    ($borrow:ident $kb:ident) => {
+        named_bindings!($borrow $kb,
+            misc.copy_mode, misc.command_palette, misc.command_palette_alt, misc.show_launcher,
+            misc.show_tab_launcher, misc.show_workspace_launcher, misc.toggle_fullscreen,
+            misc.show_debug_overlay, misc.search, misc.quick_select_url,
+            cursor.home, cursor.end, cursor.delete_line, cursor.newline,
+            copy_paste.copy, copy_paste.paste, copy_paste.copy_simple, copy_paste.paste_simple,
+            tabs.spawn_tab, tabs.spawn_tab_wsl, tabs.close_tab, tabs.prev_tab, tabs.next_tab,
+            tabs.move_tab_back, tabs.move_tab_forward, tabs.rename_tab, tabs.manual_update_title,
+            tabs.reset_title, tabs.toggle_tab_bar,
+            windows.spawn_window, windows.shrink_window, windows.grow_window, windows.maximize_window,
+            backdrops.random, backdrops.cycle_back, backdrops.cycle_forward, backdrops.select,
+            backdrops.toggle_focus,
+            panes.split_vertical, panes.split_horizontal, panes.toggle_zoom, panes.close_pane,
+            panes.nav_up, panes.nav_down, panes.nav_left, panes.nav_right, panes.swap_pane,
+            panes.scroll_up, panes.scroll_down, panes.page_up, panes.page_down,
+            key_tables.resize_font_mode, key_tables.resize_pane_mode,
+        )
+    };
+}
+
+impl KeyBindingsConfig {
+    /// Every key binding with its `group.name`, in the order they are generated
+    pub fn bindings(&self) -> Vec<(&'static str, &KeyBinding)> {
+        let kb = self;
+        all_bindings!(ref kb)
+    }
+
+    /// Mutable access to every key binding, in the same order as `bindings`
+    pub fn bindings_mut(&mut self) -> Vec<(&'static str, &mut KeyBinding)> {
+        let kb = self;
+        all_bindings!(mut kb)
+    }
 }
 
 // ============================================================================
 // GPU Types
 // ============================================================================
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum FrontEnd {
     WebGpu,
     OpenGL,
     Software,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum PowerPreference {
     LowPower,
     HighPerformance,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct GPUConfig {
     pub front_end: FrontEnd,
     pub webgpu_power_preference: PowerPreference,
@@ -436,7 +494,7 @@ pub struct GPUConfig {
 // Composite Types
 // ============================================================================
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct AppearanceConfig {
     /// Use a built-in WezTerm color scheme by name (e.g., "Catppuccin Mocha")
     /// When set, overrides the custom `colors` settings
@@ -458,7 +516,7 @@ pub struct AppearanceConfig {
 // Command Response Types
 // ============================================================================
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SaveResult {
     pub success: bool,
     pub files_written: Vec<String>,
@@ -466,20 +524,128 @@ pub struct SaveResult {
     pub config_dir: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ValidationError {
     pub field: String,
     pub message: String,
     pub value: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ValidationResult {
     pub valid: bool,
     pub errors: Vec<ValidationError>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// How values are read from the config
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportMode {
+    /// Read what is written, without running anything
+    #[default]
+    Static,
+    /// Run the config in a sandboxed Lua VM and read what it produces,
+    /// falling back to `Static` if it can't be run
+    Evaluate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// The file (or part of it) could not be read at all
+    Error,
+    /// A value was understood only partly, or not imported
+    Warning,
+}
+
+/// A problem found while reading the config, pointing at the Lua that caused it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    /// Path of the Lua file; empty when parsing a string
+    pub file: String,
+    /// 1-based line, when the problem has a position
+    pub line: Option<usize>,
+    /// 1-based column, when the problem has a position
+    pub column: Option<usize>,
+    pub severity: Severity,
+    /// Dotted config key the problem concerns (`window_background_image`)
+    pub key: Option<String>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// A diagnostic about a whole file, without a position or key
+    pub fn for_file(file: &std::path::Path, severity: Severity, message: impl Into<String>) -> Self {
+        Self {
+            file: file.to_string_lossy().to_string(),
+            line: None,
+            column: None,
+            severity,
+            key: None,
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    /// `wezterm.lua:12:3: warning: message`, leaving out what isn't known
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut location = self.file.clone();
+        for part in [self.line, self.column].into_iter().flatten() {
+            if !location.is_empty() {
+                location.push(':');
+            }
+            location.push_str(&part.to_string());
+        }
+        if !location.is_empty() {
+            write!(f, "{}: ", location)?;
+        }
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{}: {}", severity, self.message)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnmanagedKind {
+    /// A `config.<key>` setting the settings UI has no field for
+    Setting,
+    /// A `wezterm.on('event', ...)` handler
+    EventHandler,
+    /// A plugin loaded with `wezterm.plugin.require('url')`
+    Plugin,
+    /// A helper function defined in the config
+    Function,
+}
+
+impl UnmanagedKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            UnmanagedKind::Setting => "setting",
+            UnmanagedKind::EventHandler => "event handler",
+            UnmanagedKind::Plugin => "plugin",
+            UnmanagedKind::Function => "function",
+        }
+    }
+}
+
+/// Something in the config the settings UI doesn't manage. Saves leave these
+/// exactly as written.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnmanagedItem {
+    pub kind: UnmanagedKind,
+    /// Config key, event name, plugin URL or function name
+    pub name: String,
+    /// Path of the Lua file; empty when parsing a string
+    pub file: String,
+    /// 1-based line it is defined on
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Platform {
     Windows,
@@ -487,7 +653,7 @@ pub enum Platform {
     Linux,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SystemInfo {
     pub platform: Platform,
     pub config_dir: String,
@@ -495,7 +661,7 @@ pub struct SystemInfo {
     pub wezterm_installed: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ImageInfo {
     pub filename: String,
     pub path: String,
@@ -507,11 +673,16 @@ pub struct ImageInfo {
 // Default Implementations
 // ============================================================================
 
+/// A built-in default color
+fn color(text: &str) -> Color {
+    Color::parse(text).expect("default colors are valid")
+}
+
 impl Default for TabColors {
     fn default() -> Self {
         Self {
-            bg_color: "#313244".to_string(),
-            fg_color: "#cdd6f4".to_string(),
+            bg_color: color("#313244"),
+            fg_color: color("#cdd6f4"),
             italic: None,
         }
     }
@@ -520,30 +691,30 @@ impl Default for TabColors {
 impl Default for TabBarColors {
     fn default() -> Self {
         Self {
-            background: "rgba(0, 0, 0, 0.4)".to_string(),
+            background: color("rgba(0, 0, 0, 0.4)"),
             active_tab: TabColors {
-                bg_color: "#585b70".to_string(),
-                fg_color: "#cdd6f4".to_string(),
+                bg_color: color("#585b70"),
+                fg_color: color("#cdd6f4"),
                 italic: None,
             },
             inactive_tab: TabColors {
-                bg_color: "#313244".to_string(),
-                fg_color: "#bac2de".to_string(),
+                bg_color: color("#313244"),
+                fg_color: color("#bac2de"),
                 italic: None,
             },
             inactive_tab_hover: TabColors {
-                bg_color: "#313244".to_string(),
-                fg_color: "#cdd6f4".to_string(),
+                bg_color: color("#313244"),
+                fg_color: color("#cdd6f4"),
                 italic: None,
             },
             new_tab: TabColors {
-                bg_color: "#1f1f28".to_string(),
-                fg_color: "#cdd6f4".to_string(),
+                bg_color: color("#1f1f28"),
+                fg_color: color("#cdd6f4"),
                 italic: None,
             },
             new_tab_hover: TabColors {
-                bg_color: "#181825".to_string(),
-                fg_color: "#cdd6f4".to_string(),
+                bg_color: color("#181825"),
+                fg_color: color("#cdd6f4"),
                 italic: Some(true),
             },
         }
@@ -553,32 +724,32 @@ impl Default for TabBarColors {
 impl Default for ColorScheme {
     fn default() -> Self {
         Self {
-            foreground: "#cdd6f4".to_string(),
-            background: "#1f1f28".to_string(),
-            cursor_bg: "#f5e0dc".to_string(),
-            cursor_border: "#f5e0dc".to_string(),
-            cursor_fg: "#11111b".to_string(),
-            selection_bg: "#585b70".to_string(),
-            selection_fg: "#cdd6f4".to_string(),
+            foreground: color("#cdd6f4"),
+            background: color("#1f1f28"),
+            cursor_bg: color("#f5e0dc"),
+            cursor_border: color("#f5e0dc"),
+            cursor_fg: color("#11111b"),
+            selection_bg: color("#585b70"),
+            selection_fg: color("#cdd6f4"),
             ansi: [
-                "#0C0C0C".to_string(),
-                "#C50F1F".to_string(),
-                "#13A10E".to_string(),
-                "#C19C00".to_string(),
-                "#0037DA".to_string(),
-                "#881798".to_string(),
-                "#3A96DD".to_string(),
-                "#CCCCCC".to_string(),
+                color("#0C0C0C"),
+                color("#C50F1F"),
+                color("#13A10E"),
+                color("#C19C00"),
+                color("#0037DA"),
+                color("#881798"),
+                color("#3A96DD"),
+                color("#CCCCCC"),
             ],
             brights: [
-                "#767676".to_string(),
-                "#E74856".to_string(),
-                "#16C60C".to_string(),
-                "#F9F1A5".to_string(),
-                "#3B78FF".to_string(),
-                "#B4009E".to_string(),
-                "#61D6D6".to_string(),
-                "#F2F2F2".to_string(),
+                color("#767676"),
+                color("#E74856"),
+                color("#16C60C"),
+                color("#F9F1A5"),
+                color("#3B78FF"),
+                color("#B4009E"),
+                color("#61D6D6"),
+                color("#F2F2F2"),
             ],
             tab_bar: TabBarColors::default(),
             visual_bell: None,
@@ -657,7 +828,7 @@ impl Default for BackdropConfig {
             images_dir: String::new(),
             images: Vec::new(),
             current_index: 0,
-            focus_color: "#1f1f28".to_string(),
+            focus_color: color("#1f1f28"),
             overlay_opacity: 0.96,
             random_on_start: false,
         }
@@ -693,8 +864,8 @@ impl Default for GeneralConfig {
 impl Default for CommandPaletteConfig {
     fn default() -> Self {
         Self {
-            fg_color: "#cdd6f4".to_string(),
-            bg_color: "#1e1e2e".to_string(),
+            fg_color: color("#cdd6f4"),
+            bg_color: color("#1e1e2e"),
             font_size: 14.0,
         }
     }
@@ -865,6 +1036,9 @@ impl Default for KeyBindingsConfig {
             key_tables: KeyTableBindings::default(),
             mouse: MouseBindings::default(),
             custom_commands: CustomCommands::default(),
+            extra_keys: Vec::new(),
+            extra_key_tables: Vec::new(),
+            extra_mouse_bindings: Vec::new(),
         }
     }
 }
@@ -1,5 +1,9 @@
 // Models module - data structures for WezTerm configuration
 
+pub mod choice;
+pub mod color;
 pub mod config;
 
+pub use choice::*;
+pub use color::*;
 pub use config::*;
@@ -0,0 +1,342 @@
+// Settings validation - range, syntax and cross-field checks over AppearanceConfig
+//
+// The config types accept anything their Rust types can hold; these checks
+// catch values WezTerm would reject or misbehave with before they are saved.
+
+use crate::models::{AppearanceConfig, Color, KeyBinding, ValidationError, ValidationResult};
+
+/// Font sizes WezTerm renders sensibly, in points
+pub const FONT_SIZE_RANGE: (f32, f32) = (8.0, 72.0);
+/// `max_fps` and `animation_fps` are a `u8` in WezTerm
+pub const FPS_RANGE: (u32, u32) = (1, 255);
+/// Longest cursor blink interval, in milliseconds; 0 turns blinking off
+pub const MAX_BLINK_RATE: u32 = 10_000;
+
+/// Modifier names WezTerm accepts in `mods`, joined with `|`
+const MODIFIERS: &[&str] = &[
+    "NONE", "SHIFT", "CTRL", "ALT", "OPT", "META", "SUPER", "CMD", "WIN", "LEADER",
+    "LEFT_ALT", "RIGHT_ALT", "LEFT_CTRL", "RIGHT_CTRL", "LEFT_SHIFT", "RIGHT_SHIFT",
+];
+
+/// Named keys WezTerm accepts in `key`; any single character is a key too
+const KEY_NAMES: &[&str] = &[
+    "Space", "Enter", "Tab", "Backspace", "Escape", "Delete", "Insert", "Home",
+    "End", "PageUp", "PageDown", "LeftArrow", "RightArrow", "UpArrow", "DownArrow", "Clear",
+    "Cancel", "Pause", "CapsLock", "NumLock", "ScrollLock", "PrintScreen", "Print", "Help",
+    "Menu", "Applications", "Select", "Execute", "Sleep", "Copy", "Cut", "Paste", "Hyper",
+    "Super", "Meta", "Alt", "Shift", "Control", "LeftShift", "RightShift", "LeftControl",
+    "RightControl", "LeftAlt", "RightAlt", "LeftWindows", "RightWindows", "Multiply", "Add",
+    "Separator", "Subtract", "Decimal", "Divide", "VolumeMute", "VolumeDown", "VolumeUp",
+    "MediaNextTrack", "MediaPrevTrack", "MediaStop", "MediaPlayPause", "BrowserBack",
+    "BrowserForward", "BrowserRefresh", "BrowserStop", "BrowserSearch", "BrowserFavorites",
+    "BrowserHome",
+];
+
+/// Check every setting, returning all problems found (not just the first)
+pub fn validate_config(config: &AppearanceConfig) -> ValidationResult {
+    let mut errors = Vec::new();
+    check_ranges(config, &mut errors);
+    check_colors(config, &mut errors);
+    check_keybindings(config, &mut errors);
+    ValidationResult {
+        valid: errors.is_empty(),
+        errors,
+    }
+}
+
+/// The problems with a single field, e.g. `fonts.size`
+pub fn field_errors<'a>(result: &'a ValidationResult, field: &str) -> Vec<&'a ValidationError> {
+    result
+        .errors
+        .iter()
+        .filter(|e| e.field == field || e.field.starts_with(&format!("{}.", field)))
+        .collect()
+}
+
+/// One line per problem: `field: message`
+pub fn format_errors(result: &ValidationResult) -> String {
+    result
+        .errors
+        .iter()
+        .map(|e| format!("{}: {}", e.field, e.message))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn error(errors: &mut Vec<ValidationError>, field: &str, message: String, value: impl ToString) {
+    errors.push(ValidationError {
+        field: field.to_string(),
+        message,
+        value: value.to_string(),
+    });
+}
+
+fn check_float(errors: &mut Vec<ValidationError>, field: &str, value: f32, (min, max): (f32, f32)) {
+    if !(min..=max).contains(&value) {
+        error(errors, field, format!("must be between {} and {}", min, max), value);
+    }
+}
+
+fn check_int(errors: &mut Vec<ValidationError>, field: &str, value: u32, (min, max): (u32, u32)) {
+    if !(min..=max).contains(&value) {
+        error(errors, field, format!("must be between {} and {}", min, max), value);
+    }
+}
+
+fn check_non_negative(errors: &mut Vec<ValidationError>, field: &str, value: f32) {
+    if !value.is_finite() || value < 0.0 {
+        error(errors, field, "must be 0 or more".to_string(), value);
+    }
+}
+
+fn check_ranges(config: &AppearanceConfig, errors: &mut Vec<ValidationError>) {
+    check_float(errors, "fonts.size", config.fonts.size, FONT_SIZE_RANGE);
+    check_float(errors, "command_palette.font_size", config.command_palette.font_size, FONT_SIZE_RANGE);
+
+    let window = &config.window;
+    check_float(errors, "window.window_background_opacity", window.window_background_opacity, (0.0, 1.0));
+    let padding = &window.window_padding;
+    for (side, value) in [
+        ("left", padding.left),
+        ("right", padding.right),
+        ("top", padding.top),
+        ("bottom", padding.bottom),
+    ] {
+        check_non_negative(errors, &format!("window.window_padding.{}", side), value);
+    }
+    if window.tab_max_width == 0 {
+        error(errors, "window.tab_max_width", "must be at least 1".to_string(), 0);
+    }
+    let hsb = &window.inactive_pane_hsb;
+    for (part, value) in [
+        ("hue", hsb.hue),
+        ("saturation", hsb.saturation),
+        ("brightness", hsb.brightness),
+    ] {
+        check_non_negative(errors, &format!("window.inactive_pane_hsb.{}", part), value);
+    }
+
+    check_int(errors, "cursor.cursor_blink_rate", config.cursor.cursor_blink_rate, (0, MAX_BLINK_RATE));
+    check_int(errors, "cursor.animation_fps", config.cursor.animation_fps, FPS_RANGE);
+    check_int(errors, "gpu.max_fps", config.gpu.max_fps, FPS_RANGE);
+    check_float(errors, "backdrop.overlay_opacity", config.backdrop.overlay_opacity, (0.0, 1.0));
+
+    let general = &config.general;
+    if general.initial_rows == 0 {
+        error(errors, "general.initial_rows", "must be at least 1".to_string(), 0);
+    }
+    if general.initial_cols == 0 {
+        error(errors, "general.initial_cols", "must be at least 1".to_string(), 0);
+    }
+}
+
+/// Every color setting with its field path
+pub fn color_fields(config: &AppearanceConfig) -> Vec<(String, &Color)> {
+    let colors = &config.colors;
+    let mut fields: Vec<(String, &Color)> = vec![
+        ("colors.foreground".to_string(), &colors.foreground),
+        ("colors.background".to_string(), &colors.background),
+        ("colors.cursor_bg".to_string(), &colors.cursor_bg),
+        ("colors.cursor_border".to_string(), &colors.cursor_border),
+        ("colors.cursor_fg".to_string(), &colors.cursor_fg),
+        ("colors.selection_bg".to_string(), &colors.selection_bg),
+        ("colors.selection_fg".to_string(), &colors.selection_fg),
+    ];
+    for (i, color) in colors.ansi.iter().enumerate() {
+        fields.push((format!("colors.ansi.{}", i), color));
+    }
+    for (i, color) in colors.brights.iter().enumerate() {
+        fields.push((format!("colors.brights.{}", i), color));
+    }
+    let tab_bar = &colors.tab_bar;
+    fields.push(("colors.tab_bar.background".to_string(), &tab_bar.background));
+    for (name, tab) in [
+        ("active_tab", &tab_bar.active_tab),
+        ("inactive_tab", &tab_bar.inactive_tab),
+        ("inactive_tab_hover", &tab_bar.inactive_tab_hover),
+        ("new_tab", &tab_bar.new_tab),
+        ("new_tab_hover", &tab_bar.new_tab_hover),
+    ] {
+        fields.push((format!("colors.tab_bar.{}.bg_color", name), &tab.bg_color));
+        fields.push((format!("colors.tab_bar.{}.fg_color", name), &tab.fg_color));
+    }
+    for (name, color) in [
+        ("visual_bell", &colors.visual_bell),
+        ("scrollbar_thumb", &colors.scrollbar_thumb),
+        ("split", &colors.split),
+    ] {
+        if let Some(color) = color {
+            fields.push((format!("colors.{}", name), color));
+        }
+    }
+    fields.push(("backdrop.focus_color".to_string(), &config.backdrop.focus_color));
+    fields.push(("command_palette.fg_color".to_string(), &config.command_palette.fg_color));
+    fields.push(("command_palette.bg_color".to_string(), &config.command_palette.bg_color));
+    fields
+}
+
+/// A `Color` is checked when it is parsed; this re-checks the text it keeps,
+/// which is what gets written to the file
+fn check_colors(config: &AppearanceConfig, errors: &mut Vec<ValidationError>) {
+    for (field, color) in color_fields(config) {
+        if let Err(e) = Color::parse(color.as_str()) {
+            error(errors, &field, e, color.as_str());
+        }
+    }
+}
+
+/// Why `key` isn't a key WezTerm knows, if it isn't
+pub fn check_key(key: &str) -> Result<(), String> {
+    if key.chars().count() == 1 || KEY_NAMES.contains(&key) {
+        return Ok(());
+    }
+    if let Some(n) = key.strip_prefix('F').and_then(|n| n.parse::<u8>().ok()) {
+        if (1..=24).contains(&n) {
+            return Ok(());
+        }
+    }
+    if let Some(n) = key.strip_prefix("Numpad").and_then(|n| n.parse::<u8>().ok()) {
+        if n <= 9 {
+            return Ok(());
+        }
+    }
+    if let Some(rest) = key.strip_prefix("phys:").or_else(|| key.strip_prefix("mapped:")) {
+        return check_key(rest);
+    }
+    if key.strip_prefix("raw:").is_some_and(|code| code.parse::<u32>().is_ok()) {
+        return Ok(());
+    }
+    if key.is_empty() {
+        return Err("no key is set".to_string());
+    }
+    match KEY_NAMES.iter().find(|name| name.eq_ignore_ascii_case(key)) {
+        Some(name) => Err(format!("unknown key `{}`; did you mean `{}`?", key, name)),
+        None => Err(format!("unknown key `{}`", key)),
+    }
+}
+
+/// Why `mods` isn't a valid modifier list, if it isn't
+pub fn check_mods(mods: &str) -> Result<(), String> {
+    let unknown: Vec<&str> = mods
+        .split('|')
+        .map(str::trim)
+        .filter(|m| !m.is_empty() && !MODIFIERS.contains(m))
+        .collect();
+    match unknown.as_slice() {
+        [] => Ok(()),
+        [m] => Err(format!("unknown modifier `{}` (use {})", m, MODIFIERS.join(", "))),
+        _ => Err(format!("unknown modifiers `{}`", unknown.join("`, `"))),
+    }
+}
+
+fn uses_leader(mods: &str) -> bool {
+    mods.split('|').any(|m| m.trim() == "LEADER")
+}
+
+fn check_binding(errors: &mut Vec<ValidationError>, field: &str, key: &str, mods: &str) {
+    if let Err(e) = check_key(key) {
+        error(errors, &format!("{}.key", field), e, key);
+    }
+    if let Err(e) = check_mods(mods) {
+        error(errors, &format!("{}.mods", field), e, mods);
+    }
+}
+
+fn check_keybindings(config: &AppearanceConfig, errors: &mut Vec<ValidationError>) {
+    let kb = &config.keybindings;
+    let leader = &kb.leader;
+    if leader.enabled {
+        check_binding(errors, "keybindings.leader", &leader.key, &leader.mods);
+        if uses_leader(&leader.mods) {
+            error(errors, "keybindings.leader.mods", "the leader can't use LEADER".to_string(), &leader.mods);
+        }
+        if leader.timeout_ms == 0 {
+            error(errors, "keybindings.leader.timeout_ms", "must be at least 1".to_string(), 0);
+        }
+    }
+
+    let enabled = kb.bindings().into_iter().filter(|(_, b)| b.enabled);
+    for (name, KeyBinding { key, mods, .. }) in enabled {
+        let field = format!("keybindings.{}", name);
+        check_binding(errors, &field, key, mods);
+        if !leader.enabled && uses_leader(mods) {
+            error(
+                errors,
+                &format!("{}.mods", field),
+                "uses LEADER, but the leader key is disabled".to_string(),
+                mods,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config_is_valid() {
+        let result = validate_config(&AppearanceConfig::default());
+        assert!(result.valid, "{}", format_errors(&result));
+    }
+
+    #[test]
+    fn test_ranges_and_key_names() {
+        let mut config = AppearanceConfig::default();
+        config.fonts.size = 6.0;
+        config.window.window_background_opacity = 1.5;
+        config.window.window_padding.left = -2.0;
+        config.gpu.max_fps = 0;
+        config.keybindings.misc.copy_mode.key = "enter".to_string();
+        config.keybindings.misc.copy_mode.mods = "CTRL|HYPER".to_string();
+
+        let result = validate_config(&config);
+        assert!(!result.valid);
+        let fields: Vec<&str> = result.errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            [
+                "fonts.size",
+                "window.window_background_opacity",
+                "window.window_padding.left",
+                "gpu.max_fps",
+                "keybindings.misc.copy_mode.key",
+                "keybindings.misc.copy_mode.mods",
+            ]
+        );
+        assert_eq!(result.errors[0].message, "must be between 8 and 72");
+        assert_eq!(result.errors[0].value, "6");
+        assert!(result.errors[4].message.contains("did you mean `Enter`"));
+        assert_eq!(field_errors(&result, "window.window_padding").len(), 1);
+
+        // Disabled bindings aren't written, so they aren't checked
+        config.keybindings.misc.copy_mode.enabled = false;
+        assert_eq!(validate_config(&config).errors.len(), 4);
+
+        for key in ["a", "F12", "phys:Space", "raw:123", "Numpad5", "\\"] {
+            assert_eq!(check_key(key), Ok(()), "{}", key);
+        }
+        assert!(check_key("F25").is_err());
+        assert!(check_key("").is_err());
+    }
+
+    #[test]
+    fn test_leader_bindings_need_the_leader() {
+        let mut config = AppearanceConfig::default();
+        config.keybindings.leader.enabled = false;
+
+        let result = validate_config(&config);
+        let fields: Vec<&str> = result.errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            [
+                "keybindings.key_tables.resize_font_mode.mods",
+                "keybindings.key_tables.resize_pane_mode.mods",
+            ]
+        );
+
+        config.keybindings.key_tables.resize_font_mode.enabled = false;
+        config.keybindings.key_tables.resize_pane_mode.enabled = false;
+        assert!(validate_config(&config).valid);
+    }
+}
@@ -1,8 +1,17 @@
 // App state and main event loop
 
+use crate::backups::{self, RetentionPolicy};
+use crate::commands::{list_backdrop_images, BackdropImage};
 use crate::config;
-use crate::models::AppearanceConfig;
+use crate::conflict::{self, FileStamp};
+use crate::diff::FileDiff;
+use crate::fields::{self, Field, FieldKind};
+use crate::history::{self, History};
+use crate::models::{AppearanceConfig, Color as ConfigColor, Diagnostic, ImportMode, UnmanagedItem, ValidationResult};
 use crate::ui;
+use crate::ui::widgets::{BackupAction, BackupList, ColorPicker, ColorRow, DiffView, EnumPicker};
+use crate::validation::{format_errors, validate_config};
+use crate::watch::ConfigWatcher;
 use anyhow::Result;
 use crossterm::{
     event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers},
@@ -11,6 +20,7 @@ use crossterm::{
 };
 use ratatui::{backend::CrosstermBackend, widgets::ListState, Terminal};
 use std::io;
+use std::path::{Path, PathBuf};
 
 /// Settings panel categories
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -20,8 +30,13 @@ pub enum Panel {
     Fonts,
     Window,
     Cursor,
+    Backdrop,
+    CommandPalette,
+    VisualBell,
+    General,
     Gpu,
     Keybindings,
+    Unmanaged,
 }
 
 impl Panel {
@@ -32,8 +47,13 @@ impl Panel {
             Panel::Fonts,
             Panel::Window,
             Panel::Cursor,
+            Panel::Backdrop,
+            Panel::CommandPalette,
+            Panel::VisualBell,
+            Panel::General,
             Panel::Gpu,
             Panel::Keybindings,
+            Panel::Unmanaged,
         ]
     }
 
@@ -44,8 +64,13 @@ impl Panel {
             Panel::Fonts => "Fonts",
             Panel::Window => "Window",
             Panel::Cursor => "Cursor",
+            Panel::Backdrop => "Backdrop",
+            Panel::CommandPalette => "Palette",
+            Panel::VisualBell => "Bell",
+            Panel::General => "General",
             Panel::Gpu => "GPU",
             Panel::Keybindings => "Commands",
+            Panel::Unmanaged => "Unmanaged",
         }
     }
 
@@ -56,8 +81,13 @@ impl Panel {
             Panel::Fonts => "󰛖",
             Panel::Window => "󰖲",
             Panel::Cursor => "󰇀",
+            Panel::Backdrop => "󰸉",
+            Panel::CommandPalette => "󰘳",
+            Panel::VisualBell => "󰂞",
+            Panel::General => "󰒓",
             Panel::Gpu => "󰢮",
             Panel::Keybindings => "󰌌",
+            Panel::Unmanaged => "󰌾",
         }
     }
 
@@ -68,13 +98,27 @@ impl Panel {
             "fonts" => Some(Panel::Fonts),
             "window" => Some(Panel::Window),
             "cursor" => Some(Panel::Cursor),
+            "backdrop" | "backdrops" => Some(Panel::Backdrop),
+            "palette" | "command_palette" | "command-palette" => Some(Panel::CommandPalette),
+            "bell" | "visual_bell" | "visual-bell" => Some(Panel::VisualBell),
+            "general" => Some(Panel::General),
             "gpu" => Some(Panel::Gpu),
-            "keybindings" | "commands" => Some(Panel::Keybindings),
+            "keybindings" | "keys" | "commands" => Some(Panel::Keybindings),
+            "unmanaged" => Some(Panel::Unmanaged),
             _ => None,
         }
     }
 }
 
+/// A row of the Backdrop panel's image list
+#[derive(Debug, Clone)]
+pub struct BackdropEntry {
+    pub name: String,
+    pub path: String,
+    /// Place in the slideshow order, when the image is in it
+    pub position: Option<usize>,
+}
+
 /// Input mode for the app
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum InputMode {
@@ -86,16 +130,46 @@ pub enum InputMode {
     Help,
     /// Confirmation dialog
     Confirm,
+    /// Showing what couldn't be read from the config files
+    Diagnostics,
+    /// Choosing a value for an enum field
+    Picker,
+    /// Building a value for a color field
+    ColorPicker,
+    /// Reviewing the diff of a save before it is written
+    DiffPreview,
+    /// Browsing, comparing and restoring backups of the config files
+    Backups,
+    /// Choosing what to do about config files edited elsewhere since loading
+    Conflict,
 }
 
 /// Main application state
 pub struct App {
     /// Current config being edited
     pub config: AppearanceConfig,
-    /// Original config (for detecting changes)
+    /// Config as last loaded or saved (for detecting changes)
     original_config: AppearanceConfig,
+    /// The files `original_config` was read from, as they were then
+    stamps: Vec<FileStamp>,
+    /// Files changed on disk since loading, while the conflict prompt is open
+    pub external_changes: Vec<String>,
+    /// Whether the conflict prompt was opened by a save, which goes on once
+    /// it is resolved
+    pub conflict_from_save: bool,
+    /// Notices edits to the config files; `None` when the platform has no
+    /// file notifications, and changes are then only caught on save
+    watcher: Option<ConfigWatcher>,
+    /// A watched file changed and hasn't been looked at yet
+    disk_changed: bool,
+    /// Edits that can be undone and redone
+    history: History,
     /// Path to config directory
     pub config_dir: Option<String>,
+    /// How values are read from the config (`--evaluate` runs it)
+    import_mode: ImportMode,
+    /// Resolved path of the file being edited (wezterm.lua, or settings_ui.lua when installed)
+    pub config_path: String,
     /// Currently selected panel
     pub current_panel: Panel,
     /// Index of selected item in sidebar
@@ -104,10 +178,24 @@ pub struct App {
     pub field_index: usize,
     /// Current input mode
     pub input_mode: InputMode,
-    /// Whether there are unsaved changes
-    pub has_changes: bool,
     /// Status message to display
     pub status_message: Option<String>,
+    /// Problems found while loading the config
+    pub diagnostics: Vec<Diagnostic>,
+    /// Lua in the config the settings don't manage (read-only)
+    pub unmanaged: Vec<UnmanagedItem>,
+    /// Problems with the current settings; saving is blocked while any remain
+    pub validation: ValidationResult,
+    /// Choices for the enum field being picked
+    pub picker: Option<EnumPicker>,
+    /// Sliders and palettes for the color field being edited
+    pub color_picker: Option<ColorPicker>,
+    /// What the save being reviewed would write
+    pub diff_view: Option<DiffView>,
+    /// Quit once the save being reviewed is written
+    quit_after_save: bool,
+    /// Backups of the config files, while the list is open
+    pub backup_list: Option<BackupList>,
     /// Should the app quit
     pub should_quit: bool,
     /// Current input buffer for editing
@@ -132,12 +220,27 @@ pub struct App {
     pub filtered_fonts: Vec<String>,
     /// List state for font scrolling
     pub font_list_state: ListState,
+    /// Images found in the backdrop folder
+    pub backdrop_images: Vec<BackdropImage>,
 }
 
 impl App {
-    pub fn new(config_dir: Option<String>, initial_panel: Option<String>) -> Result<Self> {
-        let config = config::load_config(config_dir.as_deref()).unwrap_or_default();
+    pub fn new(config_dir: Option<String>, initial_panel: Option<String>, import_mode: ImportMode) -> Result<Self> {
+        let (config, diagnostics, unmanaged, stamps) = match config::load_config_with_metadata(config_dir.as_deref(), import_mode) {
+            Ok(loaded) => (loaded.config, loaded.diagnostics, loaded.unmanaged, loaded.stamps),
+            Err(_) => (AppearanceConfig::default(), Vec::new(), Vec::new(), Vec::new()),
+        };
+        let status_message = (!diagnostics.is_empty()).then(|| {
+            format!("Loaded with {} warnings (press ! to view)", diagnostics.len())
+        });
         let original_config = config.clone();
+        let history = History::new(&config);
+        let validation = validate_config(&config);
+        let config_path = match config::settings_module_status(config_dir.as_deref()) {
+            Ok(status) if status.hook_installed => status.module_path,
+            Ok(status) => status.config_path,
+            Err(_) => String::new(),
+        };
 
         let current_panel = initial_panel
             .and_then(|s| Panel::from_str(&s))
@@ -160,16 +263,34 @@ impl App {
         let mut font_list_state = ListState::default();
         font_list_state.select(Some(0));
 
-        Ok(Self {
+        let mut app = Self {
             config,
             original_config,
+            watcher: ConfigWatcher::new().ok().map(|mut watcher| {
+                watcher.watch(&watched_files(&stamps));
+                watcher
+            }),
+            stamps,
+            external_changes: Vec::new(),
+            conflict_from_save: false,
+            disk_changed: false,
+            history,
             config_dir,
+            import_mode,
+            config_path,
             current_panel,
             sidebar_index,
             field_index: 0,
             input_mode: InputMode::Normal,
-            has_changes: false,
-            status_message: None,
+            status_message,
+            diagnostics,
+            unmanaged,
+            validation,
+            picker: None,
+            color_picker: None,
+            diff_view: None,
+            quit_after_save: false,
+            backup_list: None,
             should_quit: false,
             input_buffer: String::new(),
             available_themes,
@@ -182,7 +303,10 @@ impl App {
             font_filter: String::new(),
             filtered_fonts,
             font_list_state,
-        })
+            backdrop_images: Vec::new(),
+        };
+        app.scan_backdrop_folder();
+        Ok(app)
     }
 
     /// Run the main event loop
@@ -220,6 +344,7 @@ impl App {
                     self.handle_key(key.code, key.modifiers);
                 }
             }
+            self.check_disk_changes();
 
             if self.should_quit {
                 break;
@@ -238,6 +363,12 @@ impl App {
             InputMode::Editing => self.handle_editing_mode(key, modifiers),
             InputMode::Help => self.handle_help_mode(key),
             InputMode::Confirm => self.handle_confirm_mode(key),
+            InputMode::Diagnostics => self.handle_diagnostics_mode(key),
+            InputMode::Picker => self.handle_picker_mode(key),
+            InputMode::ColorPicker => self.handle_color_picker_mode(key, modifiers),
+            InputMode::DiffPreview => self.handle_diff_mode(key, modifiers),
+            InputMode::Backups => self.handle_backups_mode(key, modifiers),
+            InputMode::Conflict => self.handle_conflict_mode(key),
         }
     }
 
@@ -277,8 +408,36 @@ impl App {
             }
         }
 
-        // Special handling for Fonts panel font selector (field_index > 5)
-        if self.current_panel == Panel::Fonts && self.field_index > 5 {
+        // Special handling for the Backdrop panel's image list (below its fields)
+        if self.selected_backdrop().is_some() {
+            match key {
+                KeyCode::Char(' ') => {
+                    self.toggle_backdrop();
+                    return;
+                }
+                KeyCode::Char('K') => {
+                    self.move_backdrop(-1);
+                    return;
+                }
+                KeyCode::Char('J') => {
+                    self.move_backdrop(1);
+                    return;
+                }
+                KeyCode::Char('c') => {
+                    self.start_with_backdrop();
+                    return;
+                }
+                _ => {}
+            }
+        }
+        if self.current_panel == Panel::Backdrop && key == KeyCode::Char('r') && modifiers.is_empty() {
+            self.scan_backdrop_folder();
+            self.status_message = Some(format!("Found {} images", self.backdrop_images.len()));
+            return;
+        }
+
+        // Special handling for Fonts panel font selector (below its fields)
+        if self.in_font_selector() {
             match key {
                 KeyCode::Char('/') => {
                     // Enter search mode for fonts
@@ -315,14 +474,14 @@ impl App {
         match key {
             // Quit
             KeyCode::Char('q') => {
-                if self.has_changes {
+                if self.has_changes() {
                     self.input_mode = InputMode::Confirm;
                 } else {
                     self.should_quit = true;
                 }
             }
             KeyCode::Esc => {
-                if self.has_changes {
+                if self.has_changes() {
                     self.input_mode = InputMode::Confirm;
                 } else {
                     self.should_quit = true;
@@ -331,7 +490,15 @@ impl App {
 
             // Save
             KeyCode::Char('s') if modifiers.contains(KeyModifiers::CONTROL) => {
-                self.save_config();
+                self.review_save(false);
+            }
+
+            // Undo / redo
+            KeyCode::Char('u') => {
+                self.undo();
+            }
+            KeyCode::Char('r') if modifiers.contains(KeyModifiers::CONTROL) => {
+                self.redo();
             }
 
             // Help
@@ -339,6 +506,16 @@ impl App {
                 self.input_mode = InputMode::Help;
             }
 
+            // Diagnostics from loading the config
+            KeyCode::Char('!') => {
+                self.input_mode = InputMode::Diagnostics;
+            }
+
+            // Backups of the config files
+            KeyCode::Char('b') => {
+                self.open_backups();
+            }
+
             // Navigation - up
             KeyCode::Char('k') | KeyCode::Up => {
                 self.navigate_up();
@@ -405,7 +582,7 @@ impl App {
         }
 
         // Special handling for font search
-        if self.current_panel == Panel::Fonts && self.field_index > 5 {
+        if self.in_font_selector() {
             match key {
                 KeyCode::Esc => {
                     self.input_mode = InputMode::Normal;
@@ -441,7 +618,6 @@ impl App {
             }
             KeyCode::Enter => {
                 self.apply_edit();
-                self.input_mode = InputMode::Normal;
             }
             KeyCode::Char(c) => {
                 self.input_buffer.push(c);
@@ -462,6 +638,247 @@ impl App {
         }
     }
 
+    fn handle_diagnostics_mode(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('!') => {
+                self.input_mode = InputMode::Normal;
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_picker_mode(&mut self, key: KeyCode) {
+        let Some(picker) = self.picker.as_mut() else {
+            self.input_mode = InputMode::Normal;
+            return;
+        };
+        match key {
+            KeyCode::Char('j') | KeyCode::Down | KeyCode::Tab => picker.next(),
+            KeyCode::Char('k') | KeyCode::Up | KeyCode::BackTab => picker.previous(),
+            KeyCode::Enter => {
+                let choice = picker.choice().cloned();
+                if let (Some(field), Some(choice)) = (self.selected_field(), choice) {
+                    match field.set(&mut self.config, &choice.value) {
+                        Ok(()) => {
+                            self.status_message = Some(format!("{} set to {}", field.label, choice.label));
+                            self.mark_changed();
+                        }
+                        Err(e) => self.status_message = Some(e),
+                    }
+                }
+                self.picker = None;
+                self.input_mode = InputMode::Normal;
+            }
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.picker = None;
+                self.input_mode = InputMode::Normal;
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_color_picker_mode(&mut self, key: KeyCode, modifiers: KeyModifiers) {
+        let Some(picker) = self.color_picker.as_mut() else {
+            self.input_mode = InputMode::Normal;
+            return;
+        };
+        let big = modifiers.contains(KeyModifiers::SHIFT);
+        match key {
+            KeyCode::Char('j') | KeyCode::Down | KeyCode::Tab => picker.next_row(),
+            KeyCode::Char('k') | KeyCode::Up | KeyCode::BackTab => picker.previous_row(),
+            KeyCode::Char('h') | KeyCode::Left => picker.adjust(if big { -10 } else { -1 }),
+            KeyCode::Char('l') | KeyCode::Right => picker.adjust(if big { 10 } else { 1 }),
+            KeyCode::Char('H') => picker.adjust(-10),
+            KeyCode::Char('L') => picker.adjust(10),
+            KeyCode::Char(c) if picker.row == ColorRow::Hex && (c.is_ascii_hexdigit() || c == '#') => {
+                picker.type_hex(c)
+            }
+            KeyCode::Backspace if picker.row == ColorRow::Hex => picker.delete_hex(),
+            KeyCode::Char('u') => picker.undo(),
+            KeyCode::Char('r') => picker.reset(),
+            // Type the value instead, in any notation
+            KeyCode::Char('i') => {
+                self.input_buffer = picker.color.as_str().to_string();
+                self.color_picker = None;
+                self.input_mode = InputMode::Editing;
+            }
+            KeyCode::Enter => {
+                let (changed, color) = (picker.is_changed(), picker.color.clone());
+                if let (true, Some(field)) = (changed, self.selected_field()) {
+                    match field.set(&mut self.config, color.as_str()) {
+                        Ok(()) => {
+                            self.status_message = Some(format!("{} set to {}", field.label, color));
+                            self.mark_changed();
+                        }
+                        Err(e) => self.status_message = Some(e),
+                    }
+                }
+                self.color_picker = None;
+                self.input_mode = InputMode::Normal;
+            }
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.color_picker = None;
+                self.input_mode = InputMode::Normal;
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_diff_mode(&mut self, key: KeyCode, modifiers: KeyModifiers) {
+        let Some(view) = self.diff_view.as_mut() else {
+            self.input_mode = InputMode::Normal;
+            return;
+        };
+        if scroll_diff(view, key, modifiers) {
+            return;
+        }
+        match key {
+            KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => {
+                self.diff_view = None;
+                self.input_mode = InputMode::Normal;
+                if self.save_config() && self.quit_after_save {
+                    self.should_quit = true;
+                }
+            }
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.diff_view = None;
+                self.input_mode = InputMode::Normal;
+                self.status_message = Some("Save cancelled; nothing was written".to_string());
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_backups_mode(&mut self, key: KeyCode, modifiers: KeyModifiers) {
+        let Some(list) = self.backup_list.as_mut() else {
+            self.input_mode = InputMode::Normal;
+            return;
+        };
+
+        if let Some(action) = list.confirm.take() {
+            if matches!(key, KeyCode::Char('y') | KeyCode::Char('Y')) {
+                match action {
+                    BackupAction::Restore => self.restore_selected_backup(),
+                    BackupAction::Prune(_) => self.prune_backups(),
+                }
+            }
+            return;
+        }
+
+        // The diff of the selected backup
+        if let Some(view) = list.diff.as_mut() {
+            if scroll_diff(view, key, modifiers) {
+                return;
+            }
+            match key {
+                KeyCode::Char('r') | KeyCode::Char('y') | KeyCode::Enter => list.confirm = Some(BackupAction::Restore),
+                KeyCode::Esc | KeyCode::Char('q') => list.diff = None,
+                _ => {}
+            }
+            return;
+        }
+
+        match key {
+            KeyCode::Char('j') | KeyCode::Down => list.next(),
+            KeyCode::Char('k') | KeyCode::Up => list.previous(),
+            KeyCode::Enter | KeyCode::Char('d') => {
+                let Some(backup) = list.selected_backup() else {
+                    return;
+                };
+                match backups::diff_backup(backup) {
+                    Ok(diff) if diff.hunks.is_empty() => {
+                        self.status_message = Some(format!("{} matches the current file", backup.name));
+                    }
+                    Ok(diff) => {
+                        list.diff = Some(DiffView::with_prompt(
+                            &[diff],
+                            "Restore preview",
+                            "Restore this backup?",
+                            "r: Restore  q/Esc: Back",
+                        ));
+                    }
+                    Err(e) => self.status_message = Some(format!("Error reading backup: {}", e)),
+                }
+            }
+            KeyCode::Char('r') if list.selected_backup().is_some() => list.confirm = Some(BackupAction::Restore),
+            KeyCode::Char('p') => {
+                let now = chrono::Local::now().naive_local();
+                let pruned = backups::backups_to_prune(&list.backups, &RetentionPolicy::default(), now);
+                if pruned.is_empty() {
+                    self.status_message = Some("Nothing to prune".to_string());
+                } else {
+                    list.confirm = Some(BackupAction::Prune(pruned));
+                }
+            }
+            KeyCode::Char('f') => match backups::use_backup_folder(&list.config_dir) {
+                Ok(moved) => {
+                    list.refresh();
+                    self.status_message = Some(format!(
+                        "Backups now go to {}/ ({} moved)",
+                        backups::BACKUP_FOLDER,
+                        moved.len()
+                    ));
+                }
+                Err(e) => self.status_message = Some(format!("Error moving backups: {}", e)),
+            },
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('b') => {
+                self.backup_list = None;
+                self.input_mode = InputMode::Normal;
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_conflict_mode(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Char('r') => {
+                self.external_changes.clear();
+                self.input_mode = InputMode::Normal;
+                self.status_message = Some(match self.reload_config() {
+                    Ok(()) => "Reloaded the config; your unsaved changes were dropped".to_string(),
+                    Err(e) => format!("Error reloading config: {}", e),
+                });
+            }
+            KeyCode::Char('o') => {
+                // Take the files as they are now as the ones being replaced
+                self.stamps = self.stamps.iter().map(|s| FileStamp::read(Path::new(&s.path))).collect();
+                self.external_changes.clear();
+                self.input_mode = InputMode::Normal;
+                if self.conflict_from_save {
+                    self.review_save(self.quit_after_save);
+                } else {
+                    self.status_message = Some("Keeping your changes; saving will replace the file".to_string());
+                }
+            }
+            KeyCode::Char('m') => {
+                self.external_changes.clear();
+                self.input_mode = InputMode::Normal;
+                if let Some(conflicts) = self.merge_external_changes() {
+                    if self.conflict_from_save && self.validation.valid {
+                        self.review_save(self.quit_after_save);
+                    }
+                    if self.status_message.is_none() {
+                        self.status_message = Some(if conflicts.is_empty() {
+                            "Merged the changes made on disk".to_string()
+                        } else {
+                            format!("Merged; kept your values for {}", conflicts.join(", "))
+                        });
+                    }
+                }
+            }
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('n') => {
+                self.external_changes.clear();
+                self.input_mode = InputMode::Normal;
+                self.status_message = Some(if self.conflict_from_save {
+                    "Save cancelled; nothing was written".to_string()
+                } else {
+                    "The config changed on disk; saving will ask again".to_string()
+                });
+            }
+            _ => {}
+        }
+    }
+
     fn handle_confirm_mode(&mut self, key: KeyCode) {
         match key {
             KeyCode::Char('y') | KeyCode::Char('Y') => {
@@ -471,8 +888,8 @@ impl App {
                 self.input_mode = InputMode::Normal;
             }
             KeyCode::Char('s') | KeyCode::Char('S') => {
-                self.save_config();
-                self.should_quit = true;
+                self.input_mode = InputMode::Normal;
+                self.review_save(true);
             }
             _ => {}
         }
@@ -526,98 +943,355 @@ impl App {
         if self.field_index == 0 {
             // Move from sidebar into panel
             self.field_index = 1;
-        } else if self.current_panel == Panel::Fonts && self.field_index == 1 {
-            // From "Current Font" field, jump to font selector
-            self.field_index = 6; // Enter font selector
-        } else if self.current_panel == Panel::Fonts && self.field_index <= 5 {
-            // Other font settings - enter edit mode for numeric/dropdown values
-            self.input_buffer = self.get_current_field_value();
-            self.input_mode = InputMode::Editing;
-        } else if self.current_panel == Panel::Keybindings {
-            // Toggle keybinding enabled/disabled
-            self.toggle_keybinding();
-        } else {
-            // Start editing the current field
-            self.input_buffer = self.get_current_field_value();
-            self.input_mode = InputMode::Editing;
-        }
-    }
-    
-    /// Toggle a keybinding's enabled state based on current field
-    fn toggle_keybinding(&mut self) {
-        // The keybindings panel has multiple categories
-        // We'll handle the most common toggles first
-        let toggled = match self.field_index {
-            // Custom commands (1-2)
-            1 => {
-                self.config.keybindings.custom_commands.settings_tui = !self.config.keybindings.custom_commands.settings_tui;
-                Some(("Settings-TUI in command palette", self.config.keybindings.custom_commands.settings_tui))
-            }
-            2 => {
-                self.config.keybindings.custom_commands.rename_tab = !self.config.keybindings.custom_commands.rename_tab;
-                Some(("Rename Tab in command palette", self.config.keybindings.custom_commands.rename_tab))
-            }
-            // Mouse bindings (3-4)
-            3 => {
-                self.config.keybindings.mouse.ctrl_click_open_link = !self.config.keybindings.mouse.ctrl_click_open_link;
-                Some(("Ctrl+Click open link", self.config.keybindings.mouse.ctrl_click_open_link))
-            }
-            4 => {
-                self.config.keybindings.mouse.right_click_command_palette = !self.config.keybindings.mouse.right_click_command_palette;
-                Some(("Right-click command palette", self.config.keybindings.mouse.right_click_command_palette))
-            }
-            // Disable defaults (5)
-            5 => {
-                self.config.keybindings.disable_defaults = !self.config.keybindings.disable_defaults;
-                Some(("Disable default keybindings", self.config.keybindings.disable_defaults))
-            }
-            // Leader key (6)
-            6 => {
-                self.config.keybindings.leader.enabled = !self.config.keybindings.leader.enabled;
-                Some(("Leader key", self.config.keybindings.leader.enabled))
+        } else if self.current_panel == Panel::Unmanaged {
+            // Read-only: saves keep these exactly as written
+            self.status_message = Some("Unmanaged Lua is kept as written; edit it in your config file".to_string());
+        } else if self.selected_backdrop().is_some() {
+            self.toggle_backdrop();
+        } else if let Some(field) = self.selected_field() {
+            match field.kind {
+                // The family is picked from the font list
+                _ if field.path == "fonts.family" => {
+                    self.field_index = fields::for_panel(Panel::Fonts).len() + 1;
+                }
+                FieldKind::Bool => self.toggle_field(field),
+                FieldKind::Enum { .. } => {
+                    if let Some((options, current)) = field.options(&self.config) {
+                        self.picker = Some(EnumPicker::new(field.label, options, current));
+                        self.input_mode = InputMode::Picker;
+                    }
+                }
+                FieldKind::Color => match ConfigColor::parse(&field.display(&self.config)) {
+                    Ok(color) => {
+                        self.color_picker = Some(ColorPicker::new(field.label, color, &self.config));
+                        self.input_mode = InputMode::ColorPicker;
+                    }
+                    Err(_) => {
+                        self.input_buffer = field.display(&self.config);
+                        self.input_mode = InputMode::Editing;
+                    }
+                },
+                _ => {
+                    self.input_buffer = field.display(&self.config);
+                    self.input_mode = InputMode::Editing;
+                }
             }
-            _ => None,
-        };
-        
-        if let Some((name, state)) = toggled {
-            let state_str = if state { "enabled" } else { "disabled" };
-            self.status_message = Some(format!("{} {}", name, state_str));
-            self.has_changes = true;
+        }
+    }
+
+    /// The field under the cursor, if the panel has editable fields
+    pub fn selected_field(&self) -> Option<&'static Field> {
+        fields::for_panel(self.current_panel).get(self.field_index.checked_sub(1)?)
+    }
+
+    /// Whether the cursor is in the Fonts panel's font list, below its fields
+    pub fn in_font_selector(&self) -> bool {
+        self.current_panel == Panel::Fonts && self.field_index > fields::for_panel(Panel::Fonts).len()
+    }
+
+    fn toggle_field(&mut self, field: &Field) {
+        match field.toggle(&mut self.config) {
+            Ok(on) => {
+                let state = if on { "enabled" } else { "disabled" };
+                self.status_message = Some(format!("{} {}", field.label, state));
+                self.mark_changed();
+            }
+            Err(e) => self.status_message = Some(e),
         }
     }
 
     fn get_field_count(&self) -> usize {
         match self.current_panel {
             Panel::Themes => self.filtered_themes.len().max(1), // theme list
-            Panel::Colors => 23, // fg, bg, cursor colors, 8 ansi, 8 brights, selection, etc.
-            Panel::Fonts => 5,   // family, size, weight, load_target, render_target
-            Panel::Window => 10, // opacity, padding (4), decorations, tab bar opts
-            Panel::Cursor => 5,  // style, blink_rate, ease_in, ease_out, fps
-            Panel::Gpu => 3,     // front_end, power_pref, max_fps
-            Panel::Keybindings => 6, // settings_tui, rename_tab, mouse (2), disable_defaults, leader
+            Panel::Unmanaged => self.unmanaged.len().max(1),
+            // The image list follows the fields
+            Panel::Backdrop => fields::for_panel(Panel::Backdrop).len() + self.backdrop_entries().len(),
+            panel => fields::for_panel(panel).len(),
         }
     }
 
-    fn get_current_field_value(&self) -> String {
-        // TODO: Implement based on current panel and field index
-        String::new()
-    }
-
+    /// Apply the edited value to the selected field and finish editing. A
+    /// value that isn't valid for the field leaves the edit open.
     fn apply_edit(&mut self) {
-        // TODO: Apply the input_buffer value to the appropriate config field
-        self.has_changes = true;
+        if let Some(field) = self.selected_field() {
+            if let Err(e) = field.set(&mut self.config, &self.input_buffer) {
+                self.status_message = Some(e);
+                return;
+            }
+            self.mark_changed();
+            if field.path == "backdrop.images_dir" {
+                self.scan_backdrop_folder();
+            }
+        }
         self.input_buffer.clear();
+        self.input_mode = InputMode::Normal;
+    }
+
+    /// Record an edit as one undoable step and re-check the settings, so
+    /// problems show next to the fields they concern
+    fn mark_changed(&mut self) {
+        self.history.record(&self.config);
+        self.revalidate();
+    }
+
+    fn revalidate(&mut self) {
+        self.validation = validate_config(&self.config);
+        if !self.validation.valid {
+            self.status_message = Some(self.validation_summary());
+        }
+    }
+
+    /// Whether the settings differ from the ones last loaded or saved
+    pub fn has_changes(&self) -> bool {
+        self.config != self.original_config
+    }
+
+    /// Revert the last edit
+    fn undo(&mut self) {
+        match self.history.undo(&mut self.config) {
+            Ok(Some(changes)) => {
+                self.status_message = Some(format!("Undid change to {}", history::describe(&changes)));
+                self.after_history_step();
+            }
+            Ok(None) => self.status_message = Some("Nothing to undo".to_string()),
+            Err(e) => self.status_message = Some(e),
+        }
+    }
+
+    /// Make the last undone edit again
+    fn redo(&mut self) {
+        match self.history.redo(&mut self.config) {
+            Ok(Some(changes)) => {
+                self.status_message = Some(format!("Redid change to {}", history::describe(&changes)));
+                self.after_history_step();
+            }
+            Ok(None) => self.status_message = Some("Nothing to redo".to_string()),
+            Err(e) => self.status_message = Some(e),
+        }
+    }
+
+    fn after_history_step(&mut self) {
+        // The image list is read from the folder the settings name
+        self.scan_backdrop_folder();
+        self.field_index = self.field_index.min(self.get_field_count());
+        self.revalidate();
+    }
+
+    /// Directory holding the config file, where its backups are kept
+    fn backup_dir(&self) -> Result<PathBuf, String> {
+        let config_file = config::get_wezterm_config_file(self.config_dir.as_deref())?;
+        Ok(config_file.parent().map(Path::to_path_buf).unwrap_or_default())
+    }
+
+    fn open_backups(&mut self) {
+        match self.backup_dir() {
+            Ok(dir) => {
+                self.backup_list = Some(BackupList::new(dir));
+                self.input_mode = InputMode::Backups;
+            }
+            Err(e) => self.status_message = Some(format!("Error finding backups: {}", e)),
+        }
+    }
+
+    /// Put the selected backup back in place and read the config again.
+    /// Unsaved edits are dropped along with their history.
+    fn restore_selected_backup(&mut self) {
+        let Some(list) = self.backup_list.as_mut() else {
+            return;
+        };
+        let Some(backup) = list.selected_backup().cloned() else {
+            return;
+        };
+        list.diff = None;
+        let result = match backups::restore_backup(&backup, &list.config_dir) {
+            Ok(result) => result,
+            Err(e) => {
+                self.status_message = Some(format!("Error restoring backup: {}", e));
+                return;
+            }
+        };
+        list.refresh();
+
+        self.status_message = Some(match self.reload_config() {
+            Err(e) => format!("Restored {}, but reading it failed: {}", backup.name, e),
+            Ok(()) if result.files_written.is_empty() => format!("{} already matches {}", backup.original, backup.name),
+            Ok(()) => format!("Restored {} (backup: {})", backup.name, result.backups_created.join(", ")),
+        });
+    }
+
+    fn prune_backups(&mut self) {
+        let Some(list) = self.backup_list.as_mut() else {
+            return;
+        };
+        let result = backups::prune_backups(&list.config_dir, &RetentionPolicy::default(), false);
+        list.refresh();
+        self.status_message = Some(match result {
+            Ok(pruned) => format!("Deleted {} old backup(s)", pruned.len()),
+            Err(e) => format!("Error pruning backups: {}", e),
+        });
+    }
+
+    /// Read the config from disk again, dropping unsaved edits and their
+    /// history
+    fn reload_config(&mut self) -> Result<(), String> {
+        let loaded = config::load_config_with_metadata(self.config_dir.as_deref(), self.import_mode)
+            .map_err(|e| e.to_string())?;
+        self.config = loaded.config;
+        self.diagnostics = loaded.diagnostics;
+        self.unmanaged = loaded.unmanaged;
+        self.set_stamps(loaded.stamps);
+        self.original_config = self.config.clone();
+        self.history = History::new(&self.config);
+        self.after_history_step();
+        Ok(())
+    }
+
+    /// `N invalid setting(s): field: message; ...` for the status bar
+    fn validation_summary(&self) -> String {
+        format!(
+            "{} invalid setting(s): {}",
+            self.validation.errors.len(),
+            format_errors(&self.validation).replace('\n', "; ")
+        )
+    }
+
+    /// Read the config again and fold the edits made there into the ones
+    /// made here. A setting changed in both places keeps the value set
+    /// here; those settings are returned, or `None` when reading failed.
+    fn merge_external_changes(&mut self) -> Option<Vec<String>> {
+        let merged = match config::merge_external_changes(
+            &self.original_config,
+            &self.config,
+            self.config_dir.as_deref(),
+            self.import_mode,
+        ) {
+            Ok(merged) => merged,
+            Err(e) => {
+                self.status_message = Some(format!("Error merging config: {}", e));
+                return None;
+            }
+        };
+        self.config = merged.config;
+        self.original_config = merged.loaded.config;
+        self.set_stamps(merged.loaded.stamps);
+        self.diagnostics = merged.loaded.diagnostics;
+        self.unmanaged = merged.loaded.unmanaged;
+        // Undoing the merge goes back to the edits as they were
+        self.history.record(&self.config);
+        self.after_history_step();
+        Some(merged.conflicts)
+    }
+
+    /// Pick up edits other programs made to the config files. Without
+    /// unsaved edits here the config is read again; otherwise the user
+    /// chooses what to do. Waits while an edit or popup is open.
+    fn check_disk_changes(&mut self) {
+        if self.watcher.as_mut().is_some_and(ConfigWatcher::poll) {
+            self.disk_changed = true;
+        }
+        if !self.disk_changed || self.input_mode != InputMode::Normal {
+            return;
+        }
+        self.disk_changed = false;
+
+        // Our own saves, and files touched without being changed, don't count
+        let changed = conflict::changed_files(&self.stamps);
+        if changed.is_empty() {
+            return;
+        }
+        if self.has_changes() {
+            self.external_changes = changed;
+            self.conflict_from_save = false;
+            self.input_mode = InputMode::Conflict;
+            return;
+        }
+        let names = changed
+            .iter()
+            .map(|path| Path::new(path).file_name().map_or(path.clone(), |n| n.to_string_lossy().to_string()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        self.status_message = Some(match self.reload_config() {
+            Ok(()) if self.diagnostics.is_empty() => format!("Reloaded: {} changed on disk", names),
+            Ok(()) => format!(
+                "Reloaded: {} changed on disk ({} warnings, press ! to view)",
+                names,
+                self.diagnostics.len()
+            ),
+            Err(e) => format!("{} changed on disk, but reading it failed: {}", names, e),
+        });
+    }
+
+    /// Remember the files the settings were read from, and watch them
+    fn set_stamps(&mut self, stamps: Vec<FileStamp>) {
+        if let Some(watcher) = self.watcher.as_mut() {
+            watcher.watch(&watched_files(&stamps));
+        }
+        self.stamps = stamps;
+    }
+
+    /// Show what a save would write and wait for it to be confirmed. With
+    /// nothing to write there is nothing to confirm. Files edited elsewhere
+    /// since loading are asked about first.
+    fn review_save(&mut self, quit_after: bool) {
+        if !self.validation.valid {
+            self.status_message = Some(format!("Not saved: {}", self.validation_summary()));
+            return;
+        }
+        let changed = conflict::changed_files(&self.stamps);
+        if !changed.is_empty() {
+            self.external_changes = changed;
+            self.conflict_from_save = true;
+            self.quit_after_save = quit_after;
+            self.input_mode = InputMode::Conflict;
+            return;
+        }
+        match config::preview_save(&self.config, self.config_dir.as_deref(), self.import_mode) {
+            Ok(writes) if writes.is_empty() => {
+                self.status_message = Some("No changes to write".to_string());
+                self.original_config = self.config.clone();
+                self.should_quit = quit_after;
+            }
+            Ok(writes) => {
+                let diffs: Vec<FileDiff> = writes
+                    .iter()
+                    .map(|w| FileDiff::new(&w.path.to_string_lossy(), w.existing.as_deref(), &w.content))
+                    .collect();
+                self.diff_view = Some(DiffView::new(&diffs));
+                self.quit_after_save = quit_after;
+                self.input_mode = InputMode::DiffPreview;
+            }
+            Err(e) => self.status_message = Some(format!("Error saving config: {}", e)),
+        }
     }
 
-    fn save_config(&mut self) {
-        match config::save_config(&self.config, self.config_dir.as_deref()) {
-            Ok(_) => {
-                self.has_changes = false;
+    /// Save to disk, returning whether it worked. Invalid settings are
+    /// never saved.
+    fn save_config(&mut self) -> bool {
+        if !self.validation.valid {
+            self.status_message = Some(format!("Not saved: {}", self.validation_summary()));
+            return false;
+        }
+        match config::save_config_if_unchanged(&self.config, self.config_dir.as_deref(), self.import_mode, &self.stamps) {
+            Ok(result) => {
                 self.original_config = self.config.clone();
-                self.status_message = Some("Config saved successfully!".to_string());
+                // Inserted settings move the hand-written Lua down
+                if let Ok(loaded) = config::load_config_with_metadata(self.config_dir.as_deref(), self.import_mode) {
+                    self.unmanaged = loaded.unmanaged;
+                    self.set_stamps(loaded.stamps);
+                }
+                let written = result.files_written.join(", ");
+                self.status_message = Some(if result.files_written.is_empty() {
+                    "No changes to write".to_string()
+                } else if result.backups_created.is_empty() {
+                    format!("Saved {}", written)
+                } else {
+                    format!("Saved {} (backup: {})", written, result.backups_created.join(", "))
+                });
+                true
             }
             Err(e) => {
                 self.status_message = Some(format!("Error saving config: {}", e));
+                false
             }
         }
     }
@@ -639,8 +1313,8 @@ impl App {
     pub fn apply_selected_theme(&mut self) {
         if let Some(theme_name) = self.filtered_themes.get(self.theme_index) {
             self.config.color_scheme = Some(theme_name.clone());
-            self.has_changes = true;
             self.status_message = Some(format!("Theme set to: {}", theme_name));
+            self.mark_changed();
         }
     }
 
@@ -661,8 +1335,126 @@ impl App {
     pub fn apply_selected_font(&mut self) {
         if let Some(font_name) = self.filtered_fonts.get(self.font_index) {
             self.config.fonts.family = font_name.clone();
-            self.has_changes = true;
             self.status_message = Some(format!("Font set to: {}", font_name));
+            self.mark_changed();
+        }
+    }
+
+    /// Read the images in the backdrop folder (`~` is the home directory)
+    pub fn scan_backdrop_folder(&mut self) {
+        let dir = &self.config.backdrop.images_dir;
+        let dir = match (dir.strip_prefix('~'), dirs::home_dir()) {
+            (Some(rest), Some(home)) => format!("{}{}", home.display(), rest),
+            _ => dir.clone(),
+        };
+        self.backdrop_images = if dir.is_empty() {
+            Vec::new()
+        } else {
+            list_backdrop_images(dir).unwrap_or_else(|e| {
+                self.status_message = Some(e);
+                Vec::new()
+            })
+        };
+    }
+
+    /// The image list: the slideshow in order, then the folder's other
+    /// images
+    pub fn backdrop_entries(&self) -> Vec<BackdropEntry> {
+        let images = &self.config.backdrop.images;
+        let name = |path: &str| path.rsplit(['/', '\\']).next().unwrap_or(path).to_string();
+        let mut entries: Vec<BackdropEntry> = images
+            .iter()
+            .enumerate()
+            .map(|(i, path)| BackdropEntry {
+                name: name(path),
+                path: path.clone(),
+                position: Some(i),
+            })
+            .collect();
+        entries.extend(
+            self.backdrop_images
+                .iter()
+                .filter(|image| !images.contains(&image.path))
+                .map(|image| BackdropEntry {
+                    name: image.filename.clone(),
+                    path: image.path.clone(),
+                    position: None,
+                }),
+        );
+        entries
+    }
+
+    /// The image list row under the cursor
+    pub fn selected_backdrop(&self) -> Option<BackdropEntry> {
+        if self.current_panel != Panel::Backdrop {
+            return None;
+        }
+        let row = self.field_index.checked_sub(fields::for_panel(Panel::Backdrop).len() + 1)?;
+        self.backdrop_entries().into_iter().nth(row)
+    }
+
+    /// Put the cursor on the image list row for `path`
+    fn select_backdrop(&mut self, path: &str) {
+        if let Some(row) = self.backdrop_entries().iter().position(|e| e.path == path) {
+            self.field_index = fields::for_panel(Panel::Backdrop).len() + 1 + row;
+        }
+    }
+
+    /// Add the selected image to the end of the slideshow, or take it out
+    fn toggle_backdrop(&mut self) {
+        let Some(entry) = self.selected_backdrop() else {
+            return;
+        };
+        let backdrop = &mut self.config.backdrop;
+        match entry.position {
+            Some(i) => {
+                backdrop.images.remove(i);
+                // Keep the start image the same one where possible
+                if i < backdrop.current_index {
+                    backdrop.current_index -= 1;
+                }
+                backdrop.current_index = backdrop.current_index.min(backdrop.images.len().saturating_sub(1));
+                self.status_message = Some(format!("Removed {} from the backdrops", entry.name));
+            }
+            None => {
+                backdrop.images.push(entry.path.clone());
+                self.status_message = Some(format!("Added {} to the backdrops", entry.name));
+            }
+        }
+        self.select_backdrop(&entry.path);
+        self.mark_changed();
+    }
+
+    /// Move the selected slideshow image `by` places earlier (-1) or later (1)
+    fn move_backdrop(&mut self, by: isize) {
+        let Some(BackdropEntry { path, position: Some(i), .. }) = self.selected_backdrop() else {
+            return;
+        };
+        let backdrop = &mut self.config.backdrop;
+        let Some(j) = i.checked_add_signed(by).filter(|j| *j < backdrop.images.len()) else {
+            return;
+        };
+        backdrop.images.swap(i, j);
+        // The start image moves with its picture
+        if backdrop.current_index == i {
+            backdrop.current_index = j;
+        } else if backdrop.current_index == j {
+            backdrop.current_index = i;
+        }
+        self.select_backdrop(&path);
+        self.mark_changed();
+    }
+
+    /// Show the selected slideshow image first when WezTerm starts
+    fn start_with_backdrop(&mut self) {
+        match self.selected_backdrop() {
+            Some(BackdropEntry { name, position: Some(i), .. }) => {
+                self.config.backdrop.current_index = i;
+                self.status_message = Some(format!("{} is shown on start", name));
+                self.mark_changed();
+            }
+            Some(_) => self.status_message = Some("Add the image to the backdrops first".to_string()),
+            None => {}
         }
     }
 }
@@ -886,3 +1678,24 @@ fn get_common_fonts() -> Vec<String> {
         "Intel One Mono".to_string(),
     ]
 }
+
+/// The files `stamps` describe, for the watcher
+fn watched_files(stamps: &[FileStamp]) -> Vec<PathBuf> {
+    stamps.iter().map(|stamp| PathBuf::from(&stamp.path)).collect()
+}
+
+/// Scroll `view` for a scrolling key, returning whether `key` was one
+fn scroll_diff(view: &mut DiffView, key: KeyCode, modifiers: KeyModifiers) -> bool {
+    match key {
+        KeyCode::Char('j') | KeyCode::Down => view.scroll_by(1),
+        KeyCode::Char('k') | KeyCode::Up => view.scroll_by(-1),
+        KeyCode::PageDown | KeyCode::Char(' ') => view.scroll_by(20),
+        KeyCode::Char('d') if modifiers.contains(KeyModifiers::CONTROL) => view.scroll_by(20),
+        KeyCode::PageUp => view.scroll_by(-20),
+        KeyCode::Char('u') if modifiers.contains(KeyModifiers::CONTROL) => view.scroll_by(-20),
+        KeyCode::Char('n') | KeyCode::Char(']') | KeyCode::Tab => view.next_hunk(),
+        KeyCode::Char('N') | KeyCode::Char('[') | KeyCode::BackTab => view.previous_hunk(),
+        _ => return false,
+    }
+    true
+}
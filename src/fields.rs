@@ -0,0 +1,610 @@
+// Field descriptors - the editable settings in each panel
+//
+// Every panel that edits settings lists its fields here. A field's `path`
+// points into `AppearanceConfig` as it serializes (`fonts.size`,
+// `colors.ansi.3`), which is also the path validation reports problems
+// under, so reading, editing, counting and error display all come from the
+// one descriptor. Adding a setting to a panel means adding a `Field`.
+
+use crate::app::Panel;
+use crate::models::{
+    choice_options, AppearanceConfig, AudibleBell, Choice, ChoiceOption, Color, CursorStyle,
+    EaseFunction, ExitBehavior, FontWeight, FreetypeTarget, FrontEnd, PowerPreference,
+    WindowDecorations,
+};
+use crate::validation::{FONT_SIZE_RANGE, FPS_RANGE, MAX_BLINK_RATE};
+use serde_json::Value;
+
+/// What a field holds, and so how it is edited
+#[derive(Debug, Clone, Copy)]
+pub enum FieldKind {
+    /// A number within `min..=max`; `integer` rejects fractions
+    Number { min: f64, max: f64, integer: bool },
+    /// Toggled with Enter rather than typed
+    Bool,
+    /// One of a `Choice` enum's variants, picked from a list; when `unset`
+    /// is given the field is optional and that label clears it
+    Enum {
+        options: fn() -> Vec<ChoiceOption>,
+        unset: Option<&'static str>,
+    },
+    /// Any notation `Color::parse` accepts; left empty, an optional color
+    /// is unset
+    Color,
+    Text,
+}
+
+/// An editable setting shown in a panel
+#[derive(Debug, Clone, Copy)]
+pub struct Field {
+    pub label: &'static str,
+    /// Dotted path into the serialized `AppearanceConfig`
+    pub path: &'static str,
+    pub kind: FieldKind,
+    /// Heading the panel shows above the first field of each group
+    pub group: &'static str,
+    pub help: &'static str,
+}
+
+const fn number(min: f64, max: f64) -> FieldKind {
+    FieldKind::Number { min, max, integer: false }
+}
+
+const fn integer(min: u32, max: u32) -> FieldKind {
+    FieldKind::Number { min: min as f64, max: max as f64, integer: true }
+}
+
+const fn one_of<T: Choice>() -> FieldKind {
+    FieldKind::Enum { options: choice_options::<T>, unset: None }
+}
+
+/// An enum that can also be left unset, shown as `unset`
+const fn optional<T: Choice>(unset: &'static str) -> FieldKind {
+    FieldKind::Enum { options: choice_options::<T>, unset: Some(unset) }
+}
+
+const fn field(
+    group: &'static str,
+    label: &'static str,
+    path: &'static str,
+    kind: FieldKind,
+    help: &'static str,
+) -> Field {
+    Field { label, path, kind, group, help }
+}
+
+const CORE: &str = "Core Colors";
+const ANSI: &str = "ANSI Colors (0-7)";
+const BRIGHTS: &str = "Bright Colors (8-15)";
+
+static COLORS: &[Field] = &[
+    field(CORE, "Foreground", "colors.foreground", FieldKind::Color, "Default text color"),
+    field(CORE, "Background", "colors.background", FieldKind::Color, "Default background color"),
+    field(CORE, "Cursor BG", "colors.cursor_bg", FieldKind::Color, "Cursor fill color"),
+    field(CORE, "Cursor FG", "colors.cursor_fg", FieldKind::Color, "Text under the cursor"),
+    field(CORE, "Cursor Border", "colors.cursor_border", FieldKind::Color, "Cursor outline color"),
+    field(CORE, "Selection BG", "colors.selection_bg", FieldKind::Color, "Background of selected text"),
+    field(CORE, "Selection FG", "colors.selection_fg", FieldKind::Color, "Selected text color"),
+    field(ANSI, "Black", "colors.ansi.0", FieldKind::Color, "ANSI color 0"),
+    field(ANSI, "Red", "colors.ansi.1", FieldKind::Color, "ANSI color 1"),
+    field(ANSI, "Green", "colors.ansi.2", FieldKind::Color, "ANSI color 2"),
+    field(ANSI, "Yellow", "colors.ansi.3", FieldKind::Color, "ANSI color 3"),
+    field(ANSI, "Blue", "colors.ansi.4", FieldKind::Color, "ANSI color 4"),
+    field(ANSI, "Magenta", "colors.ansi.5", FieldKind::Color, "ANSI color 5"),
+    field(ANSI, "Cyan", "colors.ansi.6", FieldKind::Color, "ANSI color 6"),
+    field(ANSI, "White", "colors.ansi.7", FieldKind::Color, "ANSI color 7"),
+    field(BRIGHTS, "Bright Black", "colors.brights.0", FieldKind::Color, "ANSI color 8"),
+    field(BRIGHTS, "Bright Red", "colors.brights.1", FieldKind::Color, "ANSI color 9"),
+    field(BRIGHTS, "Bright Green", "colors.brights.2", FieldKind::Color, "ANSI color 10"),
+    field(BRIGHTS, "Bright Yellow", "colors.brights.3", FieldKind::Color, "ANSI color 11"),
+    field(BRIGHTS, "Bright Blue", "colors.brights.4", FieldKind::Color, "ANSI color 12"),
+    field(BRIGHTS, "Bright Magenta", "colors.brights.5", FieldKind::Color, "ANSI color 13"),
+    field(BRIGHTS, "Bright Cyan", "colors.brights.6", FieldKind::Color, "ANSI color 14"),
+    field(BRIGHTS, "Bright White", "colors.brights.7", FieldKind::Color, "ANSI color 15"),
+];
+
+static FONTS: &[Field] = &[
+    field("Settings", "Current Font", "fonts.family", FieldKind::Text, "Font family; Enter opens the font list"),
+    field(
+        "Settings",
+        "Font Size",
+        "fonts.size",
+        number(FONT_SIZE_RANGE.0 as f64, FONT_SIZE_RANGE.1 as f64),
+        "Size in points",
+    ),
+    field(
+        "Settings",
+        "Weight",
+        "fonts.weight",
+        optional::<FontWeight>("Default"),
+        "Font weight; Default uses the font's regular weight",
+    ),
+    field(
+        "Settings",
+        "Load Target",
+        "fonts.freetype_load_target",
+        optional::<FreetypeTarget>("Default"),
+        "FreeType hinting used when loading glyphs",
+    ),
+    field(
+        "Settings",
+        "Render Target",
+        "fonts.freetype_render_target",
+        optional::<FreetypeTarget>("Default"),
+        "FreeType hinting used when rendering glyphs",
+    ),
+];
+
+static WINDOW: &[Field] = &[
+    field("", "Opacity", "window.window_background_opacity", number(0.0, 1.0), "0 is fully transparent, 1 is opaque"),
+    field("", "Padding Left", "window.window_padding.left", number(0.0, 1000.0), "Space left of the text, in pixels"),
+    field("", "Padding Right", "window.window_padding.right", number(0.0, 1000.0), "Space right of the text, in pixels"),
+    field("", "Padding Top", "window.window_padding.top", number(0.0, 1000.0), "Space above the text, in pixels"),
+    field("", "Padding Bottom", "window.window_padding.bottom", number(0.0, 1000.0), "Space below the text, in pixels"),
+    field("", "Decorations", "window.window_decorations", one_of::<WindowDecorations>(), "Title bar and border style"),
+    field("", "Tab Bar", "window.enable_tab_bar", FieldKind::Bool, "Show the tab bar"),
+    field("", "Hide Tab If One", "window.hide_tab_bar_if_only_one_tab", FieldKind::Bool, "Hide the tab bar with a single tab"),
+    field("", "Fancy Tab Bar", "window.use_fancy_tab_bar", FieldKind::Bool, "Use the native-looking tab bar"),
+    field("", "Tab Max Width", "window.tab_max_width", integer(1, 1000), "Widest a tab title can be, in cells"),
+];
+
+static CURSOR: &[Field] = &[
+    field("", "Cursor Style", "cursor.default_cursor_style", one_of::<CursorStyle>(), "Shape of the cursor"),
+    field(
+        "",
+        "Blink Rate (ms)",
+        "cursor.cursor_blink_rate",
+        integer(0, MAX_BLINK_RATE),
+        "Blink interval in milliseconds; 0 stops blinking",
+    ),
+    field("", "Blink Ease In", "cursor.cursor_blink_ease_in", one_of::<EaseFunction>(), "How the cursor fades in"),
+    field("", "Blink Ease Out", "cursor.cursor_blink_ease_out", one_of::<EaseFunction>(), "How the cursor fades out"),
+    field("", "Animation FPS", "cursor.animation_fps", integer(FPS_RANGE.0, FPS_RANGE.1), "Frame rate of cursor animations"),
+];
+
+static GPU: &[Field] = &[
+    field("", "Frontend", "gpu.front_end", one_of::<FrontEnd>(), "Renderer WezTerm draws with"),
+    field(
+        "",
+        "Power Preference",
+        "gpu.webgpu_power_preference",
+        one_of::<PowerPreference>(),
+        "Which GPU WebGpu prefers",
+    ),
+    field("", "Max FPS", "gpu.max_fps", integer(FPS_RANGE.0, FPS_RANGE.1), "Highest frame rate WezTerm renders at"),
+];
+
+static BACKDROP: &[Field] = &[
+    field("Settings", "Enabled", "backdrop.enabled", FieldKind::Bool, "Show a background image behind the text"),
+    field(
+        "Settings",
+        "Images Folder",
+        "backdrop.images_dir",
+        FieldKind::Text,
+        "Folder the image list is read from",
+    ),
+    field(
+        "Settings",
+        "Overlay Opacity",
+        "backdrop.overlay_opacity",
+        number(0.0, 1.0),
+        "How much the background color covers the image; 1 hides it",
+    ),
+    field("Settings", "Focus Color", "backdrop.focus_color", FieldKind::Color, "Background shown while the image is toggled off"),
+    field("Settings", "Random On Start", "backdrop.random_on_start", FieldKind::Bool, "Start with a random image from the list"),
+];
+
+static COMMAND_PALETTE: &[Field] = &[
+    field("", "Foreground", "command_palette.fg_color", FieldKind::Color, "Text color of the command palette"),
+    field("", "Background", "command_palette.bg_color", FieldKind::Color, "Background of the command palette"),
+    field(
+        "",
+        "Font Size",
+        "command_palette.font_size",
+        number(FONT_SIZE_RANGE.0 as f64, FONT_SIZE_RANGE.1 as f64),
+        "Size of the command palette's text, in points",
+    ),
+];
+
+static VISUAL_BELL: &[Field] = &[
+    field(
+        "",
+        "Fade In (ms)",
+        "visual_bell.fade_in_duration_ms",
+        integer(0, MAX_BLINK_RATE),
+        "How long the flash takes to appear; 0 with fade out 0 turns it off",
+    ),
+    field(
+        "",
+        "Fade Out (ms)",
+        "visual_bell.fade_out_duration_ms",
+        integer(0, MAX_BLINK_RATE),
+        "How long the flash takes to disappear",
+    ),
+    field("", "Fade In Ease", "visual_bell.fade_in_function", one_of::<EaseFunction>(), "How the flash fades in"),
+    field("", "Fade Out Ease", "visual_bell.fade_out_function", one_of::<EaseFunction>(), "How the flash fades out"),
+    field(
+        "",
+        "Target",
+        "visual_bell.target",
+        FieldKind::Enum { options: bell_targets, unset: None },
+        "What flashes when the bell rings",
+    ),
+    field("", "Flash Color", "colors.visual_bell", FieldKind::Color, "Color of the flash; empty uses the foreground"),
+];
+
+static GENERAL: &[Field] = &[
+    field(
+        "",
+        "Auto Reload",
+        "general.automatically_reload_config",
+        FieldKind::Bool,
+        "Reload the config when its files change",
+    ),
+    field(
+        "",
+        "Scrollback Lines",
+        "general.scrollback_lines",
+        integer(0, 999_999_999),
+        "Lines of history kept per pane",
+    ),
+    field("", "Initial Rows", "general.initial_rows", integer(1, 1000), "Height of new windows, in lines"),
+    field("", "Initial Cols", "general.initial_cols", integer(1, 1000), "Width of new windows, in columns"),
+    field("", "Exit Behavior", "general.exit_behavior", one_of::<ExitBehavior>(), "What a pane does when its program exits"),
+    field("", "Audible Bell", "general.audible_bell", one_of::<AudibleBell>(), "Sound played when the bell rings"),
+    field("", "Scroll Bar", "general.enable_scroll_bar", FieldKind::Bool, "Show a scroll bar on the right"),
+    field(
+        "",
+        "Last Tab On Close",
+        "general.switch_to_last_active_tab_when_closing_tab",
+        FieldKind::Bool,
+        "Closing a tab goes back to the one used before it",
+    ),
+    field(
+        "",
+        "Resize With Font",
+        "general.adjust_window_size_when_changing_font_size",
+        FieldKind::Bool,
+        "Grow or shrink the window when the font size changes",
+    ),
+];
+
+/// Values of `visual_bell.target`, which is kept as a string
+fn bell_targets() -> Vec<ChoiceOption> {
+    vec![
+        ChoiceOption {
+            value: "BackgroundColor".to_string(),
+            label: "Background",
+            description: "Flash the whole pane background",
+        },
+        ChoiceOption {
+            value: "CursorColor".to_string(),
+            label: "Cursor",
+            description: "Flash only the cursor",
+        },
+    ]
+}
+
+static KEYBINDINGS: &[Field] = &[
+    field(
+        "Custom Command Palette Entries",
+        "Settings-TUI",
+        "keybindings.custom_commands.settings_tui",
+        FieldKind::Bool,
+        "Opens settings TUI from command palette",
+    ),
+    field(
+        "Custom Command Palette Entries",
+        "Rename Tab",
+        "keybindings.custom_commands.rename_tab",
+        FieldKind::Bool,
+        "Opens rename prompt from command palette",
+    ),
+    field(
+        "Mouse Bindings",
+        "Ctrl+Click Open Link",
+        "keybindings.mouse.ctrl_click_open_link",
+        FieldKind::Bool,
+        "Ctrl+Click opens URLs under cursor",
+    ),
+    field(
+        "Mouse Bindings",
+        "Right-Click Cmd Palette",
+        "keybindings.mouse.right_click_command_palette",
+        FieldKind::Bool,
+        "Right-click opens command palette",
+    ),
+    field(
+        "General Settings",
+        "Disable Default Keys",
+        "keybindings.disable_defaults",
+        FieldKind::Bool,
+        "Disable WezTerm's default keybindings",
+    ),
+    field(
+        "General Settings",
+        "Leader Key",
+        "keybindings.leader.enabled",
+        FieldKind::Bool,
+        "Prefix key for the key table bindings",
+    ),
+];
+
+/// The editable fields of a panel, in display order; field index `n` in
+/// the panel is `for_panel(panel)[n - 1]`
+pub fn for_panel(panel: Panel) -> &'static [Field] {
+    match panel {
+        Panel::Colors => COLORS,
+        Panel::Fonts => FONTS,
+        Panel::Window => WINDOW,
+        Panel::Cursor => CURSOR,
+        Panel::Gpu => GPU,
+        Panel::Backdrop => BACKDROP,
+        Panel::CommandPalette => COMMAND_PALETTE,
+        Panel::VisualBell => VISUAL_BELL,
+        Panel::General => GENERAL,
+        Panel::Keybindings => KEYBINDINGS,
+        Panel::Themes | Panel::Unmanaged => &[],
+    }
+}
+
+impl Field {
+    /// The current value as shown in the panel
+    pub fn display(&self, config: &AppearanceConfig) -> String {
+        match (self.kind, self.value(config)) {
+            (FieldKind::Enum { unset: Some(unset), .. }, Value::Null) => unset.to_string(),
+            (FieldKind::Enum { options, .. }, Value::String(value)) => options()
+                .into_iter()
+                .find(|o| o.value == value)
+                .map_or(value, |o| o.label.to_string()),
+            (FieldKind::Bool, Value::Bool(on)) => if on { "Yes" } else { "No" }.to_string(),
+            // `12`, not `12.0`
+            (FieldKind::Number { .. }, Value::Number(n)) => n.as_f64().unwrap_or_default().to_string(),
+            (_, Value::String(text)) => text,
+            (_, Value::Null) => String::new(),
+            (_, value) => value.to_string(),
+        }
+    }
+
+    /// Whether a `Bool` field is on
+    pub fn is_on(&self, config: &AppearanceConfig) -> bool {
+        self.value(config) == Value::Bool(true)
+    }
+
+    /// The choices of an `Enum` field, the unset one first (with an empty
+    /// `value`), and the index of the current one
+    pub fn options(&self, config: &AppearanceConfig) -> Option<(Vec<ChoiceOption>, usize)> {
+        let FieldKind::Enum { options, unset } = self.kind else {
+            return None;
+        };
+        let mut all: Vec<ChoiceOption> = unset
+            .map(|label| ChoiceOption {
+                value: String::new(),
+                label,
+                description: "Leave unset and use WezTerm's default",
+            })
+            .into_iter()
+            .collect();
+        all.extend(options());
+        let current = match self.value(config) {
+            Value::String(value) => value,
+            _ => String::new(),
+        };
+        let index = all.iter().position(|o| o.value == current).unwrap_or(0);
+        Some((all, index))
+    }
+
+    /// Parse `input` for this field and store it in `config`. On error the
+    /// config is left as it was.
+    pub fn set(&self, config: &mut AppearanceConfig, input: &str) -> Result<(), String> {
+        let new = self.parse(input.trim())?;
+        let unset = new.is_null();
+        let mut json = to_json(config);
+        let slot = slot(&mut json, self.path).ok_or_else(|| format!("{} can't be edited", self.label))?;
+        *slot = new;
+        *config = serde_json::from_value(json).map_err(|e| match unset {
+            true => format!("{} can't be left empty", self.label),
+            false => format!("{}: {}", self.label, e),
+        })?;
+        Ok(())
+    }
+
+    /// Flip a `Bool` field, returning its new state
+    pub fn toggle(&self, config: &mut AppearanceConfig) -> Result<bool, String> {
+        let on = !self.is_on(config);
+        self.set(config, if on { "true" } else { "false" })?;
+        Ok(on)
+    }
+
+    fn value(&self, config: &AppearanceConfig) -> Value {
+        let pointer = format!("/{}", self.path.replace('.', "/"));
+        to_json(config).pointer(&pointer).cloned().unwrap_or(Value::Null)
+    }
+
+    fn parse(&self, input: &str) -> Result<Value, String> {
+        match self.kind {
+            FieldKind::Number { min, max, integer } => {
+                let n: f64 = input
+                    .parse()
+                    .ok()
+                    .filter(|n: &f64| n.is_finite())
+                    .ok_or_else(|| format!("{} must be a number", self.label))?;
+                if integer && n.fract() != 0.0 {
+                    return Err(format!("{} must be a whole number", self.label));
+                }
+                if !(min..=max).contains(&n) {
+                    return Err(format!("{} must be between {} and {}", self.label, min, max));
+                }
+                Ok(if integer { Value::from(n as i64) } else { Value::from(n) })
+            }
+            FieldKind::Bool => match input.to_ascii_lowercase().as_str() {
+                "true" | "yes" | "on" => Ok(Value::Bool(true)),
+                "false" | "no" | "off" => Ok(Value::Bool(false)),
+                _ => Err(format!("{} must be yes or no", self.label)),
+            },
+            FieldKind::Enum { options, unset } => {
+                let options = options();
+                let matches = |o: &&ChoiceOption| {
+                    o.value.eq_ignore_ascii_case(input) || o.label.eq_ignore_ascii_case(input)
+                };
+                if let Some(option) = options.iter().find(matches) {
+                    return Ok(Value::from(option.value.as_str()));
+                }
+                match unset {
+                    Some(unset) if input.is_empty() || unset.eq_ignore_ascii_case(input) => Ok(Value::Null),
+                    _ => {
+                        let labels: Vec<&str> = options.iter().map(|o| o.label).collect();
+                        Err(format!("{} must be one of: {}", self.label, labels.join(", ")))
+                    }
+                }
+            }
+            FieldKind::Color if input.is_empty() => Ok(Value::Null),
+            FieldKind::Color => Color::parse(input).map(|color| Value::from(color.as_str())),
+            FieldKind::Text => Ok(Value::from(input)),
+        }
+    }
+}
+
+/// `config` as JSON; going through text keeps `f32` values as written
+/// (`0.9`, not `0.8999999761581421`)
+pub fn to_json(config: &AppearanceConfig) -> Value {
+    serde_json::to_string(config)
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+/// The value at a dotted path, adding a missing object key (an unset
+/// optional field) on the way
+pub fn slot<'a>(mut value: &'a mut Value, path: &str) -> Option<&'a mut Value> {
+    for key in path.split('.') {
+        value = match value {
+            Value::Object(map) => map.entry(key).or_insert(Value::Null),
+            Value::Array(items) => items.get_mut(key.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+    Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The field editing `path`, whichever panel shows it
+    fn field_at(path: &str) -> Field {
+        Panel::all()
+            .iter()
+            .flat_map(|panel| for_panel(*panel))
+            .find(|field| field.path == path)
+            .copied()
+            .unwrap_or_else(|| panic!("no field for {}", path))
+    }
+
+    /// Set `path` from `input`, returning what the panel then shows
+    fn set(config: &mut AppearanceConfig, path: &str, input: &str) -> Result<String, String> {
+        let field = field_at(path);
+        field.set(config, input)?;
+        Ok(field.display(config))
+    }
+
+    #[test]
+    fn test_number_fields() {
+        let mut config = AppearanceConfig::default();
+        assert_eq!(set(&mut config, "fonts.size", " 16 "), Ok("16".to_string()));
+        assert_eq!(set(&mut config, "fonts.size", "13.5"), Ok("13.5".to_string()));
+        assert_eq!(set(&mut config, "window.tab_max_width", "30"), Ok("30".to_string()));
+
+        for input in ["", "big", "NaN", "inf"] {
+            assert_eq!(set(&mut config, "fonts.size", input), Err("Font Size must be a number".to_string()));
+        }
+        let err = set(&mut config, "fonts.size", "1000").unwrap_err();
+        assert!(err.starts_with("Font Size must be between"), "{}", err);
+        assert_eq!(
+            set(&mut config, "window.tab_max_width", "30.5"),
+            Err("Tab Max Width must be a whole number".to_string())
+        );
+        // A rejected value leaves the setting alone
+        assert_eq!(config.fonts.size, 13.5);
+        assert_eq!(config.window.tab_max_width, 30);
+    }
+
+    #[test]
+    fn test_bool_fields() {
+        let mut config = AppearanceConfig::default();
+        for (input, shown) in [("yes", "Yes"), ("OFF", "No"), ("true", "Yes"), ("no", "No")] {
+            assert_eq!(set(&mut config, "window.enable_tab_bar", input), Ok(shown.to_string()));
+        }
+        assert_eq!(
+            set(&mut config, "window.enable_tab_bar", "maybe"),
+            Err("Tab Bar must be yes or no".to_string())
+        );
+
+        let field = field_at("window.enable_tab_bar");
+        assert_eq!(field.toggle(&mut config), Ok(true));
+        assert!(field.is_on(&config));
+        assert_eq!(field.toggle(&mut config), Ok(false));
+        assert!(!config.window.enable_tab_bar);
+    }
+
+    #[test]
+    fn test_enum_fields() {
+        let mut config = AppearanceConfig::default();
+        let field = field_at("window.window_decorations");
+        let (options, _) = field.options(&config).unwrap();
+        let option = options.last().unwrap();
+
+        // By value or label, in any case
+        assert_eq!(set(&mut config, field.path, &option.value.to_lowercase()), Ok(option.label.to_string()));
+        assert_eq!(set(&mut config, field.path, option.label), Ok(option.label.to_string()));
+        assert_eq!(field.options(&config).unwrap().1, options.len() - 1);
+        let err = set(&mut config, field.path, "Floating").unwrap_err();
+        assert!(err.starts_with("Decorations must be one of: "), "{}", err);
+        // Only optional enums can be cleared
+        assert!(set(&mut config, field.path, "").is_err());
+
+        // Optional ones list the unset choice first
+        let weight = field_at("fonts.weight");
+        assert_eq!(set(&mut config, weight.path, "bold"), Ok("Bold".to_string()));
+        assert_eq!(set(&mut config, weight.path, "default"), Ok("Default".to_string()));
+        assert_eq!(config.fonts.weight, None);
+        assert_eq!(set(&mut config, weight.path, "Bold"), Ok("Bold".to_string()));
+        assert_eq!(set(&mut config, weight.path, ""), Ok("Default".to_string()));
+        assert_eq!(weight.options(&config).unwrap().0[0].label, "Default");
+    }
+
+    #[test]
+    fn test_color_and_text_fields() {
+        let mut config = AppearanceConfig::default();
+        assert_eq!(set(&mut config, "colors.foreground", "#FF8800"), Ok("#FF8800".to_string()));
+        assert_eq!(set(&mut config, "colors.foreground", "rgb(0, 128, 255)"), Ok("rgb(0, 128, 255)".to_string()));
+        assert!(set(&mut config, "colors.foreground", "#12345").is_err());
+        assert_eq!(
+            set(&mut config, "colors.foreground", ""),
+            Err("Foreground can't be left empty".to_string())
+        );
+        assert_eq!(config.colors.foreground, "rgb(0, 128, 255)");
+
+        // An optional color is unset when left empty
+        assert_eq!(set(&mut config, "colors.visual_bell", "red"), Ok("red".to_string()));
+        assert_eq!(set(&mut config, "colors.visual_bell", ""), Ok(String::new()));
+        assert_eq!(config.colors.visual_bell, None);
+
+        assert_eq!(set(&mut config, "fonts.family", "  Iosevka Term "), Ok("Iosevka Term".to_string()));
+    }
+
+    #[test]
+    fn test_display_round_trips() {
+        let mut config = AppearanceConfig::default();
+        config.fonts.weight = Some(FontWeight::Bold);
+        config.window.window_background_opacity = 0.9;
+        for field in Panel::all().iter().flat_map(|panel| for_panel(*panel)) {
+            let before = config.clone();
+            let shown = field.display(&config);
+            field
+                .set(&mut config, &shown)
+                .unwrap_or_else(|e| panic!("{} shown as {:?}: {}", field.path, shown, e));
+            assert_eq!(config, before, "{} shown as {:?}", field.path, shown);
+        }
+    }
+}
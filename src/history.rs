@@ -0,0 +1,220 @@
+// Edit history - undo and redo for the TUI
+//
+// Each edit is recorded as the settings it changed, with the value before
+// and after, found by comparing the config as JSON before and after the
+// edit. Paths are the dotted ones the field descriptors use (`fonts.size`,
+// `colors.ansi.3`), so one step holds a single field just as well as the
+// dozen colors a theme or bulk edit changes at once.
+
+use crate::fields::{slot, to_json};
+use crate::models::AppearanceConfig;
+use serde_json::Value;
+
+/// Steps kept for undo; older ones are dropped
+const MAX_STEPS: usize = 100;
+
+/// One setting changed by an edit
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    /// Dotted path into the serialized `AppearanceConfig`
+    pub path: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+/// Undo and redo stacks; each step is the changes one edit made
+#[derive(Debug, Clone)]
+pub struct History {
+    undo: Vec<Vec<Change>>,
+    redo: Vec<Vec<Change>>,
+    /// The config as of the last step, to compare the next edit against
+    current: Value,
+}
+
+impl History {
+    pub fn new(config: &AppearanceConfig) -> Self {
+        Self {
+            undo: Vec::new(),
+            redo: Vec::new(),
+            current: to_json(config),
+        }
+    }
+
+    /// Record everything that changed since the last step as one step, and
+    /// forget what could be redone. Returns whether anything changed.
+    pub fn record(&mut self, config: &AppearanceConfig) -> bool {
+        let now = to_json(config);
+        let mut changes = Vec::new();
+        diff("", &self.current, &now, &mut changes);
+        self.current = now;
+        if changes.is_empty() {
+            return false;
+        }
+        self.undo.push(changes);
+        if self.undo.len() > MAX_STEPS {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+        true
+    }
+
+    /// Put back the settings the last step changed. Returns the step, or
+    /// `None` when there is nothing to undo.
+    pub fn undo(&mut self, config: &mut AppearanceConfig) -> Result<Option<Vec<Change>>, String> {
+        let Some(changes) = self.undo.pop() else {
+            return Ok(None);
+        };
+        if let Err(e) = self.apply(config, &changes, |change| &change.old) {
+            self.undo.push(changes);
+            return Err(e);
+        }
+        self.redo.push(changes.clone());
+        Ok(Some(changes))
+    }
+
+    /// Make the last undone step again. Returns the step, or `None` when
+    /// there is nothing to redo.
+    pub fn redo(&mut self, config: &mut AppearanceConfig) -> Result<Option<Vec<Change>>, String> {
+        let Some(changes) = self.redo.pop() else {
+            return Ok(None);
+        };
+        if let Err(e) = self.apply(config, &changes, |change| &change.new) {
+            self.redo.push(changes);
+            return Err(e);
+        }
+        self.undo.push(changes.clone());
+        Ok(Some(changes))
+    }
+
+    fn apply(
+        &mut self,
+        config: &mut AppearanceConfig,
+        changes: &[Change],
+        value: fn(&Change) -> &Value,
+    ) -> Result<(), String> {
+        let mut json = to_json(config);
+        for change in changes {
+            let slot = slot(&mut json, &change.path).ok_or_else(|| format!("{} can't be restored", change.path))?;
+            *slot = value(change).clone();
+        }
+        *config = serde_json::from_value(json).map_err(|e| format!("Couldn't restore settings: {}", e))?;
+        self.current = to_json(config);
+        Ok(())
+    }
+}
+
+/// What a step changed, for the status bar (`fonts.size`, `16 settings`)
+pub fn describe(changes: &[Change]) -> String {
+    match changes {
+        [change] => change.path.clone(),
+        _ => format!("{} settings", changes.len()),
+    }
+}
+
+/// The leaf values that differ between `old` and `new`; objects and
+/// same-length arrays are compared member by member, anything else whole
+fn diff(path: &str, old: &Value, new: &Value, changes: &mut Vec<Change>) {
+    let join = |key: &str| if path.is_empty() { key.to_string() } else { format!("{}.{}", path, key) };
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            let added = new.keys().filter(|key| !old.contains_key(*key));
+            for key in old.keys().chain(added) {
+                let (before, after) = (old.get(key).unwrap_or(&Value::Null), new.get(key).unwrap_or(&Value::Null));
+                diff(&join(key), before, after, changes);
+            }
+        }
+        (Value::Array(old), Value::Array(new)) if old.len() == new.len() => {
+            for (i, (before, after)) in old.iter().zip(new).enumerate() {
+                diff(&join(&i.to_string()), before, after, changes);
+            }
+        }
+        _ if old != new => changes.push(Change {
+            path: path.to_string(),
+            old: old.clone(),
+            new: new.clone(),
+        }),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Color;
+
+    #[test]
+    fn test_undo_redo_round_trip() {
+        let original = AppearanceConfig::default();
+        let mut config = original.clone();
+        let mut history = History::new(&config);
+
+        config.fonts.size = 16.0;
+        assert!(history.record(&config));
+        // Nothing changed since the last step
+        assert!(!history.record(&config));
+        let edited = config.clone();
+
+        let step = history.undo(&mut config).unwrap().unwrap();
+        assert_eq!(describe(&step), "fonts.size");
+        assert_eq!(config, original);
+        assert_eq!(history.undo(&mut config).unwrap(), None);
+
+        history.redo(&mut config).unwrap().unwrap();
+        assert_eq!(config, edited);
+        assert_eq!(history.redo(&mut config).unwrap(), None);
+    }
+
+    #[test]
+    fn test_steps_hold_nested_paths() {
+        let original = AppearanceConfig::default();
+        let mut config = original.clone();
+        let mut history = History::new(&config);
+
+        config.colors.ansi[3] = Color::parse("#112233").unwrap();
+        config.colors.tab_bar.active_tab.bg_color = Color::parse("#445566").unwrap();
+        history.record(&config);
+        let edited = config.clone();
+
+        let step = history.undo(&mut config).unwrap().unwrap();
+        let paths: Vec<&str> = step.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(paths, ["colors.ansi.3", "colors.tab_bar.active_tab.bg_color"]);
+        assert_eq!(describe(&step), "2 settings");
+        assert_eq!(config, original);
+
+        history.redo(&mut config).unwrap();
+        assert_eq!(config, edited);
+    }
+
+    #[test]
+    fn test_oldest_steps_are_dropped() {
+        let mut config = AppearanceConfig::default();
+        let mut history = History::new(&config);
+        for step in 1..=MAX_STEPS + 5 {
+            config.general.scrollback_lines = step as u32;
+            history.record(&config);
+        }
+
+        let mut undone = 0;
+        while history.undo(&mut config).unwrap().is_some() {
+            undone += 1;
+        }
+        assert_eq!(undone, MAX_STEPS);
+        // Back to the oldest step kept, not the start
+        assert_eq!(config.general.scrollback_lines, 5);
+    }
+
+    #[test]
+    fn test_new_edit_clears_redo() {
+        let mut config = AppearanceConfig::default();
+        let mut history = History::new(&config);
+
+        config.fonts.size = 16.0;
+        history.record(&config);
+        history.undo(&mut config).unwrap();
+
+        config.window.window_background_opacity = 0.8;
+        assert!(history.record(&config));
+        assert_eq!(history.redo(&mut config).unwrap(), None);
+        assert_eq!(config.fonts.size, AppearanceConfig::default().fonts.size);
+    }
+}
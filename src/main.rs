@@ -1,22 +1,28 @@
 // WezTerm Settings TUI - Main entry point
 
 mod app;
+mod fields;
+mod history;
 mod ui;
 mod update;
+mod watch;
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
-use wezterm_settings_gui_lib::{config, models};
+use std::io::IsTerminal;
+use std::path::Path;
+use wezterm_settings_gui_lib::{backups, commands, config, conflict, diff, models, validation};
+use wezterm_settings_gui_lib::models::ImportMode;
 
 #[derive(Parser, Debug)]
 #[command(name = "wezterm-settings-tui")]
 #[command(author, version, about = "A TUI for managing WezTerm configuration")]
 struct Args {
-    /// Jump directly to a settings panel (colors, fonts, window, cursor, gpu, keys)
+    /// Jump directly to a settings panel (colors, fonts, window, cursor, backdrop, palette, bell, general, gpu, keys)
     #[arg(value_name = "PANEL")]
     panel: Option<String>,
 
-    /// Path to WezTerm config directory
+    /// Path to WezTerm config directory (or a .lua config file); overrides WEZTERM_CONFIG_FILE
     #[arg(short, long, value_name = "DIR")]
     config_dir: Option<String>,
 
@@ -27,6 +33,11 @@ struct Args {
     /// Import config from JSON file
     #[arg(long, value_name = "FILE")]
     import: Option<String>,
+
+    /// Read values by running wezterm.lua in a sandboxed Lua VM, so loops,
+    /// helper functions and conditionals are followed
+    #[arg(long, global = true)]
+    evaluate: bool,
     
     #[command(subcommand)]
     command: Option<Commands>,
@@ -39,44 +50,322 @@ enum Commands {
     
     /// Update to the latest version
     Update,
+
+    /// Move managed settings into settings_ui.lua and load it from wezterm.lua
+    InstallModule,
+
+    /// Remove the settings_ui.lua hook, writing its settings back into wezterm.lua
+    UninstallModule,
+
+    /// Show whether wezterm.lua loads settings_ui.lua
+    ModuleStatus,
+
+    /// List what couldn't be read from the config, with file and line
+    Diagnostics {
+        /// Print the diagnostics as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Check the settings for out-of-range values, bad key names and conflicts
+    Validate {
+        /// Print the result as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show what saving would change in the config files as a unified diff;
+    /// exits with 1 when there are differences
+    Diff {
+        /// Diff saving the settings in this JSON file (as written by --export)
+        /// instead of the config as it is read
+        #[arg(value_name = "JSON")]
+        settings: Option<String>,
+    },
+
+    /// List, compare, restore and prune backups of the config files
+    Backups {
+        #[command(subcommand)]
+        command: BackupCommands,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum BackupCommands {
+    /// List backups, newest first
+    List {
+        /// Print the backups as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show what restoring a backup would change
+    Diff {
+        /// Backup path, file name, or number from `backups list`
+        backup: String,
+    },
+
+    /// Put a backup back in place, backing up the current file first
+    Restore {
+        /// Backup path, file name, or number from `backups list`
+        backup: String,
+    },
+
+    /// Delete old backups; without options keeps the newest 10 of each file
+    /// and everything from the last 30 days
+    Prune {
+        /// Keep this many of the newest backups of each file
+        #[arg(long, value_name = "N")]
+        keep: Option<usize>,
+
+        /// Keep every backup from the last DAYS days
+        #[arg(long, value_name = "DAYS")]
+        days: Option<u32>,
+
+        /// List what would be deleted without deleting it
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Keep backups in a .backups/ folder, moving the existing ones there
+    UseFolder,
 }
 
 fn main() -> Result<()> {
     let args = Args::parse();
+    let mode = if args.evaluate { ImportMode::Evaluate } else { ImportMode::Static };
 
     // Handle subcommands first
     if let Some(cmd) = args.command {
         return match cmd {
             Commands::CheckUpdate => update::print_update_status(),
             Commands::Update => update::run_update(),
+            Commands::InstallModule => install_module(&args.config_dir, mode),
+            Commands::UninstallModule => uninstall_module(&args.config_dir, mode),
+            Commands::ModuleStatus => print_module_status(&args.config_dir),
+            Commands::Diagnostics { json } => print_diagnostics(&args.config_dir, mode, json),
+            Commands::Validate { json } => validate_config(&args.config_dir, mode, json),
+            Commands::Diff { settings } => print_diff(&args.config_dir, mode, settings.as_deref()),
+            Commands::Backups { command } => manage_backups(&args.config_dir, command),
         };
     }
 
     // Handle non-interactive modes
     if args.export {
-        return export_config(&args.config_dir);
+        return export_config(&args.config_dir, mode);
     }
 
     if let Some(import_path) = &args.import {
-        return import_config(import_path, &args.config_dir);
+        return import_config(import_path, &args.config_dir, mode);
     }
 
     // Run the TUI
-    let mut app = app::App::new(args.config_dir, args.panel)?;
+    let mut app = app::App::new(args.config_dir, args.panel, mode)?;
     app.run()
 }
 
-fn export_config(config_dir: &Option<String>) -> Result<()> {
-    let config = config::load_config(config_dir.as_deref())?;
+fn export_config(config_dir: &Option<String>, mode: ImportMode) -> Result<()> {
+    let config = config::load_config(config_dir.as_deref(), mode)?;
     let json = serde_json::to_string_pretty(&config)?;
     println!("{}", json);
     Ok(())
 }
 
-fn import_config(import_path: &str, config_dir: &Option<String>) -> Result<()> {
+fn import_config(import_path: &str, config_dir: &Option<String>, mode: ImportMode) -> Result<()> {
     let contents = std::fs::read_to_string(import_path)?;
     let config: models::AppearanceConfig = serde_json::from_str(&contents)?;
-    config::save_config(&config, config_dir.as_deref())?;
-    println!("Config imported successfully");
+    let result = config::save_config(&config, config_dir.as_deref(), mode)?;
+    print_save_result(&result);
+    if result.files_written.is_empty() {
+        println!("Config already up to date");
+    } else {
+        println!("Config imported successfully");
+    }
+    Ok(())
+}
+
+fn install_module(config_dir: &Option<String>, mode: ImportMode) -> Result<()> {
+    let config = config::load_config(config_dir.as_deref(), mode)?;
+    let result = config::install_settings_module(&config, config_dir.as_deref())?;
+    print_save_result(&result);
+    let status = config::settings_module_status(config_dir.as_deref())?;
+    println!("{} now loads {}", status.config_path, status.module_path);
+    Ok(())
+}
+
+fn uninstall_module(config_dir: &Option<String>, mode: ImportMode) -> Result<()> {
+    let config = config::load_config(config_dir.as_deref(), mode)?;
+    let result = config::uninstall_settings_module(&config, config_dir.as_deref())?;
+    print_save_result(&result);
+    println!("Settings module removed");
+    Ok(())
+}
+
+fn print_module_status(config_dir: &Option<String>) -> Result<()> {
+    let status = config::settings_module_status(config_dir.as_deref())?;
+    let state = match (status.hook_installed, status.module_exists) {
+        (true, true) => "installed",
+        (true, false) => "hook present, module missing",
+        (false, true) => "module present, not loaded",
+        (false, false) => "not installed",
+    };
+    println!("Settings module: {}", state);
+    println!("  config: {}", status.config_path);
+    println!("  module: {}", status.module_path);
+    Ok(())
+}
+
+fn print_diagnostics(config_dir: &Option<String>, mode: ImportMode, json: bool) -> Result<()> {
+    let loaded = config::load_config_with_metadata(config_dir.as_deref(), mode)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&loaded.diagnostics)?);
+    } else if loaded.diagnostics.is_empty() {
+        println!("No problems found in {}", loaded.config_path);
+    } else {
+        for diagnostic in &loaded.diagnostics {
+            println!("{}", diagnostic);
+        }
+    }
+    Ok(())
+}
+
+/// Exits with an error when any setting is invalid, so it can gate scripts
+fn validate_config(config_dir: &Option<String>, mode: ImportMode, json: bool) -> Result<()> {
+    let config = config::load_config(config_dir.as_deref(), mode)?;
+    let result = validation::validate_config(&config);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&result)?);
+    } else {
+        for error in &result.errors {
+            println!("{}: {} (got {})", error.field, error.message, error.value);
+        }
+    }
+    if !result.valid {
+        anyhow::bail!("{} setting(s) are not valid", result.errors.len());
+    }
+    if !json {
+        println!("All settings are valid");
+    }
+    Ok(())
+}
+
+/// Exits with 1 when saving would change a file, so dotfile checks can
+/// catch a config that has drifted from its settings
+fn print_diff(config_dir: &Option<String>, mode: ImportMode, settings: Option<&str>) -> Result<()> {
+    let config = match settings {
+        Some(path) => serde_json::from_str(&std::fs::read_to_string(path)?)?,
+        None => config::load_config(config_dir.as_deref(), mode)?,
+    };
+    let result = validation::validate_config(&config);
+    if !result.valid {
+        anyhow::bail!("Settings are not valid, so they can't be saved:\n{}", validation::format_errors(&result));
+    }
+
+    let writes = config::preview_save(&config, config_dir.as_deref(), mode)?;
+    if writes.is_empty() {
+        eprintln!("No differences");
+        return Ok(());
+    }
+    for write in &writes {
+        print_unified(&diff::FileDiff::new(&write.path.to_string_lossy(), write.existing.as_deref(), &write.content));
+    }
+    std::process::exit(1);
+}
+
+/// Print a diff, colored when stdout is a terminal and NO_COLOR isn't set
+fn print_unified(diff: &diff::FileDiff) {
+    let color = std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+    for line in diff.to_unified().lines() {
+        let code = match line.as_bytes().first() {
+            _ if !color => None,
+            _ if line.starts_with("---") || line.starts_with("+++") => Some("1"),
+            Some(b'@') => Some("36"),
+            Some(b'-') => Some("31"),
+            Some(b'+') => Some("32"),
+            _ => None,
+        };
+        match code {
+            Some(code) => println!("\x1b[{}m{}\x1b[0m", code, line),
+            None => println!("{}", line),
+        }
+    }
+}
+
+fn manage_backups(config_dir: &Option<String>, command: BackupCommands) -> Result<()> {
+    let config_file = config::get_wezterm_config_file(config_dir.as_deref()).map_err(anyhow::Error::msg)?;
+    let dir = config_file.parent().map(Path::to_path_buf).unwrap_or_default();
+
+    match command {
+        BackupCommands::List { json } => {
+            let list = backups::list_backups(&dir);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&list)?);
+            } else if list.is_empty() {
+                println!("No backups in {}", dir.display());
+            } else {
+                for (i, backup) in list.iter().enumerate() {
+                    let path = Path::new(&backup.path);
+                    println!(
+                        "{:>3}  {}  {:>9}  {}",
+                        i + 1,
+                        backup.created,
+                        backups::format_size(backup.size),
+                        path.strip_prefix(&dir).unwrap_or(path).display()
+                    );
+                }
+            }
+        }
+        BackupCommands::Diff { backup } => {
+            let backup = backups::find_backup(&dir, &backup)?;
+            let diff = backups::diff_backup(&backup)?;
+            if diff.hunks.is_empty() {
+                eprintln!("No differences");
+            } else {
+                print_unified(&diff);
+            }
+        }
+        BackupCommands::Restore { backup } => {
+            let backup = backups::find_backup(&dir, &backup)?;
+            let result = backups::restore_backup(&backup, &dir)?;
+            print_save_result(&result);
+            if result.files_written.is_empty() {
+                println!("{} already matches {}", backup.original, backup.name);
+            } else {
+                println!("Restored {}", backup.name);
+            }
+        }
+        BackupCommands::Prune { keep, days, dry_run } => {
+            let policy = if keep.is_none() && days.is_none() {
+                backups::RetentionPolicy::default()
+            } else {
+                backups::RetentionPolicy { keep_last: keep, keep_days: days }
+            };
+            let pruned = backups::prune_backups(&dir, &policy, dry_run)?;
+            for backup in &pruned {
+                println!("{} {}", if dry_run { "Would remove" } else { "Removed" }, backup.path);
+            }
+            if pruned.is_empty() {
+                println!("Nothing to prune");
+            }
+        }
+        BackupCommands::UseFolder => {
+            for path in backups::use_backup_folder(&dir)? {
+                println!("Moved {}", path.display());
+            }
+            println!(
+                "Backups now go to {}",
+                dir.join(backups::BACKUP_FOLDER).display()
+            );
+        }
+    }
     Ok(())
 }
+
+fn print_save_result(result: &models::SaveResult) {
+    for file in &result.files_written {
+        println!("Wrote {}", file);
+    }
+    for backup in &result.backups_created {
+        println!("Backed up previous config to {}", backup);
+    }
+}
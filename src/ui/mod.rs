@@ -2,14 +2,15 @@
 
 mod layout;
 mod panels;
-mod widgets;
+pub mod widgets;
 
 use crate::app::{App, InputMode, Panel};
+use crate::models::Severity;
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Clear, List, ListItem, Paragraph},
+    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
     Frame,
 };
 
@@ -32,6 +33,28 @@ pub fn draw(f: &mut Frame, app: &mut App) {
     match app.input_mode {
         InputMode::Help => draw_help_overlay(f, app),
         InputMode::Confirm => draw_confirm_dialog(f, app),
+        InputMode::Conflict => draw_conflict_dialog(f, app),
+        InputMode::Diagnostics => draw_diagnostics_overlay(f, app),
+        InputMode::Picker => {
+            if let Some(picker) = &app.picker {
+                widgets::draw_enum_picker(f, picker);
+            }
+        }
+        InputMode::ColorPicker => {
+            if let Some(picker) = &app.color_picker {
+                widgets::draw_color_picker(f, picker);
+            }
+        }
+        InputMode::DiffPreview => {
+            if let Some(view) = &app.diff_view {
+                widgets::draw_diff_view(f, view);
+            }
+        }
+        InputMode::Backups => {
+            if let Some(list) = &app.backup_list {
+                widgets::draw_backup_list(f, list, app.has_changes());
+            }
+        }
         _ => {}
     }
 }
@@ -39,10 +62,14 @@ pub fn draw(f: &mut Frame, app: &mut App) {
 fn draw_title_bar(f: &mut Frame, area: Rect, app: &mut App) {
     let icon = "󰖲"; // WezTerm-like terminal icon
     let title = " WezTerm Settings";
-    let modified = if app.has_changes { "  [modified]" } else { "" };
+    let modified = if app.has_changes() { "  [modified]" } else { "" };
+    let warnings = match app.diagnostics.len() {
+        0 => String::new(),
+        n => format!("!: {} warnings  ", n),
+    };
     
     let save_hint = "Ctrl+S: Save  ?: Help";
-    let used_len = icon.len() + title.len() + modified.len() + save_hint.len() + 2;
+    let used_len = icon.len() + title.len() + modified.len() + warnings.len() + save_hint.len() + 2;
     let padding = (area.width as usize).saturating_sub(used_len);
     
     let title_line = Line::from(vec![
@@ -50,6 +77,7 @@ fn draw_title_bar(f: &mut Frame, area: Rect, app: &mut App) {
         Span::styled(title, Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
         Span::styled(modified, Style::default().fg(Color::Yellow)),
         Span::raw(" ".repeat(padding.max(1))),
+        Span::styled(warnings, Style::default().fg(Color::Yellow)),
         Span::styled(save_hint, Style::default().fg(Color::DarkGray)),
     ]);
 
@@ -150,8 +178,13 @@ fn draw_panel(f: &mut Frame, area: Rect, app: &mut App) {
         Panel::Fonts => panels::fonts::draw(f, inner, app),
         Panel::Window => panels::window::draw(f, inner, app),
         Panel::Cursor => panels::cursor::draw(f, inner, app),
+        Panel::Backdrop => panels::backdrop::draw(f, inner, app),
+        Panel::CommandPalette => panels::command_palette::draw(f, inner, app),
+        Panel::VisualBell => panels::visual_bell::draw(f, inner, app),
+        Panel::General => panels::general::draw(f, inner, app),
         Panel::Gpu => panels::gpu::draw(f, inner, app),
         Panel::Keybindings => panels::keybindings::draw(f, inner, app),
+        Panel::Unmanaged => panels::unmanaged::draw(f, inner, app),
     }
 }
 
@@ -161,9 +194,15 @@ fn draw_status_bar(f: &mut Frame, area: Rect, app: &mut App) {
         InputMode::Editing => ("EDIT", Color::Green),
         InputMode::Help => ("HELP", Color::Yellow),
         InputMode::Confirm => ("CONFIRM", Color::Red),
+        InputMode::Diagnostics => ("ISSUES", Color::Yellow),
+        InputMode::Picker => ("PICK", Color::Magenta),
+        InputMode::ColorPicker => ("COLOR", Color::Magenta),
+        InputMode::DiffPreview => ("DIFF", Color::Yellow),
+        InputMode::Backups => ("BACKUPS", Color::Cyan),
+        InputMode::Conflict => ("CHANGED", Color::Red),
     };
 
-    let status = if let Some(ref msg) = app.status_message {
+    let mut status = if let Some(ref msg) = app.status_message {
         vec![
             Span::styled(
                 format!(" {} ", mode_indicator.0),
@@ -176,14 +215,33 @@ fn draw_status_bar(f: &mut Frame, area: Rect, app: &mut App) {
         let hints = match app.input_mode {
             InputMode::Normal => {
                 if app.current_panel == Panel::Themes && app.field_index > 0 {
-                    "j/k: Browse  /: Search  Enter: Apply  h: Back  q: Quit"
+                    "j/k: Browse  /: Search  Enter: Apply  h: Back  q: Quit".to_string()
+                } else if app.selected_backdrop().is_some() {
+                    "Enter: Add/Remove  J/K: Reorder  c: Show first  r: Rescan  h: Back".to_string()
+                } else if let Some(field) = app.selected_field() {
+                    format!("{}  Enter: Edit  ?: Help", field.help)
                 } else {
-                    "j/k: Navigate  l/Enter: Select  Tab: Next  ?: Help  q: Quit"
+                    "j/k: Navigate  l/Enter: Select  Tab: Next  ?: Help  q: Quit".to_string()
                 }
             }
-            InputMode::Editing => "Enter: Apply  Esc: Cancel",
-            InputMode::Help => "Press any key to close",
-            InputMode::Confirm => "y: Quit  n: Cancel  s: Save & Quit",
+            InputMode::Editing => match app.selected_field() {
+                Some(field) => format!("{}  Enter: Apply  Esc: Cancel", field.help),
+                _ => "Enter: Apply  Esc: Cancel".to_string(),
+            },
+            InputMode::Help => "Press any key to close".to_string(),
+            InputMode::Confirm => "y: Quit  n: Cancel  s: Save & Quit".to_string(),
+            InputMode::Conflict if app.conflict_from_save => "r: Reload  o: Overwrite  m: Merge  Esc: Cancel".to_string(),
+            InputMode::Conflict => "r: Reload  o: Keep mine  m: Merge  Esc: Decide later".to_string(),
+            InputMode::Diagnostics => "Esc: Close".to_string(),
+            InputMode::Picker => "j/k: Choose  Enter: Apply  Esc: Cancel".to_string(),
+            InputMode::DiffPreview => "j/k: Scroll  n/N: Next/prev hunk  y: Save  q/Esc: Abort".to_string(),
+            InputMode::Backups => match &app.backup_list {
+                Some(list) if list.diff.is_some() => "j/k: Scroll  n/N: Next/prev hunk  r: Restore  q/Esc: Back".to_string(),
+                _ => "j/k: Choose  Enter: Diff  r: Restore  p: Prune  f: Use .backups/  Esc: Close".to_string(),
+            },
+            InputMode::ColorPicker => {
+                "j/k: Row  h/l: Adjust  H/L: x10  u: Undo  r: Reset  i: Type  Enter: Apply  Esc: Cancel".to_string()
+            }
         };
         vec![
             Span::styled(
@@ -195,12 +253,32 @@ fn draw_status_bar(f: &mut Frame, area: Rect, app: &mut App) {
         ]
     };
 
+    // Right-aligned path of the config file being edited
+    let used: usize = status.iter().map(|s| s.width()).sum();
+    let available = (area.width as usize).saturating_sub(used + 2);
+    if available > 8 && !app.config_path.is_empty() {
+        let path = truncate_left(&app.config_path, available);
+        status.push(Span::raw(" ".repeat(available + 1 - path.chars().count())));
+        status.push(Span::styled(path, Style::default().fg(Color::DarkGray)));
+    }
+
     f.render_widget(
         Paragraph::new(Line::from(status)).style(Style::default().bg(Color::Rgb(30, 30, 46))),
         area,
     );
 }
 
+/// Shorten a string to `max` chars, keeping the end (e.g. the file name of a path)
+fn truncate_left(s: &str, max: usize) -> String {
+    let len = s.chars().count();
+    if len <= max {
+        s.to_string()
+    } else {
+        let tail: String = s.chars().skip(len - max + 1).collect();
+        format!("…{}", tail)
+    }
+}
+
 fn draw_help_overlay(f: &mut Frame, _app: &App) {
     let area = centered_rect(55, 75, f.area());
     
@@ -239,6 +317,10 @@ fn draw_help_overlay(f: &mut Frame, _app: &App) {
             Span::styled("  Actions", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
         ]),
         Line::from(Span::styled("  ─────────────────────────────", Style::default().fg(Color::DarkGray))),
+        Line::from(vec![
+            Span::styled("  u / Ctrl+R ", Style::default().fg(Color::Yellow)),
+            Span::styled("Undo / redo the last edit", Style::default().fg(Color::White)),
+        ]),
         Line::from(vec![
             Span::styled("  Ctrl+S     ", Style::default().fg(Color::Yellow)),
             Span::styled("Save config", Style::default().fg(Color::White)),
@@ -247,6 +329,14 @@ fn draw_help_overlay(f: &mut Frame, _app: &App) {
             Span::styled("  ?          ", Style::default().fg(Color::Yellow)),
             Span::styled("Toggle help", Style::default().fg(Color::White)),
         ]),
+        Line::from(vec![
+            Span::styled("  !          ", Style::default().fg(Color::Yellow)),
+            Span::styled("Show config warnings", Style::default().fg(Color::White)),
+        ]),
+        Line::from(vec![
+            Span::styled("  b          ", Style::default().fg(Color::Yellow)),
+            Span::styled("Browse, diff and restore backups", Style::default().fg(Color::White)),
+        ]),
         Line::from(vec![
             Span::styled("  q / Esc    ", Style::default().fg(Color::Yellow)),
             Span::styled("Quit", Style::default().fg(Color::White)),
@@ -277,6 +367,18 @@ fn draw_help_overlay(f: &mut Frame, _app: &App) {
             Span::styled("  Esc        ", Style::default().fg(Color::Yellow)),
             Span::styled("Cancel edit", Style::default().fg(Color::White)),
         ]),
+        Line::from(vec![
+            Span::styled("  j / k      ", Style::default().fg(Color::Yellow)),
+            Span::styled("Move through a list of choices", Style::default().fg(Color::White)),
+        ]),
+        Line::from(vec![
+            Span::styled("  h / l      ", Style::default().fg(Color::Yellow)),
+            Span::styled("Move a color slider (H / L by 10)", Style::default().fg(Color::White)),
+        ]),
+        Line::from(vec![
+            Span::styled("  u / r      ", Style::default().fg(Color::Yellow)),
+            Span::styled("Undo / reset a color", Style::default().fg(Color::White)),
+        ]),
         Line::from(""),
     ];
 
@@ -294,6 +396,62 @@ fn draw_help_overlay(f: &mut Frame, _app: &App) {
     f.render_widget(paragraph, area);
 }
 
+fn draw_diagnostics_overlay(f: &mut Frame, app: &App) {
+    let area = centered_rect(80, 70, f.area());
+
+    let mut lines = vec![Line::from("")];
+    if app.diagnostics.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "  Everything in the config was understood.",
+            Style::default().fg(Color::Green),
+        )));
+    }
+    for diagnostic in &app.diagnostics {
+        let (label, color) = match diagnostic.severity {
+            Severity::Error => ("error  ", Color::Red),
+            Severity::Warning => ("warning", Color::Yellow),
+        };
+        let file = std::path::Path::new(&diagnostic.file)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let location = match (diagnostic.line, diagnostic.column) {
+            (Some(line), Some(column)) => format!("{}:{}:{}", file, line, column),
+            (Some(line), None) => format!("{}:{}", file, line),
+            _ => file,
+        };
+        lines.push(Line::from(vec![
+            Span::styled(format!("  {} ", label), Style::default().fg(color).add_modifier(Modifier::BOLD)),
+            Span::styled(location, Style::default().fg(Color::Cyan)),
+        ]));
+        let key = diagnostic
+            .key
+            .as_ref()
+            .map(|k| format!("[{}] ", k))
+            .unwrap_or_default();
+        lines.push(Line::from(vec![
+            Span::raw("          "),
+            Span::styled(key, Style::default().fg(Color::DarkGray)),
+            Span::styled(diagnostic.message.as_str(), Style::default().fg(Color::White)),
+        ]));
+    }
+    lines.push(Line::from(""));
+
+    let paragraph = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .block(
+            Block::default()
+                .title(format!(" Config Warnings ({}) ", app.diagnostics.len()))
+                .title_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Yellow))
+                .style(Style::default().bg(Color::Rgb(30, 30, 46))),
+        );
+
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
 fn draw_confirm_dialog(f: &mut Frame, _app: &App) {
     let area = centered_rect(45, 30, f.area());
 
@@ -333,6 +491,57 @@ fn draw_confirm_dialog(f: &mut Frame, _app: &App) {
     f.render_widget(paragraph, area);
 }
 
+fn draw_conflict_dialog(f: &mut Frame, app: &App) {
+    let area = centered_rect(60, 40, f.area());
+
+    let mut lines = vec![
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("  ⚠ ", Style::default().fg(Color::Yellow)),
+            Span::styled("Changed on disk since it was loaded:", Style::default().fg(Color::White)),
+        ]),
+    ];
+    for path in &app.external_changes {
+        lines.push(Line::from(Span::styled(format!("    {}", path), Style::default().fg(Color::Cyan))));
+    }
+    lines.extend([
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("  (r) ", Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD)),
+            Span::styled("Reload, dropping your unsaved changes", Style::default().fg(Color::Gray)),
+        ]),
+        Line::from(vec![
+            Span::styled("  (o) ", Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)),
+            Span::styled(
+                if app.conflict_from_save { "Overwrite with your settings" } else { "Keep your settings" },
+                Style::default().fg(Color::Gray),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("  (m) ", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)),
+            Span::styled("Merge both, keeping yours where they clash", Style::default().fg(Color::Gray)),
+        ]),
+        Line::from(vec![
+            Span::styled("  (n) ", Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD)),
+            Span::styled("Cancel", Style::default().fg(Color::Gray)),
+        ]),
+    ]);
+
+    let paragraph = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .block(
+            Block::default()
+                .title(" Config Changed ")
+                .title_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Yellow))
+                .style(Style::default().bg(Color::Rgb(30, 30, 46))),
+        );
+
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
 /// Helper to create a centered rect
 fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
     let popup_layout = Layout::default()
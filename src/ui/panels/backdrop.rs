@@ -0,0 +1,99 @@
+// Backdrop settings panel
+
+use crate::app::{App, Panel};
+use crate::fields;
+use crate::ui::widgets::field_lines;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+/// Draw the backdrop settings panel
+pub fn draw(f: &mut Frame, area: Rect, app: &App) {
+    // Split area: left for settings, right for the image list
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(45), Constraint::Percentage(55)])
+        .split(area);
+
+    draw_settings(f, chunks[0], app);
+    draw_image_list(f, chunks[1], app);
+}
+
+fn draw_settings(f: &mut Frame, area: Rect, app: &App) {
+    let mut lines = field_lines(app, fields::for_panel(Panel::Backdrop), 16);
+
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "Move down into the image list →",
+        Style::default().fg(Color::DarkGray),
+    )));
+    lines.push(Line::from(Span::styled(
+        "Enter: add/remove  J/K: reorder",
+        Style::default().fg(Color::DarkGray),
+    )));
+    lines.push(Line::from(Span::styled(
+        "c: show first  r: reread folder",
+        Style::default().fg(Color::DarkGray),
+    )));
+
+    f.render_widget(Paragraph::new(lines), area);
+}
+
+fn draw_image_list(f: &mut Frame, area: Rect, app: &App) {
+    let backdrop = &app.config.backdrop;
+    let entries = app.backdrop_entries();
+    let selected = app.field_index.checked_sub(fields::for_panel(Panel::Backdrop).len() + 1);
+
+    let items: Vec<ListItem> = entries
+        .iter()
+        .map(|entry| {
+            let line = match entry.position {
+                Some(i) => {
+                    let is_start = i == backdrop.current_index && !backdrop.random_on_start;
+                    Line::from(vec![
+                        Span::styled("[x] ", Style::default().fg(Color::Green)),
+                        Span::styled(format!("{:>2}. ", i + 1), Style::default().fg(Color::DarkGray)),
+                        Span::styled(
+                            entry.name.clone(),
+                            Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
+                        ),
+                        Span::styled(if is_start { "  ● first" } else { "" }, Style::default().fg(Color::Cyan)),
+                    ])
+                }
+                None => Line::from(vec![
+                    Span::styled("[ ]     ", Style::default().fg(Color::DarkGray)),
+                    Span::raw(entry.name.clone()),
+                ]),
+            };
+            ListItem::new(line)
+        })
+        .collect();
+
+    let title = if backdrop.images_dir.is_empty() {
+        format!("Images ({} in use; set a folder to add more)", backdrop.images.len())
+    } else {
+        format!(
+            "Images ({} in use, {} in {})",
+            backdrop.images.len(),
+            app.backdrop_images.len(),
+            backdrop.images_dir
+        )
+    };
+    let border_style = if selected.is_some() {
+        Style::default().fg(Color::Cyan)
+    } else {
+        Style::default()
+    };
+
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).border_style(border_style).title(title))
+        .highlight_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+        .highlight_symbol("▶ ");
+    let mut state = ListState::default().with_selected(selected);
+
+    f.render_stateful_widget(list, area, &mut state);
+}
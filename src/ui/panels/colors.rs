@@ -1,6 +1,9 @@
 // Colors settings panel
 
-use crate::app::App;
+use crate::app::{App, Panel};
+use crate::fields::{self, Field};
+use crate::models::Color as ConfigColor;
+use crate::ui::widgets::{field_error, field_value, is_editing};
 use ratatui::{
     layout::{Constraint, Layout, Rect},
     style::{Color, Modifier, Style},
@@ -32,22 +35,18 @@ pub fn draw(f: &mut Frame, area: Rect, app: &App) {
     ]);
     f.render_widget(header, chunks[0]);
 
-    let colors = &app.config.colors;
-    let fields = vec![
-        ("Foreground", &colors.foreground, 1),
-        ("Background", &colors.background, 2),
-        ("Cursor BG", &colors.cursor_bg, 3),
-        ("Cursor FG", &colors.cursor_fg, 4),
-        ("Cursor Border", &colors.cursor_border, 5),
-        ("Selection BG", &colors.selection_bg, 6),
-        ("Selection FG", &colors.selection_fg, 7),
-    ];
-
-    let core_lines: Vec<Line> = fields
+    let fields = fields::for_panel(Panel::Colors);
+    let core_lines: Vec<Line> = fields[..7]
         .iter()
-        .map(|(label, value, idx)| {
-            let is_selected = app.field_index == *idx;
-            let (style, label_style) = if is_selected {
+        .enumerate()
+        .map(|(i, field)| {
+            let is_selected = app.field_index == i + 1;
+            let (style, label_style) = if is_editing(app, field) {
+                (
+                    Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+                    Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+                )
+            } else if is_selected {
                 (
                     Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
                     Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
@@ -60,16 +59,17 @@ pub fn draw(f: &mut Frame, area: Rect, app: &App) {
             };
             
             let prefix = if is_selected { " ▸ " } else { "   " };
-            let swatch = color_swatch(value);
             
-            Line::from(vec![
+            let mut spans = vec![
                 Span::styled(prefix, style),
-                Span::styled(format!("{:<14}", label), label_style),
+                Span::styled(format!("{:<14}", field.label), label_style),
                 Span::raw(" "),
-                Span::styled(format!("{:<9}", value), style),
+                Span::styled(format!("{:<9}", field_value(app, field)), style),
                 Span::raw(" "),
-                swatch,
-            ])
+                field_swatch(app, field),
+            ];
+            spans.extend(field_error(app, field.path));
+            Line::from(spans)
         })
         .collect();
 
@@ -85,27 +85,28 @@ pub fn draw(f: &mut Frame, area: Rect, app: &App) {
     ]);
     f.render_widget(ansi_header, chunks[2]);
 
-    let ansi_lines: Vec<Line> = colors
-        .ansi
+    let ansi_lines: Vec<Line> = fields[7..15]
         .iter()
         .enumerate()
-        .map(|(i, color)| {
+        .map(|(i, field)| {
             let idx = 8 + i; // Offset for field index
             let is_selected = app.field_index == idx;
-            let style = if is_selected {
+            let style = if is_editing(app, field) {
+                Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)
+            } else if is_selected {
                 Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
             } else {
                 Style::default()
             };
             
             let prefix = if is_selected { "> " } else { "  " };
-            let swatch = color_swatch(color);
+            let swatch = field_swatch(app, field);
             
             Line::from(vec![
                 Span::raw(prefix),
                 Span::styled(format!("[{}]", i), style),
                 Span::raw(" "),
-                Span::styled(format!("{:<9}", color), style),
+                Span::styled(format!("{:<9}", field_value(app, field)), style),
                 Span::raw(" "),
                 swatch,
             ])
@@ -137,27 +138,28 @@ pub fn draw(f: &mut Frame, area: Rect, app: &App) {
     ]);
     f.render_widget(brights_header, chunks[4]);
 
-    let brights_lines: Vec<Line> = colors
-        .brights
+    let brights_lines: Vec<Line> = fields[15..]
         .iter()
         .enumerate()
-        .map(|(i, color)| {
+        .map(|(i, field)| {
             let idx = 16 + i; // Offset for field index
             let is_selected = app.field_index == idx;
-            let style = if is_selected {
+            let style = if is_editing(app, field) {
+                Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)
+            } else if is_selected {
                 Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
             } else {
                 Style::default()
             };
             
             let prefix = if is_selected { "> " } else { "  " };
-            let swatch = color_swatch(color);
+            let swatch = field_swatch(app, field);
             
             Line::from(vec![
                 Span::raw(prefix),
                 Span::styled(format!("[{}]", i + 8), style),
                 Span::raw(" "),
-                Span::styled(format!("{:<9}", color), style),
+                Span::styled(format!("{:<9}", field_value(app, field)), style),
                 Span::raw(" "),
                 swatch,
             ])
@@ -179,25 +181,16 @@ pub fn draw(f: &mut Frame, area: Rect, app: &App) {
     f.render_widget(Paragraph::new(brights_text), chunks[5]);
 }
 
-/// Create a color swatch span from a hex color
-fn color_swatch(hex: &str) -> Span<'static> {
-    if let Some(color) = parse_hex_color(hex) {
-        Span::styled("██", Style::default().fg(color))
-    } else {
-        Span::styled("??", Style::default().fg(Color::Red))
+/// A swatch of a color field's current value
+fn field_swatch(app: &App, field: &Field) -> Span<'static> {
+    match ConfigColor::parse(&field.display(&app.config)) {
+        Ok(color) => Span::styled("██", Style::default().fg(tui_color(&color))),
+        Err(_) => Span::raw("  "),
     }
 }
 
-/// Parse a hex color string to a ratatui Color
-fn parse_hex_color(hex: &str) -> Option<Color> {
-    let hex = hex.trim_start_matches('#');
-    if hex.len() != 6 {
-        return None;
-    }
-    
-    let r = u8::from_str_radix(&hex[0..2], 16).ok()?;
-    let g = u8::from_str_radix(&hex[2..4], 16).ok()?;
-    let b = u8::from_str_radix(&hex[4..6], 16).ok()?;
-    
-    Some(Color::Rgb(r, g, b))
+/// The terminal color closest to a config color, ignoring alpha
+pub fn tui_color(color: &ConfigColor) -> Color {
+    let [r, g, b, _] = color.to_rgba8();
+    Color::Rgb(r, g, b)
 }
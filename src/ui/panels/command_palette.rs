@@ -0,0 +1,33 @@
+// Command palette settings panel
+
+use super::colors::tui_color;
+use crate::app::{App, Panel};
+use crate::fields;
+use crate::ui::widgets::field_lines;
+use ratatui::{
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::Paragraph,
+    Frame,
+};
+
+/// Draw the command palette settings panel
+pub fn draw(f: &mut Frame, area: Rect, app: &App) {
+    let mut lines = field_lines(app, fields::for_panel(Panel::CommandPalette), 14);
+
+    // Preview in the palette's own colors
+    let palette = &app.config.command_palette;
+    let style = Style::default().fg(tui_color(&palette.fg_color)).bg(tui_color(&palette.bg_color));
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "Preview",
+        Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+    )));
+    lines.push(Line::from(""));
+    for entry in ["  > split                        ", "    Split Pane Horizontally      ", "    Split Pane Vertically        "] {
+        lines.push(Line::from(vec![Span::raw("  "), Span::styled(entry, style)]));
+    }
+
+    f.render_widget(Paragraph::new(lines), area);
+}
@@ -1,6 +1,8 @@
 // Fonts settings panel
 
-use crate::app::{App, InputMode};
+use crate::app::{App, InputMode, Panel};
+use crate::fields;
+use crate::ui::widgets::field_lines;
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
@@ -11,8 +13,6 @@ use ratatui::{
 
 /// Draw the fonts settings panel
 pub fn draw(f: &mut Frame, area: Rect, app: &App) {
-    let fonts = &app.config.fonts;
-    
     // Split area: left for current settings, right for font selector
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
@@ -20,46 +20,14 @@ pub fn draw(f: &mut Frame, area: Rect, app: &App) {
         .split(area);
     
     // Left side: current settings and other font options
-    draw_settings(f, chunks[0], app, fonts);
+    draw_settings(f, chunks[0], app);
     
     // Right side: font selector list
     draw_font_selector(f, chunks[1], app);
 }
 
-fn draw_settings(f: &mut Frame, area: Rect, app: &App, fonts: &wezterm_settings_gui_lib::models::FontConfig) {
-    let fields = vec![
-        ("Current Font", fonts.family.clone(), 1),
-        ("Font Size", format!("{}", fonts.size), 2),
-        ("Weight", fonts.weight.as_ref().map(|w| format!("{:?}", w)).unwrap_or_else(|| "Default".to_string()), 3),
-        ("Load Target", fonts.freetype_load_target.as_ref().map(|t| format!("{:?}", t)).unwrap_or_else(|| "Normal".to_string()), 4),
-        ("Render Target", fonts.freetype_render_target.as_ref().map(|t| format!("{:?}", t)).unwrap_or_else(|| "Normal".to_string()), 5),
-    ];
-
-    let mut lines: Vec<Line> = vec![
-        Line::from(Span::styled(
-            "Settings",
-            Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
-        )),
-        Line::from(""),
-    ];
-
-    for (label, value, idx) in &fields {
-        let is_selected = app.field_index == *idx && app.field_index <= 5;
-        let style = if is_selected {
-            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
-        } else {
-            Style::default()
-        };
-        
-        let prefix = if is_selected { "> " } else { "  " };
-        
-        lines.push(Line::from(vec![
-            Span::raw(prefix),
-            Span::styled(format!("{:<14}", label), style),
-            Span::raw(" "),
-            Span::styled(value.clone(), style),
-        ]));
-    }
+fn draw_settings(f: &mut Frame, area: Rect, app: &App) {
+    let mut lines = field_lines(app, fields::for_panel(Panel::Fonts), 14);
 
     // Add hint
     lines.push(Line::from(""));
@@ -72,7 +40,7 @@ fn draw_settings(f: &mut Frame, area: Rect, app: &App, fonts: &wezterm_settings_
 }
 
 fn draw_font_selector(f: &mut Frame, area: Rect, app: &App) {
-    let is_in_selector = app.field_index > 5;
+    let is_in_selector = app.in_font_selector();
     
     // Search bar at top
     let chunks = Layout::default()
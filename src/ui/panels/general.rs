@@ -0,0 +1,12 @@
+// General settings panel
+
+use crate::app::{App, Panel};
+use crate::fields;
+use crate::ui::widgets::field_lines;
+use ratatui::{layout::Rect, widgets::Paragraph, Frame};
+
+/// Draw the general settings panel
+pub fn draw(f: &mut Frame, area: Rect, app: &App) {
+    let lines = field_lines(app, fields::for_panel(Panel::General), 18);
+    f.render_widget(Paragraph::new(lines), area);
+}
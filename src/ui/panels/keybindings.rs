@@ -1,6 +1,8 @@
 // Keybindings settings panel
 
-use crate::app::App;
+use crate::app::{App, Panel};
+use crate::fields;
+use crate::validation::field_errors;
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
@@ -28,79 +30,38 @@ pub fn draw(f: &mut Frame, area: Rect, app: &App) {
 
 fn draw_left_column(f: &mut Frame, area: Rect, app: &App, kb: &wezterm_settings_gui_lib::models::KeyBindingsConfig) {
     let mut lines: Vec<Line> = vec![];
-    
-    // Custom Commands Section
-    lines.push(Line::from(Span::styled(
-        "Custom Command Palette Entries",
-        Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
-    )));
-    lines.push(Line::from(""));
-    
-    // Settings-TUI
-    let settings_tui_enabled = kb.custom_commands.settings_tui;
-    add_toggle_line(&mut lines, 1, app.field_index, "Settings-TUI", settings_tui_enabled,
-        "Opens settings TUI from command palette");
-    
-    // Rename Tab
-    let rename_tab_enabled = kb.custom_commands.rename_tab;
-    add_toggle_line(&mut lines, 2, app.field_index, "Rename Tab", rename_tab_enabled,
-        "Opens rename prompt from command palette");
-    
-    lines.push(Line::from(""));
-    
-    // Mouse Bindings Section
-    lines.push(Line::from(Span::styled(
-        "Mouse Bindings",
-        Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
-    )));
-    lines.push(Line::from(""));
-    
-    add_toggle_line(&mut lines, 3, app.field_index, "Ctrl+Click Open Link", kb.mouse.ctrl_click_open_link,
-        "Ctrl+Click opens URLs under cursor");
-    
-    add_toggle_line(&mut lines, 4, app.field_index, "Right-Click Cmd Palette", kb.mouse.right_click_command_palette,
-        "Right-click opens command palette");
-    
-    lines.push(Line::from(""));
-    
-    // General Settings Section
-    lines.push(Line::from(Span::styled(
-        "General Settings",
-        Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
-    )));
-    lines.push(Line::from(""));
-    
-    add_toggle_line(&mut lines, 5, app.field_index, "Disable Default Keys", kb.disable_defaults,
-        "Disable WezTerm's default keybindings");
-    
     let leader_desc = format!("Leader: {} + {} ({}ms)", kb.leader.mods, kb.leader.key, kb.leader.timeout_ms);
-    lines.push(Line::from(""));
-    
-    // Leader key toggle (manually constructed to avoid lifetime issues)
-    let is_selected = app.field_index == 6;
-    let style = if is_selected {
-        Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
-    } else {
-        Style::default()
-    };
-    let prefix = if is_selected { "> " } else { "  " };
-    let status = if kb.leader.enabled { "[ON] " } else { "[OFF]" };
-    let status_style = if kb.leader.enabled {
-        Style::default().fg(Color::Green)
-    } else {
-        Style::default().fg(Color::Red)
-    };
-    
-    lines.push(Line::from(vec![
-        Span::raw(prefix),
-        Span::styled(status, status_style),
-        Span::styled(" Leader Key", style),
-    ]));
-    
-    lines.push(Line::from(vec![
-        Span::raw("       "),
-        Span::styled(leader_desc, Style::default().fg(Color::DarkGray)),
-    ]));
+
+    let mut group = "";
+    for (i, field) in fields::for_panel(Panel::Keybindings).iter().enumerate() {
+        if field.group != group {
+            if !lines.is_empty() {
+                lines.push(Line::from(""));
+            }
+            lines.push(Line::from(Span::styled(
+                field.group,
+                Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            )));
+            lines.push(Line::from(""));
+            group = field.group;
+        }
+        // The leader's description shows its current key
+        let description = if field.path == "keybindings.leader.enabled" {
+            leader_desc.clone()
+        } else {
+            field.help.to_string()
+        };
+        add_toggle_line(&mut lines, i + 1, app.field_index, field.label, field.is_on(&app.config), description);
+    }
+
+    // Binding problems, e.g. leader bindings left on with the leader off
+    for error in field_errors(&app.validation, "keybindings") {
+        let field = error.field.trim_start_matches("keybindings.");
+        lines.push(Line::from(Span::styled(
+            format!("  ✗ {}: {}", field, error.message),
+            Style::default().fg(Color::Red),
+        )));
+    }
     
     lines.push(Line::from(""));
     lines.push(Line::from(""));
@@ -174,7 +135,7 @@ fn draw_right_column(f: &mut Frame, area: Rect, _app: &App, kb: &wezterm_setting
     f.render_widget(Paragraph::new(lines), area);
 }
 
-fn add_toggle_line<'a>(lines: &mut Vec<Line<'a>>, field_idx: usize, current_idx: usize, name: &'a str, enabled: bool, description: &'a str) {
+fn add_toggle_line(lines: &mut Vec<Line>, field_idx: usize, current_idx: usize, name: &str, enabled: bool, description: String) {
     let is_selected = current_idx == field_idx;
     let style = if is_selected {
         Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
@@ -197,7 +158,7 @@ fn add_toggle_line<'a>(lines: &mut Vec<Line<'a>>, field_idx: usize, current_idx:
     
     lines.push(Line::from(vec![
         Span::raw("       "),
-        Span::styled(description.to_string(), Style::default().fg(Color::DarkGray)),
+        Span::styled(description, Style::default().fg(Color::DarkGray)),
     ]));
 }
 
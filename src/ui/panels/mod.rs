@@ -1,9 +1,14 @@
 // Panel modules for each settings category
 
+pub mod backdrop;
 pub mod colors;
+pub mod command_palette;
 pub mod cursor;
 pub mod fonts;
+pub mod general;
 pub mod gpu;
 pub mod keybindings;
 pub mod themes;
+pub mod unmanaged;
+pub mod visual_bell;
 pub mod window;
@@ -1,6 +1,8 @@
 // Themes panel - browse and select WezTerm color schemes
 
+use super::colors::tui_color;
 use crate::app::{App, InputMode};
+use crate::models::Color as ConfigColor;
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
@@ -237,9 +239,9 @@ fn draw_color_preview(f: &mut Frame, area: Rect, name: &str, colors: &ThemeColor
     f.render_widget(Paragraph::new(vec![Line::from(""), title]), chunks[0]);
 
     // Terminal preview (bg + fg sample)
-    let bg = parse_hex_color(&colors.background).unwrap_or(Color::Black);
-    let fg = parse_hex_color(&colors.foreground).unwrap_or(Color::White);
-    let cursor = parse_hex_color(&colors.cursor).unwrap_or(Color::White);
+    let bg = parse_color(&colors.background).unwrap_or(Color::Black);
+    let fg = parse_color(&colors.foreground).unwrap_or(Color::White);
+    let cursor = parse_color(&colors.cursor).unwrap_or(Color::White);
 
     let preview_text = vec![
         Line::from(vec![
@@ -262,7 +264,7 @@ fn draw_color_preview(f: &mut Frame, area: Rect, name: &str, colors: &ThemeColor
     // ANSI colors - first 8 (normal)
     let ansi_line1: Vec<Span> = std::iter::once(Span::raw("  "))
         .chain(colors.ansi[0..8].iter().map(|c| {
-            let color = parse_hex_color(c).unwrap_or(Color::White);
+            let color = parse_color(c).unwrap_or(Color::White);
             Span::styled("  ", Style::default().bg(color))
         }))
         .collect();
@@ -271,36 +273,29 @@ fn draw_color_preview(f: &mut Frame, area: Rect, name: &str, colors: &ThemeColor
     // ANSI colors - bright 8
     let ansi_line2: Vec<Span> = std::iter::once(Span::raw("  "))
         .chain(colors.brights[0..8].iter().map(|c| {
-            let color = parse_hex_color(c).unwrap_or(Color::White);
+            let color = parse_color(c).unwrap_or(Color::White);
             Span::styled("  ", Style::default().bg(color))
         }))
         .collect();
     f.render_widget(Paragraph::new(Line::from(ansi_line2)), chunks[4]);
 }
 
-/// Parse hex color string to ratatui Color
-fn parse_hex_color(hex: &str) -> Option<Color> {
-    let hex = hex.trim_start_matches('#');
-    if hex.len() != 6 {
-        return None;
-    }
-    let r = u8::from_str_radix(&hex[0..2], 16).ok()?;
-    let g = u8::from_str_radix(&hex[2..4], 16).ok()?;
-    let b = u8::from_str_radix(&hex[4..6], 16).ok()?;
-    Some(Color::Rgb(r, g, b))
+/// Parse a theme color to a ratatui Color
+fn parse_color(text: &str) -> Option<Color> {
+    ConfigColor::parse(text).ok().map(|c| tui_color(&c))
 }
 
 /// Theme color palette
-struct ThemeColors {
-    foreground: String,
-    background: String,
-    cursor: String,
-    ansi: [String; 8],
-    brights: [String; 8],
+pub struct ThemeColors {
+    pub foreground: String,
+    pub background: String,
+    pub cursor: String,
+    pub ansi: [String; 8],
+    pub brights: [String; 8],
 }
 
 /// Get colors for a known theme
-fn get_theme_colors(name: &str) -> Option<ThemeColors> {
+pub fn get_theme_colors(name: &str) -> Option<ThemeColors> {
     match name {
         "Catppuccin Mocha" => Some(ThemeColors {
             foreground: "#cdd6f4".into(),
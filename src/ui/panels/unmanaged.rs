@@ -0,0 +1,55 @@
+// Unmanaged Lua panel (read-only)
+
+use crate::app::App;
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::Paragraph,
+    Frame,
+};
+
+/// Draw the list of settings, event handlers, plugins and functions the
+/// settings don't manage. Saves keep all of them as written.
+pub fn draw(f: &mut Frame, area: Rect, app: &App) {
+    let mut lines = vec![
+        Line::from(Span::styled(
+            "Kept exactly as written on save; edit these in the config file.",
+            Style::default().fg(Color::DarkGray),
+        )),
+        Line::from(""),
+    ];
+
+    if app.unmanaged.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "  Everything in the config is managed here.",
+            Style::default().fg(Color::Green),
+        )));
+    }
+
+    for (i, item) in app.unmanaged.iter().enumerate() {
+        let is_selected = app.field_index == i + 1;
+        let style = if is_selected {
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        let prefix = if is_selected { "> " } else { "  " };
+        let file = std::path::Path::new(&item.file)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        lines.push(Line::from(vec![
+            Span::raw(prefix),
+            Span::styled(format!("{:<14}", item.kind.label()), Style::default().fg(Color::Cyan)),
+            Span::styled(item.name.clone(), style),
+            Span::styled(format!("  {}:{}", file, item.line), Style::default().fg(Color::DarkGray)),
+        ]));
+    }
+
+    // Keep the selected item in view
+    let header = 2;
+    let scroll = (app.field_index + header).saturating_sub(area.height as usize);
+    f.render_widget(Paragraph::new(lines).scroll((scroll as u16, 0)), area);
+}
@@ -0,0 +1,12 @@
+// Visual bell settings panel
+
+use crate::app::{App, Panel};
+use crate::fields;
+use crate::ui::widgets::field_lines;
+use ratatui::{layout::Rect, widgets::Paragraph, Frame};
+
+/// Draw the visual bell settings panel
+pub fn draw(f: &mut Frame, area: Rect, app: &App) {
+    let lines = field_lines(app, fields::for_panel(Panel::VisualBell), 16);
+    f.render_widget(Paragraph::new(lines), area);
+}
@@ -1 +1,940 @@
-// Custom widgets (placeholder for future use)
+// Custom widgets shared by the panels
+
+use super::panels::{colors::tui_color, themes::get_theme_colors};
+use crate::app::{App, InputMode};
+use crate::fields::Field;
+use crate::models::{AppearanceConfig, ChoiceOption, Color as ConfigColor};
+use crate::backups::{self, BackupInfo};
+use crate::diff::{DiffLine, FileDiff};
+use crate::validation::field_errors;
+use std::path::PathBuf;
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+/// The first validation problem with `field` (e.g. `fonts.size`), to show
+/// after its value
+pub fn field_error(app: &App, field: &str) -> Option<Span<'static>> {
+    let error = field_errors(&app.validation, field).into_iter().next()?;
+    Some(Span::styled(
+        format!("  ✗ {}", error.message),
+        Style::default().fg(Color::Red),
+    ))
+}
+
+/// Whether `field` is the one being typed into
+pub fn is_editing(app: &App, field: &Field) -> bool {
+    app.input_mode == InputMode::Editing && app.selected_field().is_some_and(|f| f.path == field.path)
+}
+
+/// A field's value, or the edit in progress when it is being typed into
+pub fn field_value(app: &App, field: &Field) -> String {
+    if is_editing(app, field) {
+        format!("{}_", app.input_buffer)
+    } else {
+        field.display(&app.config)
+    }
+}
+
+/// One line per field (label, value, validation problem), with a heading
+/// above each group
+pub fn field_lines(app: &App, fields: &[Field], label_width: usize) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    let mut group = "";
+    for (i, field) in fields.iter().enumerate() {
+        if field.group != group && !field.group.is_empty() {
+            if !lines.is_empty() {
+                lines.push(Line::from(""));
+            }
+            lines.push(Line::from(Span::styled(
+                field.group,
+                Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            )));
+            lines.push(Line::from(""));
+        }
+        group = field.group;
+
+        let is_selected = app.field_index == i + 1;
+        let style = if is_editing(app, field) {
+            Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)
+        } else if is_selected {
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        let prefix = if is_selected { "> " } else { "  " };
+
+        let mut spans = vec![
+            Span::raw(prefix),
+            Span::styled(format!("{:<width$}", field.label, width = label_width), style),
+            Span::raw(" "),
+            Span::styled(field_value(app, field), style),
+        ];
+        spans.extend(field_error(app, field.path));
+        lines.push(Line::from(spans));
+    }
+    lines
+}
+
+/// A popup listing every choice of an enum field, with the current one
+/// marked; works for any `Choice` enum through its `ChoiceOption`s
+#[derive(Debug, Clone)]
+pub struct EnumPicker {
+    pub title: String,
+    pub options: Vec<ChoiceOption>,
+    /// The choice in effect when the picker opened
+    pub current: usize,
+    /// The highlighted choice
+    pub selected: usize,
+}
+
+impl EnumPicker {
+    pub fn new(title: &str, options: Vec<ChoiceOption>, current: usize) -> Self {
+        Self {
+            title: title.to_string(),
+            options,
+            current,
+            selected: current,
+        }
+    }
+
+    /// Highlight the next choice, wrapping to the first
+    pub fn next(&mut self) {
+        self.selected = (self.selected + 1) % self.options.len().max(1);
+    }
+
+    /// Highlight the previous choice, wrapping to the last
+    pub fn previous(&mut self) {
+        let len = self.options.len().max(1);
+        self.selected = (self.selected + len - 1) % len;
+    }
+
+    /// The highlighted choice
+    pub fn choice(&self) -> Option<&ChoiceOption> {
+        self.options.get(self.selected)
+    }
+}
+
+/// Draw `picker` centered over the screen
+pub fn draw_enum_picker(f: &mut Frame, picker: &EnumPicker) {
+    let screen = f.area();
+    let label_width = picker.options.iter().map(|o| o.label.chars().count()).max().unwrap_or(0);
+    let content_width = picker
+        .options
+        .iter()
+        .map(|o| label_width + o.description.chars().count() + 6)
+        .max()
+        .unwrap_or(0)
+        .max(picker.title.chars().count() + 4);
+    let width = (content_width as u16 + 2).min(screen.width);
+    let height = (picker.options.len() as u16 + 2).min(screen.height);
+    let area = Rect {
+        x: screen.x + (screen.width - width) / 2,
+        y: screen.y + (screen.height - height) / 2,
+        width,
+        height,
+    };
+
+    let items: Vec<ListItem> = picker
+        .options
+        .iter()
+        .enumerate()
+        .map(|(i, option)| {
+            let marker = if i == picker.current { "● " } else { "○ " };
+            ListItem::new(Line::from(vec![
+                Span::styled(marker, Style::default().fg(Color::Green)),
+                Span::styled(
+                    format!("{:<width$}  ", option.label, width = label_width),
+                    Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(option.description, Style::default().fg(Color::DarkGray)),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .title(format!(" {} ", picker.title))
+                .title_style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan))
+                .style(Style::default().bg(Color::Rgb(30, 30, 46))),
+        )
+        .highlight_style(Style::default().bg(Color::Rgb(69, 71, 90)));
+    let mut state = ListState::default().with_selected(Some(picker.selected));
+
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(list, area, &mut state);
+}
+
+/// A row of the color picker
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorRow {
+    Hue,
+    Saturation,
+    Lightness,
+    Red,
+    Green,
+    Blue,
+    Hex,
+    Palette,
+    Scheme,
+}
+
+impl ColorRow {
+    const ALL: [ColorRow; 9] = [
+        ColorRow::Hue,
+        ColorRow::Saturation,
+        ColorRow::Lightness,
+        ColorRow::Red,
+        ColorRow::Green,
+        ColorRow::Blue,
+        ColorRow::Hex,
+        ColorRow::Palette,
+        ColorRow::Scheme,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            ColorRow::Hue => "Hue",
+            ColorRow::Saturation => "Saturation",
+            ColorRow::Lightness => "Lightness",
+            ColorRow::Red => "Red",
+            ColorRow::Green => "Green",
+            ColorRow::Blue => "Blue",
+            ColorRow::Hex => "Hex",
+            ColorRow::Palette => "Palette",
+            ColorRow::Scheme => "Scheme",
+        }
+    }
+}
+
+/// A popup for building a color field's value from HSL or RGB sliders, a
+/// hex code, the config's ANSI palette or the selected color scheme
+#[derive(Debug, Clone)]
+pub struct ColorPicker {
+    pub title: String,
+    /// The value when the picker opened
+    pub original: ConfigColor,
+    /// The color being built
+    pub color: ConfigColor,
+    /// Hue, saturation and lightness of `color`, kept apart so the hue
+    /// isn't lost while the color passes through gray
+    hsl: [f32; 3],
+    pub row: ColorRow,
+    /// The hex code as typed
+    pub hex: String,
+    /// Whether `hex` is being typed, rather than showing the color
+    typing_hex: bool,
+    /// ANSI colors 0-15 of the config being edited
+    palette: Vec<ConfigColor>,
+    /// Name and colors (foreground, background, cursor, ANSI 0-15) of the
+    /// selected built-in scheme, when it's one the TUI knows
+    scheme: Option<(String, Vec<ConfigColor>)>,
+    palette_index: usize,
+    scheme_index: usize,
+    /// Earlier states, for undo
+    history: Vec<(ConfigColor, [f32; 3])>,
+    /// The row the last change was made on; a run of steps on one row
+    /// undoes as one
+    last_changed: Option<ColorRow>,
+}
+
+impl ColorPicker {
+    pub fn new(title: &str, color: ConfigColor, config: &AppearanceConfig) -> Self {
+        let palette: Vec<ConfigColor> = config.colors.ansi.iter().chain(&config.colors.brights).cloned().collect();
+        let scheme = config.color_scheme.as_deref().and_then(|name| {
+            let theme = get_theme_colors(name)?;
+            let colors: Vec<ConfigColor> = [&theme.foreground, &theme.background, &theme.cursor]
+                .into_iter()
+                .chain(&theme.ansi)
+                .chain(&theme.brights)
+                .filter_map(|text| ConfigColor::parse(text).ok())
+                .collect();
+            Some((name.to_string(), colors))
+        });
+        let index_of = |colors: &[ConfigColor]| colors.iter().position(|c| c.rgba() == color.rgba()).unwrap_or(0);
+        Self {
+            title: title.to_string(),
+            palette_index: index_of(&palette),
+            scheme_index: scheme.as_ref().map_or(0, |(_, colors)| index_of(colors)),
+            hsl: color.to_hsl(),
+            hex: color.to_hex(),
+            typing_hex: false,
+            original: color.clone(),
+            color,
+            row: ColorRow::Hue,
+            palette,
+            scheme,
+            history: Vec::new(),
+            last_changed: None,
+        }
+    }
+
+    /// Move to the next row, wrapping to the first
+    pub fn next_row(&mut self) {
+        self.move_row(1);
+    }
+
+    /// Move to the previous row, wrapping to the last
+    pub fn previous_row(&mut self) {
+        self.move_row(-1);
+    }
+
+    fn move_row(&mut self, by: i32) {
+        let rows = self.rows();
+        let index = rows.iter().position(|r| *r == self.row).unwrap_or(0);
+        self.row = rows[step_index(index, by, rows.len())];
+        self.typing_hex = false;
+    }
+
+    /// The rows shown; the scheme row only when there's a scheme to pick from
+    fn rows(&self) -> &'static [ColorRow] {
+        match self.scheme {
+            Some(_) => &ColorRow::ALL,
+            None => &ColorRow::ALL[..ColorRow::ALL.len() - 1],
+        }
+    }
+
+    /// Move the current row's slider by `steps` (degrees of hue, percent of
+    /// saturation or lightness, or 0-255 channel units), or step through a
+    /// palette row taking each color as it goes
+    pub fn adjust(&mut self, steps: i32) {
+        let [h, s, l] = self.hsl;
+        let percent = |value: f32| (value + steps as f32 / 100.0).clamp(0.0, 1.0);
+        match self.row {
+            ColorRow::Hue => self.set_hsl([(h + steps as f32).rem_euclid(360.0), s, l]),
+            ColorRow::Saturation => self.set_hsl([h, percent(s), l]),
+            ColorRow::Lightness => self.set_hsl([h, s, percent(l)]),
+            ColorRow::Red | ColorRow::Green | ColorRow::Blue => {
+                let channel = self.row as usize - ColorRow::Red as usize;
+                let mut rgba = self.color.to_rgba8();
+                rgba[channel] = (rgba[channel] as i32 + steps).clamp(0, 255) as u8;
+                self.set_color(ConfigColor::from_rgba8(rgba));
+            }
+            ColorRow::Hex => {}
+            ColorRow::Palette => {
+                self.palette_index = step_index(self.palette_index, steps, self.palette.len());
+                if let Some(color) = self.palette.get(self.palette_index).cloned() {
+                    self.set_color(color);
+                }
+            }
+            ColorRow::Scheme => {
+                let Some((_, colors)) = &self.scheme else {
+                    return;
+                };
+                self.scheme_index = step_index(self.scheme_index, steps, colors.len());
+                if let Some(color) = colors.get(self.scheme_index).cloned() {
+                    self.set_color(color);
+                }
+            }
+        }
+    }
+
+    /// Add a character to the hex code, taking it as the color once it is
+    /// a complete one. The first character typed replaces the code shown.
+    pub fn type_hex(&mut self, c: char) {
+        if c == '#' || !self.typing_hex {
+            self.hex = "#".to_string();
+        }
+        self.typing_hex = true;
+        if c != '#' && self.hex.len() < 9 {
+            self.hex.push(c.to_ascii_lowercase());
+        }
+        self.take_hex();
+    }
+
+    /// Remove the last character of the hex code
+    pub fn delete_hex(&mut self) {
+        self.typing_hex = true;
+        self.hex.pop();
+        self.take_hex();
+    }
+
+    fn take_hex(&mut self) {
+        if !matches!(self.hex.len(), 4 | 5 | 7 | 9) {
+            return;
+        }
+        if let Ok(color) = ConfigColor::parse(&self.hex) {
+            let hex = self.hex.clone();
+            self.set_color(color);
+            self.hex = hex;
+        }
+    }
+
+    /// Whether the hex code as typed is a color
+    pub fn hex_is_valid(&self) -> bool {
+        ConfigColor::parse(&self.hex).is_ok()
+    }
+
+    /// Go back to the color before the last change
+    pub fn undo(&mut self) {
+        if let Some((color, hsl)) = self.history.pop() {
+            self.hex = color.to_hex();
+            self.color = color;
+            self.hsl = hsl;
+            self.last_changed = None;
+            self.typing_hex = false;
+        }
+    }
+
+    /// Go back to the color the picker opened with; undo brings the
+    /// discarded one back
+    pub fn reset(&mut self) {
+        if self.color != self.original {
+            self.record(None);
+            self.color = self.original.clone();
+            self.hsl = self.color.to_hsl();
+            self.hex = self.color.to_hex();
+            self.typing_hex = false;
+        }
+    }
+
+    /// Whether the color differs from the one the picker opened with
+    pub fn is_changed(&self) -> bool {
+        self.color != self.original
+    }
+
+    fn set_hsl(&mut self, hsl: [f32; 3]) {
+        self.record(Some(self.row));
+        let [h, s, l] = hsl;
+        self.color = ConfigColor::from_hsla(h, s, l, self.color.rgba()[3]);
+        self.hsl = hsl;
+        self.hex = self.color.to_hex();
+    }
+
+    fn set_color(&mut self, color: ConfigColor) {
+        self.record(Some(self.row));
+        let [h, s, l] = color.to_hsl();
+        // Grays have no hue of their own; keep the one the sliders were at
+        self.hsl = if s == 0.0 { [self.hsl[0], s, l] } else { [h, s, l] };
+        self.hex = color.to_hex();
+        self.color = color;
+    }
+
+    fn record(&mut self, row: Option<ColorRow>) {
+        if row.is_none() || row != self.last_changed {
+            self.history.push((self.color.clone(), self.hsl));
+        }
+        self.last_changed = row;
+    }
+}
+
+/// `index` moved by `steps`, wrapping within `len`
+fn step_index(index: usize, steps: i32, len: usize) -> usize {
+    if len == 0 {
+        return 0;
+    }
+    (index as i64 + steps as i64).rem_euclid(len as i64) as usize
+}
+
+/// Cells in a slider bar
+const SLIDER_WIDTH: usize = 32;
+
+/// Draw `picker` centered over the screen
+pub fn draw_color_picker(f: &mut Frame, picker: &ColorPicker) {
+    let screen = f.area();
+    let width = 96.min(screen.width);
+    let height = 15.min(screen.height);
+    let area = Rect {
+        x: screen.x + (screen.width - width) / 2,
+        y: screen.y + (screen.height - height) / 2,
+        width,
+        height,
+    };
+
+    let swatch = |color: &ConfigColor| Span::styled("        ", Style::default().bg(tui_color(color)));
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("  Old  ", Style::default().fg(Color::DarkGray)),
+            swatch(&picker.original),
+            Span::raw("  "),
+            Span::styled(picker.original.as_str().to_string(), Style::default().fg(Color::DarkGray)),
+        ]),
+        Line::from(vec![
+            Span::styled("  New  ", Style::default().fg(Color::White).add_modifier(Modifier::BOLD)),
+            swatch(&picker.color),
+            Span::raw("  "),
+            Span::styled(
+                picker.color.as_str().to_string(),
+                Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+            ),
+        ]),
+        Line::from(""),
+    ];
+
+    let [h, s, l] = picker.hsl;
+    let [r, g, b, a] = picker.color.rgba();
+    for row in ColorRow::ALL {
+        let focused = picker.row == row;
+        let label_style = if focused {
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::White)
+        };
+        let mut spans = vec![
+            Span::styled(if focused { "▸ " } else { "  " }, label_style),
+            Span::styled(format!("{:<11}", row.label()), label_style),
+        ];
+        let rgb_at = |channel: usize, t: f32| {
+            let mut rgba = [r, g, b, a];
+            rgba[channel] = t;
+            ConfigColor::from_rgba8(rgba.map(|c| (c * 255.0).round() as u8))
+        };
+        match row {
+            ColorRow::Hue => {
+                spans.extend(slider(h / 360.0, |t| ConfigColor::from_hsla(t * 360.0, s, l, 1.0)));
+                spans.push(Span::raw(format!(" {:>3.0}°", h)));
+            }
+            ColorRow::Saturation => {
+                spans.extend(slider(s, |t| ConfigColor::from_hsla(h, t, l, 1.0)));
+                spans.push(Span::raw(format!(" {:>3.0}%", s * 100.0)));
+            }
+            ColorRow::Lightness => {
+                spans.extend(slider(l, |t| ConfigColor::from_hsla(h, s, t, 1.0)));
+                spans.push(Span::raw(format!(" {:>3.0}%", l * 100.0)));
+            }
+            ColorRow::Red | ColorRow::Green | ColorRow::Blue => {
+                let channel = row as usize - ColorRow::Red as usize;
+                let value = [r, g, b][channel];
+                spans.extend(slider(value, |t| rgb_at(channel, t)));
+                spans.push(Span::raw(format!(" {:>3}", (value * 255.0).round() as u8)));
+            }
+            ColorRow::Hex => {
+                let style = if picker.hex_is_valid() {
+                    Style::default().fg(Color::White)
+                } else {
+                    Style::default().fg(Color::Red)
+                };
+                let cursor = if focused { "_" } else { "" };
+                spans.push(Span::styled(format!("{}{}", picker.hex, cursor), style));
+            }
+            ColorRow::Palette => {
+                spans.extend(swatch_row(&picker.palette, focused.then_some(picker.palette_index)));
+            }
+            ColorRow::Scheme => match &picker.scheme {
+                Some((name, colors)) => {
+                    spans.extend(swatch_row(colors, focused.then_some(picker.scheme_index)));
+                    spans.push(Span::styled(format!(" {}", name), Style::default().fg(Color::DarkGray)));
+                }
+                None => spans.push(Span::styled(
+                    "no built-in scheme selected",
+                    Style::default().fg(Color::DarkGray),
+                )),
+            },
+        }
+        lines.push(Line::from(spans));
+    }
+
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .title(format!(" {} ", picker.title))
+            .title_style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan))
+            .style(Style::default().bg(Color::Rgb(30, 30, 46))),
+    );
+
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+/// A bar shaded with the color each position would give, marked at `value`
+/// (0.0-1.0)
+fn slider(value: f32, color_at: impl Fn(f32) -> ConfigColor) -> Vec<Span<'static>> {
+    let marker = (value.clamp(0.0, 1.0) * (SLIDER_WIDTH - 1) as f32).round() as usize;
+    (0..SLIDER_WIDTH)
+        .map(|i| {
+            let color = color_at(i as f32 / (SLIDER_WIDTH - 1) as f32);
+            let style = Style::default().bg(tui_color(&color));
+            if i == marker {
+                let contrast = if color.to_hsl()[2] < 0.5 { Color::White } else { Color::Black };
+                Span::styled("┃", style.fg(contrast).add_modifier(Modifier::BOLD))
+            } else {
+                Span::styled(" ", style)
+            }
+        })
+        .collect()
+}
+
+/// Swatches of `colors`, with brackets around the `selected` one
+fn swatch_row(colors: &[ConfigColor], selected: Option<usize>) -> Vec<Span<'static>> {
+    let bracket = |i: usize| match selected {
+        Some(s) if s == i => "[",
+        Some(s) if s + 1 == i => "]",
+        _ => " ",
+    };
+    let mut spans = Vec::new();
+    for (i, color) in colors.iter().enumerate() {
+        spans.push(Span::styled(bracket(i), Style::default().fg(Color::Yellow)));
+        spans.push(Span::styled("██", Style::default().fg(tui_color(color))));
+    }
+    spans.push(Span::styled(bracket(colors.len()), Style::default().fg(Color::Yellow)));
+    spans
+}
+
+/// What a line of the diff view is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DiffRow {
+    File,
+    Hunk,
+    Context,
+    Removed,
+    Added,
+}
+
+/// A full-screen unified diff of what a save would write, scrolled a line
+/// or a hunk at a time
+#[derive(Debug, Clone)]
+pub struct DiffView {
+    /// Every file's headers and hunks, one after the other
+    lines: Vec<(DiffRow, String)>,
+    /// Where in `lines` each hunk starts
+    hunks: Vec<usize>,
+    files: usize,
+    /// First line shown
+    scroll: usize,
+    title: String,
+    /// Question and keys at the bottom
+    prompt: (String, String),
+}
+
+impl DiffView {
+    /// The diff of a save, asking to confirm it
+    pub fn new(diffs: &[FileDiff]) -> Self {
+        Self::with_prompt(diffs, "Save preview", "Save these changes?", "y: Save  q/Esc: Abort")
+    }
+
+    /// The diff titled `title`, with `question` and the `keys` that answer
+    /// it at the bottom
+    pub fn with_prompt(diffs: &[FileDiff], title: &str, question: &str, keys: &str) -> Self {
+        let mut lines = Vec::new();
+        let mut hunks = Vec::new();
+        for diff in diffs {
+            let old = if diff.is_new { "/dev/null" } else { diff.path.as_str() };
+            lines.push((DiffRow::File, format!("--- {}", old)));
+            lines.push((DiffRow::File, format!("+++ {}", diff.path)));
+            for hunk in &diff.hunks {
+                hunks.push(lines.len());
+                lines.push((DiffRow::Hunk, hunk.header()));
+                lines.extend(hunk.lines.iter().map(|line| match line {
+                    DiffLine::Context(text) => (DiffRow::Context, format!(" {}", text)),
+                    DiffLine::Removed(text) => (DiffRow::Removed, format!("-{}", text)),
+                    DiffLine::Added(text) => (DiffRow::Added, format!("+{}", text)),
+                }));
+            }
+            lines.push((DiffRow::Context, String::new()));
+        }
+        Self {
+            lines,
+            hunks,
+            files: diffs.len(),
+            scroll: 0,
+            title: title.to_string(),
+            prompt: (question.to_string(), keys.to_string()),
+        }
+    }
+
+    /// Scroll by `lines`, down when positive
+    pub fn scroll_by(&mut self, lines: isize) {
+        let last = self.lines.len().saturating_sub(1);
+        self.scroll = self.scroll.saturating_add_signed(lines).min(last);
+    }
+
+    /// Scroll to the next hunk
+    pub fn next_hunk(&mut self) {
+        if let Some(&start) = self.hunks.iter().find(|&&start| start > self.scroll) {
+            self.scroll = start;
+        }
+    }
+
+    /// Scroll to the previous hunk
+    pub fn previous_hunk(&mut self) {
+        if let Some(&start) = self.hunks.iter().rev().find(|&&start| start < self.scroll) {
+            self.scroll = start;
+        }
+    }
+
+    /// Number of the hunk at the top of the view, from 1 (0 above the first)
+    fn current_hunk(&self) -> usize {
+        self.hunks.iter().filter(|&&start| start <= self.scroll).count()
+    }
+}
+
+/// Draw `view` over everything but the status bar, with its prompt at the
+/// bottom
+pub fn draw_diff_view(f: &mut Frame, view: &DiffView) {
+    let screen = f.area();
+    let area = Rect {
+        height: screen.height.saturating_sub(1),
+        ..screen
+    };
+
+    let lines: Vec<Line> = view
+        .lines
+        .iter()
+        .skip(view.scroll)
+        .take(area.height.saturating_sub(2) as usize)
+        .map(|(row, text)| {
+            let style = match row {
+                DiffRow::File => Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+                DiffRow::Hunk => Style::default().fg(Color::Cyan),
+                DiffRow::Context => Style::default().fg(Color::Gray),
+                DiffRow::Removed => Style::default().fg(Color::Red),
+                DiffRow::Added => Style::default().fg(Color::Green),
+            };
+            Line::from(Span::styled(text.clone(), style))
+        })
+        .collect();
+
+    let title = format!(
+        " {}: {} file(s), hunk {}/{} ",
+        view.title,
+        view.files,
+        view.current_hunk().max(1),
+        view.hunks.len()
+    );
+    let prompt = Line::from(vec![
+        Span::styled(format!(" {} ", view.prompt.0), Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+        Span::styled(view.prompt.1.as_str(), Style::default().fg(Color::White)),
+    ]);
+    let block = Block::default()
+        .title(title)
+        .title_style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))
+        .title_bottom(prompt)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Rgb(30, 30, 46)));
+
+    f.render_widget(Clear, area);
+    f.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+/// What the backup list is asking to confirm
+#[derive(Debug, Clone, PartialEq)]
+pub enum BackupAction {
+    /// Put the selected backup back in place
+    Restore,
+    /// Delete these backups
+    Prune(Vec<BackupInfo>),
+}
+
+/// The backups of the config files, newest first, with the diff of the
+/// selected one against its file when open
+#[derive(Debug, Clone)]
+pub struct BackupList {
+    pub backups: Vec<BackupInfo>,
+    pub selected: usize,
+    /// Where the backups are, so paths can be shown relative to it
+    pub config_dir: PathBuf,
+    /// What restoring the selected backup would change
+    pub diff: Option<DiffView>,
+    /// Waiting for y/n before doing this
+    pub confirm: Option<BackupAction>,
+}
+
+impl BackupList {
+    pub fn new(config_dir: PathBuf) -> Self {
+        Self {
+            backups: backups::list_backups(&config_dir),
+            selected: 0,
+            config_dir,
+            diff: None,
+            confirm: None,
+        }
+    }
+
+    /// Read the folder again, keeping the selection in range
+    pub fn refresh(&mut self) {
+        self.backups = backups::list_backups(&self.config_dir);
+        self.selected = self.selected.min(self.backups.len().saturating_sub(1));
+    }
+
+    pub fn next(&mut self) {
+        if self.selected + 1 < self.backups.len() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn selected_backup(&self) -> Option<&BackupInfo> {
+        self.backups.get(self.selected)
+    }
+
+    /// A backup's path from the config directory (`.backups/wezterm.lua.backup.…`)
+    fn relative_path<'a>(&self, backup: &'a BackupInfo) -> std::borrow::Cow<'a, str> {
+        let path = std::path::Path::new(&backup.path);
+        match path.strip_prefix(&self.config_dir) {
+            Ok(relative) => relative.to_string_lossy(),
+            Err(_) => backup.path.as_str().into(),
+        }
+    }
+}
+
+/// Draw the backup list centered over the screen, or the diff of the
+/// selected backup when it is open
+pub fn draw_backup_list(f: &mut Frame, list: &BackupList, has_changes: bool) {
+    if let Some(view) = &list.diff {
+        draw_diff_view(f, view);
+        if list.confirm.is_none() {
+            return;
+        }
+    }
+
+    let screen = f.area();
+    let width = screen.width.saturating_sub(8).clamp(20.min(screen.width), 100);
+    let height = (list.backups.len() as u16 + 4).clamp(7, screen.height.saturating_sub(4).max(7)).min(screen.height);
+    let area = Rect {
+        x: screen.x + (screen.width - width) / 2,
+        y: screen.y + (screen.height - height) / 2,
+        width,
+        height,
+    };
+
+    let items: Vec<ListItem> = if list.backups.is_empty() {
+        vec![ListItem::new(Span::styled(
+            "  No backups yet; one is taken each time a save replaces a file",
+            Style::default().fg(Color::DarkGray),
+        ))]
+    } else {
+        list.backups
+            .iter()
+            .map(|backup| {
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!(" {}  ", backup.created), Style::default().fg(Color::Cyan)),
+                    Span::styled(
+                        format!("{:>9}  ", backups::format_size(backup.size)),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                    Span::styled(list.relative_path(backup).into_owned(), Style::default().fg(Color::White)),
+                ]))
+            })
+            .collect()
+    };
+
+    let (question, keys) = match (&list.confirm, list.selected_backup()) {
+        (Some(BackupAction::Restore), Some(backup)) if has_changes => (
+            format!("Restore {}? Unsaved edits will be lost.", backup.name),
+            "y: Restore  n: Cancel",
+        ),
+        (Some(BackupAction::Restore), Some(backup)) => (format!("Restore {}?", backup.name), "y: Restore  n: Cancel"),
+        (Some(BackupAction::Prune(pruned)), _) => {
+            let policy = backups::RetentionPolicy::default();
+            (
+                format!(
+                    "Delete {} backup(s), keeping the newest {} of each file and the last {} days?",
+                    pruned.len(),
+                    policy.keep_last.unwrap_or(0),
+                    policy.keep_days.unwrap_or(0)
+                ),
+                "y: Delete  n: Cancel",
+            )
+        }
+        _ => (String::new(), "Enter: Diff  r: Restore  p: Prune  f: Use .backups/  Esc: Close"),
+    };
+    let mut prompt = vec![Span::styled(format!(" {} ", keys), Style::default().fg(Color::White))];
+    if !question.is_empty() {
+        prompt.insert(
+            0,
+            Span::styled(format!(" {}", question), Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+        );
+    }
+
+    let block = Block::default()
+        .title(format!(" Backups ({}) ", list.backups.len()))
+        .title_style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))
+        .title_bottom(Line::from(prompt))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Rgb(30, 30, 46)));
+    let highlight = if list.backups.is_empty() { Style::default() } else { Style::default().bg(Color::Rgb(69, 71, 90)) };
+    let mut state = ListState::default().with_selected(Some(list.selected));
+
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(List::new(items).block(block).highlight_style(highlight), area, &mut state);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn picker(text: &str) -> ColorPicker {
+        ColorPicker::new("Foreground", ConfigColor::parse(text).unwrap(), &AppearanceConfig::default())
+    }
+
+    #[test]
+    fn test_hue_wraps_at_360() {
+        let mut picker = picker("#ff0000");
+        picker.adjust(-1);
+        assert_eq!(picker.hsl[0], 359.0);
+        picker.adjust(1);
+        assert_eq!(picker.hsl[0], 0.0);
+        assert_eq!(picker.color.as_str(), "#ff0000");
+        picker.adjust(360);
+        assert_eq!(picker.hsl[0], 0.0);
+    }
+
+    #[test]
+    fn test_hue_survives_gray() {
+        let mut picker = picker("#00aaff");
+        let hue = picker.hsl[0];
+        picker.row = ColorRow::Saturation;
+        picker.adjust(-100);
+        assert_eq!(picker.color.as_str(), "#808080");
+        assert_eq!(picker.hsl, [hue, 0.0, 0.5]);
+        picker.adjust(100);
+        assert_eq!(picker.color.as_str(), "#00aaff");
+
+        // A gray taken from RGB or hex keeps the slider's hue too
+        picker.row = ColorRow::Hex;
+        for c in "#777".chars() {
+            picker.type_hex(c);
+        }
+        assert_eq!(picker.hsl[0], hue);
+    }
+
+    #[test]
+    fn test_rgb_channels_clamp() {
+        let mut picker = picker("#102030");
+        picker.row = ColorRow::Red;
+        picker.adjust(300);
+        picker.row = ColorRow::Blue;
+        picker.adjust(-300);
+        assert_eq!(picker.color.as_str(), "#ff2000");
+        assert_eq!(picker.hex, "#ff2000");
+    }
+
+    #[test]
+    fn test_typed_hex_with_and_without_alpha() {
+        let mut picker = picker("#000000");
+        for c in "abc".chars() {
+            picker.type_hex(c);
+        }
+        assert_eq!(picker.hex, "#abc");
+        assert_eq!(picker.color.to_hex(), "#aabbcc");
+
+        for c in "#12345678".chars() {
+            picker.type_hex(c);
+        }
+        assert!(picker.hex_is_valid());
+        assert_eq!(picker.color.to_rgba8(), [0x12, 0x34, 0x56, 0x78]);
+        // Sliders keep the alpha
+        picker.row = ColorRow::Lightness;
+        picker.adjust(10);
+        assert_eq!(picker.color.to_rgba8()[3], 0x78);
+
+        // An incomplete code leaves the color alone
+        picker.row = ColorRow::Hex;
+        picker.delete_hex();
+        assert!(!picker.hex_is_valid());
+        assert_eq!(picker.color.to_rgba8()[3], 0x78);
+    }
+}
@@ -0,0 +1,146 @@
+// Config watcher - notices when the config files change on disk
+//
+// The directories holding wezterm.lua and the modules it requires are
+// watched rather than the files themselves: editors often save by writing
+// a new file and renaming it over the old one, which a watch on the old
+// file would miss. Events for other files in those directories are
+// ignored. A symlinked config is watched where it points too, so edits
+// made in a dotfiles checkout are seen. Editors also save in several
+// steps, so a change is only reported once the files have been quiet for
+// a moment.
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver};
+use std::time::{Duration, Instant};
+
+/// How long the files must be quiet before a change is reported
+const SETTLE: Duration = Duration::from_millis(250);
+
+/// Watches the config files for changes made by other programs
+pub struct ConfigWatcher {
+    watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+    /// The watched files, with their directories resolved
+    files: Vec<PathBuf>,
+    dirs: Vec<PathBuf>,
+    /// When a watched file last changed, until the change is reported
+    changed_at: Option<Instant>,
+}
+
+impl ConfigWatcher {
+    /// Start a watcher with nothing watched yet; fails when the platform
+    /// has no file notifications (or inotify is out of watches)
+    pub fn new() -> notify::Result<Self> {
+        let (sender, events) = channel();
+        Ok(Self {
+            watcher: notify::recommended_watcher(sender)?,
+            events,
+            files: Vec::new(),
+            dirs: Vec::new(),
+            changed_at: None,
+        })
+    }
+
+    /// Watch `files` instead of whatever was watched before. Files that
+    /// don't exist yet are watched for being created.
+    pub fn watch(&mut self, files: &[PathBuf]) {
+        for dir in self.dirs.drain(..) {
+            let _ = self.watcher.unwatch(&dir);
+        }
+        self.files.clear();
+        for file in files {
+            self.files.extend(resolved(file));
+            // Where a symlink points
+            if let Ok(target) = fs::canonicalize(file) {
+                self.files.extend(resolved(&target));
+            }
+        }
+        self.files.sort();
+        self.files.dedup();
+
+        let mut dirs: Vec<PathBuf> = self.files.iter().filter_map(|f| f.parent().map(Path::to_path_buf)).collect();
+        dirs.sort();
+        dirs.dedup();
+        for dir in dirs {
+            if self.watcher.watch(&dir, RecursiveMode::NonRecursive).is_ok() {
+                self.dirs.push(dir);
+            }
+        }
+    }
+
+    /// Whether a watched file changed and has since settled. Each change is
+    /// reported once.
+    pub fn poll(&mut self) -> bool {
+        while let Ok(event) = self.events.try_recv() {
+            let Ok(event) = event else {
+                continue;
+            };
+            let touches_config = event.paths.iter().any(|path| self.files.contains(path));
+            if touches_config && !matches!(event.kind, EventKind::Access(_)) {
+                self.changed_at = Some(Instant::now());
+            }
+        }
+        match self.changed_at {
+            Some(at) if at.elapsed() >= SETTLE => {
+                self.changed_at = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// `file` with its directory made absolute and symlink-free, as watch
+/// events name it
+fn resolved(file: &Path) -> Option<PathBuf> {
+    let dir = match file.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    Some(fs::canonicalize(dir).ok()?.join(file.file_name()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread::sleep;
+
+    /// Poll `watcher` until `timeout` has passed, counting the changes seen
+    fn changes_within(watcher: &mut ConfigWatcher, timeout: Duration) -> usize {
+        let start = Instant::now();
+        let mut changes = 0;
+        while start.elapsed() < timeout {
+            if watcher.poll() {
+                changes += 1;
+            }
+            sleep(Duration::from_millis(20));
+        }
+        changes
+    }
+
+    #[test]
+    fn test_burst_of_writes_is_one_change() {
+        let dir = std::env::temp_dir().join(format!("wezterm-settings-watch-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("wezterm.lua");
+        fs::write(&file, "return {}\n").unwrap();
+
+        let mut watcher = ConfigWatcher::new().unwrap();
+        watcher.watch(std::slice::from_ref(&file));
+        // Other files in the directory don't count
+        fs::write(dir.join("notes.txt"), "hello\n").unwrap();
+        assert_eq!(changes_within(&mut watcher, SETTLE * 3), 0);
+
+        // An editor saving in steps, quicker than SETTLE apart
+        for step in 0..3 {
+            fs::write(&file, format!("-- step {}\nreturn {{}}\n", step)).unwrap();
+            sleep(SETTLE / 5);
+        }
+        assert_eq!(changes_within(&mut watcher, SETTLE * 4), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}